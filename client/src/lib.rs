@@ -11,6 +11,8 @@ mod metrics;
 mod proxy;
 mod raw;
 mod retry;
+mod schedules;
+mod visibility;
 mod worker_registry;
 mod workflow_handle;
 
@@ -20,8 +22,13 @@ pub use crate::{
 };
 pub use metrics::{LONG_REQUEST_LATENCY_HISTOGRAM_NAME, REQUEST_LATENCY_HISTOGRAM_NAME};
 pub use raw::{CloudService, HealthService, OperatorService, TestService, WorkflowService};
+pub use schedules::{
+    CreateScheduleOptions, Schedule, ScheduleAction, ScheduleBackfill, ScheduleCalendarSpec,
+    ScheduleDescription, ScheduleHandle, ScheduleIntervalSpec, SchedulePolicies, ScheduleRange,
+    ScheduleSpec, ScheduleStartWorkflowAction, ScheduleState, ScheduleSummary,
+};
 pub use temporal_sdk_core_protos::temporal::api::{
-    enums::v1::ArchivalState,
+    enums::v1::{ArchivalState, ScheduleOverlapPolicy},
     filter::v1::{StartTimeFilter, StatusFilter, WorkflowExecutionFilter, WorkflowTypeFilter},
     workflowservice::v1::{
         list_closed_workflow_executions_request::Filters as ListClosedFilters,
@@ -29,9 +36,13 @@ pub use temporal_sdk_core_protos::temporal::api::{
     },
 };
 pub use tonic;
+pub use visibility::{
+    ListOptions, WorkflowExecutionCount, WorkflowExecutionCountGroup, WorkflowExecutionSummary,
+};
 pub use worker_registry::{Slot, SlotManager, SlotProvider, WorkerKey};
 pub use workflow_handle::{
-    GetWorkflowResultOpts, WorkflowExecutionInfo, WorkflowExecutionResult, WorkflowHandle,
+    GetWorkflowResultOpts, StartUpdateOpts, UpdateHandle, UpdateResult, WorkflowExecutionInfo,
+    WorkflowExecutionResult, WorkflowHandle,
 };
 
 use crate::{
@@ -41,8 +52,10 @@ use crate::{
     workflow_handle::UntypedWorkflowHandle,
 };
 use backoff::{ExponentialBackoff, SystemClock, exponential};
+use futures_util::Stream;
 use http::{Uri, uri::InvalidUri};
 use parking_lot::RwLock;
+use prost::Message;
 use std::{
     collections::HashMap,
     fmt::{Debug, Formatter},
@@ -55,12 +68,17 @@ use temporal_sdk_core_api::telemetry::metrics::TemporalMeter;
 use temporal_sdk_core_protos::{
     TaskToken,
     coresdk::IntoPayloadsExt,
+    google,
     grpc::health::v1::health_client::HealthClient,
     temporal::api::{
         cloud::cloudservice::v1::cloud_service_client::CloudServiceClient,
         common,
         common::v1::{Header, Payload, Payloads, RetryPolicy, WorkflowExecution, WorkflowType},
-        enums::v1::{TaskQueueKind, WorkflowIdConflictPolicy, WorkflowIdReusePolicy},
+        enums::v1::{
+            TaskQueueKind, UpdateWorkflowExecutionLifecycleStage, WorkflowIdConflictPolicy,
+            WorkflowIdReusePolicy,
+        },
+        errordetails::v1::{MultiOperationExecutionFailure, multi_operation_execution_failure},
         operatorservice::v1::operator_service_client::OperatorServiceClient,
         query::v1::WorkflowQuery,
         replication::v1::ClusterReplicationConfig,
@@ -872,6 +890,90 @@ impl SignalWithStartOptions {
     }
 }
 
+/// Helper struct for [WfClientExt::update_with_start_workflow].
+#[derive(Clone, derive_builder::Builder)]
+pub struct UpdateWithStartOptions {
+    /// Input payload for the workflow run
+    #[builder(setter(strip_option), default)]
+    pub input: Option<Payloads>,
+    /// Task Queue to target (required)
+    #[builder(setter(into))]
+    pub task_queue: String,
+    /// Workflow id for the workflow run
+    #[builder(setter(into))]
+    pub workflow_id: String,
+    /// Workflow type for the workflow run
+    #[builder(setter(into))]
+    pub workflow_type: String,
+    #[builder(setter(strip_option), default)]
+    /// Request id for idempotency/deduplication
+    pub request_id: Option<String>,
+    /// The update name to send (required)
+    #[builder(setter(into))]
+    pub update_name: String,
+    /// Payloads for the update
+    #[builder(setter(strip_option), default)]
+    pub update_input: Option<Payloads>,
+    #[builder(setter(strip_option), default)]
+    /// Headers for the update
+    pub update_header: Option<Header>,
+    #[builder(setter(strip_option), default)]
+    /// Id for the update. A random one is generated if unset.
+    pub update_id: Option<String>,
+    /// How far along the update must be before the call returns. Must be accepted or completed.
+    #[builder(default = "UpdateWorkflowExecutionLifecycleStage::Accepted")]
+    pub wait_for_stage: UpdateWorkflowExecutionLifecycleStage,
+}
+
+impl UpdateWithStartOptions {
+    /// Builder convenience.  Less `use` imports
+    pub fn builder() -> UpdateWithStartOptionsBuilder {
+        Default::default()
+    }
+}
+
+/// Errors returned by [WfClientExt::update_with_start_workflow], split by which of the two
+/// operations caused the failure.
+#[derive(thiserror::Error, Debug)]
+pub enum UpdateWithStartError {
+    /// Starting the workflow failed, so the update was never sent
+    #[error("Starting the workflow failed: {0}")]
+    Start(Status),
+    /// The workflow was started (or already running) but the update was not accepted
+    #[error("Sending the update failed: {0}")]
+    Update(Status),
+    /// The request failed as a whole, or the server responded in a way we didn't expect
+    #[error("Update-with-start failed: {0}")]
+    Other(Status),
+}
+
+impl UpdateWithStartError {
+    /// Attribute a failed `ExecuteMultiOperation` call to the operation which caused it. The
+    /// server reports one status per operation, with every operation that didn't fail marked
+    /// aborted.
+    fn from_multi_operation_status(status: Status) -> Self {
+        let Some(failure) = google::rpc::Status::decode(status.details())
+            .ok()
+            .and_then(|s| {
+                s.details
+                    .into_iter()
+                    .find(|d| d.type_url.ends_with("MultiOperationExecutionFailure"))
+            })
+            .and_then(|d| MultiOperationExecutionFailure::decode(d.value.as_slice()).ok())
+        else {
+            return Self::Other(status);
+        };
+        let op_status = |s: &multi_operation_execution_failure::OperationStatus| {
+            Status::new(Code::from_i32(s.code), s.message.clone())
+        };
+        match failure.statuses.as_slice() {
+            [start, _] if start.code != Code::Aborted as i32 => Self::Start(op_status(start)),
+            [_, update] if update.code != Code::Aborted as i32 => Self::Update(op_status(update)),
+            _ => Self::Other(status),
+        }
+    }
+}
+
 /// This trait provides higher-level friendlier interaction with the server.
 /// See the [WorkflowService] trait for a lower-level client.
 #[async_trait::async_trait]
@@ -1027,6 +1129,15 @@ pub trait WorkflowClientTrait: NamespacedClient {
         query: String,
     ) -> Result<ListArchivedWorkflowExecutionsResponse>;
 
+    /// Count workflow executions matching an Advanced Visibility query. A `GROUP BY` clause in
+    /// the query, ex: `GROUP BY ExecutionStatus`, also counts the executions in each group.
+    /// Returns an unimplemented error unless overridden.
+    async fn count_workflow_executions(&self, _query: String) -> Result<WorkflowExecutionCount> {
+        Err(Status::unimplemented(
+            "Counting workflow executions is not supported by this client",
+        ))
+    }
+
     /// Get Cluster Search Attributes
     async fn get_search_attributes(&self) -> Result<GetSearchAttributesResponse>;
 
@@ -1499,6 +1610,19 @@ where
         .into_inner())
     }
 
+    async fn count_workflow_executions(&self, query: String) -> Result<WorkflowExecutionCount> {
+        Ok(WorkflowService::count_workflow_executions(
+            &mut self.clone(),
+            CountWorkflowExecutionsRequest {
+                namespace: self.namespace().to_owned(),
+                query,
+            },
+        )
+        .await?
+        .into_inner()
+        .into())
+    }
+
     async fn get_search_attributes(&self) -> Result<GetSearchAttributesResponse> {
         Ok(WorkflowService::get_search_attributes(&mut self.clone(),
             GetSearchAttributesRequest {},
@@ -1565,8 +1689,18 @@ pub trait WfClientExt: WfHandleClient + Sized + Clone {
         workflow_id: impl Into<String>,
         run_id: impl Into<String>,
     ) -> UntypedWorkflowHandle<Self> {
+        self.get_workflow_handle(workflow_id, run_id)
+    }
+
+    /// Create a handle for a workflow execution whose result is deserialized as `RT`. `run_id` may
+    /// be left blank to target the latest run.
+    fn get_workflow_handle<RT>(
+        &self,
+        workflow_id: impl Into<String>,
+        run_id: impl Into<String>,
+    ) -> WorkflowHandle<Self, RT> {
         let rid = run_id.into();
-        UntypedWorkflowHandle::new(
+        WorkflowHandle::new(
             self.clone(),
             WorkflowExecutionInfo {
                 namespace: self.namespace().to_string(),
@@ -1575,6 +1709,386 @@ pub trait WfClientExt: WfHandleClient + Sized + Clone {
             },
         )
     }
+
+    /// Create a handle for a workflow started as `first_execution_run_id`, ex: from the run id in
+    /// a start response. Rather than being pinned to that run, the handle follows the workflow
+    /// across continue-as-new, targeting whichever run of the chain is the latest.
+    fn get_started_workflow_handle<RT>(
+        &self,
+        workflow_id: impl Into<String>,
+        first_execution_run_id: impl Into<String>,
+    ) -> WorkflowHandle<Self, RT> {
+        WorkflowHandle::new_started(
+            self.clone(),
+            self.namespace().to_string(),
+            workflow_id.into(),
+            first_execution_run_id.into(),
+        )
+    }
+
+    /// Create a handle for an update previously sent to a workflow, which can be used to wait for
+    /// the update's outcome. `run_id` may be left blank to target the latest run.
+    fn get_update_handle(
+        &self,
+        workflow_id: impl Into<String>,
+        run_id: impl Into<String>,
+        update_id: impl Into<String>,
+    ) -> UpdateHandle<Self> {
+        self.get_untyped_workflow_handle(workflow_id, run_id)
+            .get_update_handle(update_id)
+    }
+
+    /// Start a workflow if it isn't already running, and send it an update, atomically. Returns
+    /// a handle to the workflow along with one for the update. Unless otherwise specified in
+    /// `workflow_options`, an already-running workflow receives the update rather than causing
+    /// an error.
+    fn update_with_start_workflow(
+        &self,
+        options: UpdateWithStartOptions,
+        workflow_options: WorkflowOptions,
+    ) -> impl Future<
+        Output = Result<(UntypedWorkflowHandle<Self>, UpdateHandle<Self>), UpdateWithStartError>,
+    > + Send
+    where
+        Self: Send + Sync,
+    {
+        async move {
+            let stage = options.wait_for_stage;
+            if stage != UpdateWorkflowExecutionLifecycleStage::Accepted
+                && stage != UpdateWorkflowExecutionLifecycleStage::Completed
+            {
+                return Err(UpdateWithStartError::Other(Status::invalid_argument(
+                    "Updates can only be waited on until they're accepted or completed",
+                )));
+            }
+            let id_conflict_policy = match workflow_options.id_conflict_policy {
+                WorkflowIdConflictPolicy::Unspecified => WorkflowIdConflictPolicy::UseExisting,
+                p => p,
+            };
+            let update_id = options
+                .update_id
+                .unwrap_or_else(|| Uuid::new_v4().to_string());
+            let start = StartWorkflowExecutionRequest {
+                namespace: self.namespace().to_owned(),
+                input: options.input,
+                workflow_id: options.workflow_id.clone(),
+                workflow_type: Some(WorkflowType {
+                    name: options.workflow_type,
+                }),
+                task_queue: Some(TaskQueue {
+                    name: options.task_queue,
+                    kind: TaskQueueKind::Normal as i32,
+                    normal_name: "".to_string(),
+                }),
+                identity: self.get_identity().to_owned(),
+                request_id: options
+                    .request_id
+                    .unwrap_or_else(|| Uuid::new_v4().to_string()),
+                workflow_id_reuse_policy: workflow_options.id_reuse_policy as i32,
+                workflow_id_conflict_policy: id_conflict_policy as i32,
+                workflow_execution_timeout: workflow_options
+                    .execution_timeout
+                    .and_then(|d| d.try_into().ok()),
+                workflow_run_timeout: workflow_options.run_timeout.and_then(|d| d.try_into().ok()),
+                workflow_task_timeout: workflow_options
+                    .task_timeout
+                    .and_then(|d| d.try_into().ok()),
+                search_attributes: workflow_options.search_attributes.map(|d| d.into()),
+                retry_policy: workflow_options.retry_policy,
+                links: workflow_options.links,
+                completion_callbacks: workflow_options.completion_callbacks,
+                ..Default::default()
+            };
+            let update = UpdateWorkflowExecutionRequest {
+                namespace: self.namespace().to_owned(),
+                workflow_execution: Some(WorkflowExecution {
+                    workflow_id: options.workflow_id.clone(),
+                    run_id: "".to_string(),
+                }),
+                wait_policy: Some(update::v1::WaitPolicy {
+                    lifecycle_stage: stage as i32,
+                }),
+                request: Some(update::v1::Request {
+                    meta: Some(update::v1::Meta {
+                        update_id: update_id.clone(),
+                        identity: self.get_identity().to_owned(),
+                    }),
+                    input: Some(update::v1::Input {
+                        header: options.update_header,
+                        name: options.update_name,
+                        args: options.update_input,
+                    }),
+                }),
+                ..Default::default()
+            };
+            let request = ExecuteMultiOperationRequest {
+                namespace: self.namespace().to_owned(),
+                operations: vec![
+                    execute_multi_operation_request::Operation {
+                        operation: Some(
+                            execute_multi_operation_request::operation::Operation::StartWorkflow(
+                                start,
+                            ),
+                        ),
+                    },
+                    execute_multi_operation_request::Operation {
+                        operation: Some(
+                            execute_multi_operation_request::operation::Operation::UpdateWorkflow(
+                                update,
+                            ),
+                        ),
+                    },
+                ],
+            };
+            // As with plain updates, the server may give up waiting before the stage is reached.
+            // Both operations carry the same ids every time, so re-sending is safe.
+            let (started, updated) = loop {
+                let resp =
+                    WorkflowService::execute_multi_operation(&mut self.clone(), request.clone())
+                        .await
+                        .map_err(UpdateWithStartError::from_multi_operation_status)?
+                        .into_inner();
+                use execute_multi_operation_response::response::Response as OpResponse;
+                let mut responses = resp.responses.into_iter().map(|r| r.response);
+                let (
+                    Some(Some(OpResponse::StartWorkflow(started))),
+                    Some(Some(OpResponse::UpdateWorkflow(updated))),
+                ) = (responses.next(), responses.next())
+                else {
+                    return Err(UpdateWithStartError::Other(Status::internal(
+                        "Server returned unexpected responses to update-with-start",
+                    )));
+                };
+                if updated.stage() >= stage || updated.outcome.is_some() {
+                    break (started, updated);
+                }
+            };
+            // An already-running workflow may itself have been continued-as-new, so only a run
+            // which was just started is known to be the first of its chain
+            let wf_handle = if started.started {
+                self.get_started_workflow_handle(&options.workflow_id, &started.run_id)
+            } else {
+                self.get_untyped_workflow_handle(&options.workflow_id, &started.run_id)
+            };
+            let update_handle = UpdateHandle::new(
+                self.clone(),
+                self.namespace().to_owned(),
+                options.workflow_id,
+                Some(started.run_id),
+                update_id,
+                updated.outcome,
+            );
+            Ok((wf_handle, update_handle))
+        }
+    }
+
+    /// Create a schedule with the given id, returning a handle to it
+    fn create_schedule(
+        &self,
+        schedule_id: impl Into<String>,
+        schedule: Schedule,
+        options: CreateScheduleOptions,
+    ) -> impl Future<Output = Result<ScheduleHandle<Self>, anyhow::Error>> + Send
+    where
+        Self: Send + Sync,
+    {
+        let schedule_id = schedule_id.into();
+        async move {
+            WorkflowService::create_schedule(
+                &mut self.clone(),
+                CreateScheduleRequest {
+                    namespace: self.namespace().to_owned(),
+                    schedule_id: schedule_id.clone(),
+                    schedule: Some(schedule.into()),
+                    initial_patch: schedules::initial_patch(&options),
+                    identity: self.get_identity().to_owned(),
+                    request_id: options
+                        .request_id
+                        .unwrap_or_else(|| Uuid::new_v4().to_string()),
+                    memo: schedules::memo(options.memo),
+                    search_attributes: (!options.search_attributes.is_empty())
+                        .then(|| options.search_attributes.into()),
+                },
+            )
+            .await?;
+            Ok(self.get_schedule_handle(schedule_id))
+        }
+    }
+
+    /// Create a handle for an existing schedule
+    fn get_schedule_handle(&self, schedule_id: impl Into<String>) -> ScheduleHandle<Self> {
+        ScheduleHandle::new(
+            self.clone(),
+            self.namespace().to_owned(),
+            schedule_id.into(),
+        )
+    }
+
+    /// List the schedules in the namespace matching the visibility `query`, which may be empty to
+    /// list all of them. Pages are fetched lazily as the stream is consumed.
+    fn list_schedules(
+        &self,
+        query: impl Into<String>,
+    ) -> impl Stream<Item = Result<ScheduleSummary>> + Send + 'static
+    where
+        Self: Send + Sync + 'static,
+    {
+        self.list_schedules_with_options(query, ListOptions::default())
+    }
+
+    /// Like [WfClientExt::list_schedules], with control over page size and how many schedules are
+    /// listed in total
+    fn list_schedules_with_options(
+        &self,
+        query: impl Into<String>,
+        options: ListOptions,
+    ) -> impl Stream<Item = Result<ScheduleSummary>> + Send + 'static
+    where
+        Self: Send + Sync + 'static,
+    {
+        let client = self.clone();
+        let query = query.into();
+        visibility::paginate(options, move |maximum_page_size, next_page_token| {
+            let mut client = client.clone();
+            let request = ListSchedulesRequest {
+                namespace: client.namespace().to_owned(),
+                maximum_page_size,
+                next_page_token,
+                query: query.clone(),
+            };
+            async move {
+                let resp = WorkflowService::list_schedules(&mut client, request)
+                    .await?
+                    .into_inner();
+                let page = resp.schedules.into_iter().map(ScheduleSummary::from);
+                Ok::<_, Status>((page.collect::<Vec<_>>(), resp.next_page_token))
+            }
+        })
+    }
+
+    /// List workflow executions matching an Advanced Visibility query, which may be empty to list
+    /// all of them. Pages are fetched lazily as the stream is consumed.
+    fn list_workflows(
+        &self,
+        query: impl Into<String>,
+        options: ListOptions,
+    ) -> impl Stream<Item = Result<WorkflowExecutionSummary>> + Send + 'static
+    where
+        Self: Send + Sync + 'static,
+    {
+        let client = self.clone();
+        let query = query.into();
+        visibility::paginate(options, move |page_size, next_page_token| {
+            let client = client.clone();
+            let query = query.clone();
+            async move {
+                let resp = client
+                    .list_workflow_executions(page_size, next_page_token, query)
+                    .await?;
+                let page = resp
+                    .executions
+                    .into_iter()
+                    .map(WorkflowExecutionSummary::from);
+                Ok::<_, Status>((page.collect::<Vec<_>>(), resp.next_page_token))
+            }
+        })
+    }
+
+    /// List open workflow executions with Standard Visibility filtering. Pages are fetched lazily
+    /// as the stream is consumed.
+    fn list_open_workflows(
+        &self,
+        start_time_filter: Option<StartTimeFilter>,
+        filters: Option<ListOpenFilters>,
+        options: ListOptions,
+    ) -> impl Stream<Item = Result<WorkflowExecutionSummary>> + Send + 'static
+    where
+        Self: Send + Sync + 'static,
+    {
+        let client = self.clone();
+        visibility::paginate(options, move |page_size, next_page_token| {
+            let client = client.clone();
+            let start_time_filter = start_time_filter.clone();
+            let filters = filters.clone();
+            async move {
+                let resp = client
+                    .list_open_workflow_executions(
+                        page_size,
+                        next_page_token,
+                        start_time_filter,
+                        filters,
+                    )
+                    .await?;
+                let page = resp
+                    .executions
+                    .into_iter()
+                    .map(WorkflowExecutionSummary::from);
+                Ok::<_, Status>((page.collect::<Vec<_>>(), resp.next_page_token))
+            }
+        })
+    }
+
+    /// List closed workflow executions with Standard Visibility filtering. Pages are fetched
+    /// lazily as the stream is consumed.
+    fn list_closed_workflows(
+        &self,
+        start_time_filter: Option<StartTimeFilter>,
+        filters: Option<ListClosedFilters>,
+        options: ListOptions,
+    ) -> impl Stream<Item = Result<WorkflowExecutionSummary>> + Send + 'static
+    where
+        Self: Send + Sync + 'static,
+    {
+        let client = self.clone();
+        visibility::paginate(options, move |page_size, next_page_token| {
+            let client = client.clone();
+            let start_time_filter = start_time_filter.clone();
+            let filters = filters.clone();
+            async move {
+                let resp = client
+                    .list_closed_workflow_executions(
+                        page_size,
+                        next_page_token,
+                        start_time_filter,
+                        filters,
+                    )
+                    .await?;
+                let page = resp
+                    .executions
+                    .into_iter()
+                    .map(WorkflowExecutionSummary::from);
+                Ok::<_, Status>((page.collect::<Vec<_>>(), resp.next_page_token))
+            }
+        })
+    }
+
+    /// List archived workflow executions matching `query`. Pages are fetched lazily as the stream
+    /// is consumed.
+    fn list_archived_workflows(
+        &self,
+        query: impl Into<String>,
+        options: ListOptions,
+    ) -> impl Stream<Item = Result<WorkflowExecutionSummary>> + Send + 'static
+    where
+        Self: Send + Sync + 'static,
+    {
+        let client = self.clone();
+        let query = query.into();
+        visibility::paginate(options, move |page_size, next_page_token| {
+            let client = client.clone();
+            let query = query.clone();
+            async move {
+                let resp = client
+                    .list_archived_workflow_executions(page_size, next_page_token, query)
+                    .await?;
+                let page = resp
+                    .executions
+                    .into_iter()
+                    .map(WorkflowExecutionSummary::from);
+                Ok::<_, Status>((page.collect::<Vec<_>>(), resp.next_page_token))
+            }
+        })
+    }
 }
 
 impl<T> WfClientExt for T where T: WfHandleClient + Clone + Sized {}
@@ -1594,6 +2108,7 @@ impl<T> RequestExt for tonic::Request<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use assert_matches::assert_matches;
     use tonic::metadata::Ascii;
 
     #[test]
@@ -1689,4 +2204,50 @@ mod tests {
         let opts = builder.keep_alive(None).build().unwrap();
         assert!(opts.keep_alive.is_none());
     }
+
+    fn multi_op_failure(codes: [Code; 2]) -> Status {
+        let failure = MultiOperationExecutionFailure {
+            statuses: codes
+                .iter()
+                .map(|c| multi_operation_execution_failure::OperationStatus {
+                    code: *c as i32,
+                    message: format!("{c:?}"),
+                    details: vec![],
+                })
+                .collect(),
+        };
+        let mut status = google::rpc::Status {
+            code: Code::Aborted as i32,
+            message: "multi-op failed".to_string(),
+            details: vec![Default::default()],
+        };
+        status.details[0].type_url =
+            "type.googleapis.com/temporal.api.errordetails.v1.MultiOperationExecutionFailure"
+                .to_string();
+        status.details[0].value = failure.encode_to_vec();
+        Status::with_details(
+            Code::Aborted,
+            "multi-op failed",
+            status.encode_to_vec().into(),
+        )
+    }
+
+    #[test]
+    fn update_with_start_errors_split_by_operation() {
+        let err = UpdateWithStartError::from_multi_operation_status(multi_op_failure([
+            Code::AlreadyExists,
+            Code::Aborted,
+        ]));
+        assert_matches!(err, UpdateWithStartError::Start(s) if s.code() == Code::AlreadyExists);
+
+        let err = UpdateWithStartError::from_multi_operation_status(multi_op_failure([
+            Code::Aborted,
+            Code::InvalidArgument,
+        ]));
+        assert_matches!(err, UpdateWithStartError::Update(s) if s.code() == Code::InvalidArgument);
+
+        let err =
+            UpdateWithStartError::from_multi_operation_status(Status::unavailable("no server"));
+        assert_matches!(err, UpdateWithStartError::Other(s) if s.code() == Code::Unavailable);
+    }
 }
@@ -1027,7 +1027,9 @@ proxier! {
         PollWorkflowExecutionUpdateResponse,
         |r| {
             let labels = namespaced_request!(r);
-            r.extensions_mut().insert(labels);
+            let exts = r.extensions_mut();
+            exts.insert(labels);
+            exts.insert(IsUserLongPoll);
         }
     );
     (
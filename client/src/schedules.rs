@@ -0,0 +1,808 @@
+use crate::{InterceptedMetricsSvc, NamespacedClient, RawClientLike, WorkflowService};
+use anyhow::anyhow;
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
+use temporal_sdk_core_protos::{
+    coresdk::IntoPayloadsExt,
+    temporal::api::{
+        common::v1::{Header, Memo, Payload, RetryPolicy, WorkflowType},
+        enums::v1::{ScheduleOverlapPolicy, TaskQueueKind},
+        schedule::v1 as sched,
+        taskqueue::v1::TaskQueue,
+        workflow::v1::NewWorkflowExecutionInfo,
+        workflowservice::v1::{
+            DeleteScheduleRequest, DescribeScheduleRequest, PatchScheduleRequest,
+            UpdateScheduleRequest,
+        },
+    },
+};
+use uuid::Uuid;
+
+/// Describes the times at which a schedule takes its action. A time matches if any of the
+/// intervals, calendars or cron expressions match it, and none of the `skip` calendars do.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScheduleSpec {
+    /// Match times that are a multiple of some interval since the epoch
+    pub intervals: Vec<ScheduleIntervalSpec>,
+    /// Match times by calendar fields
+    pub calendars: Vec<ScheduleCalendarSpec>,
+    /// Match times by cron expressions, which the server converts into calendars. Expressions may
+    /// be prefixed with `CRON_TZ=<zone>`, and the `@every` shorthand is accepted too.
+    pub cron_expressions: Vec<String>,
+    /// Never take action at times matching any of these calendars
+    pub skip: Vec<ScheduleCalendarSpec>,
+    /// No action is taken before this time
+    pub start_at: Option<SystemTime>,
+    /// No action is taken after this time
+    pub end_at: Option<SystemTime>,
+    /// Each action is delayed by a random amount up to this long
+    pub jitter: Option<Duration>,
+    /// IANA time zone name calendars and cron expressions are interpreted in. Defaults to UTC.
+    pub time_zone_name: Option<String>,
+}
+
+impl ScheduleSpec {
+    /// A spec matching every `interval`
+    pub fn interval(interval: Duration) -> Self {
+        Self {
+            intervals: vec![ScheduleIntervalSpec::new(interval)],
+            ..Default::default()
+        }
+    }
+
+    /// A spec matching a single calendar
+    pub fn calendar(calendar: ScheduleCalendarSpec) -> Self {
+        Self {
+            calendars: vec![calendar],
+            ..Default::default()
+        }
+    }
+
+    /// A spec matching a single cron expression, ex: `"0 12 * * MON-FRI"`
+    pub fn cron(expression: impl Into<String>) -> Self {
+        Self {
+            cron_expressions: vec![expression.into()],
+            ..Default::default()
+        }
+    }
+}
+
+/// Matches times of the form `epoch + n * every + offset`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduleIntervalSpec {
+    /// How often to match
+    pub every: Duration,
+    /// Shifts the matched times, ex: an hourly interval with a 5 minute offset matches at 5 past
+    /// each hour. Must be smaller than `every`.
+    pub offset: Option<Duration>,
+}
+
+impl ScheduleIntervalSpec {
+    /// Match every `every`, without an offset
+    pub fn new(every: Duration) -> Self {
+        Self {
+            every,
+            offset: None,
+        }
+    }
+}
+
+/// Matches times whose calendar fields each fall in one of the ranges given for that field. The
+/// default matches midnight of every day, and `year` may be left empty to match every year.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduleCalendarSpec {
+    /// Seconds, 0-59
+    pub second: Vec<ScheduleRange>,
+    /// Minutes, 0-59
+    pub minute: Vec<ScheduleRange>,
+    /// Hours, 0-23
+    pub hour: Vec<ScheduleRange>,
+    /// Days of the month, 1-31
+    pub day_of_month: Vec<ScheduleRange>,
+    /// Months, 1-12
+    pub month: Vec<ScheduleRange>,
+    /// Years. Empty matches all years.
+    pub year: Vec<ScheduleRange>,
+    /// Days of the week, 0-6 with 0 being Sunday
+    pub day_of_week: Vec<ScheduleRange>,
+    /// Free-form description of the calendar's intent
+    pub comment: Option<String>,
+}
+
+impl Default for ScheduleCalendarSpec {
+    fn default() -> Self {
+        Self {
+            second: vec![ScheduleRange::single(0)],
+            minute: vec![ScheduleRange::single(0)],
+            hour: vec![ScheduleRange::single(0)],
+            day_of_month: vec![ScheduleRange::new(1, 31)],
+            month: vec![ScheduleRange::new(1, 12)],
+            year: vec![],
+            day_of_week: vec![ScheduleRange::new(0, 6)],
+            comment: None,
+        }
+    }
+}
+
+/// An inclusive range of values for one field of a [ScheduleCalendarSpec]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduleRange {
+    /// First value in the range
+    pub start: i32,
+    /// Last value in the range, inclusive
+    pub end: i32,
+    /// Only every `step`th value from `start` matches
+    pub step: i32,
+}
+
+impl ScheduleRange {
+    /// A range matching just `value`
+    pub fn single(value: i32) -> Self {
+        Self::new(value, value)
+    }
+
+    /// A range matching every value from `start` to `end`, inclusive
+    pub fn new(start: i32, end: i32) -> Self {
+        Self {
+            start,
+            end,
+            step: 1,
+        }
+    }
+
+    /// Only match every `step`th value of the range
+    pub fn with_step(mut self, step: i32) -> Self {
+        self.step = step;
+        self
+    }
+}
+
+/// What a schedule does each time it fires
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScheduleAction {
+    /// Start a workflow
+    StartWorkflow(ScheduleStartWorkflowAction),
+}
+
+impl ScheduleAction {
+    /// Start a workflow of type `workflow_type` on `task_queue`. The server appends the scheduled
+    /// time to `workflow_id` for each run, so that ids are unique.
+    pub fn start_workflow(
+        workflow_type: impl Into<String>,
+        workflow_id: impl Into<String>,
+        task_queue: impl Into<String>,
+        input: Vec<Payload>,
+    ) -> Self {
+        Self::StartWorkflow(ScheduleStartWorkflowAction {
+            workflow_type: workflow_type.into(),
+            workflow_id: workflow_id.into(),
+            task_queue: task_queue.into(),
+            input,
+            execution_timeout: None,
+            run_timeout: None,
+            task_timeout: None,
+            retry_policy: None,
+            memo: HashMap::new(),
+            search_attributes: HashMap::new(),
+            header: None,
+        })
+    }
+}
+
+/// Starts a workflow each time the schedule fires. See [ScheduleAction::start_workflow].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduleStartWorkflowAction {
+    /// The workflow type to start
+    pub workflow_type: String,
+    /// Prefix for the ids of started workflows
+    pub workflow_id: String,
+    /// Task queue to start the workflow on
+    pub task_queue: String,
+    /// Input to the workflow
+    pub input: Vec<Payload>,
+    /// Optionally override the default workflow execution timeout
+    pub execution_timeout: Option<Duration>,
+    /// Optionally override the default workflow run timeout
+    pub run_timeout: Option<Duration>,
+    /// Optionally override the default workflow task timeout
+    pub task_timeout: Option<Duration>,
+    /// Optionally set a retry policy for the workflow
+    pub retry_policy: Option<RetryPolicy>,
+    /// Memo attached to each started workflow
+    pub memo: HashMap<String, Payload>,
+    /// Search attributes set on each started workflow
+    pub search_attributes: HashMap<String, Payload>,
+    /// Headers passed to each started workflow
+    pub header: Option<Header>,
+}
+
+/// Controls how a schedule handles overlap, catching up, and failures
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchedulePolicies {
+    /// What to do when an action would start while the previous one is still running
+    pub overlap: ScheduleOverlapPolicy,
+    /// How far back actions missed during an outage are still taken. Server default is one year.
+    pub catchup_window: Option<Duration>,
+    /// Pause the schedule if a workflow it started fails or times out
+    pub pause_on_failure: bool,
+    /// Start workflows with exactly the action's workflow id, without appending the time
+    pub keep_original_workflow_id: bool,
+}
+
+/// The mutable state of a schedule
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScheduleState {
+    /// Human-readable note about the state, ex: why it was paused
+    pub note: Option<String>,
+    /// Whether the schedule is paused
+    pub paused: bool,
+    /// If set, the schedule only takes this many more actions
+    pub remaining_actions: Option<u64>,
+}
+
+/// A full schedule definition, as given when creating or updating a schedule
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schedule {
+    /// What the schedule does
+    pub action: ScheduleAction,
+    /// When the schedule does it
+    pub spec: ScheduleSpec,
+    /// How the schedule behaves
+    pub policies: SchedulePolicies,
+    /// Initial or updated state of the schedule
+    pub state: ScheduleState,
+}
+
+impl Schedule {
+    /// A schedule taking `action` at the times matched by `spec`, with default policies and state
+    pub fn new(action: ScheduleAction, spec: ScheduleSpec) -> Self {
+        Self {
+            action,
+            spec,
+            policies: Default::default(),
+            state: Default::default(),
+        }
+    }
+}
+
+/// A request to take the actions a schedule would have taken over a past (or future) period
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduleBackfill {
+    /// Start of the period, exclusive
+    pub start: SystemTime,
+    /// End of the period, inclusive
+    pub end: SystemTime,
+    /// Overlap policy to use for the backfilled actions, instead of the schedule's own
+    pub overlap: ScheduleOverlapPolicy,
+}
+
+/// Options for creating a schedule other than the schedule itself
+#[derive(Debug, Clone, Default)]
+pub struct CreateScheduleOptions {
+    /// Take an action as soon as the schedule is created
+    pub trigger_immediately: bool,
+    /// Backfill these periods as soon as the schedule is created
+    pub backfill: Vec<ScheduleBackfill>,
+    /// Memo attached to the schedule itself
+    pub memo: HashMap<String, Payload>,
+    /// Search attributes set on the schedule itself
+    pub search_attributes: HashMap<String, Payload>,
+    /// Request id for idempotency/deduplication
+    pub request_id: Option<String>,
+}
+
+/// The result of describing a schedule. Passed to the closure given to [ScheduleHandle::update].
+#[derive(Debug, Clone)]
+pub struct ScheduleDescription {
+    /// The schedule's id
+    pub id: String,
+    /// The schedule's current definition
+    pub schedule: Schedule,
+    /// Information about the schedule's actions so far and upcoming
+    pub info: sched::ScheduleInfo,
+    /// The schedule's memo
+    pub memo: HashMap<String, Payload>,
+    /// The schedule's search attributes
+    pub search_attributes: HashMap<String, Payload>,
+    conflict_token: Vec<u8>,
+}
+
+/// A schedule as seen when listing schedules, which contains less than a full description
+#[derive(Debug, Clone)]
+pub struct ScheduleSummary {
+    /// The schedule's id
+    pub id: String,
+    /// When the schedule takes actions
+    pub spec: ScheduleSpec,
+    /// The type of workflow the schedule starts
+    pub workflow_type: Option<String>,
+    /// Note on the schedule's state
+    pub note: Option<String>,
+    /// Whether the schedule is paused
+    pub paused: bool,
+    /// The most recent actions taken, oldest first
+    pub recent_actions: Vec<sched::ScheduleActionResult>,
+    /// When the next few actions will be taken
+    pub next_action_times: Vec<SystemTime>,
+    /// The schedule's memo
+    pub memo: HashMap<String, Payload>,
+    /// The schedule's search attributes
+    pub search_attributes: HashMap<String, Payload>,
+}
+
+/// A handle to a schedule, used to inspect and manipulate it. Obtained from
+/// [crate::WfClientExt::create_schedule] or [crate::WfClientExt::get_schedule_handle].
+pub struct ScheduleHandle<ClientT> {
+    client: ClientT,
+    namespace: String,
+    schedule_id: String,
+}
+
+impl<CT> ScheduleHandle<CT>
+where
+    CT: RawClientLike<SvcType = InterceptedMetricsSvc> + NamespacedClient + Clone,
+{
+    pub(crate) fn new(client: CT, namespace: String, schedule_id: String) -> Self {
+        Self {
+            client,
+            namespace,
+            schedule_id,
+        }
+    }
+
+    /// The schedule's id
+    pub fn id(&self) -> &str {
+        &self.schedule_id
+    }
+
+    /// Fetch the schedule's current definition and information about its actions
+    pub async fn describe(&self) -> Result<ScheduleDescription, anyhow::Error> {
+        let resp = WorkflowService::describe_schedule(
+            &mut self.client.clone(),
+            DescribeScheduleRequest {
+                namespace: self.namespace.clone(),
+                schedule_id: self.schedule_id.clone(),
+            },
+        )
+        .await?
+        .into_inner();
+        Ok(ScheduleDescription {
+            id: self.schedule_id.clone(),
+            schedule: resp
+                .schedule
+                .ok_or_else(|| anyhow!("Server returned a description with no schedule"))?
+                .try_into()?,
+            info: resp.info.unwrap_or_default(),
+            memo: resp.memo.map(Into::into).unwrap_or_default(),
+            search_attributes: resp.search_attributes.map(Into::into).unwrap_or_default(),
+            conflict_token: resp.conflict_token,
+        })
+    }
+
+    /// Update the schedule's definition. `updater` is given the current description and returns
+    /// the new definition, or `None` to leave the schedule as is. The update is rejected if the
+    /// schedule was changed by someone else in the meantime.
+    pub async fn update(
+        &self,
+        updater: impl FnOnce(ScheduleDescription) -> Option<Schedule>,
+    ) -> Result<(), anyhow::Error> {
+        let desc = self.describe().await?;
+        let conflict_token = desc.conflict_token.clone();
+        let Some(schedule) = updater(desc) else {
+            return Ok(());
+        };
+        WorkflowService::update_schedule(
+            &mut self.client.clone(),
+            UpdateScheduleRequest {
+                namespace: self.namespace.clone(),
+                schedule_id: self.schedule_id.clone(),
+                schedule: Some(schedule.into()),
+                conflict_token,
+                identity: self.client.get_identity().to_owned(),
+                request_id: Uuid::new_v4().to_string(),
+                search_attributes: None,
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Pause the schedule, optionally explaining why
+    pub async fn pause(&self, note: Option<String>) -> Result<(), anyhow::Error> {
+        self.patch(sched::SchedulePatch {
+            // Empty means "don't pause", so there must always be a note
+            pause: note.unwrap_or_else(|| "Paused via Rust SDK".to_string()),
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Unpause the schedule, optionally explaining why
+    pub async fn unpause(&self, note: Option<String>) -> Result<(), anyhow::Error> {
+        self.patch(sched::SchedulePatch {
+            unpause: note.unwrap_or_else(|| "Unpaused via Rust SDK".to_string()),
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Take the schedule's action right now, using `overlap` instead of the schedule's own overlap
+    /// policy if specified
+    pub async fn trigger(&self, overlap: ScheduleOverlapPolicy) -> Result<(), anyhow::Error> {
+        self.patch(sched::SchedulePatch {
+            trigger_immediately: Some(sched::TriggerImmediatelyRequest {
+                overlap_policy: overlap as i32,
+            }),
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Take the actions the schedule would have taken during each of the given periods
+    pub async fn backfill(
+        &self,
+        backfills: impl IntoIterator<Item = ScheduleBackfill>,
+    ) -> Result<(), anyhow::Error> {
+        self.patch(sched::SchedulePatch {
+            backfill_request: backfills.into_iter().map(Into::into).collect(),
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Delete the schedule. Workflows it already started are unaffected.
+    pub async fn delete(&self) -> Result<(), anyhow::Error> {
+        WorkflowService::delete_schedule(
+            &mut self.client.clone(),
+            DeleteScheduleRequest {
+                namespace: self.namespace.clone(),
+                schedule_id: self.schedule_id.clone(),
+                identity: self.client.get_identity().to_owned(),
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn patch(&self, patch: sched::SchedulePatch) -> Result<(), anyhow::Error> {
+        WorkflowService::patch_schedule(
+            &mut self.client.clone(),
+            PatchScheduleRequest {
+                namespace: self.namespace.clone(),
+                schedule_id: self.schedule_id.clone(),
+                patch: Some(patch),
+                identity: self.client.get_identity().to_owned(),
+                request_id: Uuid::new_v4().to_string(),
+            },
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/// Builds the initial patch sent with a create request, if any
+pub(crate) fn initial_patch(options: &CreateScheduleOptions) -> Option<sched::SchedulePatch> {
+    if !options.trigger_immediately && options.backfill.is_empty() {
+        return None;
+    }
+    Some(sched::SchedulePatch {
+        trigger_immediately: options.trigger_immediately.then_some(
+            sched::TriggerImmediatelyRequest {
+                overlap_policy: ScheduleOverlapPolicy::Unspecified as i32,
+            },
+        ),
+        backfill_request: options.backfill.iter().cloned().map(Into::into).collect(),
+        ..Default::default()
+    })
+}
+
+pub(crate) fn memo(fields: HashMap<String, Payload>) -> Option<Memo> {
+    (!fields.is_empty()).then_some(Memo { fields })
+}
+
+impl From<Schedule> for sched::Schedule {
+    fn from(s: Schedule) -> Self {
+        Self {
+            spec: Some(s.spec.into()),
+            action: Some(s.action.into()),
+            policies: Some(sched::SchedulePolicies {
+                overlap_policy: s.policies.overlap as i32,
+                catchup_window: s.policies.catchup_window.and_then(|d| d.try_into().ok()),
+                pause_on_failure: s.policies.pause_on_failure,
+                keep_original_workflow_id: s.policies.keep_original_workflow_id,
+            }),
+            state: Some(sched::ScheduleState {
+                notes: s.state.note.unwrap_or_default(),
+                paused: s.state.paused,
+                limited_actions: s.state.remaining_actions.is_some(),
+                remaining_actions: s
+                    .state
+                    .remaining_actions
+                    .map(|n| n.try_into().unwrap_or(i64::MAX))
+                    .unwrap_or_default(),
+            }),
+        }
+    }
+}
+
+impl TryFrom<sched::Schedule> for Schedule {
+    type Error = anyhow::Error;
+
+    fn try_from(s: sched::Schedule) -> Result<Self, Self::Error> {
+        let policies = s.policies.unwrap_or_default();
+        let state = s.state.unwrap_or_default();
+        Ok(Self {
+            action: s
+                .action
+                .ok_or_else(|| anyhow!("Schedule has no action"))?
+                .try_into()?,
+            spec: s.spec.unwrap_or_default().into(),
+            policies: SchedulePolicies {
+                overlap: policies.overlap_policy(),
+                catchup_window: policies.catchup_window.and_then(|d| d.try_into().ok()),
+                pause_on_failure: policies.pause_on_failure,
+                keep_original_workflow_id: policies.keep_original_workflow_id,
+            },
+            state: ScheduleState {
+                note: non_empty(state.notes),
+                paused: state.paused,
+                remaining_actions: state
+                    .limited_actions
+                    .then(|| state.remaining_actions.try_into().unwrap_or_default()),
+            },
+        })
+    }
+}
+
+impl From<ScheduleSpec> for sched::ScheduleSpec {
+    fn from(s: ScheduleSpec) -> Self {
+        Self {
+            structured_calendar: s.calendars.into_iter().map(Into::into).collect(),
+            cron_string: s.cron_expressions,
+            interval: s
+                .intervals
+                .into_iter()
+                .map(|i| sched::IntervalSpec {
+                    interval: i.every.try_into().ok(),
+                    phase: i.offset.and_then(|d| d.try_into().ok()),
+                })
+                .collect(),
+            exclude_structured_calendar: s.skip.into_iter().map(Into::into).collect(),
+            start_time: s.start_at.map(Into::into),
+            end_time: s.end_at.map(Into::into),
+            jitter: s.jitter.and_then(|d| d.try_into().ok()),
+            timezone_name: s.time_zone_name.unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<sched::ScheduleSpec> for ScheduleSpec {
+    fn from(s: sched::ScheduleSpec) -> Self {
+        // Server compiles cron strings and unstructured calendars into structured calendars, so
+        // those are all that come back from a describe
+        Self {
+            intervals: s
+                .interval
+                .into_iter()
+                .filter_map(|i| {
+                    Some(ScheduleIntervalSpec {
+                        every: i.interval?.try_into().ok()?,
+                        offset: i.phase.and_then(|d| d.try_into().ok()),
+                    })
+                })
+                .collect(),
+            calendars: s.structured_calendar.into_iter().map(Into::into).collect(),
+            cron_expressions: s.cron_string,
+            skip: s
+                .exclude_structured_calendar
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            start_at: s.start_time.and_then(|t| t.try_into().ok()),
+            end_at: s.end_time.and_then(|t| t.try_into().ok()),
+            jitter: s.jitter.and_then(|d| d.try_into().ok()),
+            time_zone_name: non_empty(s.timezone_name),
+        }
+    }
+}
+
+impl From<ScheduleCalendarSpec> for sched::StructuredCalendarSpec {
+    fn from(c: ScheduleCalendarSpec) -> Self {
+        let ranges = |rs: Vec<ScheduleRange>| rs.into_iter().map(Into::into).collect();
+        Self {
+            second: ranges(c.second),
+            minute: ranges(c.minute),
+            hour: ranges(c.hour),
+            day_of_month: ranges(c.day_of_month),
+            month: ranges(c.month),
+            year: ranges(c.year),
+            day_of_week: ranges(c.day_of_week),
+            comment: c.comment.unwrap_or_default(),
+        }
+    }
+}
+
+impl From<sched::StructuredCalendarSpec> for ScheduleCalendarSpec {
+    fn from(c: sched::StructuredCalendarSpec) -> Self {
+        let ranges = |rs: Vec<sched::Range>| rs.into_iter().map(Into::into).collect();
+        Self {
+            second: ranges(c.second),
+            minute: ranges(c.minute),
+            hour: ranges(c.hour),
+            day_of_month: ranges(c.day_of_month),
+            month: ranges(c.month),
+            year: ranges(c.year),
+            day_of_week: ranges(c.day_of_week),
+            comment: non_empty(c.comment),
+        }
+    }
+}
+
+impl From<ScheduleRange> for sched::Range {
+    fn from(r: ScheduleRange) -> Self {
+        Self {
+            start: r.start,
+            end: r.end,
+            step: r.step,
+        }
+    }
+}
+
+impl From<sched::Range> for ScheduleRange {
+    fn from(r: sched::Range) -> Self {
+        // Normalize the same way the server interprets unset fields
+        Self {
+            start: r.start,
+            end: r.end.max(r.start),
+            step: r.step.max(1),
+        }
+    }
+}
+
+impl From<ScheduleAction> for sched::ScheduleAction {
+    fn from(a: ScheduleAction) -> Self {
+        let ScheduleAction::StartWorkflow(wf) = a;
+        Self {
+            action: Some(sched::schedule_action::Action::StartWorkflow(
+                NewWorkflowExecutionInfo {
+                    workflow_id: wf.workflow_id,
+                    workflow_type: Some(WorkflowType {
+                        name: wf.workflow_type,
+                    }),
+                    task_queue: Some(TaskQueue {
+                        name: wf.task_queue,
+                        kind: TaskQueueKind::Normal as i32,
+                        normal_name: "".to_string(),
+                    }),
+                    input: wf.input.into_payloads(),
+                    workflow_execution_timeout: wf
+                        .execution_timeout
+                        .and_then(|d| d.try_into().ok()),
+                    workflow_run_timeout: wf.run_timeout.and_then(|d| d.try_into().ok()),
+                    workflow_task_timeout: wf.task_timeout.and_then(|d| d.try_into().ok()),
+                    retry_policy: wf.retry_policy,
+                    memo: memo(wf.memo),
+                    search_attributes: (!wf.search_attributes.is_empty())
+                        .then(|| wf.search_attributes.into()),
+                    header: wf.header,
+                    ..Default::default()
+                },
+            )),
+        }
+    }
+}
+
+impl TryFrom<sched::ScheduleAction> for ScheduleAction {
+    type Error = anyhow::Error;
+
+    fn try_from(a: sched::ScheduleAction) -> Result<Self, Self::Error> {
+        match a.action {
+            Some(sched::schedule_action::Action::StartWorkflow(wf)) => {
+                Ok(Self::StartWorkflow(ScheduleStartWorkflowAction {
+                    workflow_type: wf.workflow_type.map(|t| t.name).unwrap_or_default(),
+                    workflow_id: wf.workflow_id,
+                    task_queue: wf.task_queue.map(|tq| tq.name).unwrap_or_default(),
+                    input: wf.input.map(|p| p.payloads).unwrap_or_default(),
+                    execution_timeout: wf
+                        .workflow_execution_timeout
+                        .and_then(|d| d.try_into().ok()),
+                    run_timeout: wf.workflow_run_timeout.and_then(|d| d.try_into().ok()),
+                    task_timeout: wf.workflow_task_timeout.and_then(|d| d.try_into().ok()),
+                    retry_policy: wf.retry_policy,
+                    memo: wf.memo.map(Into::into).unwrap_or_default(),
+                    search_attributes: wf.search_attributes.map(Into::into).unwrap_or_default(),
+                    header: wf.header,
+                }))
+            }
+            None => Err(anyhow!("Schedule action is of an unknown type")),
+        }
+    }
+}
+
+impl From<ScheduleBackfill> for sched::BackfillRequest {
+    fn from(b: ScheduleBackfill) -> Self {
+        Self {
+            start_time: Some(b.start.into()),
+            end_time: Some(b.end.into()),
+            overlap_policy: b.overlap as i32,
+        }
+    }
+}
+
+impl From<sched::ScheduleListEntry> for ScheduleSummary {
+    fn from(e: sched::ScheduleListEntry) -> Self {
+        let info = e.info.unwrap_or_default();
+        Self {
+            id: e.schedule_id,
+            spec: info.spec.unwrap_or_default().into(),
+            workflow_type: info.workflow_type.map(|t| t.name),
+            note: non_empty(info.notes),
+            paused: info.paused,
+            recent_actions: info.recent_actions,
+            next_action_times: info
+                .future_action_times
+                .into_iter()
+                .filter_map(|t| t.try_into().ok())
+                .collect(),
+            memo: e.memo.map(Into::into).unwrap_or_default(),
+            search_attributes: e.search_attributes.map(Into::into).unwrap_or_default(),
+        }
+    }
+}
+
+fn non_empty(s: String) -> Option<String> {
+    (!s.is_empty()).then_some(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schedule_round_trips_through_proto() {
+        let mut spec = ScheduleSpec::calendar(ScheduleCalendarSpec {
+            hour: vec![ScheduleRange::new(9, 17).with_step(2)],
+            day_of_week: vec![ScheduleRange::new(1, 5)],
+            comment: Some("business hours".to_string()),
+            ..Default::default()
+        });
+        spec.intervals.push(ScheduleIntervalSpec {
+            every: Duration::from_secs(3600),
+            offset: Some(Duration::from_secs(300)),
+        });
+        spec.start_at = Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000));
+        spec.time_zone_name = Some("America/Los_Angeles".to_string());
+        let mut schedule = Schedule::new(
+            ScheduleAction::start_workflow("wf_type", "wf_id", "tq", vec![]),
+            spec,
+        );
+        schedule.policies.overlap = ScheduleOverlapPolicy::BufferOne;
+        schedule.policies.catchup_window = Some(Duration::from_secs(60));
+        schedule.state.note = Some("hi".to_string());
+        schedule.state.remaining_actions = Some(3);
+
+        let proto: sched::Schedule = schedule.clone().into();
+        assert!(proto.state.as_ref().unwrap().limited_actions);
+        let back: Schedule = proto.try_into().unwrap();
+        assert_eq!(back, schedule);
+    }
+
+    #[test]
+    fn unset_range_fields_are_normalized() {
+        let r: ScheduleRange = sched::Range {
+            start: 5,
+            end: 0,
+            step: 0,
+        }
+        .into();
+        assert_eq!(r, ScheduleRange::single(5));
+    }
+
+    #[test]
+    fn schedule_without_action_is_rejected() {
+        assert!(Schedule::try_from(sched::Schedule::default()).is_err());
+    }
+}
@@ -0,0 +1,238 @@
+use futures_util::{Stream, StreamExt, TryStreamExt, stream};
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
+use temporal_sdk_core_protos::temporal::api::{
+    common::v1::{Payload, WorkflowExecution},
+    enums::v1::WorkflowExecutionStatus,
+    workflow,
+    workflowservice::v1::CountWorkflowExecutionsResponse,
+};
+use tonic::Status;
+
+/// Controls how listing streams fetch their results
+#[derive(Debug, Clone, Default)]
+pub struct ListOptions {
+    /// How many items to request from the server at a time. Uses the server's default if unset,
+    /// and never more than `limit`.
+    pub page_size: Option<i32>,
+    /// Stop after this many items in total. No more pages are fetched once it's reached.
+    pub limit: Option<usize>,
+}
+
+/// A workflow execution as returned by visibility listings, with the commonly needed fields
+/// pulled out of the raw proto
+#[derive(Debug, Clone)]
+pub struct WorkflowExecutionSummary {
+    /// The workflow's id
+    pub workflow_id: String,
+    /// The run's id
+    pub run_id: String,
+    /// The workflow's type
+    pub workflow_type: String,
+    /// The task queue the workflow runs on
+    pub task_queue: String,
+    /// The run's status at the time it was listed
+    pub status: WorkflowExecutionStatus,
+    /// When the run started
+    pub start_time: Option<SystemTime>,
+    /// When the run began executing, which is later than `start_time` for delayed or cron runs
+    pub execution_time: Option<SystemTime>,
+    /// When the run closed, if it has
+    pub close_time: Option<SystemTime>,
+    /// How long the run took, if it has closed
+    pub execution_duration: Option<Duration>,
+    /// Number of events in the run's history
+    pub history_length: u64,
+    /// The parent workflow, if this is a child workflow
+    pub parent_execution: Option<WorkflowExecution>,
+    /// The workflow's memo
+    pub memo: HashMap<String, Payload>,
+    /// The workflow's search attributes
+    pub search_attributes: HashMap<String, Payload>,
+    /// The full info returned by the server
+    pub raw_info: workflow::v1::WorkflowExecutionInfo,
+}
+
+impl From<workflow::v1::WorkflowExecutionInfo> for WorkflowExecutionSummary {
+    fn from(info: workflow::v1::WorkflowExecutionInfo) -> Self {
+        let raw_info = info.clone();
+        let status = info.status();
+        let execution = info.execution.unwrap_or_default();
+        Self {
+            workflow_id: execution.workflow_id,
+            run_id: execution.run_id,
+            workflow_type: info.r#type.map(|t| t.name).unwrap_or_default(),
+            task_queue: info.task_queue,
+            status,
+            start_time: info.start_time.and_then(|t| t.try_into().ok()),
+            execution_time: info.execution_time.and_then(|t| t.try_into().ok()),
+            close_time: info.close_time.and_then(|t| t.try_into().ok()),
+            execution_duration: info.execution_duration.and_then(|d| d.try_into().ok()),
+            history_length: info.history_length.try_into().unwrap_or_default(),
+            parent_execution: info.parent_execution,
+            memo: info.memo.map(Into::into).unwrap_or_default(),
+            search_attributes: info.search_attributes.map(Into::into).unwrap_or_default(),
+            raw_info,
+        }
+    }
+}
+
+/// The result of counting workflow executions
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkflowExecutionCount {
+    /// The number of matching executions. Approximate unless the query groups by some field, in
+    /// which case it's the sum of the groups' counts.
+    pub count: u64,
+    /// Counts for each group, if the query had a `GROUP BY` clause
+    pub groups: Vec<WorkflowExecutionCountGroup>,
+}
+
+/// The count of executions sharing some values of the fields a count query was grouped by
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkflowExecutionCountGroup {
+    /// The values of the grouped-by fields, ex: `"Running"` when grouping by `ExecutionStatus`
+    pub values: Vec<Payload>,
+    /// The approximate number of executions in the group
+    pub count: u64,
+}
+
+impl From<CountWorkflowExecutionsResponse> for WorkflowExecutionCount {
+    fn from(resp: CountWorkflowExecutionsResponse) -> Self {
+        Self {
+            count: resp.count.try_into().unwrap_or_default(),
+            groups: resp
+                .groups
+                .into_iter()
+                .map(|g| WorkflowExecutionCountGroup {
+                    values: g.group_values,
+                    count: g.count.try_into().unwrap_or_default(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Turns a function fetching one page at a time into a stream of every item across all pages.
+/// `fetch` is called with the page size and the token of the page to fetch (empty for the first
+/// page), and returns the page's items along with the next page's token (empty after the last).
+pub(crate) fn paginate<T, F, Fut>(
+    options: ListOptions,
+    fetch: F,
+) -> impl Stream<Item = Result<T, Status>> + Send + 'static
+where
+    T: Send + 'static,
+    F: Fn(i32, Vec<u8>) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<(Vec<T>, Vec<u8>), Status>> + Send + 'static,
+{
+    // No point fetching more per page than the limit allows
+    let limit_page_size = options
+        .limit
+        .map(|limit| i32::try_from(limit).unwrap_or(i32::MAX));
+    let page_size = match (options.page_size, limit_page_size) {
+        (Some(size), Some(limit)) => size.min(limit),
+        (size, limit) => size.or(limit).unwrap_or_default(),
+    };
+    // Token is `None` once the last page has been fetched
+    stream::try_unfold(Some(vec![]), move |next_page_token| {
+        let page = next_page_token.map(|token| fetch(page_size, token));
+        async move {
+            let Some(page) = page else {
+                return Ok(None);
+            };
+            let (items, next) = page.await?;
+            let next = (!next.is_empty()).then_some(next);
+            Ok::<_, Status>(Some((stream::iter(items.into_iter().map(Ok)), next)))
+        }
+    })
+    .try_flatten()
+    .take(options.limit.unwrap_or(usize::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    fn numbered_pages(
+        fetches: Arc<AtomicUsize>,
+    ) -> impl Fn(i32, Vec<u8>) -> futures_util::future::Ready<Result<(Vec<u8>, Vec<u8>), Status>>
+    {
+        // Three pages of three items, where the token is the number of the page to fetch
+        move |page_size, token| {
+            assert_eq!(page_size, 3);
+            fetches.fetch_add(1, Ordering::SeqCst);
+            let page = token.first().copied().unwrap_or_default();
+            let items = (page * 3..page * 3 + 3).collect();
+            let next = if page < 2 { vec![page + 1] } else { vec![] };
+            futures_util::future::ready(Ok((items, next)))
+        }
+    }
+
+    #[tokio::test]
+    async fn paginates_through_all_pages() {
+        let fetches = Arc::new(AtomicUsize::new(0));
+        let opts = ListOptions {
+            page_size: Some(3),
+            limit: None,
+        };
+        let all: Vec<u8> = paginate(opts, numbered_pages(fetches.clone()))
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(all, (0..9).collect::<Vec<_>>());
+        assert_eq!(fetches.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn limit_stops_fetching_pages() {
+        let fetches = Arc::new(AtomicUsize::new(0));
+        let opts = ListOptions {
+            page_size: Some(3),
+            limit: Some(4),
+        };
+        let all: Vec<u8> = paginate(opts, numbered_pages(fetches.clone()))
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(all, vec![0, 1, 2, 3]);
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+    }
+
+    #[rstest::rstest]
+    #[case::limit_below_page_size(Some(50), 2, 2)]
+    #[case::limit_above_page_size(Some(5), 20, 5)]
+    #[case::server_default_page_size(None, 2, 2)]
+    #[tokio::test]
+    async fn pages_never_exceed_limit(
+        #[case] page_size: Option<i32>,
+        #[case] limit: usize,
+        #[case] expected_page_size: i32,
+    ) {
+        let opts = ListOptions {
+            page_size,
+            limit: Some(limit),
+        };
+        let all: Vec<u8> = paginate(opts, move |requested, _| {
+            assert_eq!(requested, expected_page_size);
+            futures_util::future::ready(Ok((vec![0; requested as usize], vec![])))
+        })
+        .try_collect()
+        .await
+        .unwrap();
+        assert_eq!(all.len(), limit.min(expected_page_size as usize));
+    }
+
+    #[tokio::test]
+    async fn errors_are_passed_through() {
+        let stream = paginate(ListOptions::default(), |_, _| async {
+            Err::<(Vec<u8>, Vec<u8>), _>(Status::unavailable("nope"))
+        });
+        let res: Result<Vec<u8>, _> = stream.try_collect().await;
+        assert_eq!(res.unwrap_err().code(), tonic::Code::Unavailable);
+    }
+}
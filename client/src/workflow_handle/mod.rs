@@ -1,16 +1,29 @@
-use crate::{InterceptedMetricsSvc, RawClientLike, WorkflowService};
+use crate::{InterceptedMetricsSvc, NamespacedClient, RawClientLike, WorkflowService};
 use anyhow::{anyhow, bail};
+use serde::de::DeserializeOwned;
 use std::{fmt::Debug, marker::PhantomData};
 use temporal_sdk_core_protos::{
-    coresdk::FromPayloadsExt,
+    coresdk::{FromJsonPayloadExt, FromPayloadsExt, IntoPayloadsExt},
     temporal::api::{
         common::v1::{Payload, WorkflowExecution},
-        enums::v1::HistoryEventFilterType,
+        enums::v1::{HistoryEventFilterType, UpdateWorkflowExecutionLifecycleStage},
         failure::v1::Failure,
-        history::v1::history_event::Attributes,
-        workflowservice::v1::GetWorkflowExecutionHistoryRequest,
+        history::v1::{History, history_event::Attributes},
+        query::v1::WorkflowQuery,
+        update::{self, v1::WaitPolicy},
+        workflowservice::v1::{
+            DescribeWorkflowExecutionRequest, DescribeWorkflowExecutionResponse,
+            GetWorkflowExecutionHistoryRequest, QueryWorkflowRequest,
+            RequestCancelWorkflowExecutionRequest, SignalWorkflowExecutionRequest,
+            TerminateWorkflowExecutionRequest, UpdateWorkflowExecutionRequest,
+        },
     },
 };
+use uuid::Uuid;
+
+mod update_handle;
+
+pub use update_handle::{UpdateHandle, UpdateResult};
 
 /// Enumerates terminal states for a particular workflow execution
 // TODO: Add non-proto failure types, flesh out details, etc.
@@ -44,6 +57,22 @@ where
     }
 }
 
+impl<T> WorkflowExecutionResult<T> {
+    fn try_map_success<U>(
+        self,
+        map: impl FnOnce(T) -> Result<U, anyhow::Error>,
+    ) -> Result<WorkflowExecutionResult<U>, anyhow::Error> {
+        Ok(match self {
+            Self::Succeeded(t) => WorkflowExecutionResult::Succeeded(map(t)?),
+            Self::Failed(f) => WorkflowExecutionResult::Failed(f),
+            Self::Cancelled(d) => WorkflowExecutionResult::Cancelled(d),
+            Self::Terminated(d) => WorkflowExecutionResult::Terminated(d),
+            Self::TimedOut => WorkflowExecutionResult::TimedOut,
+            Self::ContinuedAsNew => WorkflowExecutionResult::ContinuedAsNew,
+        })
+    }
+}
+
 /// Options for fetching workflow results
 #[derive(Debug, Clone, Copy)]
 pub struct GetWorkflowResultOpts {
@@ -59,9 +88,20 @@ impl Default for GetWorkflowResultOpts {
 
 /// A workflow handle which can refer to a specific workflow run, or a chain of workflow runs with
 /// the same workflow id.
+///
+/// Handles without a run id don't all target the same run. The server can't scope signals,
+/// queries, describing, or fetching history to a chain of runs, so those always target the latest
+/// run with the workflow id, even if it's an unrelated later execution which reused the id. When
+/// the handle has a [first execution run id](Self::first_execution_run_id), cancelling,
+/// terminating, and updating are scoped to that chain, and fail rather than reach an unrelated
+/// execution. Results are likewise read starting from the chain's first run.
 pub struct WorkflowHandle<ClientT, ResultT> {
     client: ClientT,
     info: WorkflowExecutionInfo,
+    /// If set while the info's `run_id` is not, the handle targets the latest run of the chain of
+    /// runs started by this run. Keeps it following the workflow across continue-as-new, without
+    /// affecting some unrelated later execution which reused the workflow id.
+    first_execution_run_id: Option<String>,
 
     _res_type: PhantomData<ResultT>,
 }
@@ -85,6 +125,33 @@ impl WorkflowExecutionInfo {
     {
         UntypedWorkflowHandle::new(client, self)
     }
+
+    /// Bind the workflow info to a specific client, turning it into a handle whose result is
+    /// deserialized as `RT`
+    pub fn bind<CT, RT>(self, client: CT) -> WorkflowHandle<CT, RT>
+    where
+        CT: RawClientLike<SvcType = InterceptedMetricsSvc> + Clone,
+    {
+        WorkflowHandle::new(client, self)
+    }
+
+    fn execution(&self) -> WorkflowExecution {
+        WorkflowExecution {
+            workflow_id: self.workflow_id.clone(),
+            run_id: self.run_id.clone().unwrap_or_default(),
+        }
+    }
+}
+
+/// Options for starting an update with [WorkflowHandle::start_update]
+#[derive(Debug, Clone, Default)]
+pub struct StartUpdateOpts {
+    /// Identifies the update, which makes retrying starting it idempotent. A random id is used if
+    /// unset.
+    pub update_id: Option<String>,
+    /// How far along the update must get before `start_update` returns. Defaults to accepted.
+    /// Waiting for it to be admitted isn't supported by server.
+    pub wait_for_stage: Option<UpdateWorkflowExecutionLifecycleStage>,
 }
 
 /// A workflow handle to a workflow with unknown types. Uses raw payloads.
@@ -93,34 +160,92 @@ pub(crate) type UntypedWorkflowHandle<CT> = WorkflowHandle<CT, Vec<Payload>>;
 impl<CT, RT> WorkflowHandle<CT, RT>
 where
     CT: RawClientLike<SvcType = InterceptedMetricsSvc> + Clone,
-    // TODO: Make more generic, capable of (de)serialization w/ serde
-    RT: FromPayloadsExt,
 {
     pub(crate) fn new(client: CT, info: WorkflowExecutionInfo) -> Self {
         Self {
             client,
             info,
+            first_execution_run_id: None,
             _res_type: PhantomData::<RT>,
         }
     }
 
+    /// Create a handle for the chain of runs started by `first_execution_run_id`, which targets
+    /// whichever run of that chain is the latest
+    pub(crate) fn new_started(
+        client: CT,
+        namespace: String,
+        workflow_id: String,
+        first_execution_run_id: String,
+    ) -> Self {
+        let mut handle = Self::new(
+            client,
+            WorkflowExecutionInfo {
+                namespace,
+                workflow_id,
+                run_id: None,
+            },
+        );
+        handle.first_execution_run_id = Some(first_execution_run_id).filter(|rid| !rid.is_empty());
+        handle
+    }
+
     /// Get the workflow execution info
     pub fn info(&self) -> &WorkflowExecutionInfo {
         &self.info
     }
 
+    /// The run which started the chain of runs this handle follows, if it was created from a
+    /// workflow start
+    pub fn first_execution_run_id(&self) -> Option<&str> {
+        self.first_execution_run_id.as_deref()
+    }
+
     /// Get the client attached to this handle
     pub fn client(&self) -> &CT {
         &self.client
     }
 
-    /// Await the result of the workflow execution
+    /// Await the result of the workflow execution, deserializing a successful result as `RT`
+    pub async fn get_result(
+        &self,
+        opts: GetWorkflowResultOpts,
+    ) -> Result<WorkflowExecutionResult<RT>, anyhow::Error>
+    where
+        RT: DeserializeOwned,
+    {
+        self.get_result_payloads(opts)
+            .await?
+            .try_map_success(deserialize_payloads)
+    }
+
+    /// Await the result of the workflow execution, leaving a successful result as payloads. See
+    /// [WorkflowHandle::get_result] to deserialize it.
     pub async fn get_workflow_result(
         &self,
         opts: GetWorkflowResultOpts,
-    ) -> Result<WorkflowExecutionResult<RT>, anyhow::Error> {
+    ) -> Result<WorkflowExecutionResult<RT>, anyhow::Error>
+    where
+        RT: FromPayloadsExt,
+    {
+        self.get_result_payloads(opts)
+            .await?
+            .try_map_success(|payloads| Ok(RT::from_payloads(payloads.into_payloads())))
+    }
+
+    async fn get_result_payloads(
+        &self,
+        opts: GetWorkflowResultOpts,
+    ) -> Result<WorkflowExecutionResult<Vec<Payload>>, anyhow::Error> {
         let mut next_page_tok = vec![];
-        let mut run_id = self.info.run_id.clone().unwrap_or_default();
+        // A handle following a chain of runs reads results starting from the chain's first run,
+        // so a later unrelated execution reusing the workflow id isn't mistaken for it
+        let mut run_id = self
+            .info
+            .run_id
+            .clone()
+            .or_else(|| self.first_execution_run_id.clone())
+            .unwrap_or_default();
         loop {
             let server_res = self
                 .client
@@ -165,7 +290,7 @@ where
             break match event_attrs {
                 Some(Attributes::WorkflowExecutionCompletedEventAttributes(attrs)) => {
                     follow!(attrs);
-                    Ok(WorkflowExecutionResult::Succeeded(RT::from_payloads(
+                    Ok(WorkflowExecutionResult::Succeeded(Vec::from_payloads(
                         attrs.result,
                     )))
                 }
@@ -207,3 +332,276 @@ where
         }
     }
 }
+
+impl<CT, RT> WorkflowHandle<CT, RT>
+where
+    CT: RawClientLike<SvcType = InterceptedMetricsSvc> + NamespacedClient + Clone,
+{
+    /// Send a signal to the workflow. Without a run id, this is the latest run with the workflow
+    /// id, regardless of the handle's first execution run id.
+    pub async fn signal(
+        &self,
+        signal_name: impl Into<String>,
+        input: Vec<Payload>,
+    ) -> Result<(), anyhow::Error> {
+        WorkflowService::signal_workflow_execution(
+            &mut self.client.clone(),
+            SignalWorkflowExecutionRequest {
+                namespace: self.info.namespace.clone(),
+                workflow_execution: Some(self.info.execution()),
+                signal_name: signal_name.into(),
+                input: input.into_payloads(),
+                identity: self.client.get_identity().to_owned(),
+                request_id: Uuid::new_v4().to_string(),
+                ..Default::default()
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Query the workflow, deserializing the query's result as `R`. Without a run id, this is the
+    /// latest run with the workflow id, regardless of the handle's first execution run id.
+    pub async fn query<R>(
+        &self,
+        query_type: impl Into<String>,
+        args: Vec<Payload>,
+    ) -> Result<R, anyhow::Error>
+    where
+        R: DeserializeOwned,
+    {
+        let resp = WorkflowService::query_workflow(
+            &mut self.client.clone(),
+            QueryWorkflowRequest {
+                namespace: self.info.namespace.clone(),
+                execution: Some(self.info.execution()),
+                query: Some(WorkflowQuery {
+                    query_type: query_type.into(),
+                    query_args: args.into_payloads(),
+                    header: None,
+                }),
+                ..Default::default()
+            },
+        )
+        .await?
+        .into_inner();
+        if let Some(rejected) = resp.query_rejected {
+            bail!(
+                "Query was rejected, workflow status: {:?}",
+                rejected.status()
+            );
+        }
+        deserialize_payloads(Vec::from_payloads(resp.query_result))
+    }
+
+    /// Send an update to the workflow, returning once it has reached the stage requested in
+    /// `opts` with a handle which can be used to wait for its outcome. Scoped to the handle's chain
+    /// of runs if it has a first execution run id.
+    pub async fn start_update(
+        &self,
+        update_name: impl Into<String>,
+        args: Vec<Payload>,
+        opts: StartUpdateOpts,
+    ) -> Result<UpdateHandle<CT>, anyhow::Error> {
+        let stage = opts
+            .wait_for_stage
+            .unwrap_or(UpdateWorkflowExecutionLifecycleStage::Accepted);
+        if stage == UpdateWorkflowExecutionLifecycleStage::Unspecified
+            || stage == UpdateWorkflowExecutionLifecycleStage::Admitted
+        {
+            bail!("Updates can only be waited on until they're accepted or completed");
+        }
+        let update_id = opts.update_id.unwrap_or_else(|| Uuid::new_v4().to_string());
+        let request = UpdateWorkflowExecutionRequest {
+            namespace: self.info.namespace.clone(),
+            workflow_execution: Some(self.info.execution()),
+            first_execution_run_id: self.first_execution_run_id.clone().unwrap_or_default(),
+            wait_policy: Some(WaitPolicy {
+                lifecycle_stage: stage as i32,
+            }),
+            request: Some(update::v1::Request {
+                meta: Some(update::v1::Meta {
+                    update_id: update_id.clone(),
+                    identity: self.client.get_identity().to_owned(),
+                }),
+                input: Some(update::v1::Input {
+                    header: None,
+                    name: update_name.into(),
+                    args: args.into_payloads(),
+                }),
+            }),
+        };
+        // Server gives up waiting after some time, reporting an unspecified stage. The request
+        // carries the same update id every time, so it's safe to re-send until the stage is
+        // reached.
+        let resp = loop {
+            let resp = WorkflowService::update_workflow_execution(
+                &mut self.client.clone(),
+                request.clone(),
+            )
+            .await?
+            .into_inner();
+            if resp.stage() >= stage || resp.outcome.is_some() {
+                break resp;
+            }
+        };
+        // Pin the handle to the run the update actually landed on
+        let run_id = resp
+            .update_ref
+            .and_then(|r| r.workflow_execution)
+            .map(|we| we.run_id)
+            .filter(|rid| !rid.is_empty())
+            .or_else(|| self.info.run_id.clone());
+        Ok(UpdateHandle::new(
+            self.client.clone(),
+            self.info.namespace.clone(),
+            self.info.workflow_id.clone(),
+            run_id,
+            update_id,
+            resp.outcome,
+        ))
+    }
+
+    /// Send an update to the workflow and wait for it to complete, deserializing its result as
+    /// `R`. An update which fails is returned as an error.
+    pub async fn execute_update<R>(
+        &self,
+        update_name: impl Into<String>,
+        args: Vec<Payload>,
+    ) -> Result<R, anyhow::Error>
+    where
+        R: DeserializeOwned,
+    {
+        let handle = self
+            .start_update(update_name, args, StartUpdateOpts::default())
+            .await?;
+        match handle.get_result().await? {
+            UpdateResult::Succeeded(r) => Ok(r),
+            UpdateResult::Failed(failure) => bail!("Update failed: {}", failure.message),
+        }
+    }
+
+    /// Get a handle to an update previously sent to this workflow, for waiting on its outcome
+    pub fn get_update_handle(&self, update_id: impl Into<String>) -> UpdateHandle<CT> {
+        UpdateHandle::new(
+            self.client.clone(),
+            self.info.namespace.clone(),
+            self.info.workflow_id.clone(),
+            self.info.run_id.clone(),
+            update_id.into(),
+            None,
+        )
+    }
+
+    /// Request cancellation of the workflow. Scoped to the handle's chain of runs if it has a
+    /// first execution run id.
+    pub async fn cancel(&self, reason: impl Into<String>) -> Result<(), anyhow::Error> {
+        WorkflowService::request_cancel_workflow_execution(
+            &mut self.client.clone(),
+            RequestCancelWorkflowExecutionRequest {
+                namespace: self.info.namespace.clone(),
+                workflow_execution: Some(self.info.execution()),
+                identity: self.client.get_identity().to_owned(),
+                request_id: Uuid::new_v4().to_string(),
+                first_execution_run_id: self.first_execution_run_id.clone().unwrap_or_default(),
+                reason: reason.into(),
+                links: vec![],
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Terminate the workflow, which unlike cancelling it gives the workflow no chance to clean up.
+    /// Scoped to the handle's chain of runs if it has a first execution run id.
+    pub async fn terminate(
+        &self,
+        reason: impl Into<String>,
+        details: Vec<Payload>,
+    ) -> Result<(), anyhow::Error> {
+        WorkflowService::terminate_workflow_execution(
+            &mut self.client.clone(),
+            TerminateWorkflowExecutionRequest {
+                namespace: self.info.namespace.clone(),
+                workflow_execution: Some(self.info.execution()),
+                reason: reason.into(),
+                details: details.into_payloads(),
+                identity: self.client.get_identity().to_owned(),
+                first_execution_run_id: self.first_execution_run_id.clone().unwrap_or_default(),
+                links: vec![],
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Describe the workflow run this handle targets. Without a run id, this is the latest run with
+    /// the workflow id, regardless of the handle's first execution run id.
+    pub async fn describe(&self) -> Result<DescribeWorkflowExecutionResponse, anyhow::Error> {
+        Ok(WorkflowService::describe_workflow_execution(
+            &mut self.client.clone(),
+            DescribeWorkflowExecutionRequest {
+                namespace: self.info.namespace.clone(),
+                execution: Some(self.info.execution()),
+            },
+        )
+        .await?
+        .into_inner())
+    }
+
+    /// Fetch the full history of the workflow run this handle targets, following every page.
+    /// Without a run id, this is the latest run with the workflow id, regardless of the handle's
+    /// first execution run id.
+    pub async fn fetch_history(&self) -> Result<History, anyhow::Error> {
+        let mut history = History::default();
+        let mut next_page_token = vec![];
+        loop {
+            let resp = WorkflowService::get_workflow_execution_history(
+                &mut self.client.clone(),
+                GetWorkflowExecutionHistoryRequest {
+                    namespace: self.info.namespace.clone(),
+                    execution: Some(self.info.execution()),
+                    next_page_token,
+                    ..Default::default()
+                },
+            )
+            .await?
+            .into_inner();
+            history
+                .events
+                .extend(resp.history.into_iter().flat_map(|h| h.events));
+            if resp.next_page_token.is_empty() {
+                return Ok(history);
+            }
+            next_page_token = resp.next_page_token;
+        }
+    }
+}
+
+/// Deserializes the single JSON payload a workflow, query, or update returned. Returning nothing
+/// is treated as returning `null`, so that `()` and `Option`s can be used for results.
+fn deserialize_payloads<T: DeserializeOwned>(payloads: Vec<Payload>) -> Result<T, anyhow::Error> {
+    match payloads.as_slice() {
+        [] => Ok(serde_json::from_str("null")?),
+        [p] => Ok(T::from_json_payload(p)?),
+        _ => bail!("Expected a single payload, got {}", payloads.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temporal_sdk_core_protos::coresdk::AsJsonPayloadExt;
+
+    #[test]
+    fn deserializes_payloads() {
+        let p = vec!["hi".as_json_payload().unwrap()];
+        assert_eq!(deserialize_payloads::<String>(p).unwrap(), "hi");
+        deserialize_payloads::<()>(vec![]).unwrap();
+        assert_eq!(deserialize_payloads::<Option<u8>>(vec![]).unwrap(), None);
+        let two = vec![1.as_json_payload().unwrap(), 2.as_json_payload().unwrap()];
+        assert!(deserialize_payloads::<u8>(two).is_err());
+        let not_json = vec![Payload::from(b"bytes".as_slice())];
+        assert!(deserialize_payloads::<String>(not_json).is_err());
+    }
+}
@@ -0,0 +1,181 @@
+use super::deserialize_payloads;
+use crate::{InterceptedMetricsSvc, NamespacedClient, RawClientLike, WorkflowService};
+use anyhow::anyhow;
+use serde::de::DeserializeOwned;
+use std::fmt::Debug;
+use temporal_sdk_core_protos::{
+    coresdk::FromPayloadsExt,
+    temporal::api::{
+        common::v1::{Payload, WorkflowExecution},
+        enums::v1::UpdateWorkflowExecutionLifecycleStage,
+        failure::v1::Failure,
+        update::v1::{Outcome, UpdateRef, WaitPolicy, outcome},
+        workflowservice::v1::PollWorkflowExecutionUpdateRequest,
+    },
+};
+use tonic::Code;
+
+/// The outcome of a completed workflow update
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum UpdateResult<T> {
+    /// The update's handler returned successfully
+    Succeeded(T),
+    /// The update's handler failed, or the update was rejected by its validator
+    Failed(Failure),
+}
+
+impl<T> UpdateResult<T>
+where
+    T: Debug,
+{
+    /// Unwrap the result, panicking if it was not a success
+    pub fn unwrap_success(self) -> T {
+        match self {
+            Self::Succeeded(t) => t,
+            o => panic!("Expected success, got {:?}", o),
+        }
+    }
+}
+
+/// A handle to a workflow update which has been sent, used to wait for its outcome. Can be
+/// obtained when starting an update, or later on from just the workflow and update ids.
+pub struct UpdateHandle<ClientT> {
+    client: ClientT,
+    namespace: String,
+    workflow_id: String,
+    run_id: Option<String>,
+    update_id: String,
+    /// Set if the outcome was already known when the handle was created
+    known_outcome: Option<Outcome>,
+}
+
+impl<CT> UpdateHandle<CT>
+where
+    CT: RawClientLike<SvcType = InterceptedMetricsSvc> + NamespacedClient + Clone,
+{
+    pub(crate) fn new(
+        client: CT,
+        namespace: String,
+        workflow_id: String,
+        run_id: Option<String>,
+        update_id: String,
+        known_outcome: Option<Outcome>,
+    ) -> Self {
+        Self {
+            client,
+            namespace,
+            workflow_id,
+            run_id,
+            update_id,
+            known_outcome,
+        }
+    }
+
+    /// The update's id
+    pub fn id(&self) -> &str {
+        &self.update_id
+    }
+
+    /// The id of the workflow the update was sent to
+    pub fn workflow_id(&self) -> &str {
+        &self.workflow_id
+    }
+
+    /// The run the update was sent to, if known
+    pub fn run_id(&self) -> Option<&str> {
+        self.run_id.as_deref()
+    }
+
+    /// Wait for the update to complete, deserializing a successful result as `R`
+    pub async fn get_result<R>(&self) -> Result<UpdateResult<R>, anyhow::Error>
+    where
+        R: DeserializeOwned,
+    {
+        match self.get_outcome().await? {
+            UpdateResult::Succeeded(payloads) => {
+                Ok(UpdateResult::Succeeded(deserialize_payloads(payloads)?))
+            }
+            UpdateResult::Failed(f) => Ok(UpdateResult::Failed(f)),
+        }
+    }
+
+    /// Wait for the update to complete, leaving a successful result as payloads
+    pub async fn get_outcome(&self) -> Result<UpdateResult<Vec<Payload>>, anyhow::Error> {
+        if let Some(outcome) = self.known_outcome.clone() {
+            return outcome_to_result(outcome);
+        }
+        let request = PollWorkflowExecutionUpdateRequest {
+            namespace: self.namespace.clone(),
+            update_ref: Some(UpdateRef {
+                workflow_execution: Some(WorkflowExecution {
+                    workflow_id: self.workflow_id.clone(),
+                    run_id: self.run_id.clone().unwrap_or_default(),
+                }),
+                update_id: self.update_id.clone(),
+            }),
+            identity: self.client.get_identity().to_owned(),
+            wait_policy: Some(WaitPolicy {
+                lifecycle_stage: UpdateWorkflowExecutionLifecycleStage::Completed as i32,
+            }),
+        };
+        // Server responds without an outcome once it's waited as long as it's willing to, and the
+        // request itself may time out first. Either way, there's nothing to do but poll again.
+        loop {
+            match WorkflowService::poll_workflow_execution_update(
+                &mut self.client.clone(),
+                request.clone(),
+            )
+            .await
+            {
+                Ok(resp) => {
+                    if let Some(outcome) = resp.into_inner().outcome {
+                        return outcome_to_result(outcome);
+                    }
+                }
+                Err(e) if e.code() == Code::DeadlineExceeded => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+fn outcome_to_result(outcome: Outcome) -> Result<UpdateResult<Vec<Payload>>, anyhow::Error> {
+    match outcome.value {
+        Some(outcome::Value::Success(payloads)) => {
+            Ok(UpdateResult::Succeeded(Vec::from_payloads(Some(payloads))))
+        }
+        Some(outcome::Value::Failure(failure)) => Ok(UpdateResult::Failed(failure)),
+        None => Err(anyhow!("Server returned an update outcome with no value")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temporal_sdk_core_protos::{
+        coresdk::AsJsonPayloadExt, temporal::api::common::v1::Payloads,
+    };
+
+    #[test]
+    fn converts_outcomes() {
+        let success = Outcome {
+            value: Some(outcome::Value::Success(Payloads {
+                payloads: vec!["hi".as_json_payload().unwrap()],
+            })),
+        };
+        let payloads = outcome_to_result(success).unwrap().unwrap_success();
+        assert_eq!(deserialize_payloads::<String>(payloads).unwrap(), "hi");
+
+        let failure = Outcome {
+            value: Some(outcome::Value::Failure(Failure {
+                message: "oh no".to_string(),
+                ..Default::default()
+            })),
+        };
+        let res = outcome_to_result(failure).unwrap();
+        assert!(matches!(res, UpdateResult::Failed(f) if f.message == "oh no"));
+
+        assert!(outcome_to_result(Outcome { value: None }).is_err());
+    }
+}
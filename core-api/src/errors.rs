@@ -13,6 +13,17 @@ pub enum WorkerValidationError {
     },
 }
 
+/// Errors thrown by [crate::Worker::update_config]
+#[derive(thiserror::Error, Debug)]
+pub enum WorkerConfigUpdateError {
+    /// Applying the update would result in an invalid configuration. Nothing was changed.
+    #[error("Invalid worker config update: {0}")]
+    Invalid(String),
+    /// The worker can't change its config while running. Nothing was changed.
+    #[error("This worker does not support config updates")]
+    Unsupported,
+}
+
 /// Errors thrown by [crate::Worker] polling methods
 #[derive(thiserror::Error, Debug)]
 pub enum PollError {
@@ -5,9 +5,9 @@ pub mod worker;
 use crate::{
     errors::{
         CompleteActivityError, CompleteNexusError, CompleteWfError, PollError,
-        WorkerValidationError,
+        WorkerConfigUpdateError, WorkerValidationError,
     },
-    worker::WorkerConfig,
+    worker::{PollerKind, WorkerConfig, WorkerConfigUpdate},
 };
 use temporal_sdk_core_protos::coresdk::{
     ActivityHeartbeat, ActivityTaskCompletion,
@@ -111,6 +111,35 @@ pub trait Worker: Send + Sync {
     /// Return this worker's config
     fn get_config(&self) -> &WorkerConfig;
 
+    /// Stop polling the server for the provided kinds of tasks, ex: while a dependency is down or
+    /// while draining a deployment. In-flight long polls are left to finish, since the server may
+    /// already have dispatched a task to them. Tasks they receive, and any already received, are
+    /// still delivered by the polling functions and must be completed as usual. Cached workflow
+    /// runs and the sticky queue are left intact. Does nothing for kinds which are already paused.
+    ///
+    /// The default implementation does nothing, for workers which can't pause polling.
+    fn pause_polling(&self, _kinds: &[PollerKind]) {}
+
+    /// Start polling again for the provided kinds of tasks after [Worker::pause_polling]. Does
+    /// nothing for kinds which aren't paused.
+    ///
+    /// The default implementation does nothing, for workers which can't pause polling.
+    fn resume_polling(&self, _kinds: &[PollerKind]) {}
+
+    /// Change poller concurrency and activity rate limits without restarting the worker, and
+    /// hence without losing its workflow cache. The update is validated against the worker's
+    /// current settings and either applied entirely or not at all.
+    ///
+    /// Lowering a number of pollers lets excess in-flight polls finish rather than abandoning them.
+    /// A new task queue rate limit is sent with the next activity poll. [Worker::get_config]
+    /// keeps returning the config the worker was created with.
+    ///
+    /// The default implementation rejects every update with
+    /// [WorkerConfigUpdateError::Unsupported].
+    fn update_config(&self, _update: WorkerConfigUpdate) -> Result<(), WorkerConfigUpdateError> {
+        Err(WorkerConfigUpdateError::Unsupported)
+    }
+
     /// Initiate shutdown. See [Worker::shutdown], this is just a sync version that starts the
     /// process. You can then wait on `shutdown` or [Worker::finalize_shutdown].
     fn initiate_shutdown(&self);
@@ -45,6 +45,16 @@ pub struct TelemetryOptions {
     /// A prefix to be applied to all core-created metrics. Defaults to "temporal_".
     #[builder(default = "METRIC_PREFIX.to_string()")]
     pub metric_prefix: String,
+    /// Optional OTLP exporter for core's tracing spans - set as None to disable.
+    #[builder(setter(into, strip_option), default)]
+    pub otel_traces: Option<OtelExportOptions>,
+    /// Optional OTLP exporter for core's log events - set as None to disable.
+    #[builder(setter(into, strip_option), default)]
+    pub otel_logs: Option<OtelExportOptions>,
+    /// Optional standalone HTTP server reporting on the health of the runtime's workers - set as
+    /// None to disable. The same endpoints can instead be served by the prometheus server.
+    #[builder(setter(into, strip_option), default)]
+    pub health_server: Option<HealthServerOptions>,
 }
 
 /// Options for exporting to an OpenTelemetry Collector
@@ -76,6 +86,26 @@ pub struct OtelCollectorOptions {
     pub protocol: OtlpProtocol,
 }
 
+/// Options for exporting core's traces or logs to an OpenTelemetry Collector
+#[derive(Debug, Clone, derive_builder::Builder)]
+pub struct OtelExportOptions {
+    /// The url of the OTel collector to export to
+    pub url: Url,
+    /// Optional set of HTTP headers to send to the Collector, e.g for authentication.
+    #[builder(default = "HashMap::new()")]
+    pub headers: HashMap<String, String>,
+    /// Protocol to use for communication with the collector
+    #[builder(default = "OtlpProtocol::Grpc")]
+    pub protocol: OtlpProtocol,
+    /// A map of tags to be applied as resource attributes to everything exported
+    #[builder(default)]
+    pub global_tags: HashMap<String, String>,
+    /// An [EnvFilter](https://docs.rs/tracing-subscriber/latest/tracing_subscriber/struct.EnvFilter.html)
+    /// filter string selecting what gets exported
+    #[builder(setter(into))]
+    pub filter: String,
+}
+
 /// Options for exporting metrics to Prometheus
 #[derive(Debug, Clone, derive_builder::Builder)]
 pub struct PrometheusExporterOptions {
@@ -98,6 +128,21 @@ pub struct PrometheusExporterOptions {
     pub histogram_bucket_overrides: HistogramBucketOverrides,
 }
 
+/// Options for serving worker health over HTTP. The server responds to:
+/// * `/healthz` - 200 unless some worker's pollers are running without any of them succeeding
+/// * `/readyz` - 200 once every worker has polled successfully, and none are shutting down
+/// * `/status` - JSON describing the health of each worker
+#[derive(Debug, Clone, derive_builder::Builder)]
+pub struct HealthServerOptions {
+    /// The address to serve on. If the port is 0, the OS picks one, which can be found with
+    /// `CoreRuntime::health_server_addr`.
+    pub socket_addr: SocketAddr,
+    /// How long pollers may keep polling without a successful poll before the worker is
+    /// considered unhealthy. Should be comfortably longer than the server's long poll timeout.
+    #[builder(default = "Duration::from_secs(120)")]
+    pub stale_poll_threshold: Duration,
+}
+
 /// Allows overriding the buckets used by histogram metrics
 #[derive(Debug, Clone, Default)]
 pub struct HistogramBucketOverrides {
@@ -2,6 +2,7 @@ use crate::{errors::WorkflowErrorType, telemetry::metrics::TemporalMeter};
 use std::{
     any::Any,
     collections::{HashMap, HashSet},
+    path::PathBuf,
     sync::Arc,
     time::Duration,
 };
@@ -36,6 +37,24 @@ pub struct WorkerConfig {
     /// or failures.
     #[builder(default = "0")]
     pub max_cached_workflows: usize,
+    /// If set, the workflow cache is also limited by the estimated memory its workflows retain.
+    /// Least-recently-used workflows are evicted until the estimate is back under this many bytes.
+    /// [WorkerConfig::max_cached_workflows] still applies and must be nonzero, so set it high to
+    /// limit the cache by memory alone.
+    #[builder(setter(into, strip_option), default)]
+    pub max_cached_workflows_bytes: Option<usize>,
+    /// Cache policies for particular workflow types (the map key). Each type with a policy has its
+    /// own cache partition, while all other types share the remainder of the cache. When the cache
+    /// is full, victims are chosen according to these policies before recency.
+    #[builder(default)]
+    pub workflow_cache_policies: HashMap<String, WorkflowCachePolicy>,
+    /// If set, the history of runs evicted from the cache is kept for a while. When a task for an
+    /// evicted run arrives on the sticky queue, the run is rebuilt from the kept history and the
+    /// task's new events, rather than by fetching the whole history from the server. The kept
+    /// history must reach the task's first new event. Fetching only the events in between isn't
+    /// supported, so if any are missing the whole history is fetched as usual.
+    #[builder(setter(into, strip_option), default)]
+    pub evicted_history_retention: Option<EvictedHistoryRetention>,
     /// Set a [WorkerTuner] for this worker. Either this or at least one of the `max_outstanding_*`
     /// fields must be set.
     #[builder(setter(into = false, strip_option), default)]
@@ -60,6 +79,19 @@ pub struct WorkerConfig {
     /// worker's task queue
     #[builder(default = "5")]
     pub max_concurrent_nexus_polls: usize,
+    /// If set, the number of concurrent workflow task polls scales within these bounds, rather
+    /// than always being [WorkerConfig::max_concurrent_wft_polls]. When sticky queues are enabled,
+    /// the sticky and nonsticky pollers are each scaled within them.
+    #[builder(setter(into, strip_option), default)]
+    pub workflow_poller_autoscaling: Option<PollerAutoscaling>,
+    /// If set, the number of concurrent activity task polls scales within these bounds, rather
+    /// than always being [WorkerConfig::max_concurrent_at_polls].
+    #[builder(setter(into, strip_option), default)]
+    pub activity_poller_autoscaling: Option<PollerAutoscaling>,
+    /// If set, the number of concurrent nexus task polls scales within these bounds, rather than
+    /// always being [WorkerConfig::max_concurrent_nexus_polls].
+    #[builder(setter(into, strip_option), default)]
+    pub nexus_poller_autoscaling: Option<PollerAutoscaling>,
     /// If set to true this worker will only handle workflow tasks and local activities, it will not
     /// poll for activity tasks.
     #[builder(default = "false")]
@@ -118,6 +150,14 @@ pub struct WorkerConfig {
     #[builder(default = "5")]
     pub fetching_concurrency: usize,
 
+    /// If set, every input to workflow state processing is recorded to this file, so that
+    /// whatever happened to the worker's workflows (EX: a panic or hang in core) can be
+    /// reproduced offline by replaying the recording. Recordings include workflow payloads and
+    /// grow without bound, so this is only for debugging. Only takes effect when core is built
+    /// with the `save_wf_inputs` feature.
+    #[builder(setter(into, strip_option), default)]
+    pub record_workflow_inputs_to: Option<PathBuf>,
+
     /// If set, core will issue cancels for all outstanding activities and nexus operations after
     /// shutdown has been initiated and this amount of time has elapsed.
     #[builder(default)]
@@ -129,6 +169,13 @@ pub struct WorkerConfig {
     #[builder(default = "Duration::from_secs(5)")]
     pub local_timeout_buffer_for_activities: Duration,
 
+    /// If set, any workflow activation which lang has not completed within this amount of time is
+    /// considered deadlocked. Core fails the activation on lang's behalf with a deadlock failure,
+    /// evicts the run with the `DeadlockDetected` eviction reason, and drops the late completion
+    /// if it ever arrives. Must be nonzero.
+    #[builder(setter(into, strip_option), default)]
+    pub workflow_activation_deadline: Option<Duration>,
+
     /// Any error types listed here will cause any workflow being processed by this worker to fail,
     /// rather than simply failing the workflow task.
     #[builder(default)]
@@ -191,6 +238,164 @@ impl WorkerConfig {
     }
 }
 
+/// How runs of one workflow type are treated by the workflow cache, see
+/// [WorkerConfig::workflow_cache_policies]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorkflowCachePolicy {
+    /// Never cache more than this many runs of the type. Once reached, a new run of the type
+    /// evicts the least-recently-used run of the same type rather than a run of another type.
+    pub max_cached: Option<usize>,
+    /// This many cache slots are reserved for runs of the type. Other types can't use them, and
+    /// runs of the type are never evicted to make room for other types while the type has no more
+    /// than this many runs cached.
+    pub reserved: usize,
+    /// When a run must be evicted to make room, runs of types which prefer to be kept are only
+    /// chosen if no other run can be. Useful for long-running workflows with large histories,
+    /// which are expensive to replay.
+    pub prefer_keep: bool,
+}
+
+/// Limits on how much evicted run history is kept, see [WorkerConfig::evicted_history_retention].
+/// Sizes are measured by the history's encoded size.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EvictedHistoryRetention {
+    /// Keep at most this many bytes of history in memory, discarding or spilling the histories of
+    /// the runs which were evicted longest ago to make room
+    pub max_bytes_in_memory: usize,
+    /// If set, histories which don't fit in memory are written to files in this directory rather
+    /// than being discarded
+    pub spill_directory: Option<PathBuf>,
+    /// Keep at most this many bytes of history in the spill directory
+    pub max_bytes_on_disk: usize,
+}
+
+/// Bounds for autoscaling the number of concurrent polls of one kind of task. Pollers are added
+/// while polls return tasks right away or the server reports a backlog, and removed after polls
+/// come back empty. Server `RESOURCE_EXHAUSTED` errors halve the number of pollers and pause
+/// scaling up for a while.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PollerAutoscaling {
+    /// Never run fewer pollers than this. Must be at least 1.
+    pub minimum: usize,
+    /// Never run more pollers than this
+    pub maximum: usize,
+    /// How many pollers to start with
+    pub initial: usize,
+}
+
+impl Default for PollerAutoscaling {
+    fn default() -> Self {
+        Self {
+            minimum: 1,
+            maximum: 100,
+            initial: 5,
+        }
+    }
+}
+
+impl PollerAutoscaling {
+    fn validate(&self, field: &str) -> Result<(), String> {
+        if self.minimum == 0 {
+            return Err(format!("`{field}` minimum must be at least 1"));
+        }
+        if self.initial < self.minimum || self.initial > self.maximum {
+            return Err(format!(
+                "`{field}` initial must be between its minimum and maximum"
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Changes to the subset of a [WorkerConfig] which can be applied to a running worker with
+/// [crate::Worker::update_config]. Fields which are not set are left as they are.
+#[derive(Clone, Debug, Default, derive_builder::Builder)]
+#[builder(setter(into, strip_option), default)]
+pub struct WorkerConfigUpdate {
+    /// See [WorkerConfig::max_concurrent_wft_polls]
+    pub max_concurrent_wft_polls: Option<usize>,
+    /// See [WorkerConfig::nonsticky_to_sticky_poll_ratio]
+    pub nonsticky_to_sticky_poll_ratio: Option<f32>,
+    /// See [WorkerConfig::max_concurrent_at_polls]
+    pub max_concurrent_at_polls: Option<usize>,
+    /// See [WorkerConfig::max_worker_activities_per_second]. Set to `Some(None)` to remove the
+    /// limit.
+    pub max_worker_activities_per_second: Option<Option<f64>>,
+    /// See [WorkerConfig::max_task_queue_activities_per_second]. Set to `Some(None)` to stop
+    /// sending a limit, which leaves whatever limit the server last received in place.
+    pub max_task_queue_activities_per_second: Option<Option<f64>>,
+}
+
+impl WorkerConfig {
+    /// Returns a copy of this config with the update applied, or an error describing why the
+    /// resulting config would be invalid.
+    pub fn with_update(&self, update: &WorkerConfigUpdate) -> Result<WorkerConfig, String> {
+        let mut updated = self.clone();
+        let updates_wft_polls = update.max_concurrent_wft_polls.is_some()
+            || update.nonsticky_to_sticky_poll_ratio.is_some();
+        if updates_wft_polls && self.workflow_poller_autoscaling.is_some() {
+            return Err(
+                "Workflow poller counts can't be updated while they're autoscaled".to_owned(),
+            );
+        }
+        if update.max_concurrent_at_polls.is_some() && self.activity_poller_autoscaling.is_some() {
+            return Err(
+                "Activity poller counts can't be updated while they're autoscaled".to_owned(),
+            );
+        }
+        if let Some(polls) = update.max_concurrent_wft_polls {
+            if polls == 0 {
+                return Err("`max_concurrent_wft_polls` must be at least 1".to_owned());
+            }
+            if self.max_cached_workflows > 0 && polls > self.max_cached_workflows {
+                return Err(
+                    "`max_concurrent_wft_polls` cannot exceed `max_cached_workflows`".to_owned(),
+                );
+            }
+            updated.max_concurrent_wft_polls = polls;
+        }
+        if let Some(ratio) = update.nonsticky_to_sticky_poll_ratio {
+            if !(0.0..=1.0).contains(&ratio) {
+                return Err("`nonsticky_to_sticky_poll_ratio` must be between 0 and 1".to_owned());
+            }
+            updated.nonsticky_to_sticky_poll_ratio = ratio;
+        }
+        let updates_activities = update.max_concurrent_at_polls.is_some()
+            || update.max_worker_activities_per_second.is_some()
+            || update.max_task_queue_activities_per_second.is_some();
+        if updates_activities && self.no_remote_activities {
+            return Err(
+                "Activity polling options can't be updated with `no_remote_activities` set"
+                    .to_owned(),
+            );
+        }
+        if let Some(polls) = update.max_concurrent_at_polls {
+            if polls == 0 {
+                return Err("`max_concurrent_at_polls` must be at least 1".to_owned());
+            }
+            updated.max_concurrent_at_polls = polls;
+        }
+        if let Some(per_sec) = update.max_worker_activities_per_second {
+            if per_sec.is_some_and(|x| !x.is_normal() || x.is_sign_negative()) {
+                return Err(
+                    "`max_worker_activities_per_second` must be positive and nonzero".to_owned(),
+                );
+            }
+            updated.max_worker_activities_per_second = per_sec;
+        }
+        if let Some(per_sec) = update.max_task_queue_activities_per_second {
+            if per_sec.is_some_and(|x| !x.is_finite() || x.is_sign_negative()) {
+                return Err(
+                    "`max_task_queue_activities_per_second` must be a non-negative number"
+                        .to_owned(),
+                );
+            }
+            updated.max_task_queue_activities_per_second = per_sec;
+        }
+        Ok(updated)
+    }
+}
+
 impl WorkerConfigBuilder {
     /// Unset all `max_outstanding_*` fields
     pub fn clear_max_outstanding_opts(&mut self) -> &mut Self {
@@ -201,13 +406,66 @@ impl WorkerConfigBuilder {
     }
 
     fn validate(&self) -> Result<(), String> {
+        if let Some(Some(bytes)) = self.max_cached_workflows_bytes {
+            if bytes == 0 {
+                return Err("`max_cached_workflows_bytes` must be nonzero".to_owned());
+            }
+            if self.max_cached_workflows.unwrap_or_default() == 0 {
+                return Err(
+                    "`max_cached_workflows_bytes` requires `max_cached_workflows` to be nonzero"
+                        .to_owned(),
+                );
+            }
+        }
         if self.max_concurrent_wft_polls == Some(0) {
             return Err("`max_concurrent_wft_polls` must be at least 1".to_owned());
         }
+        if let Some(policies) = self.workflow_cache_policies.as_ref() {
+            for (wf_type, policy) in policies {
+                if policy
+                    .max_cached
+                    .is_some_and(|m| m == 0 || m < policy.reserved)
+                {
+                    return Err(format!(
+                        "Cache policy for `{wf_type}` must allow at least one run, and no fewer \
+                         than it reserves"
+                    ));
+                }
+            }
+            let total_reserved: usize = policies.values().map(|p| p.reserved).sum();
+            // Leave room for the types which share the rest of the cache
+            if total_reserved > 0 && total_reserved >= self.max_cached_workflows.unwrap_or_default()
+            {
+                return Err(
+                    "`workflow_cache_policies` must reserve fewer slots than `max_cached_workflows`"
+                        .to_owned(),
+                );
+            }
+        }
         if self.max_concurrent_at_polls == Some(0) {
             return Err("`max_concurrent_at_polls` must be at least 1".to_owned());
         }
 
+        for (field, autoscaling) in [
+            (
+                "workflow_poller_autoscaling",
+                &self.workflow_poller_autoscaling,
+            ),
+            (
+                "activity_poller_autoscaling",
+                &self.activity_poller_autoscaling,
+            ),
+            ("nexus_poller_autoscaling", &self.nexus_poller_autoscaling),
+        ] {
+            if let Some(Some(autoscaling)) = autoscaling {
+                autoscaling.validate(field)?;
+            }
+        }
+
+        if self.workflow_activation_deadline == Some(Some(Duration::ZERO)) {
+            return Err("`workflow_activation_deadline` must be nonzero".to_owned());
+        }
+
         if let Some(Some(ref x)) = self.max_worker_activities_per_second {
             if !x.is_normal() || x.is_sign_negative() {
                 return Err(
@@ -374,6 +632,18 @@ impl SlotSupplierPermit {
     }
 }
 
+/// The kinds of tasks a worker polls the server for. Polling for each kind can be paused and
+/// resumed independently with [crate::Worker::pause_polling] and [crate::Worker::resume_polling].
+#[derive(Debug, Copy, Clone, derive_more::Display, Eq, PartialEq, Hash)]
+pub enum PollerKind {
+    /// Workflow tasks, on both the normal and the sticky task queue
+    Workflow,
+    /// Activity tasks
+    Activity,
+    /// Nexus tasks
+    Nexus,
+}
+
 #[derive(Debug, Copy, Clone, derive_more::Display, Eq, PartialEq)]
 pub enum SlotKindType {
     Workflow,
@@ -0,0 +1,17 @@
+use std::{env, path::PathBuf};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    let crate_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR")?);
+    let out_dir = PathBuf::from(env::var("OUT_DIR")?);
+    let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml"))?;
+    // Written to OUT_DIR rather than include/ so builds never modify the source tree. The
+    // header_tests integration test checks the committed header matches.
+    cbindgen::Builder::new()
+        .with_crate(&crate_dir)
+        .with_config(config)
+        .generate()?
+        .write_to_file(out_dir.join("temporal-sdk-core-c-bridge.h"));
+    Ok(())
+}
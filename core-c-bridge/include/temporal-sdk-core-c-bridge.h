@@ -0,0 +1,401 @@
+#ifndef TEMPORAL_SDK_CORE_C_BRIDGE_H
+#define TEMPORAL_SDK_CORE_C_BRIDGE_H
+
+/* Generated by cbindgen from the temporal-sdk-core-c-bridge crate. Do not edit. */
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+typedef enum TemporalCoreForwardedLogLevel {
+  TemporalCoreForwardedLogLevel_TRACE = 0,
+  TemporalCoreForwardedLogLevel_DEBUG,
+  TemporalCoreForwardedLogLevel_INFO,
+  TemporalCoreForwardedLogLevel_WARN,
+  TemporalCoreForwardedLogLevel_ERROR,
+} TemporalCoreForwardedLogLevel;
+
+typedef enum TemporalCoreMetricTemporalityOption {
+  TemporalCoreMetricTemporalityOption_CUMULATIVE = 1,
+  TemporalCoreMetricTemporalityOption_DELTA,
+} TemporalCoreMetricTemporalityOption;
+
+typedef enum TemporalCoreOpenTelemetryProtocol {
+  TemporalCoreOpenTelemetryProtocol_GRPC = 1,
+  TemporalCoreOpenTelemetryProtocol_HTTP,
+} TemporalCoreOpenTelemetryProtocol;
+
+/**
+ * A connection to the server, which workers can be created with
+ */
+typedef struct TemporalCoreClient TemporalCoreClient;
+
+/**
+ * A log emitted by core, only valid for the duration of a [ForwardedLogCallback]. Use the
+ * `temporal_core_forwarded_log_*` functions to read it.
+ */
+typedef struct TemporalCoreForwardedLog TemporalCoreForwardedLog;
+
+/**
+ * Owns core's runtime. Clients and workers created from it keep it alive.
+ */
+typedef struct TemporalCoreRuntime TemporalCoreRuntime;
+
+/**
+ * A worker polling a single task queue
+ */
+typedef struct TemporalCoreWorker TemporalCoreWorker;
+
+/**
+ * A borrowed view of bytes owned by the caller
+ */
+typedef struct TemporalCoreByteArrayRef {
+  const uint8_t *data;
+  size_t size;
+} TemporalCoreByteArrayRef;
+
+/**
+ * Every field may be left empty. The client cert and key must be set together to use mTLS.
+ */
+typedef struct TemporalCoreClientTlsOptions {
+  TemporalCoreByteArrayRef server_root_ca_cert;
+  TemporalCoreByteArrayRef domain;
+  TemporalCoreByteArrayRef client_cert;
+  TemporalCoreByteArrayRef client_private_key;
+} TemporalCoreClientTlsOptions;
+
+typedef struct TemporalCoreClientOptions {
+  /**
+   * Ex: `http://localhost:7233`
+   */
+  TemporalCoreByteArrayRef target_url;
+  TemporalCoreByteArrayRef client_name;
+  TemporalCoreByteArrayRef client_version;
+  /**
+   * If empty, a default identity is generated
+   */
+  TemporalCoreByteArrayRef identity;
+  /**
+   * Newline-delimited pairs of gRPC headers sent with every call
+   */
+  TemporalCoreByteArrayRef metadata;
+  /**
+   * If not empty, sent as a bearer token with every call
+   */
+  TemporalCoreByteArrayRef api_key;
+  /**
+   * May be null to connect without TLS
+   */
+  const TemporalCoreClientTlsOptions *tls_options;
+} TemporalCoreClientOptions;
+
+/**
+ * Bytes owned by core, which must be freed with [temporal_core_byte_array_free]
+ */
+typedef struct TemporalCoreByteArray {
+  const uint8_t *data;
+  size_t size;
+  /**
+   * Only needed by core to free the bytes
+   */
+  size_t cap;
+} TemporalCoreByteArray;
+
+/**
+ * Exactly one of `success` or `fail` is set. A successful client must be freed with
+ * [temporal_core_client_free].
+ */
+typedef void (*TemporalCoreClientConnectCallback)(void *user_data,
+                                                  TemporalCoreClient *success,
+                                                  const TemporalCoreByteArray *fail);
+
+typedef struct TemporalCoreRuntimeOrFail {
+  /**
+   * Null on failure. Free with [temporal_core_runtime_free].
+   */
+  TemporalCoreRuntime *runtime;
+  /**
+   * Set on failure
+   */
+  const TemporalCoreByteArray *fail;
+} TemporalCoreRuntimeOrFail;
+
+/**
+ * Invoked synchronously with every log, from whichever thread emitted it. The log is only valid
+ * for the duration of the call.
+ */
+typedef void (*TemporalCoreForwardedLogCallback)(TemporalCoreForwardedLogLevel level,
+                                                 const TemporalCoreForwardedLog *log);
+
+typedef struct TemporalCoreLoggingOptions {
+  /**
+   * An [EnvFilter](https://docs.rs/tracing-subscriber/latest/tracing_subscriber/struct.EnvFilter.html)
+   * filter string
+   */
+  TemporalCoreByteArrayRef filter;
+  /**
+   * If set, every log is passed to this callback rather than being written to the console
+   */
+  TemporalCoreForwardedLogCallback forward_to;
+} TemporalCoreLoggingOptions;
+
+typedef struct TemporalCoreOpenTelemetryOptions {
+  TemporalCoreByteArrayRef url;
+  /**
+   * Newline-delimited pairs of HTTP headers sent to the collector
+   */
+  TemporalCoreByteArrayRef headers;
+  /**
+   * If zero, the default of one second is used
+   */
+  uint32_t metric_periodicity_millis;
+  TemporalCoreMetricTemporalityOption metric_temporality;
+  bool durations_as_seconds;
+  TemporalCoreOpenTelemetryProtocol protocol;
+} TemporalCoreOpenTelemetryOptions;
+
+typedef struct TemporalCorePrometheusOptions {
+  /**
+   * Address to serve metrics on, ex: `127.0.0.1:9090`
+   */
+  TemporalCoreByteArrayRef bind_address;
+  bool counters_total_suffix;
+  bool unit_suffix;
+  bool durations_as_seconds;
+} TemporalCorePrometheusOptions;
+
+/**
+ * Exactly one of `opentelemetry` or `prometheus` must be set
+ */
+typedef struct TemporalCoreMetricsOptions {
+  const TemporalCoreOpenTelemetryOptions *opentelemetry;
+  const TemporalCorePrometheusOptions *prometheus;
+  /**
+   * Attach a `service_name` label to all metrics
+   */
+  bool attach_service_name;
+  /**
+   * Newline-delimited pairs of tags applied to all metrics
+   */
+  TemporalCoreByteArrayRef global_tags;
+  /**
+   * Prefix for every core metric. If empty, the default of `temporal_` is used.
+   */
+  TemporalCoreByteArrayRef metric_prefix;
+} TemporalCoreMetricsOptions;
+
+typedef struct TemporalCoreTelemetryOptions {
+  /**
+   * May be null to disable logging
+   */
+  const TemporalCoreLoggingOptions *logging;
+  /**
+   * May be null to disable metrics
+   */
+  const TemporalCoreMetricsOptions *metrics;
+} TemporalCoreTelemetryOptions;
+
+typedef struct TemporalCoreRuntimeOptions {
+  /**
+   * May be null, in which case core emits no logs or metrics
+   */
+  const TemporalCoreTelemetryOptions *telemetry;
+} TemporalCoreRuntimeOptions;
+
+typedef struct TemporalCoreWorkerOrFail {
+  /**
+   * Null on failure. Free with [temporal_core_worker_free].
+   */
+  TemporalCoreWorker *worker;
+  /**
+   * Set on failure
+   */
+  const TemporalCoreByteArray *fail;
+} TemporalCoreWorkerOrFail;
+
+/**
+ * Options for creating a worker. Numeric limits left as zero use core's defaults, except for
+ * `max_cached_workflows`, where zero disables the cache.
+ */
+typedef struct TemporalCoreWorkerOptions {
+  /**
+   * Suffixed so the header can be included from C++, where `namespace` is a keyword
+   */
+  TemporalCoreByteArrayRef namespace_;
+  TemporalCoreByteArrayRef task_queue;
+  TemporalCoreByteArrayRef build_id;
+  /**
+   * If empty, the client's identity is used
+   */
+  TemporalCoreByteArrayRef identity_override;
+  uint32_t max_cached_workflows;
+  uint32_t max_outstanding_workflow_tasks;
+  uint32_t max_outstanding_activities;
+  uint32_t max_outstanding_local_activities;
+  uint32_t max_outstanding_nexus_tasks;
+  uint32_t max_concurrent_workflow_task_polls;
+  float nonsticky_to_sticky_poll_ratio;
+  uint32_t max_concurrent_activity_task_polls;
+  uint32_t max_concurrent_nexus_task_polls;
+  /**
+   * Set to only process workflows and local activities
+   */
+  bool no_remote_activities;
+  uint64_t sticky_queue_schedule_to_start_timeout_millis;
+  uint64_t max_heartbeat_throttle_interval_millis;
+  uint64_t default_heartbeat_throttle_interval_millis;
+  /**
+   * Zero leaves activities unlimited
+   */
+  double max_activities_per_second;
+  /**
+   * Zero leaves the task queue unlimited
+   */
+  double max_task_queue_activities_per_second;
+  /**
+   * Zero means activities aren't given any time to finish on their own during shutdown
+   */
+  uint64_t graceful_shutdown_period_millis;
+} TemporalCoreWorkerOptions;
+
+/**
+ * Invoked once a call completes. `fail` is set if it failed.
+ */
+typedef void (*TemporalCoreWorkerCallback)(void *user_data, const TemporalCoreByteArray *fail);
+
+/**
+ * Invoked with the result of a poll. `success` is set to the encoded protobuf of the polled
+ * activation or task, and `fail` is set if polling failed. If neither is set, the worker has shut
+ * down and there's nothing left to poll for.
+ */
+typedef void (*TemporalCoreWorkerPollCallback)(void *user_data,
+                                               const TemporalCoreByteArray *success,
+                                               const TemporalCoreByteArray *fail);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Connects to the server, invoking `callback` once connected or once connecting fails
+ */
+void temporal_core_client_connect(TemporalCoreRuntime *runtime,
+                                  const TemporalCoreClientOptions *options,
+                                  void *user_data,
+                                  TemporalCoreClientConnectCallback callback);
+
+/**
+ * Frees the client. Workers created with it keep their own connection alive.
+ */
+void temporal_core_client_free(TemporalCoreClient *client);
+
+/**
+ * Frees bytes which were handed out by core. Does nothing if `bytes` is null.
+ */
+void temporal_core_byte_array_free(const TemporalCoreByteArray *bytes);
+
+TemporalCoreRuntimeOrFail temporal_core_runtime_new(const TemporalCoreRuntimeOptions *options);
+
+/**
+ * Frees the runtime. Clients and workers created from it keep the underlying runtime alive
+ * until they're freed too.
+ */
+void temporal_core_runtime_free(TemporalCoreRuntime *runtime);
+
+TemporalCoreByteArrayRef temporal_core_forwarded_log_target(const TemporalCoreForwardedLog *log);
+
+TemporalCoreByteArrayRef temporal_core_forwarded_log_message(const TemporalCoreForwardedLog *log);
+
+uint64_t temporal_core_forwarded_log_timestamp_millis(const TemporalCoreForwardedLog *log);
+
+/**
+ * The log's structured fields, as a JSON object
+ */
+TemporalCoreByteArrayRef temporal_core_forwarded_log_fields_json(const TemporalCoreForwardedLog *log);
+
+TemporalCoreWorkerOrFail temporal_core_worker_new(TemporalCoreClient *client,
+                                                  const TemporalCoreWorkerOptions *options);
+
+/**
+ * Frees the worker. Workers should be shut down with [temporal_core_worker_finalize_shutdown]
+ * before they're freed.
+ */
+void temporal_core_worker_free(TemporalCoreWorker *worker);
+
+/**
+ * Checks the worker's namespace exists and that it can reach the server
+ */
+void temporal_core_worker_validate(TemporalCoreWorker *worker,
+                                   void *user_data,
+                                   TemporalCoreWorkerCallback callback);
+
+void temporal_core_worker_poll_workflow_activation(TemporalCoreWorker *worker,
+                                                   void *user_data,
+                                                   TemporalCoreWorkerPollCallback callback);
+
+void temporal_core_worker_poll_activity_task(TemporalCoreWorker *worker,
+                                             void *user_data,
+                                             TemporalCoreWorkerPollCallback callback);
+
+void temporal_core_worker_poll_nexus_task(TemporalCoreWorker *worker,
+                                          void *user_data,
+                                          TemporalCoreWorkerPollCallback callback);
+
+/**
+ * Completes an activation, given an encoded `WorkflowActivationCompletion`
+ */
+void temporal_core_worker_complete_workflow_activation(TemporalCoreWorker *worker,
+                                                       TemporalCoreByteArrayRef completion,
+                                                       void *user_data,
+                                                       TemporalCoreWorkerCallback callback);
+
+/**
+ * Completes an activity task, given an encoded `ActivityTaskCompletion`
+ */
+void temporal_core_worker_complete_activity_task(TemporalCoreWorker *worker,
+                                                 TemporalCoreByteArrayRef completion,
+                                                 void *user_data,
+                                                 TemporalCoreWorkerCallback callback);
+
+/**
+ * Completes a nexus task, given an encoded `NexusTaskCompletion`
+ */
+void temporal_core_worker_complete_nexus_task(TemporalCoreWorker *worker,
+                                              TemporalCoreByteArrayRef completion,
+                                              void *user_data,
+                                              TemporalCoreWorkerCallback callback);
+
+/**
+ * Records a heartbeat, given an encoded `ActivityHeartbeat`. Returns a failure if the heartbeat
+ * couldn't be decoded, else null.
+ */
+const TemporalCoreByteArray *temporal_core_worker_record_activity_heartbeat(TemporalCoreWorker *worker,
+                                                                             TemporalCoreByteArrayRef heartbeat);
+
+/**
+ * Requests the run be evicted from the cache. Returns a failure if the run id isn't valid UTF-8
+ * or the worker has been finalized, else null.
+ */
+const TemporalCoreByteArray *temporal_core_worker_request_workflow_eviction(TemporalCoreWorker *worker,
+                                                                            TemporalCoreByteArrayRef run_id);
+
+/**
+ * Begins shutting the worker down. Polls keep returning remaining work until there is none, at
+ * which point they return with neither success nor failure.
+ */
+void temporal_core_worker_initiate_shutdown(TemporalCoreWorker *worker);
+
+/**
+ * Waits for the worker to finish shutting down and releases its resources. Must only be called
+ * once every poll has returned, after which no other calls may be made with the worker except
+ * [temporal_core_worker_free].
+ */
+void temporal_core_worker_finalize_shutdown(TemporalCoreWorker *worker,
+                                            void *user_data,
+                                            TemporalCoreWorkerCallback callback);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* TEMPORAL_SDK_CORE_C_BRIDGE_H */
@@ -0,0 +1,124 @@
+use crate::{ByteArray, ByteArrayRef, UserDataHandle, runtime::Runtime};
+use std::ffi::c_void;
+use temporal_client::{ConfiguredClient, TemporalServiceClientWithMetrics};
+use temporal_sdk_core::{ClientOptionsBuilder, ClientTlsConfig, RetryClient, TlsConfig, Url};
+
+#[repr(C)]
+pub struct ClientOptions {
+    /// Ex: `http://localhost:7233`
+    pub target_url: ByteArrayRef,
+    pub client_name: ByteArrayRef,
+    pub client_version: ByteArrayRef,
+    /// If empty, a default identity is generated
+    pub identity: ByteArrayRef,
+    /// Newline-delimited pairs of gRPC headers sent with every call
+    pub metadata: ByteArrayRef,
+    /// If not empty, sent as a bearer token with every call
+    pub api_key: ByteArrayRef,
+    /// May be null to connect without TLS
+    pub tls_options: *const ClientTlsOptions,
+}
+
+/// Every field may be left empty. The client cert and key must be set together to use mTLS.
+#[repr(C)]
+pub struct ClientTlsOptions {
+    pub server_root_ca_cert: ByteArrayRef,
+    pub domain: ByteArrayRef,
+    pub client_cert: ByteArrayRef,
+    pub client_private_key: ByteArrayRef,
+}
+
+/// A connection to the server, which workers can be created with
+pub struct Client {
+    pub(crate) runtime: Runtime,
+    pub(crate) core: RetryClient<ConfiguredClient<TemporalServiceClientWithMetrics>>,
+}
+
+/// Exactly one of `success` or `fail` is set. A successful client must be freed with
+/// [temporal_core_client_free].
+pub type ClientConnectCallback =
+    unsafe extern "C" fn(user_data: *mut c_void, success: *mut Client, fail: *const ByteArray);
+
+/// Connects to the server, invoking `callback` once connected or once connecting fails
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn temporal_core_client_connect(
+    runtime: *mut Runtime,
+    options: *const ClientOptions,
+    user_data: *mut c_void,
+    callback: ClientConnectCallback,
+) {
+    // Safety: caller promises pointers are valid for the call
+    let (runtime, options) = unsafe { ((*runtime).clone(), &*options) };
+    let user_data = UserDataHandle(user_data);
+    let core_options = options.to_core_options();
+    let metric_meter = runtime.core.telemetry().get_temporal_metric_meter();
+    let handle = runtime.core.tokio_handle();
+    handle.spawn(async move {
+        let user_data = user_data;
+        let connected = match core_options {
+            Ok(o) => o
+                .connect_no_namespace(metric_meter)
+                .await
+                .map_err(Into::into),
+            Err(e) => Err(e),
+        };
+        // Safety: the caller promised the callback is valid
+        match connected {
+            Ok(core) => unsafe {
+                callback(
+                    user_data.0,
+                    Box::into_raw(Box::new(Client { runtime, core })),
+                    std::ptr::null(),
+                )
+            },
+            Err(e) => unsafe { callback(user_data.0, std::ptr::null_mut(), ByteArray::fail(e)) },
+        }
+    });
+}
+
+/// Frees the client. Workers created with it keep their own connection alive.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn temporal_core_client_free(client: *mut Client) {
+    if !client.is_null() {
+        // Safety: created by temporal_core_client_connect, and the caller won't use it again
+        drop(unsafe { Box::from_raw(client) });
+    }
+}
+
+impl ClientOptions {
+    fn to_core_options(&self) -> Result<temporal_sdk_core::ClientOptions, anyhow::Error> {
+        let mut opts = ClientOptionsBuilder::default();
+        opts.target_url(Url::parse(self.target_url.to_str()?)?)
+            .client_name(self.client_name.to_string()?)
+            .client_version(self.client_version.to_string()?)
+            .api_key(self.api_key.to_option_string()?);
+        if let Some(identity) = self.identity.to_option_string()? {
+            opts.identity(identity);
+        }
+        let metadata = self.metadata.to_string_map_on_newlines()?;
+        if !metadata.is_empty() {
+            opts.headers(Some(metadata));
+        }
+        // Safety: caller promises nested options are either null or valid for the call
+        if let Some(tls) = unsafe { self.tls_options.as_ref() } {
+            let client_tls_config = match (
+                tls.client_cert.to_slice().is_empty(),
+                tls.client_private_key.to_slice().is_empty(),
+            ) {
+                (true, true) => None,
+                (false, false) => Some(ClientTlsConfig {
+                    client_cert: tls.client_cert.to_vec(),
+                    client_private_key: tls.client_private_key.to_vec(),
+                }),
+                _ => anyhow::bail!("Client cert and private key must be set together"),
+            };
+            opts.tls_cfg(TlsConfig {
+                server_root_ca_cert: (!tls.server_root_ca_cert.to_slice().is_empty())
+                    .then(|| tls.server_root_ca_cert.to_vec()),
+                domain: tls.domain.to_option_string()?,
+                client_tls_config,
+            });
+        }
+        Ok(opts.build()?)
+    }
+}
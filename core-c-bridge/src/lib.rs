@@ -0,0 +1,174 @@
+#![allow(clippy::missing_safety_doc)] // Safety requirements are described once, below
+
+//! Exposes core's runtime, client, and worker over a stable C ABI, so that SDKs for new languages
+//! can be built directly on top of it rather than each writing their own bridge. The header for
+//! it is generated into `include/temporal-sdk-core-c-bridge.h` when this crate is built.
+//!
+//! Conventions shared by every function:
+//! * Strings and byte buffers passed in are [ByteArrayRef]s, which only need to remain valid for
+//!   the duration of the call. Strings must be UTF-8 and aren't null terminated.
+//! * Protobuf messages are passed in both directions as their encoded bytes.
+//! * Anything returned as a `*const ByteArray`, including through callbacks, is owned by the
+//!   caller and must be freed with [temporal_core_byte_array_free]. Failures are returned the same
+//!   way, as UTF-8 messages.
+//! * Callbacks are invoked from threads owned by the runtime, and never before the call which
+//!   registered them has returned. `user_data` is passed back to them untouched.
+//! * Pointers to runtimes, clients, and workers must be valid and, unless noted, must not be freed
+//!   while calls using them are still outstanding.
+
+pub mod client;
+pub mod runtime;
+pub mod worker;
+
+use std::{collections::HashMap, ffi::c_void};
+
+/// A borrowed view of bytes owned by the caller
+#[repr(C)]
+pub struct ByteArrayRef {
+    pub data: *const u8,
+    pub size: usize,
+}
+
+impl ByteArrayRef {
+    /// Views the bytes, which must outlive the returned slice
+    fn to_slice(&self) -> &[u8] {
+        if self.data.is_null() || self.size == 0 {
+            return &[];
+        }
+        // Safety: callers promise data points to size valid bytes for the length of the call
+        unsafe { std::slice::from_raw_parts(self.data, self.size) }
+    }
+
+    fn to_vec(&self) -> Vec<u8> {
+        self.to_slice().to_vec()
+    }
+
+    fn to_str(&self) -> Result<&str, anyhow::Error> {
+        Ok(std::str::from_utf8(self.to_slice())?)
+    }
+
+    fn to_string(&self) -> Result<String, anyhow::Error> {
+        self.to_str().map(ToOwned::to_owned)
+    }
+
+    /// Empty strings are treated as unset
+    fn to_option_string(&self) -> Result<Option<String>, anyhow::Error> {
+        self.to_str().map(|s| (!s.is_empty()).then(|| s.to_owned()))
+    }
+
+    /// Parses newline-delimited pairs, ex: `key1\nvalue1\nkey2\nvalue2`
+    fn to_string_map_on_newlines(&self) -> Result<HashMap<String, String>, anyhow::Error> {
+        let s = self.to_str()?;
+        if s.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let parts: Vec<_> = s.split('\n').collect();
+        if parts.len() % 2 != 0 {
+            anyhow::bail!("Newline-delimited pairs must have an even number of entries");
+        }
+        Ok(parts
+            .chunks_exact(2)
+            .map(|kv| (kv[0].to_owned(), kv[1].to_owned()))
+            .collect())
+    }
+
+    /// Views a string owned by core, which must outlive the returned view
+    fn borrowed(s: &str) -> Self {
+        Self {
+            data: s.as_ptr(),
+            size: s.len(),
+        }
+    }
+}
+
+/// Bytes owned by core, which must be freed with [temporal_core_byte_array_free]
+#[repr(C)]
+pub struct ByteArray {
+    pub data: *const u8,
+    pub size: usize,
+    /// Only needed by core to free the bytes
+    pub cap: usize,
+}
+
+impl ByteArray {
+    fn from_vec(vec: Vec<u8>) -> Self {
+        let mut vec = std::mem::ManuallyDrop::new(vec);
+        Self {
+            data: vec.as_mut_ptr(),
+            size: vec.len(),
+            cap: vec.capacity(),
+        }
+    }
+
+    fn from_utf8(s: String) -> Self {
+        Self::from_vec(s.into_bytes())
+    }
+
+    /// Moves the array to the heap, for handing to the caller
+    fn into_raw(self) -> *const Self {
+        Box::into_raw(Box::new(self))
+    }
+
+    fn fail(err: impl std::fmt::Display) -> *const Self {
+        Self::from_utf8(err.to_string()).into_raw()
+    }
+}
+
+/// Frees bytes which were handed out by core. Does nothing if `bytes` is null.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn temporal_core_byte_array_free(bytes: *const ByteArray) {
+    if bytes.is_null() {
+        return;
+    }
+    // Safety: the array was created by ByteArray::into_raw, and the caller won't use it again
+    unsafe {
+        let bytes = Box::from_raw(bytes as *mut ByteArray);
+        drop(Vec::from_raw_parts(
+            bytes.data as *mut u8,
+            bytes.size,
+            bytes.cap,
+        ));
+    }
+}
+
+/// Lets callers' opaque `user_data` be moved to the runtime's threads to invoke callbacks with
+#[derive(Clone, Copy)]
+struct UserDataHandle(*mut c_void);
+// Safety: core never dereferences user data, it's only handed back to the caller's callbacks
+unsafe impl Send for UserDataHandle {}
+unsafe impl Sync for UserDataHandle {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn newline_pairs_parse() {
+        let s = "a\n1\nb\n2";
+        let map = ByteArrayRef::borrowed(s)
+            .to_string_map_on_newlines()
+            .unwrap();
+        assert_eq!(map["a"], "1");
+        assert_eq!(map["b"], "2");
+        assert!(
+            ByteArrayRef::borrowed("a\n1\nb")
+                .to_string_map_on_newlines()
+                .is_err()
+        );
+        assert!(
+            ByteArrayRef::borrowed("")
+                .to_string_map_on_newlines()
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn byte_arrays_round_trip() {
+        let bytes = ByteArray::from_utf8("hello".to_string()).into_raw();
+        let view = unsafe { std::slice::from_raw_parts((*bytes).data, (*bytes).size) };
+        assert_eq!(view, b"hello");
+        unsafe { temporal_core_byte_array_free(bytes) };
+        unsafe { temporal_core_byte_array_free(std::ptr::null()) };
+    }
+}
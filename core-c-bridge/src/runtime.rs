@@ -0,0 +1,311 @@
+use crate::{ByteArray, ByteArrayRef};
+use std::{
+    fmt::{Debug, Formatter},
+    mem::ManuallyDrop,
+    net::SocketAddr,
+    sync::{Arc, OnceLock},
+    time::{Duration, UNIX_EPOCH},
+};
+use temporal_sdk_core::{
+    CoreRuntime, TokioRuntimeBuilder,
+    telemetry::{build_otlp_metric_exporter, start_prometheus_metric_exporter},
+};
+use temporal_sdk_core_api::telemetry::{
+    CoreLog, CoreLogConsumer, Logger, MetricTemporality, OtelCollectorOptionsBuilder, OtlpProtocol,
+    PrometheusExporterOptionsBuilder, TelemetryOptionsBuilder, metrics::CoreMeter,
+};
+use tracing_core::Level;
+use url::Url;
+
+#[repr(C)]
+pub struct RuntimeOptions {
+    /// May be null, in which case core emits no logs or metrics
+    pub telemetry: *const TelemetryOptions,
+}
+
+#[repr(C)]
+pub struct TelemetryOptions {
+    /// May be null to disable logging
+    pub logging: *const LoggingOptions,
+    /// May be null to disable metrics
+    pub metrics: *const MetricsOptions,
+}
+
+#[repr(C)]
+pub struct LoggingOptions {
+    /// An [EnvFilter](https://docs.rs/tracing-subscriber/latest/tracing_subscriber/struct.EnvFilter.html)
+    /// filter string
+    pub filter: ByteArrayRef,
+    /// If set, every log is passed to this callback rather than being written to the console
+    pub forward_to: Option<ForwardedLogCallback>,
+}
+
+/// Invoked synchronously with every log, from whichever thread emitted it. The log is only valid
+/// for the duration of the call.
+pub type ForwardedLogCallback =
+    unsafe extern "C" fn(level: ForwardedLogLevel, log: *const ForwardedLog);
+
+/// Exactly one of `opentelemetry` or `prometheus` must be set
+#[repr(C)]
+pub struct MetricsOptions {
+    pub opentelemetry: *const OpenTelemetryOptions,
+    pub prometheus: *const PrometheusOptions,
+    /// Attach a `service_name` label to all metrics
+    pub attach_service_name: bool,
+    /// Newline-delimited pairs of tags applied to all metrics
+    pub global_tags: ByteArrayRef,
+    /// Prefix for every core metric. If empty, the default of `temporal_` is used.
+    pub metric_prefix: ByteArrayRef,
+}
+
+#[repr(C)]
+pub struct OpenTelemetryOptions {
+    pub url: ByteArrayRef,
+    /// Newline-delimited pairs of HTTP headers sent to the collector
+    pub headers: ByteArrayRef,
+    /// If zero, the default of one second is used
+    pub metric_periodicity_millis: u32,
+    pub metric_temporality: MetricTemporalityOption,
+    pub durations_as_seconds: bool,
+    pub protocol: OpenTelemetryProtocol,
+}
+
+#[repr(C)]
+pub enum MetricTemporalityOption {
+    Cumulative = 1,
+    Delta,
+}
+
+#[repr(C)]
+pub enum OpenTelemetryProtocol {
+    Grpc = 1,
+    Http,
+}
+
+#[repr(C)]
+pub struct PrometheusOptions {
+    /// Address to serve metrics on, ex: `127.0.0.1:9090`
+    pub bind_address: ByteArrayRef,
+    pub counters_total_suffix: bool,
+    pub unit_suffix: bool,
+    pub durations_as_seconds: bool,
+}
+
+/// Owns core's runtime. Clients and workers created from it keep it alive.
+#[derive(Clone)]
+pub struct Runtime {
+    /// Only taken when the runtime is dropped
+    pub(crate) core: ManuallyDrop<Arc<CoreRuntime>>,
+}
+
+impl Drop for Runtime {
+    fn drop(&mut self) {
+        // Safety: the field is never touched again
+        let core = unsafe { ManuallyDrop::take(&mut self.core) };
+        // Tokio panics when a runtime is dropped from async context, which is where the last
+        // reference ends up when a client or worker is freed from one of their callbacks
+        if tokio::runtime::Handle::try_current().is_ok() {
+            std::thread::spawn(move || drop(core));
+        }
+    }
+}
+
+#[repr(C)]
+pub struct RuntimeOrFail {
+    /// Null on failure. Free with [temporal_core_runtime_free].
+    pub runtime: *mut Runtime,
+    /// Set on failure
+    pub fail: *const ByteArray,
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn temporal_core_runtime_new(
+    options: *const RuntimeOptions,
+) -> RuntimeOrFail {
+    // Safety: caller promises options is either null or valid for the call
+    let telemetry = unsafe { options.as_ref().and_then(|o| o.telemetry.as_ref()) };
+    match Runtime::new(telemetry) {
+        Ok(runtime) => RuntimeOrFail {
+            runtime: Box::into_raw(Box::new(runtime)),
+            fail: std::ptr::null(),
+        },
+        Err(e) => RuntimeOrFail {
+            runtime: std::ptr::null_mut(),
+            fail: ByteArray::fail(format!("{e:#}")),
+        },
+    }
+}
+
+/// Frees the runtime. Clients and workers created from it keep the underlying runtime alive
+/// until they're freed too.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn temporal_core_runtime_free(runtime: *mut Runtime) {
+    if !runtime.is_null() {
+        // Safety: created by temporal_core_runtime_new, and the caller won't use it again
+        drop(unsafe { Box::from_raw(runtime) });
+    }
+}
+
+impl Runtime {
+    fn new(telemetry: Option<&TelemetryOptions>) -> Result<Self, anyhow::Error> {
+        let mut telemetry_opts = TelemetryOptionsBuilder::default();
+        // Safety: caller promises nested options are either null or valid for the call
+        let (logging, metrics) = telemetry.map_or((None, None), |t| unsafe {
+            (t.logging.as_ref(), t.metrics.as_ref())
+        });
+        if let Some(logging) = logging {
+            let filter = logging.filter.to_string()?;
+            telemetry_opts.logging(if let Some(callback) = logging.forward_to {
+                Logger::Push {
+                    filter,
+                    consumer: Arc::new(LogForwarder { callback }),
+                }
+            } else {
+                Logger::Console { filter }
+            });
+        }
+        if let Some(metrics) = metrics {
+            telemetry_opts.attach_service_name(metrics.attach_service_name);
+            if let Some(prefix) = metrics.metric_prefix.to_option_string()? {
+                telemetry_opts.metric_prefix(prefix);
+            }
+        }
+        let mut core = CoreRuntime::new(telemetry_opts.build()?, TokioRuntimeBuilder::default())?;
+        // Metric exporters need a running tokio runtime, so are attached afterward
+        if let Some(metrics) = metrics {
+            let _guard = core.tokio_handle().enter();
+            let meter = metrics.build_meter()?;
+            core.telemetry_mut().attach_late_init_metrics(meter);
+        }
+        Ok(Self {
+            core: ManuallyDrop::new(Arc::new(core)),
+        })
+    }
+}
+
+impl MetricsOptions {
+    fn build_meter(&self) -> Result<Arc<dyn CoreMeter>, anyhow::Error> {
+        let global_tags = self.global_tags.to_string_map_on_newlines()?;
+        // Safety: caller promises nested options are either null or valid for the call
+        let (otel, prom) = unsafe { (self.opentelemetry.as_ref(), self.prometheus.as_ref()) };
+        match (otel, prom) {
+            (Some(otel), None) => {
+                let mut opts = OtelCollectorOptionsBuilder::default();
+                opts.url(Url::parse(otel.url.to_str()?)?)
+                    .headers(otel.headers.to_string_map_on_newlines()?)
+                    .metric_temporality(match otel.metric_temporality {
+                        MetricTemporalityOption::Cumulative => MetricTemporality::Cumulative,
+                        MetricTemporalityOption::Delta => MetricTemporality::Delta,
+                    })
+                    .global_tags(global_tags)
+                    .use_seconds_for_durations(otel.durations_as_seconds)
+                    .protocol(match otel.protocol {
+                        OpenTelemetryProtocol::Grpc => OtlpProtocol::Grpc,
+                        OpenTelemetryProtocol::Http => OtlpProtocol::Http,
+                    });
+                if otel.metric_periodicity_millis > 0 {
+                    opts.metric_periodicity(Duration::from_millis(
+                        otel.metric_periodicity_millis.into(),
+                    ));
+                }
+                Ok(Arc::new(build_otlp_metric_exporter(opts.build()?)?))
+            }
+            (None, Some(prom)) => {
+                let opts = PrometheusExporterOptionsBuilder::default()
+                    .socket_addr(prom.bind_address.to_str()?.parse::<SocketAddr>()?)
+                    .global_tags(global_tags)
+                    .counters_total_suffix(prom.counters_total_suffix)
+                    .unit_suffix(prom.unit_suffix)
+                    .use_seconds_for_durations(prom.durations_as_seconds)
+                    .build()?;
+                Ok(start_prometheus_metric_exporter(opts)?.meter)
+            }
+            _ => anyhow::bail!("Exactly one of opentelemetry or prometheus must be set"),
+        }
+    }
+}
+
+#[repr(C)]
+pub enum ForwardedLogLevel {
+    Trace = 0,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+/// A log emitted by core, only valid for the duration of a [ForwardedLogCallback]. Use the
+/// `temporal_core_forwarded_log_*` functions to read it.
+pub struct ForwardedLog {
+    core: CoreLog,
+    fields_json: OnceLock<String>,
+}
+
+struct LogForwarder {
+    callback: ForwardedLogCallback,
+}
+
+impl Debug for LogForwarder {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("LogForwarder")
+    }
+}
+
+impl CoreLogConsumer for LogForwarder {
+    fn on_log(&self, log: CoreLog) {
+        let level = match log.level {
+            Level::TRACE => ForwardedLogLevel::Trace,
+            Level::DEBUG => ForwardedLogLevel::Debug,
+            Level::INFO => ForwardedLogLevel::Info,
+            Level::WARN => ForwardedLogLevel::Warn,
+            _ => ForwardedLogLevel::Error,
+        };
+        let log = ForwardedLog {
+            core: log,
+            fields_json: OnceLock::new(),
+        };
+        // Safety: the caller promised the callback is valid for as long as the runtime lives
+        unsafe { (self.callback)(level, &log) };
+    }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn temporal_core_forwarded_log_target(
+    log: *const ForwardedLog,
+) -> ByteArrayRef {
+    // Safety: only called with logs passed to the callback, during the callback
+    ByteArrayRef::borrowed(unsafe { &(*log).core.target })
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn temporal_core_forwarded_log_message(
+    log: *const ForwardedLog,
+) -> ByteArrayRef {
+    // Safety: only called with logs passed to the callback, during the callback
+    ByteArrayRef::borrowed(unsafe { &(*log).core.message })
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn temporal_core_forwarded_log_timestamp_millis(
+    log: *const ForwardedLog,
+) -> u64 {
+    // Safety: only called with logs passed to the callback, during the callback
+    let log = unsafe { &*log };
+    log.core
+        .timestamp
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// The log's structured fields, as a JSON object
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn temporal_core_forwarded_log_fields_json(
+    log: *const ForwardedLog,
+) -> ByteArrayRef {
+    // Safety: only called with logs passed to the callback, during the callback
+    let log = unsafe { &*log };
+    ByteArrayRef::borrowed(log.fields_json.get_or_init(|| {
+        serde_json::to_string(&log.core.fields).unwrap_or_else(|_| "{}".to_owned())
+    }))
+}
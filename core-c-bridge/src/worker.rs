@@ -0,0 +1,456 @@
+use crate::{ByteArray, ByteArrayRef, UserDataHandle, client::Client, runtime::Runtime};
+use prost::Message;
+use std::{ffi::c_void, future::Future, sync::Arc, time::Duration};
+use temporal_sdk_core::{WorkerConfig, WorkerConfigBuilder, init_worker};
+use temporal_sdk_core_api::{Worker as _, errors::PollError};
+use temporal_sdk_core_protos::coresdk::{
+    ActivityHeartbeat, ActivityTaskCompletion, nexus::NexusTaskCompletion,
+    workflow_completion::WorkflowActivationCompletion,
+};
+
+/// Options for creating a worker. Numeric limits left as zero use core's defaults, except for
+/// `max_cached_workflows`, where zero disables the cache.
+#[repr(C)]
+pub struct WorkerOptions {
+    /// Suffixed so the header can be included from C++, where `namespace` is a keyword
+    pub namespace_: ByteArrayRef,
+    pub task_queue: ByteArrayRef,
+    pub build_id: ByteArrayRef,
+    /// If empty, the client's identity is used
+    pub identity_override: ByteArrayRef,
+    pub max_cached_workflows: u32,
+    pub max_outstanding_workflow_tasks: u32,
+    pub max_outstanding_activities: u32,
+    pub max_outstanding_local_activities: u32,
+    pub max_outstanding_nexus_tasks: u32,
+    pub max_concurrent_workflow_task_polls: u32,
+    pub nonsticky_to_sticky_poll_ratio: f32,
+    pub max_concurrent_activity_task_polls: u32,
+    pub max_concurrent_nexus_task_polls: u32,
+    /// Set to only process workflows and local activities
+    pub no_remote_activities: bool,
+    pub sticky_queue_schedule_to_start_timeout_millis: u64,
+    pub max_heartbeat_throttle_interval_millis: u64,
+    pub default_heartbeat_throttle_interval_millis: u64,
+    /// Zero leaves activities unlimited
+    pub max_activities_per_second: f64,
+    /// Zero leaves the task queue unlimited
+    pub max_task_queue_activities_per_second: f64,
+    /// Zero means activities aren't given any time to finish on their own during shutdown
+    pub graceful_shutdown_period_millis: u64,
+}
+
+/// A worker polling a single task queue
+pub struct Worker {
+    runtime: Runtime,
+    /// Taken once the worker is finalized
+    core: Option<Arc<temporal_sdk_core::Worker>>,
+}
+
+#[repr(C)]
+pub struct WorkerOrFail {
+    /// Null on failure. Free with [temporal_core_worker_free].
+    pub worker: *mut Worker,
+    /// Set on failure
+    pub fail: *const ByteArray,
+}
+
+/// Invoked once a call completes. `fail` is set if it failed.
+pub type WorkerCallback = unsafe extern "C" fn(user_data: *mut c_void, fail: *const ByteArray);
+
+/// Invoked with the result of a poll. `success` is set to the encoded protobuf of the polled
+/// activation or task, and `fail` is set if polling failed. If neither is set, the worker has shut
+/// down and there's nothing left to poll for.
+pub type WorkerPollCallback =
+    unsafe extern "C" fn(user_data: *mut c_void, success: *const ByteArray, fail: *const ByteArray);
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn temporal_core_worker_new(
+    client: *mut Client,
+    options: *const WorkerOptions,
+) -> WorkerOrFail {
+    // Safety: caller promises pointers are valid for the call
+    let (client, options) = unsafe { (&*client, &*options) };
+    let created = options.to_core_config().and_then(|config| {
+        let _guard = client.runtime.core.tokio_handle().enter();
+        init_worker(&client.runtime.core, config, client.core.clone())
+    });
+    match created {
+        Ok(core) => WorkerOrFail {
+            worker: Box::into_raw(Box::new(Worker {
+                runtime: client.runtime.clone(),
+                core: Some(Arc::new(core)),
+            })),
+            fail: std::ptr::null(),
+        },
+        Err(e) => WorkerOrFail {
+            worker: std::ptr::null_mut(),
+            fail: ByteArray::fail(format!("{e:#}")),
+        },
+    }
+}
+
+/// Frees the worker. Workers should be shut down with [temporal_core_worker_finalize_shutdown]
+/// before they're freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn temporal_core_worker_free(worker: *mut Worker) {
+    if !worker.is_null() {
+        // Safety: created by temporal_core_worker_new, and the caller won't use it again
+        drop(unsafe { Box::from_raw(worker) });
+    }
+}
+
+/// Checks the worker's namespace exists and that it can reach the server
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn temporal_core_worker_validate(
+    worker: *mut Worker,
+    user_data: *mut c_void,
+    callback: WorkerCallback,
+) {
+    // Safety: caller promises the worker is valid for the call
+    let worker = unsafe { &*worker };
+    worker.spawn_call(user_data, callback, |core| async move {
+        core.validate().await.map_err(Into::into)
+    });
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn temporal_core_worker_poll_workflow_activation(
+    worker: *mut Worker,
+    user_data: *mut c_void,
+    callback: WorkerPollCallback,
+) {
+    // Safety: caller promises the worker is valid for the call
+    let worker = unsafe { &*worker };
+    worker.spawn_poll(user_data, callback, |core| async move {
+        core.poll_workflow_activation().await
+    });
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn temporal_core_worker_poll_activity_task(
+    worker: *mut Worker,
+    user_data: *mut c_void,
+    callback: WorkerPollCallback,
+) {
+    // Safety: caller promises the worker is valid for the call
+    let worker = unsafe { &*worker };
+    worker.spawn_poll(user_data, callback, |core| async move {
+        core.poll_activity_task().await
+    });
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn temporal_core_worker_poll_nexus_task(
+    worker: *mut Worker,
+    user_data: *mut c_void,
+    callback: WorkerPollCallback,
+) {
+    // Safety: caller promises the worker is valid for the call
+    let worker = unsafe { &*worker };
+    worker.spawn_poll(user_data, callback, |core| async move {
+        core.poll_nexus_task().await
+    });
+}
+
+/// Completes an activation, given an encoded `WorkflowActivationCompletion`
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn temporal_core_worker_complete_workflow_activation(
+    worker: *mut Worker,
+    completion: ByteArrayRef,
+    user_data: *mut c_void,
+    callback: WorkerCallback,
+) {
+    // Safety: caller promises the worker is valid for the call
+    let worker = unsafe { &*worker };
+    let completion = WorkflowActivationCompletion::decode(completion.to_slice());
+    worker.spawn_call(user_data, callback, |core| async move {
+        Ok(core.complete_workflow_activation(completion?).await?)
+    });
+}
+
+/// Completes an activity task, given an encoded `ActivityTaskCompletion`
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn temporal_core_worker_complete_activity_task(
+    worker: *mut Worker,
+    completion: ByteArrayRef,
+    user_data: *mut c_void,
+    callback: WorkerCallback,
+) {
+    // Safety: caller promises the worker is valid for the call
+    let worker = unsafe { &*worker };
+    let completion = ActivityTaskCompletion::decode(completion.to_slice());
+    worker.spawn_call(user_data, callback, |core| async move {
+        Ok(core.complete_activity_task(completion?).await?)
+    });
+}
+
+/// Completes a nexus task, given an encoded `NexusTaskCompletion`
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn temporal_core_worker_complete_nexus_task(
+    worker: *mut Worker,
+    completion: ByteArrayRef,
+    user_data: *mut c_void,
+    callback: WorkerCallback,
+) {
+    // Safety: caller promises the worker is valid for the call
+    let worker = unsafe { &*worker };
+    let completion = NexusTaskCompletion::decode(completion.to_slice());
+    worker.spawn_call(user_data, callback, |core| async move {
+        Ok(core.complete_nexus_task(completion?).await?)
+    });
+}
+
+/// Records a heartbeat, given an encoded `ActivityHeartbeat`. Returns a failure if the heartbeat
+/// couldn't be decoded, else null.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn temporal_core_worker_record_activity_heartbeat(
+    worker: *mut Worker,
+    heartbeat: ByteArrayRef,
+) -> *const ByteArray {
+    // Safety: caller promises the worker is valid for the call
+    let worker = unsafe { &*worker };
+    let recorded = ActivityHeartbeat::decode(heartbeat.to_slice())
+        .map_err(anyhow::Error::from)
+        .and_then(|hb| Ok(worker.core()?.record_activity_heartbeat(hb)));
+    match recorded {
+        Ok(()) => std::ptr::null(),
+        Err(e) => ByteArray::fail(e),
+    }
+}
+
+/// Requests the run be evicted from the cache. Returns a failure if the run id isn't valid UTF-8
+/// or the worker has been finalized, else null.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn temporal_core_worker_request_workflow_eviction(
+    worker: *mut Worker,
+    run_id: ByteArrayRef,
+) -> *const ByteArray {
+    // Safety: caller promises the worker is valid for the call
+    let worker = unsafe { &*worker };
+    let requested = run_id
+        .to_str()
+        .and_then(|run_id| Ok(worker.core()?.request_workflow_eviction(run_id)));
+    match requested {
+        Ok(()) => std::ptr::null(),
+        Err(e) => ByteArray::fail(e),
+    }
+}
+
+/// Begins shutting the worker down. Polls keep returning remaining work until there is none, at
+/// which point they return with neither success nor failure.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn temporal_core_worker_initiate_shutdown(worker: *mut Worker) {
+    // Safety: caller promises the worker is valid for the call
+    let worker = unsafe { &*worker };
+    if let Ok(core) = worker.core() {
+        core.initiate_shutdown();
+    }
+}
+
+/// Waits for the worker to finish shutting down and releases its resources. Must only be called
+/// once every poll has returned, after which no other calls may be made with the worker except
+/// [temporal_core_worker_free].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn temporal_core_worker_finalize_shutdown(
+    worker: *mut Worker,
+    user_data: *mut c_void,
+    callback: WorkerCallback,
+) {
+    // Safety: caller promises the worker is valid for the call, and not used concurrently
+    let worker = unsafe { &mut *worker };
+    let user_data = UserDataHandle(user_data);
+    let core = worker.core.take();
+    worker.runtime.core.tokio_handle().spawn(async move {
+        let user_data = user_data;
+        let fail = match core.map(Arc::try_unwrap) {
+            Some(Ok(core)) => {
+                core.finalize_shutdown().await;
+                std::ptr::null()
+            }
+            Some(Err(_)) => ByteArray::fail("Worker is still in use, ex: by an outstanding poll"),
+            None => ByteArray::fail("Worker has already been finalized"),
+        };
+        // Safety: the caller promised the callback is valid
+        unsafe { callback(user_data.0, fail) };
+    });
+}
+
+impl Worker {
+    fn core(&self) -> Result<&Arc<temporal_sdk_core::Worker>, anyhow::Error> {
+        self.core
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Worker has already been finalized"))
+    }
+
+    /// Runs `call` on the runtime, then reports its outcome to `callback`
+    fn spawn_call<F, Fut>(&self, user_data: *mut c_void, callback: WorkerCallback, call: F)
+    where
+        F: FnOnce(Arc<temporal_sdk_core::Worker>) -> Fut,
+        Fut: Future<Output = Result<(), anyhow::Error>> + Send + 'static,
+    {
+        let user_data = UserDataHandle(user_data);
+        let called = self.core().cloned().map(call);
+        self.runtime.core.tokio_handle().spawn(async move {
+            let user_data = user_data;
+            let fail = match called {
+                Ok(fut) => fut.await.err().map_or(std::ptr::null(), ByteArray::fail),
+                Err(e) => ByteArray::fail(e),
+            };
+            // Safety: the caller promised the callback is valid
+            unsafe { callback(user_data.0, fail) };
+        });
+    }
+
+    /// Runs `poll` on the runtime, then reports the encoded result to `callback`
+    fn spawn_poll<F, Fut, T>(&self, user_data: *mut c_void, callback: WorkerPollCallback, poll: F)
+    where
+        F: FnOnce(Arc<temporal_sdk_core::Worker>) -> Fut,
+        Fut: Future<Output = Result<T, PollError>> + Send + 'static,
+        T: Message,
+    {
+        let user_data = UserDataHandle(user_data);
+        let polled = self.core().cloned().map(poll);
+        self.runtime.core.tokio_handle().spawn(async move {
+            let user_data = user_data;
+            let (success, fail) = match polled {
+                Ok(fut) => match fut.await {
+                    Ok(polled) => (
+                        ByteArray::from_vec(polled.encode_to_vec()).into_raw(),
+                        std::ptr::null(),
+                    ),
+                    Err(PollError::ShutDown) => (std::ptr::null(), std::ptr::null()),
+                    Err(e) => (std::ptr::null(), ByteArray::fail(e)),
+                },
+                Err(e) => (std::ptr::null(), ByteArray::fail(e)),
+            };
+            // Safety: the caller promised the callback is valid
+            unsafe { callback(user_data.0, success, fail) };
+        });
+    }
+}
+
+impl WorkerOptions {
+    fn to_core_config(&self) -> Result<WorkerConfig, anyhow::Error> {
+        let nonzero = |v: u32| (v > 0).then_some(v as usize);
+        let millis = |v: u64| (v > 0).then(|| Duration::from_millis(v));
+        let positive = |v: f64| (v > 0.0).then_some(v);
+        let mut config = WorkerConfigBuilder::default();
+        config
+            .namespace(self.namespace_.to_string()?)
+            .task_queue(self.task_queue.to_string()?)
+            .worker_build_id(self.build_id.to_string()?)
+            .client_identity_override(self.identity_override.to_option_string()?)
+            .max_cached_workflows(self.max_cached_workflows as usize)
+            .no_remote_activities(self.no_remote_activities)
+            .max_worker_activities_per_second(positive(self.max_activities_per_second))
+            .max_task_queue_activities_per_second(positive(
+                self.max_task_queue_activities_per_second,
+            ))
+            .graceful_shutdown_period(millis(self.graceful_shutdown_period_millis));
+        if let Some(v) = nonzero(self.max_outstanding_workflow_tasks) {
+            config.max_outstanding_workflow_tasks(v);
+        }
+        if let Some(v) = nonzero(self.max_outstanding_activities) {
+            config.max_outstanding_activities(v);
+        }
+        if let Some(v) = nonzero(self.max_outstanding_local_activities) {
+            config.max_outstanding_local_activities(v);
+        }
+        if let Some(v) = nonzero(self.max_outstanding_nexus_tasks) {
+            config.max_outstanding_nexus_tasks(v);
+        }
+        if let Some(v) = nonzero(self.max_concurrent_workflow_task_polls) {
+            config.max_concurrent_wft_polls(v);
+        }
+        if self.nonsticky_to_sticky_poll_ratio > 0.0 {
+            config.nonsticky_to_sticky_poll_ratio(self.nonsticky_to_sticky_poll_ratio);
+        }
+        if let Some(v) = nonzero(self.max_concurrent_activity_task_polls) {
+            config.max_concurrent_at_polls(v);
+        }
+        if let Some(v) = nonzero(self.max_concurrent_nexus_task_polls) {
+            config.max_concurrent_nexus_polls(v);
+        }
+        if let Some(v) = millis(self.sticky_queue_schedule_to_start_timeout_millis) {
+            config.sticky_queue_schedule_to_start_timeout(v);
+        }
+        if let Some(v) = millis(self.max_heartbeat_throttle_interval_millis) {
+            config.max_heartbeat_throttle_interval(v);
+        }
+        if let Some(v) = millis(self.default_heartbeat_throttle_interval_millis) {
+            config.default_heartbeat_throttle_interval(v);
+        }
+        Ok(config.build()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(task_queue: &str) -> WorkerOptions {
+        WorkerOptions {
+            namespace_: ByteArrayRef::borrowed("default"),
+            task_queue: ByteArrayRef::borrowed(task_queue),
+            build_id: ByteArrayRef::borrowed("build"),
+            identity_override: ByteArrayRef::borrowed(""),
+            max_cached_workflows: 0,
+            max_outstanding_workflow_tasks: 0,
+            max_outstanding_activities: 7,
+            max_outstanding_local_activities: 0,
+            max_outstanding_nexus_tasks: 0,
+            max_concurrent_workflow_task_polls: 0,
+            nonsticky_to_sticky_poll_ratio: 0.0,
+            max_concurrent_activity_task_polls: 0,
+            max_concurrent_nexus_task_polls: 0,
+            no_remote_activities: false,
+            sticky_queue_schedule_to_start_timeout_millis: 0,
+            max_heartbeat_throttle_interval_millis: 0,
+            default_heartbeat_throttle_interval_millis: 0,
+            max_activities_per_second: 0.0,
+            max_task_queue_activities_per_second: 2.5,
+            graceful_shutdown_period_millis: 100,
+        }
+    }
+
+    #[test]
+    fn zeroes_use_core_defaults() {
+        let config = options("tq").to_core_config().unwrap();
+        let defaults = WorkerConfigBuilder::default()
+            .namespace("default")
+            .task_queue("tq")
+            .worker_build_id("build")
+            .build()
+            .unwrap();
+        assert_eq!(config.task_queue, "tq");
+        assert_eq!(config.client_identity_override, None);
+        assert_eq!(config.max_outstanding_activities, Some(7));
+        assert_eq!(config.max_outstanding_workflow_tasks, None);
+        assert_eq!(
+            config.max_concurrent_wft_polls,
+            defaults.max_concurrent_wft_polls
+        );
+        assert_eq!(
+            config.sticky_queue_schedule_to_start_timeout,
+            defaults.sticky_queue_schedule_to_start_timeout
+        );
+        assert_eq!(config.max_worker_activities_per_second, None);
+        assert_eq!(config.max_task_queue_activities_per_second, Some(2.5));
+        assert_eq!(
+            config.graceful_shutdown_period,
+            Some(Duration::from_millis(100))
+        );
+    }
+
+    #[test]
+    fn invalid_utf8_fails() {
+        let mut opts = options("tq");
+        let bad = [0xff, 0xfe];
+        opts.namespace_ = ByteArrayRef {
+            data: bad.as_ptr(),
+            size: bad.len(),
+        };
+        assert!(opts.to_core_config().is_err());
+    }
+}
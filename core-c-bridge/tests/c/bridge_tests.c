@@ -0,0 +1,307 @@
+// Exercises the bridge the way a C caller would. Run by c_tests.rs, which starts an in-memory server
+// for the tests which need one and passes its url in TEMPORAL_TEST_SERVER_URL.
+
+#include <pthread.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "temporal-sdk-core-c-bridge.h"
+
+#define CHECK(cond)                                                      \
+  do {                                                                   \
+    if (!(cond)) {                                                       \
+      fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #cond); \
+      exit(1);                                                           \
+    }                                                                    \
+  } while (0)
+
+static TemporalCoreByteArrayRef str_ref(const char *s) {
+  TemporalCoreByteArrayRef ref = {(const uint8_t *)s, strlen(s)};
+  return ref;
+}
+
+// Lets the main thread wait for a callback invoked on one of the runtime's threads
+typedef struct {
+  pthread_mutex_t mutex;
+  pthread_cond_t cond;
+  bool done;
+  TemporalCoreClient *client;
+  const TemporalCoreByteArray *fail;
+} connect_result;
+
+static void on_connect(void *user_data, TemporalCoreClient *success,
+                       const TemporalCoreByteArray *fail) {
+  connect_result *result = user_data;
+  pthread_mutex_lock(&result->mutex);
+  result->client = success;
+  result->fail = fail;
+  result->done = true;
+  pthread_cond_signal(&result->cond);
+  pthread_mutex_unlock(&result->mutex);
+}
+
+// Lets the main thread wait for a worker call or poll to invoke its callback
+typedef struct {
+  pthread_mutex_t mutex;
+  pthread_cond_t cond;
+  bool done;
+  const TemporalCoreByteArray *success;
+  const TemporalCoreByteArray *fail;
+} call_result;
+
+#define CALL_RESULT_INIT {PTHREAD_MUTEX_INITIALIZER, PTHREAD_COND_INITIALIZER, false, NULL, NULL}
+
+static void finish_call(call_result *result, const TemporalCoreByteArray *success,
+                        const TemporalCoreByteArray *fail) {
+  pthread_mutex_lock(&result->mutex);
+  result->success = success;
+  result->fail = fail;
+  result->done = true;
+  pthread_cond_signal(&result->cond);
+  pthread_mutex_unlock(&result->mutex);
+}
+
+static void on_poll(void *user_data, const TemporalCoreByteArray *success,
+                    const TemporalCoreByteArray *fail) {
+  finish_call(user_data, success, fail);
+}
+
+static void on_worker_call(void *user_data, const TemporalCoreByteArray *fail) {
+  finish_call(user_data, NULL, fail);
+}
+
+static void wait_for_call(call_result *result) {
+  pthread_mutex_lock(&result->mutex);
+  while (!result->done) {
+    pthread_cond_wait(&result->cond, &result->mutex);
+  }
+  pthread_mutex_unlock(&result->mutex);
+}
+
+static TemporalCoreClient *connect_to_test_server(TemporalCoreRuntime *runtime) {
+  const char *url = getenv("TEMPORAL_TEST_SERVER_URL");
+  CHECK(url != NULL);
+  TemporalCoreClientOptions options = {0};
+  options.target_url = str_ref(url);
+  options.client_name = str_ref("c-bridge-tests");
+  options.client_version = str_ref("0.1.0");
+
+  connect_result result = {PTHREAD_MUTEX_INITIALIZER, PTHREAD_COND_INITIALIZER, false, NULL,
+                           NULL};
+  temporal_core_client_connect(runtime, &options, &result, on_connect);
+  pthread_mutex_lock(&result.mutex);
+  while (!result.done) {
+    pthread_cond_wait(&result.cond, &result.mutex);
+  }
+  pthread_mutex_unlock(&result.mutex);
+  CHECK(result.fail == NULL);
+  CHECK(result.client != NULL);
+  return result.client;
+}
+
+static TemporalCoreWorkerOptions worker_options(void) {
+  TemporalCoreWorkerOptions options = {0};
+  options.namespace_ = str_ref("default");
+  options.task_queue = str_ref("c-bridge-tests");
+  options.build_id = str_ref("c-bridge-tests");
+  return options;
+}
+
+typedef void (*poll_fn)(TemporalCoreWorker *worker, void *user_data,
+                        TemporalCoreWorkerPollCallback callback);
+
+// Polls until the worker reports it has shut down. No work is ever scheduled, so nothing may be
+// polled successfully along the way.
+static void poll_until_shutdown(TemporalCoreWorker *worker, poll_fn poll) {
+  for (;;) {
+    call_result result = CALL_RESULT_INIT;
+    poll(worker, &result, on_poll);
+    wait_for_call(&result);
+    CHECK(result.success == NULL);
+    if (result.fail == NULL) {
+      return;
+    }
+    temporal_core_byte_array_free(result.fail);
+  }
+}
+
+static void test_runtime_lifecycle(void) {
+  TemporalCoreRuntimeOrFail created = temporal_core_runtime_new(NULL);
+  CHECK(created.fail == NULL);
+  CHECK(created.runtime != NULL);
+  temporal_core_runtime_free(created.runtime);
+}
+
+static void test_bad_metrics_options_fail(void) {
+  TemporalCorePrometheusOptions prom = {0};
+  prom.bind_address = str_ref("not an address");
+  TemporalCoreMetricsOptions metrics = {0};
+  metrics.prometheus = &prom;
+  TemporalCoreTelemetryOptions telemetry = {NULL, &metrics};
+  TemporalCoreRuntimeOptions options = {&telemetry};
+
+  TemporalCoreRuntimeOrFail created = temporal_core_runtime_new(&options);
+  CHECK(created.runtime == NULL);
+  CHECK(created.fail != NULL);
+  CHECK(created.fail->size > 0);
+  temporal_core_byte_array_free(created.fail);
+}
+
+static void test_connect_failure_reaches_callback(void) {
+  TemporalCoreRuntimeOrFail created = temporal_core_runtime_new(NULL);
+  CHECK(created.runtime != NULL);
+
+  TemporalCoreClientOptions options = {0};
+  // Nothing listens on port 1, so connecting fails quickly
+  options.target_url = str_ref("http://127.0.0.1:1");
+  options.client_name = str_ref("c-bridge-tests");
+  options.client_version = str_ref("0.1.0");
+
+  connect_result result = {PTHREAD_MUTEX_INITIALIZER, PTHREAD_COND_INITIALIZER, false, NULL,
+                           NULL};
+  temporal_core_client_connect(created.runtime, &options, &result, on_connect);
+  pthread_mutex_lock(&result.mutex);
+  while (!result.done) {
+    pthread_cond_wait(&result.cond, &result.mutex);
+  }
+  pthread_mutex_unlock(&result.mutex);
+
+  CHECK(result.client == NULL);
+  CHECK(result.fail != NULL);
+  temporal_core_byte_array_free(result.fail);
+  temporal_core_runtime_free(created.runtime);
+}
+
+// Frees the client, and with it the last reference to the runtime, from the connect callback,
+// which runs on one of the runtime's own threads
+typedef struct {
+  pthread_mutex_t mutex;
+  pthread_cond_t cond;
+  bool runtime_freed;
+  bool done;
+  const TemporalCoreByteArray *fail;
+} free_in_callback;
+
+static void on_connect_free(void *user_data, TemporalCoreClient *success,
+                            const TemporalCoreByteArray *fail) {
+  free_in_callback *state = user_data;
+  pthread_mutex_lock(&state->mutex);
+  while (!state->runtime_freed) {
+    pthread_cond_wait(&state->cond, &state->mutex);
+  }
+  temporal_core_client_free(success);
+  state->fail = fail;
+  state->done = true;
+  pthread_cond_broadcast(&state->cond);
+  pthread_mutex_unlock(&state->mutex);
+}
+
+static void test_free_from_callback(void) {
+  TemporalCoreRuntimeOrFail created = temporal_core_runtime_new(NULL);
+  CHECK(created.runtime != NULL);
+  const char *url = getenv("TEMPORAL_TEST_SERVER_URL");
+  CHECK(url != NULL);
+  TemporalCoreClientOptions options = {0};
+  options.target_url = str_ref(url);
+  options.client_name = str_ref("c-bridge-tests");
+  options.client_version = str_ref("0.1.0");
+
+  free_in_callback state = {PTHREAD_MUTEX_INITIALIZER, PTHREAD_COND_INITIALIZER, false, false,
+                            NULL};
+  temporal_core_client_connect(created.runtime, &options, &state, on_connect_free);
+  // The callback waits for this, so the client it frees holds the runtime's last reference
+  temporal_core_runtime_free(created.runtime);
+  pthread_mutex_lock(&state.mutex);
+  state.runtime_freed = true;
+  pthread_cond_broadcast(&state.cond);
+  while (!state.done) {
+    pthread_cond_wait(&state.cond, &state.mutex);
+  }
+  pthread_mutex_unlock(&state.mutex);
+  CHECK(state.fail == NULL);
+}
+
+static void test_worker_lifecycle(void) {
+  TemporalCoreRuntimeOrFail created = temporal_core_runtime_new(NULL);
+  CHECK(created.runtime != NULL);
+  TemporalCoreClient *client = connect_to_test_server(created.runtime);
+
+  TemporalCoreWorkerOptions options = worker_options();
+  TemporalCoreWorkerOrFail worker = temporal_core_worker_new(client, &options);
+  CHECK(worker.fail == NULL);
+  CHECK(worker.worker != NULL);
+  // The worker keeps its own connection alive
+  temporal_core_client_free(client);
+
+  const TemporalCoreByteArray *evict_fail =
+      temporal_core_worker_request_workflow_eviction(worker.worker, str_ref("some-run"));
+  CHECK(evict_fail == NULL);
+  const uint8_t not_utf8[] = {0xff, 0xfe};
+  TemporalCoreByteArrayRef bad_run_id = {not_utf8, sizeof(not_utf8)};
+  evict_fail = temporal_core_worker_request_workflow_eviction(worker.worker, bad_run_id);
+  CHECK(evict_fail != NULL);
+  CHECK(evict_fail->size > 0);
+  temporal_core_byte_array_free(evict_fail);
+
+  temporal_core_worker_initiate_shutdown(worker.worker);
+  poll_until_shutdown(worker.worker, temporal_core_worker_poll_workflow_activation);
+  poll_until_shutdown(worker.worker, temporal_core_worker_poll_activity_task);
+  call_result finalized = CALL_RESULT_INIT;
+  temporal_core_worker_finalize_shutdown(worker.worker, &finalized, on_worker_call);
+  wait_for_call(&finalized);
+  CHECK(finalized.fail == NULL);
+
+  // Finalizing twice reports a failure rather than crashing
+  call_result refinalized = CALL_RESULT_INIT;
+  temporal_core_worker_finalize_shutdown(worker.worker, &refinalized, on_worker_call);
+  wait_for_call(&refinalized);
+  CHECK(refinalized.fail != NULL);
+  temporal_core_byte_array_free(refinalized.fail);
+
+  // As do calls with a finalized worker
+  evict_fail = temporal_core_worker_request_workflow_eviction(worker.worker, str_ref("some-run"));
+  CHECK(evict_fail != NULL);
+  temporal_core_byte_array_free(evict_fail);
+
+  temporal_core_worker_free(worker.worker);
+  temporal_core_runtime_free(created.runtime);
+}
+
+static void test_bad_worker_options_fail(void) {
+  TemporalCoreRuntimeOrFail created = temporal_core_runtime_new(NULL);
+  CHECK(created.runtime != NULL);
+  TemporalCoreClient *client = connect_to_test_server(created.runtime);
+
+  TemporalCoreWorkerOptions options = worker_options();
+  const uint8_t not_utf8[] = {0xff, 0xfe};
+  TemporalCoreByteArrayRef bad_namespace = {not_utf8, sizeof(not_utf8)};
+  options.namespace_ = bad_namespace;
+  TemporalCoreWorkerOrFail worker = temporal_core_worker_new(client, &options);
+  CHECK(worker.worker == NULL);
+  CHECK(worker.fail != NULL);
+  CHECK(worker.fail->size > 0);
+  temporal_core_byte_array_free(worker.fail);
+
+  temporal_core_client_free(client);
+  temporal_core_runtime_free(created.runtime);
+}
+
+static void test_free_null(void) {
+  temporal_core_byte_array_free(NULL);
+  temporal_core_client_free(NULL);
+  temporal_core_worker_free(NULL);
+  temporal_core_runtime_free(NULL);
+}
+
+int main(void) {
+  test_runtime_lifecycle();
+  test_bad_metrics_options_fail();
+  test_connect_failure_reaches_callback();
+  test_free_from_callback();
+  test_worker_lifecycle();
+  test_bad_worker_options_fail();
+  test_free_null();
+  printf("All bridge tests passed\n");
+  return 0;
+}
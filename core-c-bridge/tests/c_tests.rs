@@ -0,0 +1,51 @@
+//! Compiles the C tests against the static library built for this profile and runs them, which
+//! checks the header and library agree with each other as well as with a real C compiler. Fails
+//! when no C compiler is installed rather than passing without testing anything.
+
+#![cfg(target_os = "linux")]
+
+use std::{path::PathBuf, process::Command};
+use temporal_sdk_core_test_utils::in_memory_server::InMemoryServer;
+
+#[test]
+fn c_bridge_tests() {
+    assert!(
+        Command::new("cc").arg("--version").output().is_ok(),
+        "The C bridge tests need a C compiler (`cc`) on the path"
+    );
+    let crate_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    // Test binaries live in target/<profile>/deps, next to which the library is built
+    let profile_dir = std::env::current_exe()
+        .unwrap()
+        .parent()
+        .and_then(|deps| deps.parent())
+        .unwrap()
+        .to_path_buf();
+    let lib = profile_dir.join("libtemporal_sdk_core_c_bridge.a");
+    assert!(
+        lib.exists(),
+        "Static library not found at {}",
+        lib.display()
+    );
+    let exe = profile_dir.join("c_bridge_tests");
+
+    let compiled = Command::new("cc")
+        .arg(crate_dir.join("tests/c/bridge_tests.c"))
+        .arg("-I")
+        .arg(crate_dir.join("include"))
+        .arg(&lib)
+        .args(["-lpthread", "-ldl", "-lm", "-o"])
+        .arg(&exe)
+        .status()
+        .expect("Failed to run C compiler");
+    assert!(compiled.success(), "Compiling C tests failed");
+
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let server = rt.block_on(InMemoryServer::start()).unwrap();
+    let ran = Command::new(&exe)
+        .env("TEMPORAL_TEST_SERVER_URL", server.target_url().as_str())
+        .status()
+        .unwrap();
+    rt.block_on(server.shutdown());
+    assert!(ran.success(), "C tests failed");
+}
@@ -0,0 +1,21 @@
+//! Checks the committed header matches the one cbindgen generates from the current source. Set
+//! `UPDATE_C_BRIDGE_HEADER=1` to overwrite the committed header instead.
+
+use std::path::PathBuf;
+
+#[test]
+fn committed_header_is_up_to_date() {
+    let generated = include_str!(concat!(env!("OUT_DIR"), "/temporal-sdk-core-c-bridge.h"));
+    let committed_path =
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("include/temporal-sdk-core-c-bridge.h");
+    if std::env::var_os("UPDATE_C_BRIDGE_HEADER").is_some() {
+        std::fs::write(&committed_path, generated).unwrap();
+        return;
+    }
+    let committed = std::fs::read_to_string(&committed_path).unwrap();
+    assert!(
+        committed == generated,
+        "{} is out of date, rerun this test with UPDATE_C_BRIDGE_HEADER=1 to regenerate it",
+        committed_path.display()
+    );
+}
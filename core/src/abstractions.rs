@@ -2,7 +2,7 @@
 
 pub(crate) mod take_cell;
 
-use crate::MetricsContext;
+use crate::{MetricsContext, worker::debug_snapshot::SlotUsageDebugSnapshot};
 use std::{
     fmt::{Debug, Formatter},
     sync::{
@@ -75,6 +75,16 @@ where
         self.supplier.available_slots()
     }
 
+    pub(crate) fn debug_snapshot(&self) -> SlotUsageDebugSnapshot {
+        let issued = *self.extant_permits.1.borrow();
+        SlotUsageDebugSnapshot {
+            kind: SK::kind(),
+            issued,
+            in_use: issued.saturating_sub(self.unused_claimants.load(Ordering::Acquire)),
+            available: self.available_permits(),
+        }
+    }
+
     #[cfg(test)]
     pub(crate) fn unused_permits(&self) -> Option<usize> {
         self.available_permits()
@@ -362,6 +372,22 @@ impl<SK: SlotKind> OwnedMeteredSemPermit<SK> {
 
 #[derive(Debug)]
 pub(crate) struct UsedMeteredSemPermit<SK: SlotKind>(#[allow(dead_code)] OwnedMeteredSemPermit<SK>);
+#[cfg(feature = "save_wf_inputs")]
+impl<SK: SlotKind> UsedMeteredSemPermit<SK> {
+    /// Stands in for the permit of a WFT replayed from recorded workflow stream inputs, which
+    /// isn't recorded since it's irrelevant to workflow state.
+    pub(crate) fn fake_deserialized() -> Self {
+        UsedMeteredSemPermit(OwnedMeteredSemPermit {
+            unused_claimants: None,
+            release_ctx: ReleaseCtx {
+                permit: SlotSupplierPermit::default(),
+                stored_info: None,
+            },
+            use_fn: Box::new(|_| {}),
+            release_fn: Box::new(|_| {}),
+        })
+    }
+}
 
 macro_rules! dbg_panic {
   ($($arg:tt)*) => {
@@ -1,19 +1,28 @@
 use crate::{
+    replay::DEFAULT_WORKFLOW_TYPE,
     test_help::{
         MockPollCfg, MocksHolder, ResponseType, WorkerExt, build_mock_pollers, canned_histories,
-        hist_to_poll_resp, mock_worker, single_hist_mock_sg,
+        hist_to_poll_resp, mock_sdk_cfg, mock_worker, single_hist_mock_sg,
     },
     worker::{LEGACY_QUERY_ID, client::mocks::mock_workflow_client},
 };
 use futures_util::stream;
 use std::{
     collections::{HashMap, VecDeque},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
     time::Duration,
 };
+use temporal_client::WorkflowOptions;
+use temporal_sdk::{QueryInfo, WfContext};
 use temporal_sdk_core_api::Worker as WorkerTrait;
 use temporal_sdk_core_protos::{
     TestHistoryBuilder,
+    constants::STACK_TRACE_QUERY_NAME,
     coresdk::{
+        AsJsonPayloadExt, FromJsonPayloadExt,
         workflow_activation::{
             WorkflowActivationJob, remove_from_cache::EvictionReason, workflow_activation_job,
         },
@@ -1022,3 +1031,76 @@ async fn queries_arent_lost_in_buffer_void(#[values(false, true)] buffered_becau
 
     core.shutdown().await;
 }
+
+#[tokio::test]
+async fn sdk_query_handlers() {
+    let wfid = "fake_wf_id";
+    let t = canned_histories::single_timer("1");
+    let query = |query_type: &str, query_args: Option<Payload>| WorkflowQuery {
+        query_type: query_type.to_string(),
+        query_args: query_args.map(Into::into),
+        header: None,
+    };
+    let tasks = [hist_to_poll_resp(&t, wfid.to_owned(), 1.into()), {
+        let mut pr = hist_to_poll_resp(&t, wfid.to_owned(), ResponseType::OneTask(2));
+        pr.queries = HashMap::from([
+            (
+                "registered".to_string(),
+                query("fired", Some("Timer fired".as_json_payload().unwrap())),
+            ),
+            ("unregistered".to_string(), query("nope", None)),
+            ("stack".to_string(), query(STACK_TRACE_QUERY_NAME, None)),
+        ]);
+        pr
+    }];
+    let mut mh = MockPollCfg::from_resp_batches(wfid, t, tasks, mock_workflow_client());
+    mh.completion_mock_fn = Some(Box::new(|c| {
+        if c.commands[0].command_type() == CommandType::CompleteWorkflowExecution {
+            assert_eq!(c.query_responses.len(), 3);
+            for qr in c.query_responses.iter() {
+                match qr.query_id.as_str() {
+                    "registered" => assert_matches!(
+                        qr.variant.as_ref(),
+                        Some(query_result::Variant::Succeeded(qs)) => {
+                            let resp =
+                                String::from_json_payload(qs.response.as_ref().unwrap()).unwrap();
+                            assert_eq!(resp, "Timer fired: true");
+                        }
+                    ),
+                    "unregistered" => assert_matches!(
+                        qr.variant.as_ref(),
+                        Some(query_result::Variant::Failed(f)) if f.message.contains("nope")
+                    ),
+                    "stack" => assert_matches!(
+                        qr.variant.as_ref(),
+                        Some(query_result::Variant::Succeeded(_))
+                    ),
+                    other => panic!("Unexpected query response {other}"),
+                }
+            }
+        }
+        Ok(Default::default())
+    }));
+    let mut worker = mock_sdk_cfg(mh, |wc| wc.max_cached_workflows = 10);
+
+    worker.register_wf(DEFAULT_WORKFLOW_TYPE, |ctx: WfContext| async move {
+        let fired = Arc::new(AtomicBool::new(false));
+        let fired_c = fired.clone();
+        ctx.query_handler("fired", move |_: &QueryInfo, prefix: String| {
+            Ok(format!("{prefix}: {}", fired_c.load(Ordering::Acquire)))
+        });
+        ctx.timer(Duration::from_secs(1)).await;
+        fired.store(true, Ordering::Release);
+        Ok(().into())
+    });
+    worker
+        .submit_wf(
+            wfid.to_owned(),
+            DEFAULT_WORKFLOW_TYPE.to_owned(),
+            vec![],
+            WorkflowOptions::default(),
+        )
+        .await
+        .unwrap();
+    worker.run_until_done().await.unwrap();
+}
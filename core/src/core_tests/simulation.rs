@@ -0,0 +1,47 @@
+use crate::test_help::{Simulation, SimulationConfig};
+use std::time::Duration;
+
+#[rstest::rstest]
+fn simulated_workflows_all_complete(
+    #[values(0.0, 0.3)] rpc_failure_rate: f64,
+    #[values(0.0, 0.3)] eviction_rate: f64,
+    #[values(1, 2)] seed: u64,
+) {
+    Simulation::new(
+        seed,
+        SimulationConfig {
+            rpc_failure_rate,
+            eviction_rate,
+            ..Default::default()
+        },
+    )
+    .run();
+}
+
+#[rstest::rstest]
+fn simulated_shutdown_mid_run(#[values(1, 2)] seed: u64) {
+    Simulation::new(
+        seed,
+        SimulationConfig {
+            rpc_failure_rate: 0.2,
+            eviction_rate: 0.2,
+            shutdown_after: Some(Duration::from_millis(50)),
+            ..Default::default()
+        },
+    )
+    .run();
+}
+
+// Only with the runtime's own randomness seeded are runs reproduced exactly
+#[cfg(tokio_unstable)]
+#[test]
+fn simulation_is_reproducible() {
+    let config = SimulationConfig {
+        rpc_failure_rate: 0.3,
+        eviction_rate: 0.3,
+        ..Default::default()
+    };
+    let first = Simulation::new(1, config.clone()).run();
+    let second = Simulation::new(1, config).run();
+    assert_eq!(first, second);
+}
@@ -1,5 +1,6 @@
 use crate::{
     PollError, prost_dur,
+    telemetry::telemetry_init,
     test_help::{
         MockPollCfg, MockWorkerInputs, MocksHolder, ResponseType, WorkerExt, build_fake_worker,
         build_mock_pollers, canned_histories, mock_worker, test_worker_cfg,
@@ -13,16 +14,31 @@ use crate::{
     },
 };
 use futures_util::{stream, stream::StreamExt};
-use std::{cell::RefCell, time::Duration};
-use temporal_sdk_core_api::Worker;
+use std::{
+    cell::RefCell,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+use temporal_sdk_core_api::{
+    Worker,
+    errors::WorkerConfigUpdateError,
+    worker::{PollerKind, SlotKindType, WorkerConfigUpdateBuilder},
+};
 use temporal_sdk_core_protos::{
     coresdk::{
+        ActivityTaskCompletion,
+        activity_result::ActivityExecutionResult,
         workflow_activation::workflow_activation_job,
         workflow_commands::{CompleteWorkflowExecution, StartTimer, workflow_command},
         workflow_completion::WorkflowActivationCompletion,
     },
     temporal::api::workflowservice::v1::{
-        PollWorkflowTaskQueueResponse, RespondWorkflowTaskCompletedResponse, ShutdownWorkerResponse,
+        PollActivityTaskQueueResponse, PollWorkflowTaskQueueResponse,
+        RespondActivityTaskCompletedResponse, RespondWorkflowTaskCompletedResponse,
+        ShutdownWorkerResponse,
     },
 };
 use temporal_sdk_core_test_utils::{WorkerTestHelpers, start_timer_cmd};
@@ -361,3 +377,175 @@ async fn worker_shutdown_api(#[case] use_cache: bool, #[case] api_success: bool)
         );
     });
 }
+
+#[tokio::test]
+async fn config_updates_are_validated() {
+    let worker = build_fake_worker("fake_wf_id", canned_histories::single_timer("1"), [1]);
+    let invalid_updates = [
+        WorkerConfigUpdateBuilder::default()
+            .max_concurrent_wft_polls(0_usize)
+            .build(),
+        WorkerConfigUpdateBuilder::default()
+            .nonsticky_to_sticky_poll_ratio(2.0_f32)
+            .build(),
+        WorkerConfigUpdateBuilder::default()
+            .max_worker_activities_per_second(Some(-1.0))
+            .build(),
+    ];
+    for update in invalid_updates {
+        assert_matches!(
+            worker.update_config(update.unwrap()),
+            Err(WorkerConfigUpdateError::Invalid(_))
+        );
+    }
+    worker
+        .update_config(
+            WorkerConfigUpdateBuilder::default()
+                .nonsticky_to_sticky_poll_ratio(0.5_f32)
+                .max_task_queue_activities_per_second(Some(10.0))
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+}
+
+#[tokio::test]
+async fn config_updates_change_activity_polls() {
+    let mut mock = mock_workflow_client();
+    let (seen_tx, mut seen_rx) = tokio::sync::mpsc::unbounded_channel();
+    let task_num = AtomicUsize::new(0);
+    mock.expect_poll_activity_task()
+        .returning(move |_, max_tps| {
+            let _ = seen_tx.send(max_tps);
+            let num = task_num.fetch_add(1, Ordering::Relaxed);
+            Ok(PollActivityTaskQueueResponse {
+                task_token: num.to_le_bytes().to_vec(),
+                activity_id: format!("act{num}"),
+                ..Default::default()
+            })
+        });
+    mock.expect_complete_activity_task()
+        .returning(|_, _| Ok(RespondActivityTaskCompletedResponse::default()));
+    // With a single slot, the next poll can't be issued until the current task is completed
+    let worker = worker::Worker::new_test(
+        test_worker_cfg()
+            .max_concurrent_at_polls(1_usize)
+            .max_outstanding_activities(1_usize)
+            .build()
+            .unwrap(),
+        mock,
+    );
+    let task = worker.poll_activity_task().await.unwrap();
+    assert_eq!(seen_rx.recv().await.unwrap(), None);
+    worker
+        .update_config(
+            WorkerConfigUpdateBuilder::default()
+                .max_task_queue_activities_per_second(Some(10.0))
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+    worker
+        .complete_activity_task(ActivityTaskCompletion {
+            task_token: task.task_token,
+            result: Some(ActivityExecutionResult::ok(vec![1].into())),
+        })
+        .await
+        .unwrap();
+    worker.poll_activity_task().await.unwrap();
+    assert_eq!(seen_rx.recv().await.unwrap(), Some(10.0));
+}
+
+#[tokio::test]
+async fn debug_snapshot_reports_outstanding_work() {
+    let worker = build_fake_worker("fake_wf_id", canned_histories::single_timer("1"), [1]);
+    let act = worker.poll_workflow_activation().await.unwrap();
+
+    let snapshot = worker.debug_snapshot().await;
+    assert!(!snapshot.workflows_unresponsive);
+    let runs = snapshot.workflows.as_ref().unwrap().cached_runs.as_slice();
+    assert_eq!(runs.len(), 1);
+    assert_eq!(runs[0].run_id, act.run_id);
+    assert_eq!(runs[0].workflow_id, "fake_wf_id");
+    assert!(runs[0].pending_activation.is_some());
+    // Only a fingerprint of the task token is included
+    let wft = runs[0].outstanding_wft.as_ref().unwrap();
+    assert_eq!(wft.task_token.len(), 16);
+    let wft_slots = snapshot
+        .slots
+        .iter()
+        .find(|s| s.kind == SlotKindType::Workflow)
+        .unwrap();
+    assert_eq!(wft_slots.in_use, 1);
+    // Must be serializable to be exposed by lang
+    let json = serde_json::to_value(&snapshot).unwrap();
+    assert_eq!(json["slots"][0]["kind"], "Workflow");
+
+    worker
+        .complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+            act.run_id,
+            start_timer_cmd(1, Duration::from_secs(1)),
+        ))
+        .await
+        .unwrap();
+    let snapshot = worker.debug_snapshot().await;
+    let runs = snapshot.workflows.unwrap().cached_runs;
+    assert!(runs[0].pending_activation.is_none());
+    assert!(runs[0].outstanding_wft.is_none());
+    worker.drain_pollers_and_shutdown().await;
+}
+
+#[tokio::test]
+async fn worker_health_reflects_polls_and_shutdown() {
+    let telem = telemetry_init(Default::default()).unwrap();
+    let registry = telem.worker_registry();
+    let mut mock = mock_workflow_client();
+    let polled = AtomicBool::new(false);
+    mock.expect_poll_activity_task().returning(move |_, _| {
+        if !polled.swap(true, Ordering::Relaxed) {
+            return Err(tonic::Status::unavailable("not yet"));
+        }
+        Ok(PollActivityTaskQueueResponse {
+            task_token: vec![1],
+            activity_id: "act1".to_string(),
+            ..Default::default()
+        })
+    });
+    let worker = worker::Worker::new(
+        test_worker_cfg()
+            .max_concurrent_at_polls(1_usize)
+            .build()
+            .unwrap(),
+        None,
+        Arc::new(mock),
+        Some(&telem),
+    );
+    let threshold = Duration::from_secs(60);
+    let report = registry.report(threshold);
+    assert_eq!(report.workers.len(), 1);
+    assert!(report.live);
+    assert!(!report.ready);
+
+    worker.poll_activity_task().await.unwrap_err();
+    let report = registry.report(threshold);
+    assert!(!report.ready);
+    let pollers = &report.workers[0].pollers;
+    assert_eq!(pollers[0].kind, PollerKind::Activity);
+    assert_eq!(pollers[0].consecutive_failures, 1);
+    assert!(pollers[0].since_last_successful_poll.is_none());
+
+    worker.poll_activity_task().await.unwrap();
+    let report = registry.report(threshold);
+    assert!(report.live);
+    assert!(report.ready);
+    assert_eq!(report.workers[0].pollers[0].consecutive_failures, 0);
+
+    worker.initiate_shutdown();
+    let report = registry.report(threshold);
+    assert!(report.live);
+    assert!(!report.ready);
+    assert!(report.workers[0].shutdown_requested);
+
+    drop(worker);
+    assert!(registry.report(threshold).workers.is_empty());
+}
@@ -34,8 +34,8 @@ use temporal_sdk_core_api::{
     Worker as WorkerTrait,
     errors::PollError,
     worker::{
-        SlotMarkUsedContext, SlotReleaseContext, SlotReservationContext, SlotSupplier,
-        SlotSupplierPermit, WorkflowSlotKind,
+        EvictedHistoryRetention, SlotMarkUsedContext, SlotReleaseContext, SlotReservationContext,
+        SlotSupplier, SlotSupplierPermit, WorkflowCachePolicy, WorkflowSlotKind,
     },
 };
 use temporal_sdk_core_protos::{
@@ -1662,6 +1662,86 @@ async fn cache_miss_will_fetch_history() {
     worker.shutdown().await;
 }
 
+#[tokio::test]
+async fn cache_miss_uses_retained_history_of_evicted_run() {
+    let mut t = TestHistoryBuilder::default();
+    t.add_by_type(EventType::WorkflowExecutionStarted);
+    t.add_full_wf_task();
+    t.add_we_signaled("sig", vec![]);
+    t.add_full_wf_task();
+    t.add_workflow_execution_completed();
+
+    let mut mh = MockPollCfg::from_resp_batches(
+        "fake_wf_id",
+        t,
+        [ResponseType::ToTaskNum(1), ResponseType::OneTask(2)],
+        mock_workflow_client(),
+    );
+    mh.mock_client
+        .expect_get_workflow_execution_history()
+        .times(0);
+    let mut mock = build_mock_pollers(mh);
+    mock.worker_cfg(|cfg| {
+        cfg.max_cached_workflows = 1;
+        cfg.evicted_history_retention = Some(EvictedHistoryRetention {
+            max_bytes_in_memory: 1 << 20,
+            ..Default::default()
+        });
+    });
+    let worker = mock_worker(mock);
+
+    let activation = worker.poll_workflow_activation().await.unwrap();
+    worker.request_wf_eviction(
+        &activation.run_id,
+        "whatever",
+        EvictionReason::LangRequested,
+    );
+    worker
+        .complete_workflow_activation(WorkflowActivationCompletion::empty(&activation.run_id))
+        .await
+        .unwrap();
+    // Handle the eviction, then the run is rebuilt from the retained history
+    for i in 1..=2 {
+        let activation = worker.poll_workflow_activation().await.unwrap();
+        assert_eq!(activation.history_length, 3);
+        if i == 1 {
+            assert_matches!(
+                activation.jobs.as_slice(),
+                [WorkflowActivationJob {
+                    variant: Some(workflow_activation_job::Variant::RemoveFromCache(_)),
+                }]
+            );
+        } else {
+            assert_matches!(
+                activation.jobs.as_slice(),
+                [WorkflowActivationJob {
+                    variant: Some(workflow_activation_job::Variant::InitializeWorkflow(_)),
+                }]
+            );
+        }
+        worker
+            .complete_workflow_activation(WorkflowActivationCompletion::empty(activation.run_id))
+            .await
+            .unwrap();
+    }
+    let activation = worker.poll_workflow_activation().await.unwrap();
+    assert_eq!(activation.history_length, 7);
+    assert_matches!(
+        activation.jobs.as_slice(),
+        [WorkflowActivationJob {
+            variant: Some(workflow_activation_job::Variant::SignalWorkflow(_)),
+        }]
+    );
+    worker
+        .complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+            activation.run_id,
+            CompleteWorkflowExecution { result: None }.into(),
+        ))
+        .await
+        .unwrap();
+    worker.shutdown().await;
+}
+
 #[tokio::test]
 async fn history_byte_size_and_can_suggestion_in_activation() {
     let mut t = TestHistoryBuilder::default();
@@ -1933,6 +2013,227 @@ async fn poll_faster_than_complete_wont_overflow_cache() {
     assert_eq!(core.cached_workflows().await, 3);
 }
 
+#[tokio::test]
+async fn cache_memory_budget_evicts_lru_run() {
+    let tasks: Vec<_> = (1..=2)
+        .map(|i| FakeWfResponses {
+            wf_id: format!("wf-{i}"),
+            hist: canned_histories::single_timer("1"),
+            response_batches: vec![ResponseType::ToTaskNum(1)],
+        })
+        .collect();
+    let mut mock_client = mock_workflow_client();
+    mock_client
+        .expect_complete_workflow_task()
+        .times(2)
+        .returning(|_| Ok(Default::default()));
+    let mut mock_cfg = MockPollCfg::new(tasks, true, 0);
+    mock_cfg.mock_client = mock_client;
+    let mut mock = build_mock_pollers(mock_cfg);
+    mock.worker_cfg(|wc| {
+        wc.max_cached_workflows = 10;
+        // Any one run is bigger than this, so only one fits at a time
+        wc.max_cached_workflows_bytes = Some(1);
+    });
+    let core = mock_worker(mock);
+
+    let p1 = core.poll_workflow_activation().await.unwrap();
+    assert_matches!(
+        &p1.jobs[0].variant,
+        Some(workflow_activation_job::Variant::InitializeWorkflow(sw)) if sw.workflow_id == "wf-1"
+    );
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        p1.run_id.clone(),
+        start_timer_cmd(1, Duration::from_secs(1)),
+    ))
+    .await
+    .unwrap();
+    // The task for the second run can't fit until the first is evicted, despite the count limit
+    let evict = core.poll_workflow_activation().await.unwrap();
+    assert_eq!(evict.run_id, p1.run_id);
+    assert_matches!(
+        evict.jobs.as_slice(),
+        [WorkflowActivationJob {
+            variant: Some(workflow_activation_job::Variant::RemoveFromCache(_)),
+        }]
+    );
+    core.complete_workflow_activation(WorkflowActivationCompletion::empty(evict.run_id))
+        .await
+        .unwrap();
+    let p2 = core.poll_workflow_activation().await.unwrap();
+    assert_matches!(
+        &p2.jobs[0].variant,
+        Some(workflow_activation_job::Variant::InitializeWorkflow(sw)) if sw.workflow_id == "wf-2"
+    );
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        p2.run_id,
+        start_timer_cmd(1, Duration::from_secs(1)),
+    ))
+    .await
+    .unwrap();
+    assert_eq!(core.cached_workflows().await, 1);
+}
+
+#[tokio::test]
+async fn cache_policy_spares_preferred_type() {
+    let tasks: Vec<_> = [("entity", "wf-1"), ("short", "wf-2"), ("short", "wf-3")]
+        .into_iter()
+        .map(|(wf_type, wf_id)| {
+            let mut hist = canned_histories::single_timer("1");
+            hist.set_wf_type(wf_type);
+            FakeWfResponses {
+                wf_id: wf_id.to_string(),
+                hist,
+                response_batches: vec![ResponseType::ToTaskNum(1)],
+            }
+        })
+        .collect();
+    let mut mock_client = mock_workflow_client();
+    mock_client
+        .expect_complete_workflow_task()
+        .times(3)
+        .returning(|_| Ok(Default::default()));
+    let mut mock_cfg = MockPollCfg::new(tasks, true, 0);
+    mock_cfg.mock_client = mock_client;
+    let mut mock = build_mock_pollers(mock_cfg);
+    mock.worker_cfg(|wc| {
+        wc.max_cached_workflows = 2;
+        wc.max_outstanding_workflow_tasks = Some(2);
+        wc.workflow_cache_policies = HashMap::from([(
+            "entity".to_string(),
+            WorkflowCachePolicy {
+                prefer_keep: true,
+                ..Default::default()
+            },
+        )]);
+    });
+    let core = mock_worker(mock);
+
+    let mut run_ids = vec![];
+    for _ in 0..2 {
+        let act = core.poll_workflow_activation().await.unwrap();
+        core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+            act.run_id.clone(),
+            start_timer_cmd(1, Duration::from_secs(1)),
+        ))
+        .await
+        .unwrap();
+        run_ids.push(act.run_id);
+    }
+    // The entity run is least recently used, but the other one is evicted instead
+    let evict = core.poll_workflow_activation().await.unwrap();
+    assert_eq!(evict.run_id, run_ids[1]);
+    assert_matches!(
+        evict.jobs.as_slice(),
+        [WorkflowActivationJob {
+            variant: Some(workflow_activation_job::Variant::RemoveFromCache(_)),
+        }]
+    );
+    core.complete_workflow_activation(WorkflowActivationCompletion::empty(evict.run_id))
+        .await
+        .unwrap();
+    let p3 = core.poll_workflow_activation().await.unwrap();
+    assert_matches!(
+        &p3.jobs[0].variant,
+        Some(workflow_activation_job::Variant::InitializeWorkflow(sw)) if sw.workflow_id == "wf-3"
+    );
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        p3.run_id,
+        start_timer_cmd(1, Duration::from_secs(1)),
+    ))
+    .await
+    .unwrap();
+    assert_eq!(core.cached_workflows().await, 2);
+}
+
+/// Runs two workflows of the "limited" type, which the given cache policies only leave room for
+/// one of, even though the cache itself has more
+async fn one_type_over_its_cache_policy(policies: HashMap<String, WorkflowCachePolicy>) {
+    let tasks: Vec<_> = ["wf-1", "wf-2"]
+        .into_iter()
+        .map(|wf_id| {
+            let mut hist = canned_histories::single_timer("1");
+            hist.set_wf_type("limited");
+            FakeWfResponses {
+                wf_id: wf_id.to_string(),
+                hist,
+                response_batches: vec![ResponseType::ToTaskNum(1)],
+            }
+        })
+        .collect();
+    let mut mock_client = mock_workflow_client();
+    mock_client
+        .expect_complete_workflow_task()
+        .times(2)
+        .returning(|_| Ok(Default::default()));
+    let mut mock_cfg = MockPollCfg::new(tasks, true, 0);
+    mock_cfg.mock_client = mock_client;
+    let mut mock = build_mock_pollers(mock_cfg);
+    mock.worker_cfg(|wc| {
+        wc.max_cached_workflows = 3;
+        wc.max_outstanding_workflow_tasks = Some(2);
+        wc.workflow_cache_policies = policies;
+    });
+    let core = mock_worker(mock);
+
+    let p1 = core.poll_workflow_activation().await.unwrap();
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        p1.run_id.clone(),
+        start_timer_cmd(1, Duration::from_secs(1)),
+    ))
+    .await
+    .unwrap();
+    // The cache has free slots, but none the second run may use, so the first is evicted
+    let evict = core.poll_workflow_activation().await.unwrap();
+    assert_eq!(evict.run_id, p1.run_id);
+    assert_matches!(
+        evict.jobs.as_slice(),
+        [WorkflowActivationJob {
+            variant: Some(workflow_activation_job::Variant::RemoveFromCache(_)),
+        }]
+    );
+    core.complete_workflow_activation(WorkflowActivationCompletion::empty(evict.run_id))
+        .await
+        .unwrap();
+    let p2 = core.poll_workflow_activation().await.unwrap();
+    assert_matches!(
+        &p2.jobs[0].variant,
+        Some(workflow_activation_job::Variant::InitializeWorkflow(sw)) if sw.workflow_id == "wf-2"
+    );
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        p2.run_id,
+        start_timer_cmd(1, Duration::from_secs(1)),
+    ))
+    .await
+    .unwrap();
+    assert_eq!(core.cached_workflows().await, 1);
+}
+
+#[tokio::test]
+async fn cache_policy_max_cached_limits_type() {
+    one_type_over_its_cache_policy(HashMap::from([(
+        "limited".to_string(),
+        WorkflowCachePolicy {
+            max_cached: Some(1),
+            ..Default::default()
+        },
+    )]))
+    .await;
+}
+
+#[tokio::test]
+async fn cache_policy_reserved_slots_not_used_by_other_types() {
+    // Another type reserves two of the three slots, leaving one for everything else
+    one_type_over_its_cache_policy(HashMap::from([(
+        "other".to_string(),
+        WorkflowCachePolicy {
+            reserved: 2,
+            ..Default::default()
+        },
+    )]))
+    .await;
+}
+
 #[tokio::test]
 async fn eviction_waits_until_replay_finished() {
     let wfid = "fake_wf_id";
@@ -3134,3 +3435,164 @@ async fn pass_timer_summary_to_metadata() {
         .unwrap();
     worker.run_until_done().await.unwrap();
 }
+
+#[tokio::test]
+async fn activation_not_completed_before_deadline_is_failed_and_evicted() {
+    let wfid = "fake_wf_id";
+    let t = canned_histories::single_timer("1");
+    let mut mock = mock_workflow_client();
+    mock.expect_fail_workflow_task()
+        .withf(|_, cause, f| {
+            *cause == WorkflowTaskFailedCause::WorkflowWorkerUnhandledFailure
+                && f.as_ref()
+                    .is_some_and(|f| f.message.contains("Potential deadlock detected"))
+        })
+        .times(1)
+        .returning(|_, _, _| Ok(Default::default()));
+    let mut mock = single_hist_mock_sg(wfid, t, [1], mock, true);
+    mock.worker_cfg(|wc| {
+        wc.max_cached_workflows = 2;
+        wc.workflow_activation_deadline = Some(Duration::from_millis(100));
+    });
+    let core = mock_worker(mock);
+
+    // Lang never completes the first activation in time
+    let activation = core.poll_workflow_activation().await.unwrap();
+    let evict_act = core.poll_workflow_activation().await.unwrap();
+    assert_matches!(
+        evict_act.jobs.as_slice(),
+        [WorkflowActivationJob {
+            variant: Some(workflow_activation_job::Variant::RemoveFromCache(r)),
+        }] if r.reason() == EvictionReason::DeadlockDetected
+    );
+    // The late completion is dropped rather than being mistaken for the eviction reply
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        activation.run_id,
+        start_timer_cmd(1, Duration::from_secs(1)),
+    ))
+    .await
+    .unwrap();
+    core.complete_workflow_activation(WorkflowActivationCompletion::empty(evict_act.run_id))
+        .await
+        .unwrap();
+    core.shutdown().await;
+}
+
+#[tokio::test]
+async fn eviction_reply_before_late_completion_is_not_dropped() {
+    let wfid = "fake_wf_id";
+    let t = canned_histories::single_timer("1");
+    let mut mock = mock_workflow_client();
+    mock.expect_fail_workflow_task()
+        .times(1)
+        .returning(|_, _, _| Ok(Default::default()));
+    let mut mock = single_hist_mock_sg(wfid, t, [1], mock, true);
+    mock.worker_cfg(|wc| {
+        wc.max_cached_workflows = 2;
+        wc.workflow_activation_deadline = Some(Duration::from_millis(100));
+    });
+    let core = mock_worker(mock);
+
+    let activation = core.poll_workflow_activation().await.unwrap();
+    let evict_act = core.poll_workflow_activation().await.unwrap();
+    assert_matches!(
+        evict_act.jobs.as_slice(),
+        [WorkflowActivationJob {
+            variant: Some(workflow_activation_job::Variant::RemoveFromCache(r)),
+        }] if r.reason() == EvictionReason::DeadlockDetected
+    );
+    // Lang handles the eviction while the stuck activation is still running
+    core.complete_workflow_activation(WorkflowActivationCompletion::empty(evict_act.run_id))
+        .await
+        .unwrap();
+    assert_eq!(core.cached_workflows().await, 0);
+    // The stuck activation eventually returns, and its completion is the one dropped
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        activation.run_id,
+        start_timer_cmd(1, Duration::from_secs(1)),
+    ))
+    .await
+    .unwrap();
+    core.shutdown().await;
+}
+
+#[tokio::test]
+async fn nondeterminism_eviction_carries_diagnostic() {
+    let wfid = "fake_wf_id";
+    let t = canned_histories::single_timer("1");
+    let mut mock = mock_workflow_client();
+    mock.expect_fail_workflow_task()
+        .withf(|_, cause, _| *cause == WorkflowTaskFailedCause::NonDeterministicError)
+        .times(1)
+        .returning(|_, _, _| Ok(Default::default()));
+    let mut mock = single_hist_mock_sg(wfid, t, [ResponseType::AllHistory], mock, true);
+    mock.worker_cfg(|wc| wc.max_cached_workflows = 2);
+    let core = mock_worker(mock);
+
+    // History says a timer was started, but lang schedules an activity instead
+    let activation = core.poll_workflow_activation().await.unwrap();
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        activation.run_id,
+        ScheduleActivity {
+            seq: 1,
+            activity_id: "1".to_string(),
+            ..default_act_sched()
+        }
+        .into(),
+    ))
+    .await
+    .unwrap();
+
+    let evict_act = core.poll_workflow_activation().await.unwrap();
+    assert_eq!(
+        evict_act.eviction_reason(),
+        Some(EvictionReason::Nondeterminism)
+    );
+    let diag = evict_act.nondeterminism_diagnostic().unwrap();
+    assert_eq!(diag.event_id, 5);
+    assert_eq!(diag.expected, "TimerStarted");
+    assert_eq!(diag.actual, "ScheduleActivityTask");
+    assert_eq!(diag.workflow_task_number, 1);
+    assert!(!diag.machine.is_empty());
+    core.complete_workflow_activation(WorkflowActivationCompletion::empty(evict_act.run_id))
+        .await
+        .unwrap();
+    core.shutdown().await;
+}
+
+#[tokio::test]
+async fn command_side_nondeterminism_carries_diagnostic() {
+    let wfid = "fake_wf_id";
+    let t = canned_histories::single_timer("1");
+    let mut mock = mock_workflow_client();
+    mock.expect_fail_workflow_task()
+        .withf(|_, cause, _| *cause == WorkflowTaskFailedCause::NonDeterministicError)
+        .times(1)
+        .returning(|_, _, _| Ok(Default::default()));
+    let mut mock = single_hist_mock_sg(wfid, t, [ResponseType::AllHistory], mock, true);
+    mock.worker_cfg(|wc| wc.max_cached_workflows = 2);
+    let core = mock_worker(mock);
+
+    // Lang cancels a timer it never started, which no machine can match
+    let activation = core.poll_workflow_activation().await.unwrap();
+    core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+        activation.run_id,
+        CancelTimer { seq: 7 }.into(),
+    ))
+    .await
+    .unwrap();
+
+    let evict_act = core.poll_workflow_activation().await.unwrap();
+    assert_eq!(
+        evict_act.eviction_reason(),
+        Some(EvictionReason::Nondeterminism)
+    );
+    let diag = evict_act.nondeterminism_diagnostic().unwrap();
+    assert!(diag.event_id > 0);
+    assert_eq!(diag.actual, "CancelTimer(7)");
+    assert_eq!(diag.workflow_task_number, 1);
+    core.complete_workflow_activation(WorkflowActivationCompletion::empty(evict_act.run_id))
+        .await
+        .unwrap();
+    core.shutdown().await;
+}
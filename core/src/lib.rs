@@ -44,14 +44,15 @@ pub use worker::{
     FixedSizeSlotSupplier, RealSysInfo, ResourceBasedSlotsOptions,
     ResourceBasedSlotsOptionsBuilder, ResourceBasedTuner, ResourceSlotOptions, SlotSupplierOptions,
     TunerBuilder, TunerHolder, TunerHolderOptions, TunerHolderOptionsBuilder, Worker, WorkerConfig,
-    WorkerConfigBuilder,
+    WorkerConfigBuilder, WorkerDebugSnapshot, debug_snapshot,
 };
 
 use crate::{
     replay::{HistoryForReplay, ReplayWorkerInput},
     telemetry::{
-        TelemetryInstance, metrics::MetricsContext, remove_trace_subscriber_for_current_thread,
-        set_trace_subscriber_for_current_thread, telemetry_init,
+        TelemetryInstance, WorkerRegistry, metrics::MetricsContext,
+        remove_trace_subscriber_for_current_thread, set_trace_subscriber_for_current_thread,
+        telemetry_init,
     },
     worker::client::WorkerClientBag,
 };
@@ -206,6 +207,9 @@ pub struct CoreRuntime {
     telemetry: TelemetryInstance,
     runtime: Option<tokio::runtime::Runtime>,
     runtime_handle: tokio::runtime::Handle,
+    /// Address and handle of the standalone health server, if one was configured
+    #[cfg(feature = "otel")]
+    health_server: Option<(std::net::SocketAddr, tokio::task::AbortHandle)>,
 }
 
 /// Wraps a [tokio::runtime::Builder] to allow layering multiple on_thread_start functions
@@ -286,7 +290,11 @@ impl CoreRuntime {
         if let Some(sub) = telemetry.trace_subscriber() {
             set_trace_subscriber_for_current_thread(sub);
         }
+        #[cfg(feature = "otel")]
+        let mut telemetry = telemetry;
         Self {
+            #[cfg(feature = "otel")]
+            health_server: telemetry.start_health_server(),
             telemetry,
             runtime: None,
             runtime_handle,
@@ -307,10 +315,25 @@ impl CoreRuntime {
     pub fn telemetry_mut(&mut self) -> &mut TelemetryInstance {
         &mut self.telemetry
     }
+
+    /// Returns the registry every worker initialized with this runtime reports its health to
+    pub fn worker_registry(&self) -> WorkerRegistry {
+        self.telemetry.worker_registry()
+    }
+
+    /// Returns the address the standalone health server is listening on, if one was configured
+    #[cfg(feature = "otel")]
+    pub fn health_server_addr(&self) -> Option<std::net::SocketAddr> {
+        self.health_server.as_ref().map(|(addr, _)| *addr)
+    }
 }
 
 impl Drop for CoreRuntime {
     fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        if let Some((_, handle)) = self.health_server.take() {
+            handle.abort();
+        }
         remove_trace_subscriber_for_current_thread();
     }
 }
@@ -1,7 +1,8 @@
 mod poll_buffer;
 
 pub(crate) use poll_buffer::{
-    WorkflowTaskPoller, new_activity_task_buffer, new_nexus_task_buffer, new_workflow_task_buffer,
+    ActivityPollRateLimits, PollPauser, PollScaler, PollerBehavior, WorkflowTaskPoller,
+    new_activity_task_buffer, new_nexus_task_buffer, new_workflow_task_buffer,
 };
 pub use temporal_client::{
     Client, ClientOptions, ClientOptionsBuilder, ClientTlsConfig, RetryClient, RetryConfig,
@@ -10,11 +11,11 @@ pub use temporal_client::{
 
 use crate::{
     abstractions::{OwnedMeteredSemPermit, TrackedOwnedMeteredSemPermit},
-    telemetry::metrics::MetricsContext,
+    telemetry::{PollerHealth, metrics::MetricsContext},
 };
 use anyhow::{anyhow, bail};
 use futures_util::{Stream, stream};
-use std::{fmt::Debug, marker::PhantomData};
+use std::{fmt::Debug, marker::PhantomData, sync::Arc};
 use temporal_sdk_core_api::worker::{ActivitySlotKind, NexusSlotKind, SlotKind, WorkflowSlotKind};
 use temporal_sdk_core_protos::temporal::api::workflowservice::v1::{
     PollActivityTaskQueueResponse, PollNexusTaskQueueResponse, PollWorkflowTaskQueueResponse,
@@ -79,6 +80,45 @@ where
     }
 }
 
+/// Records the outcome of every poll made through the wrapped poller as part of the worker's health
+pub(crate) struct HealthTrackedPoller<P> {
+    inner: P,
+    health: Arc<PollerHealth>,
+}
+
+impl<P> HealthTrackedPoller<P> {
+    pub(crate) fn new(inner: P, health: Arc<PollerHealth>) -> Self {
+        Self { inner, health }
+    }
+}
+
+#[async_trait::async_trait]
+impl<T, P> Poller<T> for HealthTrackedPoller<P>
+where
+    T: Send + Sync + 'static,
+    P: Poller<T> + Send + Sync + 'static,
+{
+    async fn poll(&self) -> Option<Result<T>> {
+        let r = self.inner.poll().await;
+        if let Some(r) = r.as_ref() {
+            self.health.record_poll(r.is_ok());
+        }
+        r
+    }
+
+    fn notify_shutdown(&self) {
+        self.inner.notify_shutdown();
+    }
+
+    async fn shutdown(self) {
+        self.inner.shutdown().await;
+    }
+
+    async fn shutdown_box(self: Box<Self>) {
+        self.inner.shutdown().await;
+    }
+}
+
 #[cfg(test)]
 mockall::mock! {
     pub ManualPoller<T: Send + Sync + 'static> {}
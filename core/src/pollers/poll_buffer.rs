@@ -3,18 +3,24 @@ use crate::{
     pollers::{self, Poller},
     worker::client::WorkerClient,
 };
-use futures_util::{FutureExt, StreamExt, future::BoxFuture, stream::FuturesUnordered};
-use governor::{Quota, RateLimiter};
+use futures_util::{
+    FutureExt, StreamExt,
+    future::{self, BoxFuture},
+    stream::FuturesUnordered,
+};
+use governor::{DefaultDirectRateLimiter, Quota, RateLimiter};
 use std::{
     fmt::Debug,
     future::Future,
     sync::{
-        Arc,
+        Arc, Weak,
         atomic::{AtomicBool, AtomicUsize, Ordering},
     },
-    time::Duration,
+    time::{Duration, Instant},
+};
+use temporal_sdk_core_api::worker::{
+    ActivitySlotKind, NexusSlotKind, PollerAutoscaling, SlotKind, WorkflowSlotKind,
 };
-use temporal_sdk_core_api::worker::{ActivitySlotKind, NexusSlotKind, SlotKind, WorkflowSlotKind};
 use temporal_sdk_core_protos::temporal::api::{
     taskqueue::v1::TaskQueue,
     workflowservice::v1::{
@@ -23,8 +29,9 @@ use temporal_sdk_core_protos::temporal::api::{
 };
 use tokio::{
     sync::{
-        Mutex, broadcast,
+        Mutex,
         mpsc::{UnboundedReceiver, unbounded_channel},
+        watch,
     },
     task::JoinHandle,
 };
@@ -35,10 +42,175 @@ type PollReceiver<T, SK> =
 pub(crate) struct LongPollBuffer<T, SK: SlotKind> {
     buffered_polls: PollReceiver<T, SK>,
     shutdown: CancellationToken,
-    join_handles: FuturesUnordered<JoinHandle<()>>,
+    pool: Arc<PollerPool>,
     /// Pollers won't actually start polling until initialized & value is sent
-    starter: broadcast::Sender<()>,
+    starter: watch::Sender<bool>,
     did_start: AtomicBool,
+    pauser: PollPauser,
+}
+
+/// The tasks running a [LongPollBuffer]'s pollers. More are spawned as needed when the number of
+/// pollers is raised, while pollers beyond the current maximum sit idle.
+struct PollerPool {
+    /// Spawns the poller with the provided index, unless the buffer is shutting down. Pollers are
+    /// given a weak reference to the pool, so that they can spawn more without keeping it alive.
+    #[allow(clippy::type_complexity)]
+    spawn_poller: Box<dyn Fn(Weak<PollerPool>, usize) -> Option<JoinHandle<()>> + Send + Sync>,
+    join_handles: parking_lot::Mutex<FuturesUnordered<JoinHandle<()>>>,
+    max_pollers: watch::Sender<usize>,
+}
+
+impl PollerPool {
+    fn spawn_up_to(self: &Arc<Self>, num_pollers: usize) {
+        let join_handles = self.join_handles.lock();
+        for ix in join_handles.len()..num_pollers {
+            if let Some(jh) = (self.spawn_poller)(Arc::downgrade(self), ix) {
+                join_handles.push(jh);
+            }
+        }
+    }
+
+    fn set_max_pollers(self: &Arc<Self>, max_pollers: usize) {
+        self.spawn_up_to(max_pollers);
+        self.max_pollers.send_replace(max_pollers);
+    }
+}
+
+/// How many pollers a [LongPollBuffer] runs
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum PollerBehavior {
+    /// Always run this many pollers
+    SimpleMaximum(usize),
+    /// Scale the number of pollers within bounds, see [PollerAutoscaler]
+    Autoscaling(PollerAutoscaling),
+}
+
+impl PollerBehavior {
+    pub(crate) fn new(max_pollers: usize, autoscaling: Option<PollerAutoscaling>) -> Self {
+        autoscaling.map_or(Self::SimpleMaximum(max_pollers), Self::Autoscaling)
+    }
+}
+
+/// What the autoscaler needs to know about poll responses
+pub(crate) trait PollResponseInfo {
+    /// True if the poll timed out without receiving a task
+    fn is_empty(&self) -> bool;
+    /// How many more tasks the server says are waiting, if it says
+    fn backlog_hint(&self) -> Option<i64> {
+        None
+    }
+}
+
+impl PollResponseInfo for PollWorkflowTaskQueueResponse {
+    fn is_empty(&self) -> bool {
+        self.task_token.is_empty()
+    }
+    fn backlog_hint(&self) -> Option<i64> {
+        Some(self.backlog_count_hint)
+    }
+}
+impl PollResponseInfo for PollActivityTaskQueueResponse {
+    fn is_empty(&self) -> bool {
+        self.task_token.is_empty()
+    }
+}
+impl PollResponseInfo for PollNexusTaskQueueResponse {
+    fn is_empty(&self) -> bool {
+        self.task_token.is_empty()
+    }
+}
+
+/// Polls which return a task within this long were answered from a backlog, rather than waiting
+/// for a task to show up, so there is work for more pollers.
+const QUICK_POLL_THRESHOLD: Duration = Duration::from_secs(1);
+/// How long to avoid scaling up after the server reports it's overloaded
+const RESOURCE_EXHAUSTED_COOLDOWN: Duration = Duration::from_secs(10);
+
+/// Adjusts how many of a [LongPollBuffer]'s pollers are active based on what polls return. Only
+/// `initial` pollers are spawned up front, and more are spawned as the count grows. Those beyond a
+/// lowered count sit idle.
+struct PollerAutoscaler {
+    bounds: PollerAutoscaling,
+    num_pollers: watch::Sender<usize>,
+    scale_up_blocked_until: parking_lot::Mutex<Option<Instant>>,
+}
+
+impl PollerAutoscaler {
+    fn record<T: PollResponseInfo>(&self, result: &pollers::Result<T>, poll_took: Duration) {
+        let target = |cur: usize| -> Option<usize> {
+            match result {
+                Ok(r) if r.is_empty() => Some(cur.saturating_sub(1)),
+                Ok(r) => match r.backlog_hint() {
+                    // At most double, so that one large hint doesn't swamp the server
+                    Some(backlog) if backlog > 0 => Some(cur + (backlog as usize).min(cur)),
+                    _ if poll_took < QUICK_POLL_THRESHOLD => Some(cur + 1),
+                    _ => None,
+                },
+                Err(e) if e.code() == tonic::Code::ResourceExhausted => Some(cur / 2),
+                Err(_) => None,
+            }
+        };
+        if matches!(result, Err(e) if e.code() == tonic::Code::ResourceExhausted) {
+            *self.scale_up_blocked_until.lock() =
+                Some(Instant::now() + RESOURCE_EXHAUSTED_COOLDOWN);
+        }
+        let scale_up_blocked = self
+            .scale_up_blocked_until
+            .lock()
+            .is_some_and(|until| Instant::now() < until);
+        self.num_pollers.send_if_modified(|cur| {
+            let Some(new) = target(*cur) else {
+                return false;
+            };
+            if new > *cur && scale_up_blocked {
+                return false;
+            }
+            let new = new.clamp(self.bounds.minimum, self.bounds.maximum);
+            let changed = new != *cur;
+            *cur = new;
+            changed
+        });
+    }
+}
+
+/// Changes the maximum number of concurrent pollers of a [LongPollBuffer]. When it is lowered,
+/// excess pollers finish their in-flight poll and then stop.
+#[derive(Clone)]
+pub(crate) struct PollScaler(Arc<PollerPool>);
+
+impl PollScaler {
+    pub(crate) fn set_max_pollers(&self, max_pollers: usize) {
+        self.0.set_max_pollers(max_pollers);
+    }
+}
+
+/// Pauses and resumes the pollers of a [LongPollBuffer]. While paused, pollers start no new long
+/// polls and stop waiting for slot permits. Polls already in flight are left to finish, since the
+/// server may have dispatched a task to them, and their responses are buffered and handed out like
+/// any other.
+#[derive(Clone, Debug)]
+pub(crate) struct PollPauser(watch::Sender<bool>);
+
+impl PollPauser {
+    fn new() -> Self {
+        Self(watch::channel(false).0)
+    }
+
+    pub(crate) fn pause(&self) {
+        self.0.send_replace(true);
+    }
+
+    pub(crate) fn resume(&self) {
+        self.0.send_replace(false);
+    }
+}
+
+/// Resolves once polling is paused. Never resolves if the pauser is gone, since then nothing can
+/// pause polling anymore.
+async fn until_paused(paused: &mut watch::Receiver<bool>) {
+    if paused.wait_for(|p| *p).await.is_err() {
+        future::pending::<()>().await;
+    }
 }
 
 struct ActiveCounter<'a, F: Fn(usize)>(&'a AtomicUsize, Option<F>);
@@ -68,13 +240,13 @@ where
 
 impl<T, SK> LongPollBuffer<T, SK>
 where
-    T: Send + Debug + 'static,
+    T: PollResponseInfo + Send + Debug + 'static,
     SK: SlotKind + 'static,
 {
     pub(crate) fn new<FT, DelayFut>(
         poll_fn: impl Fn() -> FT + Send + Sync + 'static,
         permit_dealer: MeteredPermitDealer<SK>,
-        max_pollers: usize,
+        behavior: PollerBehavior,
         shutdown: CancellationToken,
         num_pollers_handler: Option<impl Fn(usize) + Send + Sync + 'static>,
         pre_permit_delay: Option<impl Fn() -> DelayFut + Send + Sync + 'static>,
@@ -84,25 +256,48 @@ where
         DelayFut: Future<Output = ()> + Send,
     {
         let (tx, rx) = unbounded_channel();
-        let (starter, wait_for_start) = broadcast::channel(1);
+        let (starter, wait_for_start) = watch::channel(false);
         let permit_dealer = Arc::new(permit_dealer);
         let active_pollers = Arc::new(AtomicUsize::new(0));
-        let join_handles = FuturesUnordered::new();
         let pf = Arc::new(poll_fn);
         let nph = num_pollers_handler.map(Arc::new);
         let pre_permit_delay = pre_permit_delay.map(Arc::new);
-        for _ in 0..max_pollers {
-            let tx = tx.clone();
+        let pauser = PollPauser::new();
+        let initial_pollers = match behavior {
+            PollerBehavior::SimpleMaximum(max) => max,
+            PollerBehavior::Autoscaling(bounds) => bounds.initial,
+        };
+        let (max_pollers_tx, max_pollers_rx) = watch::channel(initial_pollers);
+        let autoscaler = match behavior {
+            PollerBehavior::SimpleMaximum(_) => None,
+            PollerBehavior::Autoscaling(bounds) => Some(Arc::new(PollerAutoscaler {
+                bounds,
+                num_pollers: max_pollers_tx.clone(),
+                scale_up_blocked_until: Default::default(),
+            })),
+        };
+        // Pollers hold the only strong senders, so that polling the buffer ends once they're gone
+        let weak_tx = tx.downgrade();
+        let paused_rx = pauser.0.subscribe();
+        let spawn_shutdown = shutdown.clone();
+        let spawn_poller = move |pool: Weak<PollerPool>, ix: usize| {
+            let tx = weak_tx.upgrade()?;
+            if spawn_shutdown.is_cancelled() {
+                return None;
+            }
             let pf = pf.clone();
-            let shutdown = shutdown.clone();
+            let shutdown = spawn_shutdown.clone();
             let ap = active_pollers.clone();
             let permit_dealer = permit_dealer.clone();
             let nph = nph.clone();
             let pre_permit_delay = pre_permit_delay.clone();
-            let mut wait_for_start = wait_for_start.resubscribe();
-            let jh = tokio::spawn(async move {
+            let mut wait_for_start = wait_for_start.clone();
+            let mut paused = paused_rx.clone();
+            let mut max_pollers = max_pollers_rx.clone();
+            let autoscaler = autoscaler.clone();
+            Some(tokio::spawn(async move {
                 tokio::select! {
-                    _ = wait_for_start.recv() => (),
+                    _ = wait_for_start.wait_for(|s| *s) => (),
                     _ = shutdown.cancelled() => return,
                 }
                 drop(wait_for_start);
@@ -112,6 +307,15 @@ where
                     if shutdown.is_cancelled() {
                         break;
                     }
+                    // Don't reserve a slot while paused or while this poller is beyond the current
+                    // maximum. Errors mean the senders are gone, and so nothing can change anymore.
+                    tokio::select! {
+                        _ = async {
+                            let _ = paused.wait_for(|p| !*p).await;
+                            let _ = max_pollers.wait_for(|m| ix < *m).await;
+                        } => (),
+                        _ = shutdown.cancelled() => break,
+                    }
                     if let Some(ref ppd) = pre_permit_delay {
                         tokio::select! {
                             _ = ppd() => (),
@@ -121,25 +325,51 @@ where
                     let permit = tokio::select! {
                         p = permit_dealer.acquire_owned() => p,
                         _ = shutdown.cancelled() => break,
+                        _ = until_paused(&mut paused) => continue,
                     };
                     let _active_guard = ActiveCounter::new(ap.as_ref(), nph);
+                    let poll_started = Instant::now();
                     let r = tokio::select! {
                         r = pf() => r,
                         _ = shutdown.cancelled() => break,
                     };
+                    if let Some(autoscaler) = autoscaler.as_ref() {
+                        autoscaler.record(&r, poll_started.elapsed());
+                        let num_pollers = *max_pollers.borrow();
+                        if let Some(pool) = pool.upgrade() {
+                            pool.spawn_up_to(num_pollers);
+                        }
+                    }
                     let _ = tx.send(r.map(|r| (r, permit)));
                 }
-            });
-            join_handles.push(jh);
-        }
+            }))
+        };
+        let pool = Arc::new(PollerPool {
+            spawn_poller: Box::new(spawn_poller),
+            join_handles: Default::default(),
+            max_pollers: max_pollers_tx,
+        });
+        pool.spawn_up_to(initial_pollers);
+        drop(tx);
         Self {
             buffered_polls: Mutex::new(rx),
             shutdown,
-            join_handles,
+            pool,
             starter,
             did_start: AtomicBool::new(false),
+            pauser,
         }
     }
+
+    /// Returns a handle which can pause and resume this buffer's pollers
+    pub(crate) fn pauser(&self) -> PollPauser {
+        self.pauser.clone()
+    }
+
+    /// Returns a handle which can change how many pollers this buffer runs
+    pub(crate) fn scaler(&self) -> PollScaler {
+        PollScaler(self.pool.clone())
+    }
 }
 
 #[async_trait::async_trait]
@@ -154,7 +384,7 @@ where
     #[instrument(name = "long_poll", level = "trace", skip(self))]
     async fn poll(&self) -> Option<pollers::Result<(T, OwnedMeteredSemPermit<SK>)>> {
         if !self.did_start.fetch_or(true, Ordering::Relaxed) {
-            let _ = self.starter.send(());
+            self.starter.send_replace(true);
         }
 
         let mut locked = self.buffered_polls.lock().await;
@@ -165,9 +395,10 @@ where
         self.shutdown.cancel();
     }
 
-    async fn shutdown(mut self) {
+    async fn shutdown(self) {
         self.notify_shutdown();
-        while let Some(jh) = self.join_handles.next().await {
+        let mut join_handles = std::mem::take(&mut *self.pool.join_handles.lock());
+        while let Some(jh) = join_handles.next().await {
             if let Err(e) = jh {
                 if e.is_panic() {
                     let as_panic = e.into_panic().downcast::<String>();
@@ -243,7 +474,7 @@ pub(crate) type PollWorkflowTaskBuffer =
 pub(crate) fn new_workflow_task_buffer(
     client: Arc<dyn WorkerClient>,
     task_queue: TaskQueue,
-    concurrent_pollers: usize,
+    poller_behavior: PollerBehavior,
     permit_dealer: MeteredPermitDealer<WorkflowSlotKind>,
     shutdown: CancellationToken,
     num_pollers_handler: Option<impl Fn(usize) + Send + Sync + 'static>,
@@ -255,45 +486,77 @@ pub(crate) fn new_workflow_task_buffer(
             async move { client.poll_workflow_task(task_queue).await }
         },
         permit_dealer,
-        concurrent_pollers,
+        poller_behavior,
         shutdown,
         num_pollers_handler,
         None::<fn() -> BoxFuture<'static, ()>>,
     )
 }
 
+/// Limits on how quickly activities are polled for, which can be changed while the worker runs
+pub(crate) struct ActivityPollRateLimits {
+    /// Limits how many activities per second this worker will process
+    worker: parking_lot::RwLock<Option<Arc<DefaultDirectRateLimiter>>>,
+    /// Sent with each poll, limiting how many activities per second the server will dispatch from
+    /// the task queue
+    task_queue_per_second: parking_lot::RwLock<Option<f64>>,
+}
+
+impl ActivityPollRateLimits {
+    pub(crate) fn new(max_tps: Option<f64>, max_worker_acts_per_sec: Option<f64>) -> Self {
+        let limits = Self {
+            worker: Default::default(),
+            task_queue_per_second: parking_lot::RwLock::new(max_tps),
+        };
+        limits.set_worker_rate(max_worker_acts_per_sec);
+        limits
+    }
+
+    /// Replaces the per-worker limit. Permits already handed out by the old limiter are kept.
+    pub(crate) fn set_worker_rate(&self, per_second: Option<f64>) {
+        *self.worker.write() = per_second.and_then(|ps| {
+            Quota::with_period(Duration::from_secs_f64(ps.recip()))
+                .map(|q| Arc::new(RateLimiter::direct(q)))
+        });
+    }
+
+    /// Replaces the task queue limit, which takes effect with the next poll request
+    pub(crate) fn set_task_queue_rate(&self, per_second: Option<f64>) {
+        *self.task_queue_per_second.write() = per_second;
+    }
+}
+
 pub(crate) type PollActivityTaskBuffer =
     LongPollBuffer<PollActivityTaskQueueResponse, ActivitySlotKind>;
-#[allow(clippy::too_many_arguments)]
 pub(crate) fn new_activity_task_buffer(
     client: Arc<dyn WorkerClient>,
     task_queue: String,
-    concurrent_pollers: usize,
+    poller_behavior: PollerBehavior,
     semaphore: MeteredPermitDealer<ActivitySlotKind>,
-    max_tps: Option<f64>,
+    rate_limits: Arc<ActivityPollRateLimits>,
     shutdown: CancellationToken,
     num_pollers_handler: Option<impl Fn(usize) + Send + Sync + 'static>,
-    max_worker_acts_per_sec: Option<f64>,
 ) -> PollActivityTaskBuffer {
-    let rate_limiter = max_worker_acts_per_sec.and_then(|ps| {
-        Quota::with_period(Duration::from_secs_f64(ps.recip()))
-            .map(|q| Arc::new(RateLimiter::direct(q)))
-    });
+    let delay_limits = rate_limits.clone();
     LongPollBuffer::new(
         move || {
             let client = client.clone();
             let task_queue = task_queue.clone();
+            let max_tps = *rate_limits.task_queue_per_second.read();
             async move { client.poll_activity_task(task_queue, max_tps).await }
         },
         semaphore,
-        concurrent_pollers,
+        poller_behavior,
         shutdown,
         num_pollers_handler,
-        rate_limiter.map(|rl| {
-            move || {
-                let rl = rl.clone();
-                async move { rl.until_ready().await }.boxed()
+        Some(move || {
+            let rl = delay_limits.worker.read().clone();
+            async move {
+                if let Some(rl) = rl {
+                    rl.until_ready().await
+                }
             }
+            .boxed()
         }),
     )
 }
@@ -302,7 +565,7 @@ pub(crate) type PollNexusTaskBuffer = LongPollBuffer<PollNexusTaskQueueResponse,
 pub(crate) fn new_nexus_task_buffer(
     client: Arc<dyn WorkerClient>,
     task_queue: String,
-    concurrent_pollers: usize,
+    poller_behavior: PollerBehavior,
     semaphore: MeteredPermitDealer<NexusSlotKind>,
     shutdown: CancellationToken,
     num_pollers_handler: Option<impl Fn(usize) + Send + Sync + 'static>,
@@ -314,7 +577,7 @@ pub(crate) fn new_nexus_task_buffer(
             async move { client.poll_nexus_task(task_queue).await }
         },
         semaphore,
-        concurrent_pollers,
+        poller_behavior,
         shutdown,
         num_pollers_handler,
         None::<fn() -> BoxFuture<'static, ()>>,
@@ -364,7 +627,10 @@ mod tests {
     use futures_util::FutureExt;
     use std::time::Duration;
     use temporal_sdk_core_protos::temporal::api::enums::v1::TaskQueueKind;
-    use tokio::{select, sync::mpsc::channel};
+    use tokio::{
+        select,
+        sync::{Notify, mpsc::channel},
+    };
 
     #[tokio::test]
     async fn only_polls_once_with_1_poller() {
@@ -387,7 +653,7 @@ mod tests {
                 kind: TaskQueueKind::Normal as i32,
                 normal_name: "".to_string(),
             },
-            1,
+            PollerBehavior::SimpleMaximum(1),
             fixed_size_permit_dealer(10),
             CancellationToken::new(),
             None::<fn(usize)>,
@@ -417,4 +683,191 @@ mod tests {
         pb.poll().await.unwrap().unwrap();
         pb.shutdown().await;
     }
+
+    #[tokio::test]
+    async fn pausing_lets_in_flight_polls_finish() {
+        let release = Arc::new(Notify::new());
+        let poll_count = Arc::new(AtomicUsize::new(0));
+        let mut mock_client = mock_manual_workflow_client();
+        let (release_poll, count_poll) = (release.clone(), poll_count.clone());
+        mock_client
+            .expect_poll_workflow_task()
+            .times(2)
+            .returning(move |_| {
+                // Only the first poll ever gets a response
+                if count_poll.fetch_add(1, Ordering::Relaxed) > 0 {
+                    return future::pending().boxed();
+                }
+                let release = release_poll.clone();
+                async move {
+                    release.notified().await;
+                    Ok(Default::default())
+                }
+                .boxed()
+            });
+        let permit_dealer = fixed_size_permit_dealer(10);
+        let mut extant_permits = permit_dealer.get_extant_count_rcv();
+        let pb = new_workflow_task_buffer(
+            Arc::new(mock_client),
+            TaskQueue {
+                name: "sometq".to_string(),
+                kind: TaskQueueKind::Normal as i32,
+                normal_name: "".to_string(),
+            },
+            PollerBehavior::SimpleMaximum(1),
+            permit_dealer,
+            CancellationToken::new(),
+            None::<fn(usize)>,
+        );
+        let pauser = pb.pauser();
+        // Polling starts the pollers
+        let _ = tokio::time::timeout(Duration::from_millis(50), pb.poll()).await;
+        extant_permits.wait_for(|c| *c == 1).await.unwrap();
+
+        // The in-flight poll keeps its slot while paused, and its response isn't lost
+        pauser.pause();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(*extant_permits.borrow(), 1);
+        release.notify_one();
+        let (_, permit) = pb.poll().await.unwrap().unwrap();
+        drop(permit);
+        extant_permits.wait_for(|c| *c == 0).await.unwrap();
+        // Paused pollers don't poll again
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(poll_count.load(Ordering::Relaxed), 1);
+        assert_eq!(*extant_permits.borrow(), 0);
+
+        pauser.resume();
+        extant_permits.wait_for(|c| *c == 1).await.unwrap();
+        pb.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn scaling_changes_number_of_pollers() {
+        let mut mock_client = mock_manual_workflow_client();
+        mock_client
+            .expect_poll_workflow_task()
+            .times(3)
+            .returning(|_| future::pending().boxed());
+        let permit_dealer = fixed_size_permit_dealer(10);
+        let mut extant_permits = permit_dealer.get_extant_count_rcv();
+        let pb = new_workflow_task_buffer(
+            Arc::new(mock_client),
+            TaskQueue {
+                name: "sometq".to_string(),
+                kind: TaskQueueKind::Normal as i32,
+                normal_name: "".to_string(),
+            },
+            PollerBehavior::SimpleMaximum(1),
+            permit_dealer,
+            CancellationToken::new(),
+            None::<fn(usize)>,
+        );
+        let _ = tokio::time::timeout(Duration::from_millis(50), pb.poll()).await;
+        extant_permits.wait_for(|c| *c == 1).await.unwrap();
+
+        pb.scaler().set_max_pollers(3);
+        extant_permits.wait_for(|c| *c == 3).await.unwrap();
+        // Lowering the count leaves in-flight polls alone
+        pb.scaler().set_max_pollers(1);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(*extant_permits.borrow(), 3);
+        pb.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn autoscaling_spawns_pollers_on_demand() {
+        let mut mock_client = mock_manual_workflow_client();
+        let returned_task = AtomicBool::new(false);
+        mock_client
+            .expect_poll_workflow_task()
+            .times(3)
+            .returning(move |_| {
+                if returned_task.swap(true, Ordering::Relaxed) {
+                    return future::pending().boxed();
+                }
+                future::ready(Ok(PollWorkflowTaskQueueResponse {
+                    task_token: vec![1],
+                    ..Default::default()
+                }))
+                .boxed()
+            });
+        let (num_pollers_tx, mut num_pollers) = watch::channel(0);
+        let pb = new_workflow_task_buffer(
+            Arc::new(mock_client),
+            TaskQueue {
+                name: "sometq".to_string(),
+                kind: TaskQueueKind::Normal as i32,
+                normal_name: "".to_string(),
+            },
+            PollerBehavior::Autoscaling(PollerAutoscaling {
+                minimum: 1,
+                maximum: 5,
+                initial: 1,
+            }),
+            fixed_size_permit_dealer(10),
+            CancellationToken::new(),
+            Some(move |np| {
+                num_pollers_tx.send_replace(np);
+            }),
+        );
+        assert_eq!(pb.pool.join_handles.lock().len(), 1);
+
+        // The task came back quickly, so one more poller is spawned, rather than all of them
+        pb.poll().await.unwrap().unwrap();
+        num_pollers.wait_for(|np| *np == 2).await.unwrap();
+        assert_eq!(pb.pool.join_handles.lock().len(), 2);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(*num_pollers.borrow(), 2);
+        pb.shutdown().await;
+    }
+
+    #[test]
+    fn autoscaler_follows_poll_results() {
+        let (num_pollers, num_rx) = watch::channel(5);
+        let autoscaler = PollerAutoscaler {
+            bounds: PollerAutoscaling {
+                minimum: 2,
+                maximum: 20,
+                initial: 5,
+            },
+            num_pollers,
+            scale_up_blocked_until: Default::default(),
+        };
+        let task = || {
+            Ok(PollWorkflowTaskQueueResponse {
+                task_token: vec![1],
+                ..Default::default()
+            })
+        };
+        let slow = Duration::from_secs(5);
+
+        autoscaler.record(&task(), Duration::ZERO);
+        assert_eq!(*num_rx.borrow(), 6);
+        // Backlogs can at most double the count
+        autoscaler.record(
+            &Ok(PollWorkflowTaskQueueResponse {
+                backlog_count_hint: 100,
+                ..task().unwrap()
+            }),
+            slow,
+        );
+        assert_eq!(*num_rx.borrow(), 12);
+        autoscaler.record(&task(), slow);
+        assert_eq!(*num_rx.borrow(), 12);
+        autoscaler.record(&Ok(PollWorkflowTaskQueueResponse::default()), slow);
+        assert_eq!(*num_rx.borrow(), 11);
+        // Overload halves the count and stops it growing for a while
+        autoscaler.record::<PollWorkflowTaskQueueResponse>(
+            &Err(tonic::Status::resource_exhausted("slow down")),
+            slow,
+        );
+        assert_eq!(*num_rx.borrow(), 5);
+        autoscaler.record(&task(), Duration::ZERO);
+        assert_eq!(*num_rx.borrow(), 5);
+        for _ in 0..10 {
+            autoscaler.record(&Ok(PollWorkflowTaskQueueResponse::default()), slow);
+        }
+        assert_eq!(*num_rx.borrow(), 2);
+    }
 }
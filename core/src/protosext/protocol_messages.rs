@@ -9,6 +9,10 @@ use temporal_sdk_core_protos::temporal::api::{
 
 /// A decoded & verified of a [Message] that came with a WFT.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "save_wf_inputs",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub(crate) struct IncomingProtocolMessage {
     pub(crate) id: String,
     pub(crate) protocol_instance_id: String,
@@ -99,6 +103,10 @@ impl TryFrom<&HistoryEvent> for IncomingProtocolMessage {
 /// All the protocol [Message] bodies Core understands that might come to us when receiving a new
 /// WFT.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "save_wf_inputs",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub(crate) enum IncomingProtocolMessageBody {
     UpdateRequest(UpdateRequest),
 }
@@ -122,6 +130,10 @@ impl TryFrom<Option<prost_types::Any>> for IncomingProtocolMessageBody {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "save_wf_inputs",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub(crate) struct UpdateRequest {
     pub(crate) original: update::v1::Request,
 }
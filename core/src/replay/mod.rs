@@ -2,6 +2,8 @@
 //! to replay canned histories. It should be used by Lang SDKs to provide replay capabilities to
 //! users during testing.
 
+#[cfg(feature = "save_wf_inputs")]
+pub use crate::worker::replay_wf_state_inputs;
 use crate::{
     Worker,
     worker::{
@@ -12,6 +14,8 @@ use crate::{
 use futures_util::{FutureExt, Stream, StreamExt};
 use parking_lot::Mutex;
 use std::{
+    collections::{HashMap, HashSet},
+    num::NonZeroUsize,
     pin::Pin,
     sync::{Arc, OnceLock},
     task::{Context, Poll},
@@ -21,16 +25,19 @@ pub use temporal_sdk_core_protos::{
     DEFAULT_WORKFLOW_TYPE, HistoryInfo, TestHistoryBuilder, default_wes_attribs,
 };
 use temporal_sdk_core_protos::{
-    coresdk::workflow_activation::remove_from_cache::EvictionReason,
+    TaskToken,
+    coresdk::workflow_activation::{NondeterminismDiagnostic, remove_from_cache::EvictionReason},
     temporal::api::{
         common::v1::WorkflowExecution,
+        enums::v1::WorkflowTaskFailedCause,
+        failure::v1::Failure,
         history::v1::History,
         workflowservice::v1::{
             RespondWorkflowTaskCompletedResponse, RespondWorkflowTaskFailedResponse,
         },
     },
 };
-use tokio::sync::{Mutex as TokioMutex, mpsc, mpsc::UnboundedSender};
+use tokio::sync::{Mutex as TokioMutex, Notify, mpsc, mpsc::UnboundedSender};
 use tokio_stream::wrappers::UnboundedReceiverStream;
 use tokio_util::sync::CancellationToken;
 
@@ -40,6 +47,8 @@ pub struct ReplayWorkerInput<I> {
     /// worker is instantiated.
     pub config: WorkerConfig,
     history_stream: I,
+    concurrency: NonZeroUsize,
+    verdict_tx: Option<UnboundedSender<ReplayVerdict>>,
     /// If specified use this as the basis for the internal mocked client
     pub(crate) client_override: Option<MockManualWorkerClient>,
 }
@@ -53,16 +62,43 @@ where
         Self {
             config,
             history_stream,
+            concurrency: NonZeroUsize::MIN,
+            verdict_tx: None,
             client_override: None,
         }
     }
 
+    /// Replay up to `concurrency` histories at once. Defaults to one, in which case histories are
+    /// replayed strictly one after another.
+    ///
+    /// Histories sharing a run id are never replayed at the same time as one another.
+    pub fn with_concurrency(mut self, concurrency: NonZeroUsize) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// Returns a stream which yields one [ReplayVerdict] for every history once it has finished
+    /// replaying. The stream ends once the replay worker has shut down and been dropped.
+    ///
+    /// Must be called before the worker is created. Calling it again replaces the previously
+    /// returned stream, which will then end without yielding anything.
+    pub fn verdicts(&mut self) -> ReplayVerdictStream {
+        let (tx, rcvr) = mpsc::unbounded_channel();
+        self.verdict_tx = Some(tx);
+        ReplayVerdictStream { rcvr }
+    }
+
     pub(crate) fn into_core_worker(mut self) -> Result<Worker, anyhow::Error> {
-        self.config.max_cached_workflows = 1;
-        self.config.max_concurrent_wft_polls = 1;
+        self.config.max_cached_workflows = self.concurrency.get();
+        self.config.max_concurrent_wft_polls = self.concurrency.get();
         self.config.no_remote_activities = true;
-        let historator = Historator::new(self.history_stream);
+        let historator = Historator::new(
+            self.history_stream,
+            self.concurrency,
+            self.verdict_tx.take(),
+        );
         let post_activate = historator.get_post_activate_hook();
+        let record_wft_failure = historator.get_wft_failure_recorder();
         let shutdown_tok = historator.get_shutdown_setter();
         // Create a mock client which can be used by a replay worker to serve up canned histories.
         // It will return the entire history in one workflow task. If a workflow task failure is
@@ -76,7 +112,6 @@ where
             mock_manual_workflow_client()
         };
 
-        let hist_allow_tx = historator.replay_done_tx.clone();
         let historator = Arc::new(TokioMutex::new(historator));
 
         // TODO: Should use `new_with_pollers` and avoid re-doing mocking stuff
@@ -89,10 +124,20 @@ where
 
                 if let Some(history) = hlock.next().await {
                     let hist_info = HistoryInfo::new_from_history(&history.hist, None).unwrap();
+                    let run_id = hist_info.orig_run_id().to_string();
                     let mut resp = hist_info.as_poll_wft_response();
+                    let in_flight = InFlightHistory {
+                        workflow_id: history.workflow_id.clone(),
+                        task_token: resp.task_token.clone(),
+                        wft_failure: None,
+                    };
+                    let (dat, run_finished) = (hlock.dat.clone(), hlock.run_finished.clone());
+                    // Other pollers may dispatch other histories while this one waits
+                    drop(hlock);
+                    mark_in_flight(&dat, &run_finished, &run_id, in_flight).await;
                     resp.workflow_execution = Some(WorkflowExecution {
                         workflow_id: history.workflow_id,
-                        run_id: hist_info.orig_run_id().to_string(),
+                        run_id,
                     });
                     Ok(resp)
                 } else {
@@ -110,8 +155,8 @@ where
         });
         client
             .expect_fail_workflow_task()
-            .returning(move |_, _, _| {
-                hist_allow_tx.send("Failed".to_string()).unwrap();
+            .returning(move |tt, cause, failure| {
+                record_wft_failure(tt, cause, failure);
                 async move { Ok(RespondWorkflowTaskFailedResponse::default()) }.boxed()
             });
         let mut worker = Worker::new(self.config, None, Arc::new(client), None);
@@ -139,6 +184,60 @@ impl From<HistoryInfo> for HistoryForReplay {
     }
 }
 
+/// The result of replaying one [HistoryForReplay]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayVerdict {
+    /// The workflow id the history was provided with
+    pub workflow_id: String,
+    /// The run id from the history's workflow execution started event
+    pub run_id: String,
+    /// How replaying the history went
+    pub outcome: ReplayOutcome,
+}
+
+/// The outcome of replaying one history
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayOutcome {
+    /// The workflow code replayed the entire history without any workflow task failing
+    Success,
+    /// Replay failed because the workflow code did not match the history
+    Nondeterminism {
+        /// Details of where replay diverged from the history, if core detected the problem.
+        /// Absent if lang reported the nondeterminism itself.
+        diagnostic: Option<NondeterminismDiagnostic>,
+        /// The failure the workflow task was failed with
+        failure: Option<Failure>,
+    },
+    /// A workflow task failed for some reason other than nondeterminism, ex: the workflow code
+    /// panicked
+    WorkflowTaskFailed {
+        /// The cause the workflow task was failed with
+        cause: WorkflowTaskFailedCause,
+        /// The failure the workflow task was failed with
+        failure: Option<Failure>,
+    },
+}
+
+impl ReplayOutcome {
+    /// Returns true if the history replayed successfully
+    pub fn is_success(&self) -> bool {
+        matches!(self, Self::Success)
+    }
+}
+
+/// A stream of [ReplayVerdict]s, see [ReplayWorkerInput::verdicts]
+pub struct ReplayVerdictStream {
+    rcvr: mpsc::UnboundedReceiver<ReplayVerdict>,
+}
+
+impl Stream for ReplayVerdictStream {
+    type Item = ReplayVerdict;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rcvr.poll_recv(cx)
+    }
+}
+
 /// Allows lang to feed histories into the replayer one at a time. Simply drop the feeder to signal
 /// to the worker that you're done and it should initiate shutdown.
 pub struct HistoryFeeder {
@@ -180,20 +279,31 @@ pub(crate) struct Historator {
     allow_stream: UnboundedReceiverStream<String>,
     worker_closer: Arc<OnceLock<CancellationToken>>,
     dat: Arc<Mutex<HistoratorDat>>,
+    /// Notified whenever a history stops being in flight
+    run_finished: Arc<Notify>,
     replay_done_tx: UnboundedSender<String>,
+    verdict_tx: Option<UnboundedSender<ReplayVerdict>>,
 }
 impl Historator {
-    pub(crate) fn new(histories: impl Stream<Item = HistoryForReplay> + Send + 'static) -> Self {
+    pub(crate) fn new(
+        histories: impl Stream<Item = HistoryForReplay> + Send + 'static,
+        concurrency: NonZeroUsize,
+        verdict_tx: Option<UnboundedSender<ReplayVerdict>>,
+    ) -> Self {
         let dat = Arc::new(Mutex::new(HistoratorDat::default()));
         let (replay_done_tx, replay_done_rx) = mpsc::unbounded_channel();
-        // Need to allow the first history item
-        replay_done_tx.send("fake".to_string()).unwrap();
+        // Need to allow the first history items
+        for _ in 0..concurrency.get() {
+            replay_done_tx.send("fake".to_string()).unwrap();
+        }
         Self {
             iter: Box::pin(histories.fuse()),
             allow_stream: UnboundedReceiverStream::new(replay_done_rx),
             worker_closer: Arc::new(OnceLock::new()),
             dat,
+            run_finished: Default::default(),
             replay_done_tx,
+            verdict_tx,
         }
     }
 
@@ -202,15 +312,76 @@ impl Historator {
     pub(crate) fn get_post_activate_hook(
         &self,
     ) -> impl Fn(&Worker, PostActivateHookData) + Send + Sync + use<> {
+        let dat = self.dat.clone();
+        let run_finished = self.run_finished.clone();
         let done_tx = self.replay_done_tx.clone();
+        let verdict_tx = self.verdict_tx.clone();
         move |worker, data| {
-            if !data.replaying {
+            let mut dat = dat.lock();
+            // The eviction of a run which already has a verdict is being acknowledged
+            if dat.awaiting_evict_reply.remove(data.run_id) {
+                return;
+            }
+            let Some(has_failure) = dat
+                .in_flight
+                .get(data.run_id)
+                .map(|h| h.wft_failure.is_some())
+            else {
+                return;
+            };
+            if !has_failure && data.replaying {
+                return;
+            }
+            let in_flight = dat
+                .in_flight
+                .remove(data.run_id)
+                .expect("In flight history was just checked");
+            let outcome = if let Some((cause, failure)) = in_flight.wft_failure {
+                // If this completion is what failed the task, core will evict the run next.
+                // Otherwise the failure was reported automatically and this is the eviction reply.
+                if data.reported_wft_failure {
+                    dat.awaiting_evict_reply.insert(data.run_id.to_string());
+                }
+                if cause == WorkflowTaskFailedCause::NonDeterministicError {
+                    ReplayOutcome::Nondeterminism {
+                        diagnostic: data.nondeterminism.cloned(),
+                        failure,
+                    }
+                } else {
+                    ReplayOutcome::WorkflowTaskFailed { cause, failure }
+                }
+            } else {
                 worker.request_wf_eviction(
                     data.run_id,
                     "Always evict workflows after replay",
                     EvictionReason::LangRequested,
                 );
-                done_tx.send(data.run_id.to_string()).unwrap();
+                dat.awaiting_evict_reply.insert(data.run_id.to_string());
+                ReplayOutcome::Success
+            };
+            if let Some(tx) = verdict_tx.as_ref() {
+                let _ = tx.send(ReplayVerdict {
+                    workflow_id: in_flight.workflow_id,
+                    run_id: data.run_id.to_string(),
+                    outcome,
+                });
+            }
+            done_tx.send(data.run_id.to_string()).unwrap();
+            run_finished.notify_waiters();
+        }
+    }
+
+    /// Returns a callback the mocked client uses to record workflow task failures against the
+    /// history they belong to. The verdict is issued by the post-activation hook once the run is
+    /// done with.
+    pub(crate) fn get_wft_failure_recorder(
+        &self,
+    ) -> impl Fn(TaskToken, WorkflowTaskFailedCause, Option<Failure>) + Send + Sync + use<> {
+        let dat = self.dat.clone();
+        move |tt, cause, failure| {
+            let mut dat = dat.lock();
+            if let Some(in_flight) = dat.in_flight.values_mut().find(|h| h.task_token == tt.0) {
+                in_flight.wft_failure = Some((cause, failure));
             }
         }
     }
@@ -248,7 +419,39 @@ impl Stream for Historator {
     }
 }
 
+/// Histories sharing a run id can't be in the worker at the same time, so if one is still
+/// replaying, wait for it to finish before marking this one as in flight.
+async fn mark_in_flight(
+    dat: &Mutex<HistoratorDat>,
+    run_finished: &Notify,
+    run_id: &str,
+    history: InFlightHistory,
+) {
+    loop {
+        // Created before checking, so a run finishing in between isn't missed
+        let finished = run_finished.notified();
+        {
+            let mut dat = dat.lock();
+            if !dat.in_flight.contains_key(run_id) {
+                dat.in_flight.insert(run_id.to_string(), history);
+                return;
+            }
+        }
+        finished.await;
+    }
+}
+
 #[derive(Default)]
 struct HistoratorDat {
     all_dispatched: bool,
+    /// Histories which have been handed to the worker but have no verdict yet, by run id
+    in_flight: HashMap<String, InFlightHistory>,
+    /// Runs which have a verdict, but whose eviction has not yet been replied to
+    awaiting_evict_reply: HashSet<String>,
+}
+
+struct InFlightHistory {
+    workflow_id: String,
+    task_token: Vec<u8>,
+    wft_failure: Option<(WorkflowTaskFailedCause, Option<Failure>)>,
 }
@@ -0,0 +1,313 @@
+//! Tracks the health of workers so it can be reported to orchestrators, ex: through the endpoints
+//! described by [temporal_sdk_core_api::telemetry::HealthServerOptions]. Every worker created
+//! with a [crate::CoreRuntime] registers itself with the runtime's [WorkerRegistry].
+
+use parking_lot::Mutex;
+use serde::{Serialize, Serializer};
+use std::{
+    fmt::Display,
+    sync::{
+        Arc, Weak,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+use temporal_sdk_core_api::worker::PollerKind;
+use tokio_util::sync::CancellationToken;
+
+/// The set of workers whose health is reported on together. Workers remove themselves once
+/// dropped.
+#[derive(Clone, Default)]
+pub struct WorkerRegistry {
+    workers: Arc<Mutex<Vec<Weak<WorkerHealth>>>>,
+}
+
+impl WorkerRegistry {
+    pub(crate) fn register(&self, worker: &Arc<WorkerHealth>) {
+        let mut workers = self.workers.lock();
+        workers.retain(|w| w.strong_count() > 0);
+        workers.push(Arc::downgrade(worker));
+    }
+
+    /// Reports on the health of every registered worker. Pollers which keep polling without
+    /// succeeding for longer than `stale_poll_threshold` make their worker unhealthy.
+    pub fn report(&self, stale_poll_threshold: Duration) -> RuntimeHealthReport {
+        let workers: Vec<_> = {
+            let mut workers = self.workers.lock();
+            workers.retain(|w| w.strong_count() > 0);
+            workers.iter().filter_map(Weak::upgrade).collect()
+        };
+        let workers: Vec<_> = workers
+            .iter()
+            .map(|w| w.report(stale_poll_threshold))
+            .collect();
+        RuntimeHealthReport {
+            live: workers.iter().all(|w| w.live),
+            ready: !workers.is_empty() && workers.iter().all(|w| w.ready),
+            workers,
+        }
+    }
+}
+
+/// The health of every worker in a [WorkerRegistry]
+#[derive(Debug, Clone, Serialize)]
+pub struct RuntimeHealthReport {
+    /// True if every worker is live
+    pub live: bool,
+    /// True if there is at least one worker, and every worker is ready
+    pub ready: bool,
+    /// Health of each registered worker
+    pub workers: Vec<WorkerHealthReport>,
+}
+
+/// The health of a single worker
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkerHealthReport {
+    /// The namespace the worker polls in
+    pub namespace: String,
+    /// The task queue the worker polls
+    pub task_queue: String,
+    /// True once shutdown of the worker has been initiated
+    pub shutdown_requested: bool,
+    /// False if any of the worker's pollers are polling without succeeding. Workers which are
+    /// shutting down are always live, so they aren't interrupted while draining.
+    pub live: bool,
+    /// True if the worker is live, has polled successfully, and isn't shutting down
+    pub ready: bool,
+    /// Only includes kinds of tasks which the worker has started polling for
+    pub pollers: Vec<PollerHealthReport>,
+}
+
+/// The health of a worker's pollers for one kind of task
+#[derive(Debug, Clone, Serialize)]
+pub struct PollerHealthReport {
+    /// The kind of task being polled for
+    #[serde(serialize_with = "serialize_display")]
+    pub kind: PollerKind,
+    /// Number of polls currently in flight. Drops to zero while all slots are in use, or while
+    /// polling is paused.
+    pub num_pollers: usize,
+    /// Unset if no poll has succeeded yet
+    pub since_last_successful_poll: Option<Duration>,
+    /// Number of polls which have failed since the last successful one
+    pub consecutive_failures: u64,
+}
+
+/// Health state for a single worker, updated by its pollers
+pub(crate) struct WorkerHealth {
+    namespace: String,
+    task_queue: String,
+    shutdown_token: CancellationToken,
+    pollers: Mutex<Vec<(PollerKind, Arc<PollerHealth>)>>,
+}
+
+impl WorkerHealth {
+    pub(crate) fn new(
+        namespace: String,
+        task_queue: String,
+        shutdown_token: CancellationToken,
+    ) -> Self {
+        Self {
+            namespace,
+            task_queue,
+            shutdown_token,
+            pollers: Default::default(),
+        }
+    }
+
+    /// Returns the health of the pollers for `kind`, which may be shared by several poll buffers
+    pub(crate) fn poller(&self, kind: PollerKind) -> Arc<PollerHealth> {
+        let mut pollers = self.pollers.lock();
+        if let Some((_, ph)) = pollers.iter().find(|(k, _)| *k == kind) {
+            return ph.clone();
+        }
+        let ph = Arc::new(PollerHealth::default());
+        pollers.push((kind, ph.clone()));
+        ph
+    }
+
+    fn report(&self, stale_poll_threshold: Duration) -> WorkerHealthReport {
+        let shutdown_requested = self.shutdown_token.is_cancelled();
+        let mut pollers_live = true;
+        let mut pollers_ready = true;
+        let mut any_succeeded = false;
+        let mut pollers = vec![];
+        for (kind, ph) in self.pollers.lock().iter() {
+            let state = ph.state.lock();
+            let Some(started_at) = state.started_at else {
+                continue;
+            };
+            let num_pollers = ph.num_pollers.load(Ordering::Relaxed);
+            let last_progress = state.last_success.unwrap_or(started_at);
+            if num_pollers > 0 && last_progress.elapsed() >= stale_poll_threshold {
+                pollers_live = false;
+            }
+            pollers_ready &= state.last_success.is_some();
+            any_succeeded |= state.last_success.is_some();
+            pollers.push(PollerHealthReport {
+                kind: *kind,
+                num_pollers,
+                since_last_successful_poll: state.last_success.map(|i| i.elapsed()),
+                consecutive_failures: ph.consecutive_failures.load(Ordering::Relaxed),
+            });
+        }
+        WorkerHealthReport {
+            namespace: self.namespace.clone(),
+            task_queue: self.task_queue.clone(),
+            shutdown_requested,
+            live: shutdown_requested || pollers_live,
+            ready: !shutdown_requested && pollers_live && pollers_ready && any_succeeded,
+            pollers,
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct PollerHealth {
+    num_pollers: AtomicUsize,
+    consecutive_failures: AtomicU64,
+    state: Mutex<PollerHealthState>,
+}
+
+#[derive(Default)]
+struct PollerHealthState {
+    /// When polling first started. Unset if lang has never polled for this kind of task.
+    started_at: Option<Instant>,
+    last_success: Option<Instant>,
+}
+
+impl PollerHealth {
+    /// Returns a function which keeps track of the number of pollers in one poll buffer, to be
+    /// called with its current number of pollers whenever that changes
+    pub(crate) fn num_pollers_handler(self: &Arc<Self>) -> impl Fn(usize) + Send + Sync + 'static {
+        let me = self.clone();
+        let buffer_pollers = AtomicUsize::new(0);
+        move |np| {
+            let prev = buffer_pollers.swap(np, Ordering::Relaxed);
+            if np >= prev {
+                me.num_pollers.fetch_add(np - prev, Ordering::Relaxed);
+                if np > 0 {
+                    me.state.lock().started_at.get_or_insert_with(Instant::now);
+                }
+            } else {
+                me.num_pollers.fetch_sub(prev - np, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Records the outcome of a poll. Polls which time out without a task still count as
+    /// successful.
+    pub(crate) fn record_poll(&self, succeeded: bool) {
+        let mut state = self.state.lock();
+        let now = Instant::now();
+        state.started_at.get_or_insert(now);
+        if succeeded {
+            state.last_success = Some(now);
+            self.consecutive_failures.store(0, Ordering::Relaxed);
+        } else {
+            self.consecutive_failures.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+fn serialize_display<T: Display, S: Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registered_worker(registry: &WorkerRegistry) -> (Arc<WorkerHealth>, CancellationToken) {
+        let shutdown = CancellationToken::new();
+        let health = Arc::new(WorkerHealth::new(
+            "ns".to_string(),
+            "tq".to_string(),
+            shutdown.clone(),
+        ));
+        registry.register(&health);
+        (health, shutdown)
+    }
+
+    #[test]
+    fn not_ready_until_polled_successfully() {
+        let registry = WorkerRegistry::default();
+        let report = registry.report(Duration::from_secs(60));
+        assert!(report.live);
+        assert!(!report.ready);
+
+        let (health, _shutdown) = registered_worker(&registry);
+        let wf = health.poller(PollerKind::Workflow);
+        let set_pollers = wf.num_pollers_handler();
+        set_pollers(2);
+        let report = registry.report(Duration::from_secs(60));
+        assert!(report.live);
+        assert!(!report.ready);
+        assert_eq!(report.workers[0].pollers[0].num_pollers, 2);
+
+        wf.record_poll(true);
+        let report = registry.report(Duration::from_secs(60));
+        assert!(report.live);
+        assert!(report.ready);
+    }
+
+    #[test]
+    fn pollers_without_success_are_not_live() {
+        let registry = WorkerRegistry::default();
+        let (health, _shutdown) = registered_worker(&registry);
+        let act = health.poller(PollerKind::Activity);
+        let set_pollers = act.num_pollers_handler();
+        set_pollers(1);
+        act.record_poll(false);
+        act.record_poll(false);
+        let report = registry.report(Duration::ZERO);
+        assert!(!report.live);
+        assert!(!report.ready);
+        assert_eq!(report.workers[0].pollers[0].consecutive_failures, 2);
+
+        // No polls in flight means nothing is failing, ex: because all slots are in use
+        set_pollers(0);
+        assert!(registry.report(Duration::ZERO).live);
+    }
+
+    #[test]
+    fn pollers_sharing_a_kind_are_summed() {
+        let registry = WorkerRegistry::default();
+        let (health, _shutdown) = registered_worker(&registry);
+        let normal = health.poller(PollerKind::Workflow).num_pollers_handler();
+        let sticky = health.poller(PollerKind::Workflow).num_pollers_handler();
+        normal(3);
+        sticky(2);
+        normal(1);
+        let report = registry.report(Duration::from_secs(60));
+        assert_eq!(report.workers[0].pollers.len(), 1);
+        assert_eq!(report.workers[0].pollers[0].num_pollers, 3);
+    }
+
+    #[test]
+    fn shutting_down_workers_are_live_but_not_ready() {
+        let registry = WorkerRegistry::default();
+        let (health, shutdown) = registered_worker(&registry);
+        let wf = health.poller(PollerKind::Workflow);
+        wf.record_poll(true);
+        assert!(registry.report(Duration::from_secs(60)).ready);
+
+        shutdown.cancel();
+        let report = registry.report(Duration::from_secs(60));
+        assert!(report.live);
+        assert!(!report.ready);
+        assert!(report.workers[0].shutdown_requested);
+    }
+
+    #[test]
+    fn dropped_workers_are_unregistered() {
+        let registry = WorkerRegistry::default();
+        let (health, _shutdown) = registered_worker(&registry);
+        assert_eq!(registry.report(Duration::ZERO).workers.len(), 1);
+        drop(health);
+        assert!(registry.report(Duration::ZERO).workers.is_empty());
+    }
+}
@@ -0,0 +1,179 @@
+use crate::telemetry::health::{RuntimeHealthReport, WorkerRegistry};
+use http_body_util::Full;
+use hyper::{Method, Request, Response, body::Bytes, header::CONTENT_TYPE, service::service_fn};
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo},
+    server::conn::auto,
+};
+use std::{
+    net::{SocketAddr, TcpListener},
+    time::Duration,
+};
+use temporal_sdk_core_api::telemetry::HealthServerOptions;
+
+/// The worker registry a server reports on, along with how to judge it
+#[derive(Clone)]
+pub(super) struct HealthEndpoints {
+    pub(super) registry: WorkerRegistry,
+    pub(super) stale_poll_threshold: Duration,
+}
+
+impl HealthEndpoints {
+    fn report(&self) -> RuntimeHealthReport {
+        self.registry.report(self.stale_poll_threshold)
+    }
+}
+
+/// Serves worker health, for servers which don't also export prometheus metrics
+pub(super) struct HealthServer {
+    listener: TcpListener,
+    bound_addr: SocketAddr,
+    endpoints: HealthEndpoints,
+}
+
+impl HealthServer {
+    pub(super) fn new(
+        opts: &HealthServerOptions,
+        registry: WorkerRegistry,
+    ) -> Result<Self, anyhow::Error> {
+        let listener = TcpListener::bind(opts.socket_addr)?;
+        Ok(Self {
+            bound_addr: listener.local_addr()?,
+            listener,
+            endpoints: HealthEndpoints {
+                registry,
+                stale_poll_threshold: opts.stale_poll_threshold,
+            },
+        })
+    }
+
+    pub(super) async fn run(self) -> Result<(), anyhow::Error> {
+        self.listener.set_nonblocking(true)?;
+        let listener = tokio::net::TcpListener::from_std(self.listener)?;
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let io = TokioIo::new(stream);
+            let endpoints = self.endpoints.clone();
+            tokio::task::spawn(async move {
+                let server = auto::Builder::new(TokioExecutor::new());
+                if let Err(e) = server
+                    .serve_connection(
+                        io,
+                        service_fn(move |req| {
+                            let resp = health_response(&req, &endpoints).unwrap_or_else(not_found);
+                            async move { Ok::<_, hyper::Error>(resp) }
+                        }),
+                    )
+                    .await
+                {
+                    warn!("Error serving health connection: {:?}", e);
+                }
+            });
+        }
+    }
+
+    pub(super) fn bound_addr(&self) -> SocketAddr {
+        self.bound_addr
+    }
+}
+
+/// Responds to requests for any of the health endpoints, or returns `None` if the request is for
+/// something else
+pub(super) fn health_response<B>(
+    req: &Request<B>,
+    endpoints: &HealthEndpoints,
+) -> Option<Response<Full<Bytes>>> {
+    if req.method() != Method::GET {
+        return None;
+    }
+    let check = |healthy: bool| {
+        let (status, body) = if healthy {
+            (200, "ok")
+        } else {
+            (503, "unavailable")
+        };
+        Response::builder()
+            .status(status)
+            .body(body.into())
+            .expect("Can't fail to construct health resp")
+    };
+    let response = match req.uri().path() {
+        "/healthz" => check(endpoints.report().live),
+        "/readyz" => check(endpoints.report().ready),
+        "/status" => {
+            let body = serde_json::to_vec(&endpoints.report())
+                .expect("Health report is always serializable");
+            Response::builder()
+                .status(200)
+                .header(CONTENT_TYPE, "application/json")
+                .body(body.into())
+                .expect("Can't fail to construct status resp")
+        }
+        _ => return None,
+    };
+    Some(response)
+}
+
+pub(super) fn not_found() -> Response<Full<Bytes>> {
+    Response::builder()
+        .status(404)
+        .body(vec![].into())
+        .expect("Can't fail to construct empty resp")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::health::WorkerHealth;
+    use http_body_util::BodyExt;
+    use std::sync::Arc;
+    use temporal_sdk_core_api::worker::PollerKind;
+    use tokio_util::sync::CancellationToken;
+
+    fn get(path: &str) -> Request<()> {
+        Request::get(path).body(()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn serves_health_endpoints() {
+        let endpoints = HealthEndpoints {
+            registry: WorkerRegistry::default(),
+            stale_poll_threshold: Duration::from_secs(60),
+        };
+        assert_eq!(
+            health_response(&get("/healthz"), &endpoints)
+                .unwrap()
+                .status(),
+            200
+        );
+        // Nothing is ready without any workers
+        assert_eq!(
+            health_response(&get("/readyz"), &endpoints)
+                .unwrap()
+                .status(),
+            503
+        );
+        assert!(health_response(&get("/metrics"), &endpoints).is_none());
+
+        let health = Arc::new(WorkerHealth::new(
+            "ns".to_string(),
+            "tq".to_string(),
+            CancellationToken::new(),
+        ));
+        endpoints.registry.register(&health);
+        health.poller(PollerKind::Workflow).record_poll(true);
+        assert_eq!(
+            health_response(&get("/readyz"), &endpoints)
+                .unwrap()
+                .status(),
+            200
+        );
+        let status = health_response(&get("/status"), &endpoints).unwrap();
+        assert_eq!(status.headers()[CONTENT_TYPE], "application/json");
+        let body = status.into_body().collect().await.unwrap().to_bytes();
+        let status: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(status["ready"], true);
+        assert_eq!(status["workers"][0]["task_queue"], "tq");
+        assert_eq!(status["workers"][0]["pollers"][0]["kind"], "Workflow");
+    }
+}
@@ -37,6 +37,7 @@ struct Instruments {
     wf_task_sched_to_start_latency: Arc<dyn HistogramDuration>,
     wf_task_replay_latency: Arc<dyn HistogramDuration>,
     wf_task_execution_latency: Arc<dyn HistogramDuration>,
+    wf_activation_deadlock_counter: Arc<dyn Counter>,
     act_poll_no_task: Arc<dyn Counter>,
     act_task_received_counter: Arc<dyn Counter>,
     act_execution_failed: Arc<dyn Counter>,
@@ -60,6 +61,7 @@ struct Instruments {
     sticky_cache_hit: Arc<dyn Counter>,
     sticky_cache_miss: Arc<dyn Counter>,
     sticky_cache_size: Arc<dyn Gauge>,
+    sticky_cache_estimated_bytes: Arc<dyn Gauge>,
     sticky_cache_forced_evictions: Arc<dyn Counter>,
 }
 
@@ -174,6 +176,13 @@ impl MetricsContext {
             .record(dur, &self.kvs);
     }
 
+    /// Lang did not complete a workflow activation before the activation deadline
+    pub(crate) fn wf_activation_deadlock(&self) {
+        self.instruments
+            .wf_activation_deadlock_counter
+            .add(1, &self.kvs);
+    }
+
     /// An activity long poll timed out
     pub(crate) fn act_poll_timeout(&self) {
         self.instruments.act_poll_no_task.add(1, &self.kvs);
@@ -301,6 +310,13 @@ impl MetricsContext {
         self.instruments.sticky_cache_size.record(size, &self.kvs);
     }
 
+    /// Record the estimated memory used by cached workflows, in bytes
+    pub(crate) fn cache_estimated_bytes(&self, bytes: u64) {
+        self.instruments
+            .sticky_cache_estimated_bytes
+            .record(bytes, &self.kvs);
+    }
+
     /// Count a workflow being evicted from the cache
     pub(crate) fn forced_cache_eviction(&self) {
         self.instruments
@@ -367,6 +383,13 @@ impl Instruments {
                 unit: "duration".into(),
                 description: "Histogram of workflow task execution (not replay) latencies".into(),
             }),
+            wf_activation_deadlock_counter: meter.counter(MetricParameters {
+                name: "workflow_activation_deadlock_detected".into(),
+                description: "Count of workflow activations which were not completed before the \
+                              activation deadline"
+                    .into(),
+                unit: "".into(),
+            }),
             act_poll_no_task: meter.counter(MetricParameters {
                 name: "activity_poll_no_task".into(),
                 description: "Count of activity task queue poll timeouts (no new task)".into(),
@@ -489,6 +512,11 @@ impl Instruments {
                 description: "Current number of cached workflows".into(),
                 unit: "".into(),
             }),
+            sticky_cache_estimated_bytes: meter.gauge(MetricParameters {
+                name: "sticky_cache_estimated_size_bytes".into(),
+                description: "Estimated memory used by cached workflows".into(),
+                unit: "bytes".into(),
+            }),
             sticky_cache_forced_evictions: meter.counter(MetricParameters {
                 name: "sticky_cache_total_forced_eviction".into(),
                 description: "Count of evictions of cached workflows".into(),
@@ -506,6 +534,7 @@ const KEY_POLLER_TYPE: &str = "poller_type";
 const KEY_WORKER_TYPE: &str = "worker_type";
 const KEY_EAGER: &str = "eager";
 const KEY_TASK_FAILURE_TYPE: &str = "failure_reason";
+const KEY_CACHE_PARTITION: &str = "cache_partition";
 
 pub(crate) fn workflow_poller() -> MetricKeyValue {
     MetricKeyValue::new(KEY_POLLER_TYPE, "workflow_task")
@@ -528,6 +557,9 @@ pub(crate) fn activity_type(ty: String) -> MetricKeyValue {
 pub(crate) fn workflow_type(ty: String) -> MetricKeyValue {
     MetricKeyValue::new(KEY_WF_TYPE, ty)
 }
+pub(crate) fn cache_partition(partition: String) -> MetricKeyValue {
+    MetricKeyValue::new(KEY_CACHE_PARTITION, partition)
+}
 pub(crate) fn workflow_worker_type() -> MetricKeyValue {
     MetricKeyValue::new(KEY_WORKER_TYPE, "WorkflowWorker")
 }
@@ -1,6 +1,9 @@
 //! This module helps with the initialization and management of telemetry. IE: Metrics and tracing.
 //! Logs from core are all traces, which may be exported to the console, in memory, or externally.
 
+mod health;
+#[cfg(feature = "otel")]
+mod health_server;
 mod log_export;
 pub(crate) mod metrics;
 #[cfg(feature = "otel")]
@@ -18,8 +21,11 @@ pub use metrics::{
 #[cfg(feature = "otel")]
 pub use otel::{build_otlp_metric_exporter, start_prometheus_metric_exporter};
 
+pub use health::{PollerHealthReport, RuntimeHealthReport, WorkerHealthReport, WorkerRegistry};
 pub use log_export::{CoreLogBuffer, CoreLogBufferedConsumer, CoreLogStreamConsumer};
 
+pub(crate) use health::{PollerHealth, WorkerHealth};
+
 use crate::telemetry::{log_export::CoreLogConsumerLayer, metrics::PrefixedMetricsMeter};
 use itertools::Itertools;
 use parking_lot::Mutex;
@@ -60,6 +66,14 @@ pub struct TelemetryInstance {
     /// the user has not opted into any tracing configuration.
     trace_subscriber: Option<Arc<dyn Subscriber + Send + Sync>>,
     attach_service_name: bool,
+    /// Workers created with this telemetry register their health here
+    worker_registry: WorkerRegistry,
+    /// Kept alive so spans and logs keep being exported over OTLP
+    #[cfg(feature = "otel")]
+    _otel_tracing: Option<otel::OtelTracingExport>,
+    /// Bound during init, but can't run until a tokio runtime exists
+    #[cfg(feature = "otel")]
+    health_server: Option<health_server::HealthServer>,
 }
 
 impl TelemetryInstance {
@@ -76,6 +90,11 @@ impl TelemetryInstance {
             metrics,
             trace_subscriber,
             attach_service_name,
+            worker_registry: Default::default(),
+            #[cfg(feature = "otel")]
+            _otel_tracing: None,
+            #[cfg(feature = "otel")]
+            health_server: None,
         }
     }
 
@@ -92,6 +111,30 @@ impl TelemetryInstance {
         self.metrics = Some(meter);
     }
 
+    /// Returns the registry which workers created with this telemetry report their health to
+    pub fn worker_registry(&self) -> WorkerRegistry {
+        self.worker_registry.clone()
+    }
+
+    /// Starts the standalone health server configured in [TelemetryOptions], if there is one and
+    /// it isn't running yet. Returns the address it's bound to along with a handle to stop it.
+    ///
+    /// # Panics
+    /// If there is no currently active Tokio runtime
+    #[cfg(feature = "otel")]
+    pub(crate) fn start_health_server(
+        &mut self,
+    ) -> Option<(std::net::SocketAddr, tokio::task::AbortHandle)> {
+        let srv = self.health_server.take()?;
+        let bound_addr = srv.bound_addr();
+        let handle = tokio::spawn(async move {
+            if let Err(e) = srv.run().await {
+                error!(error=%e, "Worker health server stopped");
+            }
+        });
+        Some((bound_addr, handle.abort_handle()))
+    }
+
     /// Returns our wrapper for metric meters, including the `metric_prefix` from
     /// [TelemetryOptions]. This should be used to initialize clients or for any other
     /// temporal-owned metrics. User defined metrics should use [Self::get_metric_meter].
@@ -171,9 +214,39 @@ pub fn telemetry_init(opts: TelemetryOptions) -> Result<TelemetryInstance, anyho
     let mut console_pretty_layer = None;
     let mut console_compact_layer = None;
     let mut forward_layer = None;
+    #[cfg(feature = "otel")]
+    let mut otel_span_layer = None;
+    #[cfg(feature = "otel")]
+    let mut otel_log_layer = None;
     // ===================================
 
-    let tracing_sub = opts.logging.map(|logger| {
+    let exports_over_otel = opts.otel_traces.is_some() || opts.otel_logs.is_some();
+    #[cfg(feature = "otel")]
+    let otel_tracing = if exports_over_otel {
+        let export = otel::OtelTracingExport::new(opts.otel_traces, opts.otel_logs)?;
+        if let Some((tracer, filter)) = export.tracer.clone() {
+            otel_span_layer = Some(
+                tracing_opentelemetry::layer()
+                    .with_tracer(tracer)
+                    .with_filter(otel_export_filter(&filter)?),
+            );
+        }
+        if let Some((provider, filter)) = export.logger_provider.as_ref() {
+            otel_log_layer = Some(
+                opentelemetry_appender_tracing::layer::OpenTelemetryTracingBridge::new(provider)
+                    .with_filter(otel_export_filter(filter)?),
+            );
+        }
+        Some(export)
+    } else {
+        None
+    };
+    #[cfg(not(feature = "otel"))]
+    if exports_over_otel {
+        anyhow::bail!("Exporting traces or logs over OTLP requires the `otel` feature");
+    }
+
+    if let Some(logger) = opts.logging {
         match logger {
             Logger::Console { filter } => {
                 // This is silly dupe but can't be avoided without boxing.
@@ -212,23 +285,57 @@ pub fn telemetry_init(opts: TelemetryOptions) -> Result<TelemetryInstance, anyho
                     Some(CoreLogConsumerLayer::new(consumer).with_filter(EnvFilter::new(filter)));
             }
         };
-        let reg = tracing_subscriber::registry()
-            .with(console_pretty_layer)
-            .with(console_compact_layer)
-            .with(forward_layer);
+    }
+    let tracing_sub = (console_pretty_layer.is_some()
+        || console_compact_layer.is_some()
+        || forward_layer.is_some()
+        || exports_over_otel)
+        .then(|| {
+            let reg = tracing_subscriber::registry()
+                .with(console_pretty_layer)
+                .with(console_compact_layer)
+                .with(forward_layer);
+
+            #[cfg(feature = "otel")]
+            let reg = reg.with(otel_span_layer).with(otel_log_layer);
 
-        #[cfg(feature = "tokio-console")]
-        let reg = reg.with(console_subscriber::spawn());
-        Arc::new(reg) as Arc<dyn Subscriber + Send + Sync>
-    });
+            #[cfg(feature = "tokio-console")]
+            let reg = reg.with(console_subscriber::spawn());
+            Arc::new(reg) as Arc<dyn Subscriber + Send + Sync>
+        });
 
-    Ok(TelemetryInstance::new(
+    #[allow(unused_mut)]
+    let mut instance = TelemetryInstance::new(
         tracing_sub,
         logs_out,
         opts.metric_prefix,
         opts.metrics,
         opts.attach_service_name,
-    ))
+    );
+    #[cfg(feature = "otel")]
+    {
+        instance._otel_tracing = otel_tracing;
+        instance.health_server = opts
+            .health_server
+            .map(|hopts| health_server::HealthServer::new(&hopts, instance.worker_registry()))
+            .transpose()?;
+    }
+    #[cfg(not(feature = "otel"))]
+    if opts.health_server.is_some() {
+        anyhow::bail!("Serving worker health requires the `otel` feature");
+    }
+    Ok(instance)
+}
+
+/// The exporters themselves emit traces (from hyper, tonic, etc) which must not be exported, or
+/// every export would produce more things to export.
+#[cfg(feature = "otel")]
+fn otel_export_filter(filter: &str) -> Result<EnvFilter, anyhow::Error> {
+    let mut env_filter = EnvFilter::new(filter);
+    for noisy in ["hyper", "tonic", "h2", "reqwest", "opentelemetry"] {
+        env_filter = env_filter.add_directive(format!("{noisy}=off").parse()?);
+    }
+    Ok(env_filter)
 }
 
 /// Initialize telemetry/tracing globally. Useful for testing. Only takes affect when called
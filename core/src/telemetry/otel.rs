@@ -1,5 +1,7 @@
 use super::{
     TELEM_SERVICE_NAME, default_buckets_for,
+    health::WorkerRegistry,
+    health_server::HealthEndpoints,
     metrics::{
         ACTIVITY_EXEC_LATENCY_HISTOGRAM_NAME, ACTIVITY_SCHED_TO_START_LATENCY_HISTOGRAM_NAME,
         DEFAULT_MS_BUCKETS, WORKFLOW_E2E_LATENCY_HISTOGRAM_NAME,
@@ -13,20 +15,28 @@ use crate::{abstractions::dbg_panic, telemetry::metrics::DEFAULT_S_BUCKETS};
 use opentelemetry::{
     self, Key, KeyValue, Value, global,
     metrics::{Meter, MeterProvider as MeterProviderT},
+    trace::TracerProvider as TracerProviderT,
 };
-use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_otlp::{HttpExporterBuilder, TonicExporterBuilder, WithExportConfig};
 use opentelemetry_sdk::{
     Resource,
+    logs::LoggerProvider,
     metrics::{
         Aggregation, Instrument, InstrumentKind, MeterProviderBuilder, PeriodicReader,
         SdkMeterProvider, View, data::Temporality, new_view, reader::TemporalitySelector,
     },
     runtime,
+    trace::{Tracer, TracerProvider},
+};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, OnceLock},
+    time::Duration,
 };
-use std::{collections::HashMap, net::SocketAddr, sync::Arc, time::Duration};
 use temporal_sdk_core_api::telemetry::{
-    HistogramBucketOverrides, MetricTemporality, OtelCollectorOptions, OtlpProtocol,
-    PrometheusExporterOptions,
+    HistogramBucketOverrides, MetricTemporality, OtelCollectorOptions, OtelExportOptions,
+    OtlpProtocol, PrometheusExporterOptions,
     metrics::{
         CoreMeter, Counter, Gauge, GaugeF64, Histogram, HistogramDuration, HistogramF64,
         MetricAttributes, MetricParameters, NewAttributes,
@@ -160,10 +170,121 @@ pub fn build_otlp_metric_exporter(
     })
 }
 
+/// Span and log exporters sending core's tracing output over OTLP. Exports happen on a small
+/// runtime owned by this struct, since telemetry is initialized before core's own runtime exists.
+pub(super) struct OtelTracingExport {
+    pub(super) tracer: Option<(Tracer, String)>,
+    pub(super) logger_provider: Option<(LoggerProvider, String)>,
+    tracer_provider: Option<TracerProvider>,
+    runtime: Option<tokio::runtime::Runtime>,
+}
+
+impl OtelTracingExport {
+    pub(super) fn new(
+        traces: Option<OtelExportOptions>,
+        logs: Option<OtelExportOptions>,
+    ) -> Result<Self, anyhow::Error> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("temporal-core-otel-export")
+            .enable_all()
+            .build()?;
+        // Batch processors spawn their export tasks onto the runtime that's current when built
+        let _guard = runtime.enter();
+        let tracer_provider = traces
+            .as_ref()
+            .map(|opts| {
+                let exporter = match opts.protocol {
+                    OtlpProtocol::Grpc => grpc_exporter(opts)?.build_span_exporter()?,
+                    OtlpProtocol::Http => http_exporter(opts).build_span_exporter()?,
+                };
+                Ok::<_, anyhow::Error>(
+                    TracerProvider::builder()
+                        .with_batch_exporter(exporter, runtime::Tokio)
+                        .with_resource(default_resource(&opts.global_tags))
+                        .build(),
+                )
+            })
+            .transpose()?;
+        let logger_provider = logs
+            .map(|opts| {
+                let exporter = match opts.protocol {
+                    OtlpProtocol::Grpc => grpc_exporter(&opts)?.build_log_exporter()?,
+                    OtlpProtocol::Http => http_exporter(&opts).build_log_exporter()?,
+                };
+                let provider = LoggerProvider::builder()
+                    .with_batch_exporter(exporter, runtime::Tokio)
+                    .with_resource(default_resource(&opts.global_tags))
+                    .build();
+                Ok::<_, anyhow::Error>((provider, opts.filter))
+            })
+            .transpose()?;
+        let tracer = tracer_provider
+            .as_ref()
+            .zip(traces)
+            .map(|(provider, opts)| (provider.tracer(TELEM_SERVICE_NAME), opts.filter));
+        drop(_guard);
+        Ok(Self {
+            tracer,
+            logger_provider,
+            tracer_provider,
+            runtime: Some(runtime),
+        })
+    }
+}
+
+impl Drop for OtelTracingExport {
+    fn drop(&mut self) {
+        // Flush whatever is still batched before the export runtime goes away
+        if let Some(tp) = self.tracer_provider.take() {
+            if let Err(e) = tp.shutdown() {
+                warn!("Error shutting down OTel trace exporter: {:?}", e);
+            }
+        }
+        if let Some((lp, _)) = self.logger_provider.take() {
+            if let Err(e) = lp.shutdown() {
+                warn!("Error shutting down OTel log exporter: {:?}", e);
+            }
+        }
+        // Dropping a runtime inside another blocks, which tokio forbids
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+fn grpc_exporter(opts: &OtelExportOptions) -> Result<TonicExporterBuilder, anyhow::Error> {
+    let mut exporter = TonicExporterBuilder::default().with_endpoint(opts.url.to_string());
+    if opts.url.scheme() == "https" || opts.url.scheme() == "grpcs" {
+        exporter = exporter.with_tls_config(ClientTlsConfig::new().with_native_roots());
+    }
+    Ok(exporter.with_metadata(MetadataMap::from_headers((&opts.headers).try_into()?)))
+}
+
+fn http_exporter(opts: &OtelExportOptions) -> HttpExporterBuilder {
+    HttpExporterBuilder::default()
+        .with_endpoint(opts.url.to_string())
+        .with_headers(opts.headers.clone())
+}
+
 pub struct StartedPromServer {
     pub meter: Arc<CoreOtelMeter>,
     pub bound_addr: SocketAddr,
     pub abort_handle: AbortHandle,
+    health: Arc<OnceLock<HealthEndpoints>>,
+}
+
+impl StartedPromServer {
+    /// Additionally serve the worker health endpoints described by
+    /// [temporal_sdk_core_api::telemetry::HealthServerOptions] from this server, reporting on the
+    /// workers in `registry` (ex: [crate::CoreRuntime::worker_registry]). Only the first call has
+    /// any effect.
+    pub fn serve_worker_health(&self, registry: WorkerRegistry, stale_poll_threshold: Duration) {
+        let _ = self.health.set(HealthEndpoints {
+            registry,
+            stale_poll_threshold,
+        });
+    }
 }
 
 /// Builds and runs a prometheus endpoint which can be scraped by prom instances for metrics export.
@@ -182,6 +303,7 @@ pub fn start_prometheus_metric_exporter(
     )?
     .build();
     let bound_addr = srv.bound_addr()?;
+    let health = srv.health_endpoints();
     let handle = tokio::spawn(async move { srv.run().await });
     Ok(StartedPromServer {
         meter: Arc::new(CoreOtelMeter {
@@ -191,6 +313,7 @@ pub fn start_prometheus_metric_exporter(
         }),
         bound_addr,
         abort_handle: handle.abort_handle(),
+        health,
     })
 }
 
@@ -354,4 +477,26 @@ pub(crate) mod tests {
         let service_name = resource.get(Key::from("service.name"));
         assert_eq!(service_name, Some(Value::from(TELEM_SERVICE_NAME)));
     }
+
+    #[test]
+    fn traces_and_logs_export_installs_subscriber() {
+        let export_opts = || {
+            temporal_sdk_core_api::telemetry::OtelExportOptionsBuilder::default()
+                .url("http://localhost:4317".parse().unwrap())
+                .filter("INFO")
+                .build()
+                .unwrap()
+        };
+        let telem = crate::telemetry_init(
+            temporal_sdk_core_api::telemetry::TelemetryOptionsBuilder::default()
+                .otel_traces(export_opts())
+                .otel_logs(export_opts())
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+        assert!(telem.trace_subscriber().is_some());
+        // Nothing is listening, but dropping must not hang or panic
+        drop(telem);
+    }
 }
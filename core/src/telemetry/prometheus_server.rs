@@ -1,3 +1,4 @@
+use crate::telemetry::health_server::{HealthEndpoints, health_response, not_found};
 use http_body_util::Full;
 use hyper::{Method, Request, Response, body::Bytes, header::CONTENT_TYPE, service::service_fn};
 use hyper_util::{
@@ -6,14 +7,18 @@ use hyper_util::{
 };
 use opentelemetry_prometheus::PrometheusExporter;
 use prometheus::{Encoder, Registry, TextEncoder};
-use std::net::{SocketAddr, TcpListener};
+use std::{
+    net::{SocketAddr, TcpListener},
+    sync::{Arc, OnceLock},
+};
 use temporal_sdk_core_api::telemetry::PrometheusExporterOptions;
 use tokio::io;
 
-/// Exposes prometheus metrics for scraping
+/// Exposes prometheus metrics for scraping, and optionally worker health
 pub(super) struct PromServer {
     listener: TcpListener,
     registry: Registry,
+    health: Arc<OnceLock<HealthEndpoints>>,
 }
 
 impl PromServer {
@@ -38,6 +43,7 @@ impl PromServer {
             Self {
                 listener: TcpListener::bind(opts.socket_addr)?,
                 registry,
+                health: Default::default(),
             },
             exporter.build()?,
         ))
@@ -52,12 +58,13 @@ impl PromServer {
             let (stream, _) = listener.accept().await?;
             let io = TokioIo::new(stream);
             let regclone = self.registry.clone();
+            let health = self.health.clone();
             tokio::task::spawn(async move {
                 let server = auto::Builder::new(TokioExecutor::new());
                 if let Err(e) = server
                     .serve_connection(
                         io,
-                        service_fn(move |req| metrics_req(req, regclone.clone())),
+                        service_fn(move |req| metrics_req(req, regclone.clone(), health.clone())),
                     )
                     .await
                 {
@@ -70,12 +77,19 @@ impl PromServer {
     pub(super) fn bound_addr(&self) -> io::Result<SocketAddr> {
         self.listener.local_addr()
     }
+
+    /// Worker health is served once these endpoints are set, which can happen after the server
+    /// has started
+    pub(super) fn health_endpoints(&self) -> Arc<OnceLock<HealthEndpoints>> {
+        self.health.clone()
+    }
 }
 
-/// Serves prometheus metrics in the expected format for scraping
+/// Serves prometheus metrics in the expected format for scraping, and worker health if enabled
 async fn metrics_req(
     req: Request<hyper::body::Incoming>,
     registry: Registry,
+    health: Arc<OnceLock<HealthEndpoints>>,
 ) -> Result<Response<Full<Bytes>>, hyper::Error> {
     let response = match (req.method(), req.uri().path()) {
         (&Method::GET, "/metrics") => {
@@ -90,10 +104,10 @@ async fn metrics_req(
                 .body(buffer.into())
                 .unwrap()
         }
-        _ => Response::builder()
-            .status(404)
-            .body(vec![].into())
-            .expect("Can't fail to construct empty resp"),
+        _ => health
+            .get()
+            .and_then(|endpoints| health_response(&req, endpoints))
+            .unwrap_or_else(not_found),
     };
     Ok(response)
 }
@@ -1,3 +1,6 @@
+mod simulation;
+
+pub(crate) use simulation::{Simulation, SimulationConfig};
 pub(crate) use temporal_sdk_core_test_utils::canned_histories;
 
 use crate::{
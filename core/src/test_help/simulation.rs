@@ -0,0 +1,671 @@
+//! A seeded simulation harness which runs a worker against a fake server and lang, randomizing
+//! everything the worker can't control: when polls return, which tasks they return, RPC failures,
+//! local activity completion order, and eviction requests. After the run, invariants which should
+//! hold no matter what happened are checked.
+//!
+//! Each source of decisions draws from its own RNG, all derived from a single seed in a fixed
+//! order, so how often one source is drawn from never changes what another decides. The seed is
+//! printed if the run fails. Tests pin their seeds, and `SIMULATION_SEED=<seed>` overrides them to
+//! reproduce a failure, or `SIMULATION_SEED=random` explores new ones.
+//!
+//! Everything runs on one thread, in a single-threaded runtime with a paused clock, including
+//! workflow processing which normally has a thread of its own. Delays take no real time, and the
+//! clock only moves once every task is waiting on it. The runtime's own randomness, which decides
+//! between branches of `select!` which are ready at once, is seeded too when built with
+//! `--cfg tokio_unstable`, in which case a seed reproduces the exact same run. Otherwise a seed
+//! reproduces the same decisions, but not necessarily the order in which the worker saw them.
+
+use crate::{
+    Worker,
+    errors::PollError,
+    replay::TestHistoryBuilder,
+    test_help::{ResponseType, hist_to_poll_resp, test_worker_cfg},
+    worker::{
+        PROCESS_WORKFLOWS_LOCALLY,
+        client::{
+            WorkflowTaskCompletion,
+            mocks::{MockManualWorkerClient, mock_manual_workflow_client},
+        },
+    },
+};
+use futures_util::FutureExt;
+use parking_lot::Mutex;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use temporal_sdk_core_api::Worker as WorkerTrait;
+use temporal_sdk_core_protos::{
+    coresdk::{
+        ActivityTaskCompletion,
+        activity_result::ActivityExecutionResult,
+        activity_task::activity_task,
+        workflow_activation::{WorkflowActivation, workflow_activation_job},
+        workflow_commands::{ActivityCancellationType, CompleteWorkflowExecution},
+        workflow_completion::WorkflowActivationCompletion,
+    },
+    temporal::api::{
+        enums::v1::{CommandType, EventType},
+        workflowservice::v1::{
+            GetWorkflowExecutionHistoryResponse, PollWorkflowTaskQueueResponse,
+            RespondWorkflowTaskCompletedResponse, RespondWorkflowTaskFailedResponse,
+            ShutdownWorkerResponse,
+        },
+    },
+};
+use temporal_sdk_core_test_utils::{
+    canned_histories, schedule_local_activity_cmd, start_timer_cmd,
+};
+use tokio::{
+    sync::watch,
+    task::LocalSet,
+    time::{Instant, sleep},
+};
+use tokio_util::sync::CancellationToken;
+
+const SEED_ENV_VAR: &str = "SIMULATION_SEED";
+const TIMER_WF_TYPE: &str = "sim_timer_wf";
+const LA_WF_TYPE: &str = "sim_la_wf";
+
+/// Knobs for a [Simulation]. Anything not listed here (cache size, poller counts, permits, ...) is
+/// chosen from the seed.
+#[derive(Clone, Debug)]
+pub(crate) struct SimulationConfig {
+    /// Workflows which start a timer and complete once it fires, over two workflow tasks
+    pub(crate) timer_workflows: usize,
+    /// Workflows which run a local activity and complete once it resolves, in one workflow task
+    pub(crate) local_activity_workflows: usize,
+    /// Chance that any workflow task completion RPC fails. Half of failures happen after the
+    /// server already accepted the completion.
+    pub(crate) rpc_failure_rate: f64,
+    /// Chance that lang asks for the run to be evicted while handling any activation
+    pub(crate) eviction_rate: f64,
+    /// Upper bound on any randomized delay: poll responses, lang handling activations, and local
+    /// activity execution
+    pub(crate) max_delay: Duration,
+    /// How long the fake server waits on a delivered workflow task before handing it out again
+    pub(crate) task_timeout: Duration,
+    /// If set, shutdown starts this long into the run rather than once every workflow completes
+    pub(crate) shutdown_after: Option<Duration>,
+    /// The whole run, including shutdown, must finish within this long
+    pub(crate) time_limit: Duration,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            timer_workflows: 10,
+            local_activity_workflows: 10,
+            rpc_failure_rate: 0.1,
+            eviction_rate: 0.1,
+            max_delay: Duration::from_millis(20),
+            task_timeout: Duration::from_secs(1),
+            shutdown_after: None,
+            time_limit: Duration::from_secs(60),
+        }
+    }
+}
+
+pub(crate) struct Simulation {
+    seed: u64,
+    config: SimulationConfig,
+}
+
+impl Simulation {
+    /// Uses the provided seed, unless `SIMULATION_SEED` is set to another seed, or to `random` for
+    /// a random one
+    pub(crate) fn new(seed: u64, config: SimulationConfig) -> Self {
+        let seed = match std::env::var(SEED_ENV_VAR) {
+            Ok(s) if s == "random" => rand::random(),
+            Ok(s) => s
+                .parse()
+                .unwrap_or_else(|_| panic!("{SEED_ENV_VAR} must be a u64 or `random`, got {s}")),
+            Err(_) => seed,
+        };
+        Self { seed, config }
+    }
+
+    /// Runs the simulation on a runtime of its own, panicking if any invariant is violated.
+    /// Returns the fake server's log of what it handed out and what it was sent.
+    pub(crate) fn run(self) -> Vec<String> {
+        let _reporter = SeedReporter(self.seed);
+        let mut rt = tokio::runtime::Builder::new_current_thread();
+        rt.enable_all().start_paused(true);
+        #[cfg(tokio_unstable)]
+        rt.rng_seed(tokio::runtime::RngSeed::from_bytes(
+            &self.seed.to_le_bytes(),
+        ));
+        let rt = rt.build().unwrap();
+        PROCESS_WORKFLOWS_LOCALLY.set(true);
+        let log = LocalSet::new().block_on(&rt, self.simulate());
+        PROCESS_WORKFLOWS_LOCALLY.set(false);
+        log
+    }
+
+    async fn simulate(self) -> Vec<String> {
+        let Self { seed, config } = self;
+        let mut rngs = SimRngs::new(seed);
+
+        let max_cached = rngs.worker_config.random_range(1..=4_usize);
+        let max_outstanding = rngs.worker_config.random_range(1..=3_usize);
+        let wft_polls = rngs.worker_config.random_range(1..=max_cached.min(3));
+        info!(
+            seed,
+            max_cached, max_outstanding, wft_polls, "Starting worker simulation"
+        );
+
+        let server = Arc::new(SimServer::new(
+            &config,
+            rngs.poll_delays,
+            rngs.task_choice,
+            &mut rngs.workflows,
+        ));
+        let worker = Arc::new(Worker::new_test(
+            test_worker_cfg()
+                .max_cached_workflows(max_cached)
+                .max_outstanding_workflow_tasks(max_outstanding)
+                .max_concurrent_wft_polls(wft_polls)
+                .no_remote_activities(true)
+                .build()
+                .unwrap(),
+            server.clone().mock_client(),
+        ));
+        let lang = SimLang {
+            worker: worker.clone(),
+            server: server.clone(),
+            max_delay: config.max_delay,
+            eviction_rate: config.eviction_rate,
+        };
+
+        let started = Instant::now();
+        let finished = tokio::time::timeout(config.time_limit, async {
+            tokio::join!(
+                lang.drive_activations(rngs.lang_delays, rngs.evictions),
+                lang.drive_local_activities(rngs.la_delays),
+                async {
+                    match config.shutdown_after {
+                        Some(after) => sleep(after).await,
+                        None => server.all_completed().await,
+                    }
+                    worker.initiate_shutdown();
+                }
+            );
+            worker.shutdown().await;
+        })
+        .await;
+
+        assert!(
+            finished.is_ok(),
+            "Worker didn't shut down within {:?}. Workflows still running: {:?}",
+            config.time_limit,
+            server.incomplete_workflows()
+        );
+        info!(elapsed = ?started.elapsed(), "Worker simulation finished");
+        let violations = server.violations.lock().clone();
+        assert!(
+            violations.is_empty(),
+            "Invariants violated: {violations:#?}"
+        );
+        if config.shutdown_after.is_none() {
+            assert_eq!(server.incomplete_workflows(), Vec::<String>::new());
+        }
+        assert_eq!(
+            worker.available_wft_permits(),
+            Some(max_outstanding),
+            "Not all workflow task permits were returned"
+        );
+        // Covers local activity and activity permits too, which have no fixed total to compare to
+        for slots in worker.slot_usage() {
+            assert_eq!(
+                slots.issued, 0,
+                "Not all {} permits were returned",
+                slots.kind
+            );
+        }
+        std::mem::take(&mut *server.log.lock())
+    }
+}
+
+/// The RNGs of every source of randomized decisions, derived from the seed in the order of fields
+struct SimRngs {
+    worker_config: StdRng,
+    poll_delays: StdRng,
+    task_choice: StdRng,
+    /// Parent of the per-workflow RNGs, drawn from in workflow order
+    workflows: StdRng,
+    lang_delays: StdRng,
+    evictions: StdRng,
+    la_delays: StdRng,
+}
+
+impl SimRngs {
+    fn new(seed: u64) -> Self {
+        let mut seeds = StdRng::seed_from_u64(seed);
+        Self {
+            worker_config: child_rng(&mut seeds),
+            poll_delays: child_rng(&mut seeds),
+            task_choice: child_rng(&mut seeds),
+            workflows: child_rng(&mut seeds),
+            lang_delays: child_rng(&mut seeds),
+            evictions: child_rng(&mut seeds),
+            la_delays: child_rng(&mut seeds),
+        }
+    }
+}
+
+fn child_rng(parent: &mut StdRng) -> StdRng {
+    StdRng::seed_from_u64(parent.random())
+}
+
+/// Prints the seed if the simulation panics, so the run can be reproduced
+struct SeedReporter(u64);
+
+impl Drop for SeedReporter {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            eprintln!(
+                "Worker simulation failed with seed {seed}. Reproduce with {SEED_ENV_VAR}={seed}",
+                seed = self.0
+            );
+        }
+    }
+}
+
+/// A fake server holding the histories of all the simulated workflows and handing out their
+/// tasks. Each workflow has at most one outstanding task at a time, like the real server.
+struct SimServer {
+    poll_delay_rng: Mutex<StdRng>,
+    /// Picks which ready workflow's task a poll gets
+    task_choice_rng: Mutex<StdRng>,
+    state: Mutex<ServerState>,
+    remaining: watch::Sender<usize>,
+    /// Anything that went wrong which couldn't be surfaced as a panic where it happened
+    violations: Mutex<Vec<String>>,
+    /// Every task handed out and every completion received, in order
+    log: Mutex<Vec<String>>,
+    rpc_failure_rate: f64,
+    max_delay: Duration,
+    task_timeout: Duration,
+}
+
+#[derive(Default)]
+struct ServerState {
+    workflows: Vec<SimWorkflow>,
+    /// Indexes of workflows whose next task can be handed out
+    ready: Vec<usize>,
+    /// Every task token ever handed out, and the workflow it belongs to
+    tokens: HashMap<Vec<u8>, usize>,
+    next_token: u64,
+}
+
+struct SimWorkflow {
+    id: String,
+    hist: TestHistoryBuilder,
+    /// Number of the workflow task to be handed out next, or the one that is outstanding
+    wft_num: usize,
+    last_wft_num: usize,
+    outstanding: Option<Vec<u8>>,
+    completed: bool,
+    /// Decides whether tasks after the first are partial, as if from the sticky queue
+    sticky_rng: StdRng,
+    /// Decides whether completing a task fails, and how
+    failure_rng: StdRng,
+}
+
+impl SimServer {
+    fn new(
+        config: &SimulationConfig,
+        poll_delay_rng: StdRng,
+        task_choice_rng: StdRng,
+        workflow_rngs: &mut StdRng,
+    ) -> Self {
+        let mut workflows = vec![];
+        for i in 0..config.timer_workflows {
+            let mut hist = canned_histories::single_timer("1");
+            hist.set_wf_type(TIMER_WF_TYPE);
+            workflows.push(SimWorkflow::new(
+                format!("timer-wf-{i}"),
+                hist,
+                2,
+                workflow_rngs,
+            ));
+        }
+        for i in 0..config.local_activity_workflows {
+            let mut hist = TestHistoryBuilder::default();
+            hist.add_by_type(EventType::WorkflowExecutionStarted);
+            hist.add_full_wf_task();
+            hist.set_wf_type(LA_WF_TYPE);
+            workflows.push(SimWorkflow::new(
+                format!("la-wf-{i}"),
+                hist,
+                1,
+                workflow_rngs,
+            ));
+        }
+        let (remaining, _) = watch::channel(workflows.len());
+        Self {
+            poll_delay_rng: Mutex::new(poll_delay_rng),
+            task_choice_rng: Mutex::new(task_choice_rng),
+            state: Mutex::new(ServerState {
+                ready: (0..workflows.len()).collect(),
+                workflows,
+                ..Default::default()
+            }),
+            remaining,
+            violations: Mutex::new(vec![]),
+            log: Mutex::new(vec![]),
+            rpc_failure_rate: config.rpc_failure_rate,
+            max_delay: config.max_delay,
+            task_timeout: config.task_timeout,
+        }
+    }
+
+    fn mock_client(self: Arc<Self>) -> MockManualWorkerClient {
+        let mut client = mock_manual_workflow_client();
+        let server = self.clone();
+        client.expect_poll_workflow_task().returning(move |_| {
+            let server = server.clone();
+            async move {
+                let delay = random_delay(&mut server.poll_delay_rng.lock(), server.max_delay);
+                sleep(delay).await;
+                Ok(server.next_task().unwrap_or_default())
+            }
+            .boxed()
+        });
+        let server = self.clone();
+        client
+            .expect_complete_workflow_task()
+            .returning(move |completion| {
+                let res = server.complete(completion);
+                async move { res }.boxed()
+            });
+        let server = self.clone();
+        client
+            .expect_fail_workflow_task()
+            .returning(move |token, _, _| {
+                server.task_abandoned(&token.0);
+                async move { Ok(RespondWorkflowTaskFailedResponse::default()) }.boxed()
+            });
+        let server = self.clone();
+        client
+            .expect_get_workflow_execution_history()
+            .returning(move |wf_id, _, _| {
+                let res = server.history(&wf_id);
+                async move { res }.boxed()
+            });
+        client
+            .expect_shutdown_worker()
+            .returning(|_| async move { Ok(ShutdownWorkerResponse {}) }.boxed());
+        client
+    }
+
+    fn next_task(self: &Arc<Self>) -> Option<PollWorkflowTaskQueueResponse> {
+        let mut state = self.state.lock();
+        let state = &mut *state;
+        if state.ready.is_empty() {
+            return None;
+        }
+        let choice = self
+            .task_choice_rng
+            .lock()
+            .random_range(0..state.ready.len());
+        let wf_ix = state.ready.swap_remove(choice);
+        state.next_token += 1;
+        let token = state.next_token.to_le_bytes().to_vec();
+        state.tokens.insert(token.clone(), wf_ix);
+
+        let wf = &mut state.workflows[wf_ix];
+        // Later tasks may go to the sticky queue, in which case the worker needs to have the run
+        // cached or fetch the rest of history
+        let sticky = wf.wft_num > 1 && wf.sticky_rng.random_bool(0.5);
+        let response_type = if sticky {
+            ResponseType::OneTask(wf.wft_num)
+        } else {
+            ResponseType::ToTaskNum(wf.wft_num)
+        };
+        self.log.lock().push(format!(
+            "Handed out task {} of {} (sticky: {sticky})",
+            wf.wft_num, wf.id
+        ));
+        let mut resp = hist_to_poll_resp(&wf.hist, wf.id.clone(), response_type).resp;
+        resp.task_token = token.clone();
+        wf.outstanding = Some(token.clone());
+
+        let server = self.clone();
+        tokio::spawn(async move {
+            sleep(server.task_timeout).await;
+            server.task_abandoned(&token);
+        });
+        Some(resp)
+    }
+
+    fn complete(
+        &self,
+        completion: WorkflowTaskCompletion,
+    ) -> Result<RespondWorkflowTaskCompletedResponse, tonic::Status> {
+        let mut state = self.state.lock();
+        let state = &mut *state;
+        let token = completion.task_token.0;
+        let Some(&wf_ix) = state.tokens.get(&token) else {
+            self.violation(format!("Completion for unknown task token {token:?}"));
+            return Err(tonic::Status::not_found("Workflow task not found"));
+        };
+        let wf = &mut state.workflows[wf_ix];
+        if wf.outstanding.as_ref() != Some(&token) {
+            return Err(tonic::Status::not_found("Workflow task not found"));
+        }
+
+        let fails = wf.failure_rng.random_bool(self.rpc_failure_rate);
+        self.log.lock().push(format!(
+            "Completed task {} of {} with {:?} (fails: {fails})",
+            wf.wft_num, wf.id, completion.commands
+        ));
+        if fails && wf.failure_rng.random_bool(0.5) {
+            // Lost before the server saw it, so the task will time out and be handed out again
+            wf.outstanding = None;
+            state.ready.push(wf_ix);
+            return Err(tonic::Status::unavailable(
+                "Simulated failure before completing",
+            ));
+        }
+
+        wf.outstanding = None;
+        let completes_wf = completion
+            .commands
+            .iter()
+            .any(|c| c.command_type == CommandType::CompleteWorkflowExecution as i32);
+        if completes_wf {
+            wf.completed = true;
+            self.remaining.send_modify(|r| *r -= 1);
+        } else if wf.wft_num < wf.last_wft_num {
+            wf.wft_num += 1;
+            state.ready.push(wf_ix);
+        } else {
+            let id = wf.id.clone();
+            self.violation(format!(
+                "Workflow {id} completed its last task without completing: {:?}",
+                completion.commands
+            ));
+        }
+
+        if fails {
+            return Err(tonic::Status::unavailable(
+                "Simulated failure after completing",
+            ));
+        }
+        Ok(RespondWorkflowTaskCompletedResponse::default())
+    }
+
+    /// The task was failed, or timed out. Either way, it gets handed out again if it was still
+    /// outstanding.
+    fn task_abandoned(&self, token: &[u8]) {
+        let mut state = self.state.lock();
+        let Some(&wf_ix) = state.tokens.get(token) else {
+            return;
+        };
+        let wf = &mut state.workflows[wf_ix];
+        if wf.outstanding.as_deref() == Some(token) {
+            wf.outstanding = None;
+            state.ready.push(wf_ix);
+        }
+    }
+
+    fn history(&self, wf_id: &str) -> Result<GetWorkflowExecutionHistoryResponse, tonic::Status> {
+        let state = self.state.lock();
+        let Some(wf) = state.workflows.iter().find(|wf| wf.id == wf_id) else {
+            self.violation(format!("History fetched for unknown workflow {wf_id}"));
+            return Err(tonic::Status::not_found("Workflow not found"));
+        };
+        Ok(GetWorkflowExecutionHistoryResponse {
+            history: Some(wf.hist.get_history_info(wf.wft_num).unwrap().into()),
+            ..Default::default()
+        })
+    }
+
+    async fn all_completed(&self) {
+        let _ = self.remaining.subscribe().wait_for(|r| *r == 0).await;
+    }
+
+    fn incomplete_workflows(&self) -> Vec<String> {
+        self.state
+            .lock()
+            .workflows
+            .iter()
+            .filter(|wf| !wf.completed)
+            .map(|wf| wf.id.clone())
+            .collect()
+    }
+
+    fn violation(&self, msg: String) {
+        error!("{msg}");
+        self.violations.lock().push(msg);
+    }
+}
+
+impl SimWorkflow {
+    fn new(id: String, hist: TestHistoryBuilder, last_wft_num: usize, rng: &mut StdRng) -> Self {
+        Self {
+            id,
+            hist,
+            wft_num: 1,
+            last_wft_num,
+            outstanding: None,
+            completed: false,
+            sticky_rng: child_rng(rng),
+            failure_rng: child_rng(rng),
+        }
+    }
+}
+
+/// Plays the part of lang, running the simulated workflows and local activities
+struct SimLang {
+    worker: Arc<Worker>,
+    server: Arc<SimServer>,
+    max_delay: Duration,
+    eviction_rate: f64,
+}
+
+impl SimLang {
+    async fn drive_activations(&self, mut delay_rng: StdRng, mut eviction_rng: StdRng) {
+        loop {
+            let act = match self.worker.poll_workflow_activation().await {
+                Ok(act) => act,
+                Err(PollError::ShutDown) => break,
+                Err(e) => {
+                    self.server
+                        .violation(format!("Unexpected activation poll error: {e:?}"));
+                    break;
+                }
+            };
+            sleep(random_delay(&mut delay_rng, self.max_delay)).await;
+            let completion = respond_to(&act);
+            if !act.is_only_eviction() && eviction_rng.random_bool(self.eviction_rate) {
+                self.worker.request_workflow_eviction(&act.run_id);
+            }
+            if let Err(e) = self.worker.complete_workflow_activation(completion).await {
+                self.server.violation(format!(
+                    "Failed completing activation for run {}: {e:?}",
+                    act.run_id
+                ));
+            }
+        }
+    }
+
+    async fn drive_local_activities(&self, mut delay_rng: StdRng) {
+        let running: Arc<Mutex<HashMap<Vec<u8>, CancellationToken>>> = Default::default();
+        loop {
+            let task = match self.worker.poll_activity_task().await {
+                Ok(task) => task,
+                Err(PollError::ShutDown) => break,
+                Err(e) => {
+                    self.server
+                        .violation(format!("Unexpected activity poll error: {e:?}"));
+                    break;
+                }
+            };
+            match task.variant {
+                Some(activity_task::Variant::Start(_)) => {
+                    let cancel = CancellationToken::new();
+                    running
+                        .lock()
+                        .insert(task.task_token.clone(), cancel.clone());
+                    let delay = random_delay(&mut delay_rng, self.max_delay);
+                    let worker = self.worker.clone();
+                    let running = running.clone();
+                    tokio::spawn(async move {
+                        let result = tokio::select! {
+                            _ = sleep(delay) => ActivityExecutionResult::ok(vec![1].into()),
+                            _ = cancel.cancelled() => {
+                                ActivityExecutionResult::cancel_from_details(None)
+                            }
+                        };
+                        running.lock().remove(&task.task_token);
+                        // May legitimately fail if the activity was already resolved elsewhere
+                        let _ = worker
+                            .complete_activity_task(ActivityTaskCompletion {
+                                task_token: task.task_token,
+                                result: Some(result),
+                            })
+                            .await;
+                    });
+                }
+                Some(activity_task::Variant::Cancel(_)) => {
+                    if let Some(cancel) = running.lock().get(&task.task_token) {
+                        cancel.cancel();
+                    }
+                }
+                None => {}
+            }
+        }
+    }
+}
+
+/// The simulated workflow code. Timer workflows wait on one timer, local activity workflows run one
+/// local activity, and both complete afterward.
+fn respond_to(act: &WorkflowActivation) -> WorkflowActivationCompletion {
+    let mut cmds = vec![];
+    for job in &act.jobs {
+        match &job.variant {
+            Some(workflow_activation_job::Variant::InitializeWorkflow(init)) => {
+                match init.workflow_type.as_str() {
+                    TIMER_WF_TYPE => cmds.push(start_timer_cmd(1, Duration::from_secs(1))),
+                    LA_WF_TYPE => cmds.push(schedule_local_activity_cmd(
+                        1,
+                        "1",
+                        ActivityCancellationType::TryCancel,
+                        Duration::from_secs(10),
+                    )),
+                    other => panic!("Unknown simulated workflow type {other}"),
+                }
+            }
+            Some(
+                workflow_activation_job::Variant::FireTimer(_)
+                | workflow_activation_job::Variant::ResolveActivity(_),
+            ) => cmds.push(CompleteWorkflowExecution { result: None }.into()),
+            _ => {}
+        }
+    }
+    WorkflowActivationCompletion::from_cmds(act.run_id.clone(), cmds)
+}
+
+fn random_delay(rng: &mut StdRng, max: Duration) -> Duration {
+    Duration::from_micros(rng.random_range(0..=max.as_micros() as u64))
+}
@@ -15,7 +15,11 @@ use crate::{
     pollers::{BoxedActPoller, PermittedTqResp, TrackedPermittedTqResp, new_activity_task_poller},
     telemetry::metrics::{MetricsContext, activity_type, eager, workflow_type},
     worker::{
-        activities::activity_heartbeat_manager::ActivityHeartbeatError, client::WorkerClient,
+        activities::activity_heartbeat_manager::ActivityHeartbeatError,
+        client::WorkerClient,
+        debug_snapshot::{
+            ActivityTaskDebugSnapshot, HeartbeatManagerDebugSnapshot, task_token_fingerprint,
+        },
     },
 };
 use activity_heartbeat_manager::ActivityHeartbeatManager;
@@ -52,6 +56,7 @@ use tokio::{
         mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel},
     },
     task::JoinHandle,
+    time::error::Elapsed,
 };
 use tokio_stream::wrappers::UnboundedReceiverStream;
 use tokio_util::sync::CancellationToken;
@@ -454,6 +459,31 @@ impl WorkerActivityTasks {
         }
     }
 
+    pub(crate) fn debug_snapshot(&self) -> Vec<ActivityTaskDebugSnapshot> {
+        self.outstanding_activity_tasks
+            .iter()
+            .map(|entry| {
+                let info = entry.value();
+                ActivityTaskDebugSnapshot {
+                    task_token: task_token_fingerprint(entry.key()),
+                    activity_type: info.base.activity_type.clone(),
+                    workflow_id: info.base.workflow_id.clone(),
+                    run_id: info.base.workflow_run_id.clone(),
+                    age: info.base.start_time.elapsed(),
+                    cancel_issued: info.issued_cancel_to_lang.map(|r| format!("{r:?}")),
+                }
+            })
+            .collect()
+    }
+
+    /// Returns `None` if heartbeating has already shut down, and errors if it doesn't respond in
+    /// time
+    pub(crate) async fn heartbeat_debug_snapshot(
+        &self,
+    ) -> Result<Option<HeartbeatManagerDebugSnapshot>, Elapsed> {
+        self.heartbeat_manager.debug_snapshot().await
+    }
+
     #[cfg(test)]
     pub(crate) fn remaining_activity_capacity(&self) -> Option<usize> {
         self.eager_activities_semaphore.unused_permits()
@@ -705,8 +735,10 @@ fn worker_shutdown_failure() -> Failure {
 mod tests {
     use super::*;
     use crate::{
-        abstractions::tests::fixed_size_permit_dealer, pollers::new_activity_task_buffer,
-        prost_dur, worker::client::mocks::mock_workflow_client,
+        abstractions::tests::fixed_size_permit_dealer,
+        pollers::{ActivityPollRateLimits, PollerBehavior, new_activity_task_buffer},
+        prost_dur,
+        worker::client::mocks::mock_workflow_client,
     };
     use temporal_sdk_core_protos::coresdk::activity_result::ActivityExecutionResult;
 
@@ -743,12 +775,11 @@ mod tests {
         let ap = new_activity_task_buffer(
             mock_client.clone(),
             "tq".to_string(),
-            5, // Lots of concurrent pollers, to ensure we don't poll to much when that's the case
+            PollerBehavior::SimpleMaximum(5), // Lots of concurrent pollers, to ensure we don't poll to much when that's the case
             sem.clone(),
-            None,
+            Arc::new(ActivityPollRateLimits::new(None, Some(2.0))),
             shutdown_token.clone(),
             None::<fn(usize)>,
-            Some(2.0),
         );
         let atm = WorkerActivityTasks::new(
             sem.clone(),
@@ -832,12 +863,11 @@ mod tests {
         let ap = new_activity_task_buffer(
             mock_client.clone(),
             "tq".to_string(),
-            1,
+            PollerBehavior::SimpleMaximum(1),
             sem.clone(),
-            None,
+            Arc::new(ActivityPollRateLimits::new(None, None)),
             shutdown_token.clone(),
             None::<fn(usize)>,
-            None,
         );
         let atm = WorkerActivityTasks::new(
             sem.clone(),
@@ -903,12 +933,11 @@ mod tests {
         let ap = new_activity_task_buffer(
             mock_client.clone(),
             "tq".to_string(),
-            1,
+            PollerBehavior::SimpleMaximum(1),
             sem.clone(),
-            None,
+            Arc::new(ActivityPollRateLimits::new(None, None)),
             shutdown_token.clone(),
             None::<fn(usize)>,
-            None,
         );
         let atm = WorkerActivityTasks::new(
             sem.clone(),
@@ -1,7 +1,14 @@
 use crate::{
     TaskToken,
     abstractions::take_cell::TakeCell,
-    worker::{activities::PendingActivityCancel, client::WorkerClient},
+    worker::{
+        activities::PendingActivityCancel,
+        client::WorkerClient,
+        debug_snapshot::{
+            ActivityHeartbeatDebugSnapshot, DEBUG_SNAPSHOT_TIMEOUT, HeartbeatManagerDebugSnapshot,
+            task_token_fingerprint,
+        },
+    },
 };
 use futures_util::StreamExt;
 use std::{
@@ -19,8 +26,10 @@ use tokio::{
     sync::{
         Notify,
         mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel},
+        oneshot,
     },
     task::JoinHandle,
+    time::error::Elapsed,
 };
 use tokio_util::sync::CancellationToken;
 
@@ -42,6 +51,7 @@ enum HeartbeatAction {
     },
     CompleteReport(TaskToken),
     CompleteThrottle(TaskToken),
+    GetDebugSnapshot(oneshot::Sender<HeartbeatManagerDebugSnapshot>),
 }
 
 #[derive(Debug)]
@@ -116,6 +126,7 @@ impl ActivityHeartbeatManager {
                             HeartbeatAction::CompleteReport(tt) => hb_states.handle_report_completed(tt),
                             HeartbeatAction::CompleteThrottle(tt) => hb_states.handle_throttle_completed(tt),
                             HeartbeatAction::Evict{ token, on_complete } => hb_states.evict(token, on_complete),
+                            HeartbeatAction::GetDebugSnapshot(tx) => hb_states.send_debug_snapshot(tx),
                         },
                         hb_states,
                     ))
@@ -221,6 +232,20 @@ impl ActivityHeartbeatManager {
         completed.notified().await;
     }
 
+    /// Returns the state of heartbeating for all tracked activities, or `None` if the manager has
+    /// shut down. Errors if the manager doesn't respond within [DEBUG_SNAPSHOT_TIMEOUT].
+    pub(super) async fn debug_snapshot(
+        &self,
+    ) -> Result<Option<HeartbeatManagerDebugSnapshot>, Elapsed> {
+        let (tx, rx) = oneshot::channel();
+        let _ = self
+            .heartbeat_tx
+            .send(HeartbeatAction::GetDebugSnapshot(tx));
+        tokio::time::timeout(DEBUG_SNAPSHOT_TIMEOUT, rx)
+            .await
+            .map(Result::ok)
+    }
+
     /// Initiates shutdown procedure by stopping lifecycle loop and awaiting for all in-flight
     /// heartbeat requests to be flushed to the server.
     pub(super) async fn shutdown(&self) {
@@ -321,6 +346,26 @@ impl HeartbeatStreamState {
         }
     }
 
+    fn send_debug_snapshot(
+        &self,
+        tx: oneshot::Sender<HeartbeatManagerDebugSnapshot>,
+    ) -> Option<HeartbeatExecutorAction> {
+        let _ = tx.send(HeartbeatManagerDebugSnapshot {
+            tracked_activities: self
+                .tt_to_state
+                .iter()
+                .map(|(tt, st)| ActivityHeartbeatDebugSnapshot {
+                    task_token: task_token_fingerprint(tt),
+                    report_in_flight: st.is_record_in_flight,
+                    has_unsent_details: st.last_recorded_details.is_some(),
+                    since_last_send: st.last_send_requested.elapsed(),
+                })
+                .collect(),
+            awaiting_flush: self.tt_needs_flush.len(),
+        });
+        None
+    }
+
     /// Heartbeat report to server completed
     fn handle_report_completed(&mut self, tt: TaskToken) -> Option<HeartbeatExecutorAction> {
         if let Some(not) = self.tt_needs_flush.remove(&tt) {
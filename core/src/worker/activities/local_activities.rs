@@ -4,7 +4,10 @@ use crate::{
     protosext::ValidScheduleLA,
     retry_logic::RetryPolicyExt,
     telemetry::metrics::{activity_type, workflow_type},
-    worker::workflow::HeartbeatTimeoutMsg,
+    worker::{
+        debug_snapshot::{LocalActivityTaskDebugSnapshot, task_token_fingerprint},
+        workflow::HeartbeatTimeoutMsg,
+    },
 };
 use futures_util::{
     Stream, StreamExt, future, future::AbortRegistration, stream, stream::BoxStream,
@@ -59,6 +62,10 @@ struct LocalInFlightActInfo {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "save_wf_inputs",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub(crate) enum LocalActivityExecutionResult {
     Completed(Success),
     Failed(ActFail),
@@ -104,6 +111,10 @@ impl LocalActivityExecutionResult {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "save_wf_inputs",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub(crate) struct LocalActivityResolution {
     pub(crate) seq: u32,
     pub(crate) result: LocalActivityExecutionResult,
@@ -261,6 +272,23 @@ impl LocalActivityManager {
         )
     }
 
+    pub(crate) fn debug_snapshot(&self) -> Vec<LocalActivityTaskDebugSnapshot> {
+        self.dat
+            .lock()
+            .outstanding_activity_tasks
+            .iter()
+            .map(|(tt, info)| LocalActivityTaskDebugSnapshot {
+                task_token: task_token_fingerprint(tt),
+                activity_type: info.la_info.schedule_cmd.activity_type.clone(),
+                seq: info.la_info.schedule_cmd.seq,
+                workflow_id: info.la_info.workflow_exec_info.workflow_id.clone(),
+                run_id: info.la_info.workflow_exec_info.run_id.clone(),
+                attempt: info.attempt,
+                age: info.dispatch_time.elapsed(),
+            })
+            .collect()
+    }
+
     #[cfg(test)]
     pub(crate) fn num_outstanding(&self) -> usize {
         self.dat.lock().outstanding_activity_tasks.len()
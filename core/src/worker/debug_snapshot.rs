@@ -0,0 +1,201 @@
+//! Types describing a point-in-time view of a worker's internal state, as returned by
+//! [crate::Worker::debug_snapshot]. They exist to help debug stuck or misbehaving workers, and make
+//! no stability guarantees beyond being serializable.
+
+use serde::{Serialize, Serializer};
+use std::{
+    fmt::Display,
+    hash::{DefaultHasher, Hash, Hasher},
+    time::Duration,
+};
+use temporal_sdk_core_api::worker::SlotKindType;
+use temporal_sdk_core_protos::TaskToken;
+
+/// How long to wait for a part of the worker to answer a debug snapshot request before marking it
+/// unresponsive. Snapshots are most useful when something is stuck, so they must not get stuck too.
+pub(crate) const DEBUG_SNAPSHOT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Everything a worker is currently tracking
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkerDebugSnapshot {
+    /// The task queue the worker polls
+    pub task_queue: String,
+    /// True once shutdown of the worker has been initiated
+    pub shutdown_requested: bool,
+    /// Unset if workflow processing has already shut down, or didn't respond in time
+    pub workflows: Option<WorkflowsDebugSnapshot>,
+    /// True if workflow processing didn't respond to the snapshot request in time, which is a sign
+    /// that it's stuck
+    pub workflows_unresponsive: bool,
+    /// Activity tasks which have been handed to lang but not yet completed
+    pub outstanding_activity_tasks: Vec<ActivityTaskDebugSnapshot>,
+    /// Local activity tasks which have been handed to lang but not yet completed
+    pub outstanding_local_activity_tasks: Vec<LocalActivityTaskDebugSnapshot>,
+    /// Nexus tasks which have been handed to lang but not yet completed
+    pub outstanding_nexus_tasks: Vec<NexusTaskDebugSnapshot>,
+    /// Slot usage for each kind of task
+    pub slots: Vec<SlotUsageDebugSnapshot>,
+    /// Unset if this worker doesn't poll for activities, heartbeating has shut down, or it didn't
+    /// respond in time
+    pub heartbeats: Option<HeartbeatManagerDebugSnapshot>,
+    /// True if heartbeating didn't respond to the snapshot request in time, which is a sign that
+    /// it's stuck
+    pub heartbeats_unresponsive: bool,
+}
+
+/// State of workflow processing
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkflowsDebugSnapshot {
+    /// Runs which are currently in the workflow cache
+    pub cached_runs: Vec<CachedRunDebugSnapshot>,
+    /// Polled workflow tasks for runs which aren't cached, waiting for a cache slot to free up
+    pub buffered_polls: Vec<BufferedPollDebugSnapshot>,
+}
+
+/// A run in the workflow cache
+#[derive(Debug, Clone, Serialize)]
+pub struct CachedRunDebugSnapshot {
+    /// Run id of the workflow execution
+    pub run_id: String,
+    /// Workflow id of the workflow execution
+    pub workflow_id: String,
+    /// Name of the workflow's type
+    pub workflow_type: String,
+    /// Id of the last history event applied to the run's state
+    pub last_event_id: i64,
+    /// The kind of activation which lang has been sent but not yet completed, if any
+    pub pending_activation: Option<String>,
+    /// The workflow task the run is currently processing, if any
+    pub outstanding_wft: Option<OutstandingWftDebugSnapshot>,
+    /// True if another workflow task for the run arrived while one was outstanding
+    pub has_buffered_wft: bool,
+    /// True if the run is waiting on local activities before it can complete its workflow task
+    pub waiting_on_local_activities: bool,
+    /// Set to the reason the run is going to be evicted, if it is
+    pub eviction_requested: Option<String>,
+}
+
+/// A workflow task which has been received but not yet completed
+#[derive(Debug, Clone, Serialize)]
+pub struct OutstandingWftDebugSnapshot {
+    /// Fingerprint of the task token, which identifies it without revealing it
+    pub task_token: String,
+    /// Attempt number of the task, starting at 1
+    pub attempt: u32,
+    /// How long ago the task was received
+    pub age: Duration,
+    /// Queries which will be answered once the task is complete
+    pub pending_queries: usize,
+}
+
+/// Workflow tasks for a run which is waiting for room in the cache
+#[derive(Debug, Clone, Serialize)]
+pub struct BufferedPollDebugSnapshot {
+    /// Run id of the workflow execution
+    pub run_id: String,
+    /// Workflow id of the workflow execution
+    pub workflow_id: String,
+    /// Name of the workflow's type
+    pub workflow_type: String,
+    /// Can be more than one if, ex, several queries arrived for the run
+    pub num_tasks: usize,
+}
+
+/// An activity task which lang is working on
+#[derive(Debug, Clone, Serialize)]
+pub struct ActivityTaskDebugSnapshot {
+    /// Fingerprint of the task token, which identifies it without revealing it
+    pub task_token: String,
+    /// Name of the activity's type
+    pub activity_type: String,
+    /// Workflow id of the workflow which scheduled the activity
+    pub workflow_id: String,
+    /// Run id of the workflow which scheduled the activity
+    pub run_id: String,
+    /// How long ago the task was handed to lang
+    pub age: Duration,
+    /// Set if a cancel has been issued to lang for the activity, with the reason
+    pub cancel_issued: Option<String>,
+}
+
+/// A local activity task which lang is working on
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalActivityTaskDebugSnapshot {
+    /// Fingerprint of the task token, which identifies it without revealing it
+    pub task_token: String,
+    /// Name of the activity's type
+    pub activity_type: String,
+    /// Sequence number of the local activity within its workflow
+    pub seq: u32,
+    /// Workflow id of the workflow which scheduled the local activity
+    pub workflow_id: String,
+    /// Run id of the workflow which scheduled the local activity
+    pub run_id: String,
+    /// Attempt number of the local activity, starting at 1
+    pub attempt: u32,
+    /// How long ago the task was handed to lang
+    pub age: Duration,
+}
+
+/// A nexus task which lang is working on
+#[derive(Debug, Clone, Serialize)]
+pub struct NexusTaskDebugSnapshot {
+    /// Fingerprint of the task token, which identifies it without revealing it
+    pub task_token: String,
+    /// Either `start` or `cancel`
+    pub request_kind: String,
+    /// How long ago the task was handed to lang
+    pub age: Duration,
+}
+
+/// How the slots for one kind of task are being used
+#[derive(Debug, Clone, Serialize)]
+pub struct SlotUsageDebugSnapshot {
+    /// The kind of task the slots are for
+    #[serde(serialize_with = "serialize_display")]
+    pub kind: SlotKindType,
+    /// Slots which have been reserved from the slot supplier
+    pub issued: usize,
+    /// Issued slots which are being used by a task, rather than waiting on a poll
+    pub in_use: usize,
+    /// Slots the supplier could still hand out, if it knows
+    pub available: Option<usize>,
+}
+
+/// State of activity heartbeating
+#[derive(Debug, Clone, Serialize)]
+pub struct HeartbeatManagerDebugSnapshot {
+    /// Activities which have heartbeated recently enough that they're still being tracked
+    pub tracked_activities: Vec<ActivityHeartbeatDebugSnapshot>,
+    /// Activities whose final heartbeat details are waiting to be flushed before completing
+    pub awaiting_flush: usize,
+}
+
+/// Heartbeating state of a single activity
+#[derive(Debug, Clone, Serialize)]
+pub struct ActivityHeartbeatDebugSnapshot {
+    /// Fingerprint of the task token, which identifies it without revealing it
+    pub task_token: String,
+    /// True if a heartbeat is currently being sent to the server
+    pub report_in_flight: bool,
+    /// True if lang has heartbeated details which are waiting out the throttle interval
+    pub has_unsent_details: bool,
+    /// How long ago a heartbeat was last sent to the server
+    pub since_last_send: Duration,
+}
+
+/// Identifies a task token without including it, since anyone holding a task token can complete
+/// the task. The same token always has the same fingerprint, so tasks can be matched up across a
+/// snapshot.
+pub(crate) fn task_token_fingerprint(tt: &TaskToken) -> String {
+    let mut hasher = DefaultHasher::new();
+    tt.0.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+fn serialize_display<T: Display, S: Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
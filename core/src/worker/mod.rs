@@ -1,10 +1,12 @@
 mod activities;
 pub(crate) mod client;
+pub mod debug_snapshot;
 mod nexus;
 mod slot_provider;
 pub(crate) mod tuner;
 mod workflow;
 
+pub use debug_snapshot::WorkerDebugSnapshot;
 pub use temporal_sdk_core_api::worker::{WorkerConfig, WorkerConfigBuilder};
 pub use tuner::{
     FixedSizeSlotSupplier, RealSysInfo, ResourceBasedSlotsOptions,
@@ -16,6 +18,10 @@ pub(crate) use activities::{
     ExecutingLAId, LocalActRequest, LocalActivityExecutionResult, LocalActivityResolution,
     NewLocalAct,
 };
+#[cfg(test)]
+pub(crate) use workflow::PROCESS_WORKFLOWS_LOCALLY;
+#[cfg(feature = "save_wf_inputs")]
+pub use workflow::replay_wf_state_inputs;
 pub(crate) use workflow::{LEGACY_QUERY_ID, wft_poller::new_wft_poller};
 
 use crate::{
@@ -23,12 +29,13 @@ use crate::{
     abstractions::{MeteredPermitDealer, PermitDealerContextData, dbg_panic},
     errors::CompleteWfError,
     pollers::{
-        BoxedActPoller, BoxedNexusPoller, WorkflowTaskPoller, new_activity_task_buffer,
+        ActivityPollRateLimits, BoxedActPoller, BoxedNexusPoller, HealthTrackedPoller, PollPauser,
+        PollScaler, PollerBehavior, WorkflowTaskPoller, new_activity_task_buffer,
         new_nexus_task_buffer, new_workflow_task_buffer,
     },
     protosext::validate_activity_completion,
     telemetry::{
-        TelemetryInstance,
+        TelemetryInstance, WorkerHealth,
         metrics::{
             MetricsContext, activity_poller, activity_worker_type, local_activity_worker_type,
             nexus_poller, nexus_worker_type, workflow_poller, workflow_sticky_poller,
@@ -38,6 +45,7 @@ use crate::{
     worker::{
         activities::{LACompleteAction, LocalActivityManager, NextPendingLAAction},
         client::WorkerClient,
+        debug_snapshot::SlotUsageDebugSnapshot,
         nexus::NexusManager,
         workflow::{LAReqSink, LocalResolution, WorkflowBasics, Workflows},
     },
@@ -56,14 +64,22 @@ use std::{
     time::Duration,
 };
 use temporal_client::{ConfiguredClient, TemporalServiceClientWithMetrics, WorkerKey};
-use temporal_sdk_core_api::errors::{CompleteNexusError, WorkerValidationError};
+use temporal_sdk_core_api::{
+    errors::{CompleteNexusError, WorkerConfigUpdateError, WorkerValidationError},
+    worker::{
+        ActivitySlotKind, LocalActivitySlotKind, NexusSlotKind, PollerKind, WorkerConfigUpdate,
+        WorkflowSlotKind,
+    },
+};
 use temporal_sdk_core_protos::{
     TaskToken,
     coresdk::{
         ActivityTaskCompletion,
         activity_result::activity_execution_result,
         activity_task::ActivityTask,
-        workflow_activation::{WorkflowActivation, remove_from_cache::EvictionReason},
+        workflow_activation::{
+            NondeterminismDiagnostic, WorkflowActivation, remove_from_cache::EvictionReason,
+        },
         workflow_completion::WorkflowActivationCompletion,
     },
     temporal::api::{
@@ -116,6 +132,43 @@ pub struct Worker {
     local_activities_complete: Arc<AtomicBool>,
     /// Used to track all permits have been released
     all_permits_tracker: tokio::sync::Mutex<AllPermitsTracker>,
+    /// Adjust the pollers while the worker runs
+    poller_controls: PollerControls,
+    /// The config with any updates from [WorkerTrait::update_config] applied
+    current_config: Mutex<WorkerConfig>,
+    /// Kept to report slot usage in [Worker::debug_snapshot]
+    permit_dealers: PermitDealers,
+    /// Reported on by the runtime's health endpoints for as long as the worker is alive
+    _health: Arc<WorkerHealth>,
+}
+
+/// Handles to the parts of the pollers which can be changed while the worker runs. Unset when
+/// pollers are mocked, or for kinds of tasks which aren't polled for.
+#[derive(Default)]
+struct PollerControls {
+    pausers: Vec<(PollerKind, PollPauser)>,
+    nonsticky_wft: Option<PollScaler>,
+    sticky_wft: Option<PollScaler>,
+    activity: Option<PollScaler>,
+    activity_rate_limits: Option<Arc<ActivityPollRateLimits>>,
+}
+
+struct PermitDealers {
+    workflow: MeteredPermitDealer<WorkflowSlotKind>,
+    activity: MeteredPermitDealer<ActivitySlotKind>,
+    local_activity: MeteredPermitDealer<LocalActivitySlotKind>,
+    nexus: MeteredPermitDealer<NexusSlotKind>,
+}
+
+impl PermitDealers {
+    fn debug_snapshot(&self) -> Vec<SlotUsageDebugSnapshot> {
+        vec![
+            self.workflow.debug_snapshot(),
+            self.activity.debug_snapshot(),
+            self.local_activity.debug_snapshot(),
+            self.nexus.debug_snapshot(),
+        ]
+    }
 }
 
 struct AllPermitsTracker {
@@ -217,6 +270,52 @@ impl WorkerTrait for Worker {
         &self.config
     }
 
+    fn pause_polling(&self, kinds: &[PollerKind]) {
+        info!(task_queue=%self.config.task_queue, ?kinds, "Pausing polling");
+        self.poller_controls
+            .pausers
+            .iter()
+            .filter(|(kind, _)| kinds.contains(kind))
+            .for_each(|(_, pauser)| pauser.pause());
+    }
+
+    fn resume_polling(&self, kinds: &[PollerKind]) {
+        info!(task_queue=%self.config.task_queue, ?kinds, "Resuming polling");
+        self.poller_controls
+            .pausers
+            .iter()
+            .filter(|(kind, _)| kinds.contains(kind))
+            .for_each(|(_, pauser)| pauser.resume());
+    }
+
+    fn update_config(&self, update: WorkerConfigUpdate) -> Result<(), WorkerConfigUpdateError> {
+        let mut current = self.current_config.lock();
+        let updated = current
+            .with_update(&update)
+            .map_err(WorkerConfigUpdateError::Invalid)?;
+        let controls = &self.poller_controls;
+        if let Some(nonsticky) = controls.nonsticky_wft.as_ref() {
+            nonsticky.set_max_pollers(if controls.sticky_wft.is_some() {
+                updated.max_nonsticky_polls()
+            } else {
+                updated.max_concurrent_wft_polls
+            });
+        }
+        if let Some(sticky) = controls.sticky_wft.as_ref() {
+            sticky.set_max_pollers(updated.max_sticky_polls());
+        }
+        if let Some(activity) = controls.activity.as_ref() {
+            activity.set_max_pollers(updated.max_concurrent_at_polls);
+        }
+        if let Some(limits) = controls.activity_rate_limits.as_ref() {
+            limits.set_worker_rate(updated.max_worker_activities_per_second);
+            limits.set_task_queue_rate(updated.max_task_queue_activities_per_second);
+        }
+        info!(task_queue=%self.config.task_queue, ?update, "Updated worker config");
+        *current = updated;
+        Ok(())
+    }
+
     /// Begins the shutdown process, tells pollers they should stop. Is idempotent.
     fn initiate_shutdown(&self) {
         if !self.shutdown_token.is_cancelled() {
@@ -319,6 +418,14 @@ impl Worker {
             tuner.attach_metrics(meter.clone());
         }
         let shutdown_token = CancellationToken::new();
+        let health = Arc::new(WorkerHealth::new(
+            config.namespace.clone(),
+            config.task_queue.clone(),
+            shutdown_token.clone(),
+        ));
+        if let Some(ti) = telem_instance {
+            ti.worker_registry().register(&health);
+        }
         let slot_context_data = Arc::new(PermitDealerContextData {
             task_queue: config.task_queue.clone(),
             worker_identity: config.client_identity_override.clone().unwrap_or_default(),
@@ -351,6 +458,7 @@ impl Worker {
             None,
             slot_context_data.clone(),
         );
+        let mut poller_controls = PollerControls::default();
         let (wft_stream, act_poller, nexus_poller) = match task_pollers {
             TaskPollers::Real => {
                 let max_nonsticky_polls = if sticky_queue_name.is_some() {
@@ -360,6 +468,8 @@ impl Worker {
                 };
                 let max_sticky_polls = config.max_sticky_polls();
                 let wft_metrics = metrics.with_new_attrs([workflow_poller()]);
+                let wft_health = health.poller(PollerKind::Workflow);
+                let wft_pollers_health = wft_health.num_pollers_handler();
                 let wf_task_poll_buffer = new_workflow_task_buffer(
                     client.clone(),
                     TaskQueue {
@@ -367,49 +477,77 @@ impl Worker {
                         kind: TaskQueueKind::Normal as i32,
                         normal_name: "".to_string(),
                     },
-                    max_nonsticky_polls,
+                    PollerBehavior::new(max_nonsticky_polls, config.workflow_poller_autoscaling),
                     wft_slots.clone(),
                     shutdown_token.child_token(),
                     Some(move |np| {
                         wft_metrics.record_num_pollers(np);
+                        wft_pollers_health(np);
                     }),
                 );
+                poller_controls
+                    .pausers
+                    .push((PollerKind::Workflow, wf_task_poll_buffer.pauser()));
+                poller_controls.nonsticky_wft = Some(wf_task_poll_buffer.scaler());
                 let sticky_queue_poller = sticky_queue_name.as_ref().map(|sqn| {
                     let sticky_metrics = metrics.with_new_attrs([workflow_sticky_poller()]);
-                    new_workflow_task_buffer(
+                    let sticky_pollers_health = wft_health.num_pollers_handler();
+                    let sticky_buffer = new_workflow_task_buffer(
                         client.clone(),
                         TaskQueue {
                             name: sqn.clone(),
                             kind: TaskQueueKind::Sticky as i32,
                             normal_name: config.task_queue.clone(),
                         },
-                        max_sticky_polls,
+                        PollerBehavior::new(max_sticky_polls, config.workflow_poller_autoscaling),
                         wft_slots.clone().into_sticky(),
                         shutdown_token.child_token(),
                         Some(move |np| {
                             sticky_metrics.record_num_pollers(np);
+                            sticky_pollers_health(np);
                         }),
-                    )
+                    );
+                    poller_controls
+                        .pausers
+                        .push((PollerKind::Workflow, sticky_buffer.pauser()));
+                    poller_controls.sticky_wft = Some(sticky_buffer.scaler());
+                    sticky_buffer
                 });
                 let act_poll_buffer = if config.no_remote_activities {
                     None
                 } else {
                     let act_metrics = metrics.with_new_attrs([activity_poller()]);
+                    let act_health = health.poller(PollerKind::Activity);
+                    let act_pollers_health = act_health.num_pollers_handler();
+                    let rate_limits = Arc::new(ActivityPollRateLimits::new(
+                        config.max_task_queue_activities_per_second,
+                        config.max_worker_activities_per_second,
+                    ));
                     let ap = new_activity_task_buffer(
                         client.clone(),
                         config.task_queue.clone(),
-                        config.max_concurrent_at_polls,
+                        PollerBehavior::new(
+                            config.max_concurrent_at_polls,
+                            config.activity_poller_autoscaling,
+                        ),
                         act_slots.clone(),
-                        config.max_task_queue_activities_per_second,
+                        rate_limits.clone(),
                         shutdown_token.child_token(),
-                        Some(move |np| act_metrics.record_num_pollers(np)),
-                        config.max_worker_activities_per_second,
+                        Some(move |np| {
+                            act_metrics.record_num_pollers(np);
+                            act_pollers_health(np);
+                        }),
                     );
-                    Some(Box::from(ap) as BoxedActPoller)
+                    poller_controls
+                        .pausers
+                        .push((PollerKind::Activity, ap.pauser()));
+                    poller_controls.activity = Some(ap.scaler());
+                    poller_controls.activity_rate_limits = Some(rate_limits);
+                    Some(Box::new(HealthTrackedPoller::new(ap, act_health)) as BoxedActPoller)
                 };
-                let wf_task_poll_buffer = Box::new(WorkflowTaskPoller::new(
-                    wf_task_poll_buffer,
-                    sticky_queue_poller,
+                let wf_task_poll_buffer = Box::new(HealthTrackedPoller::new(
+                    WorkflowTaskPoller::new(wf_task_poll_buffer, sticky_queue_poller),
+                    wft_health,
                 ));
                 let wft_stream = new_wft_poller(wf_task_poll_buffer, metrics.clone());
                 let wft_stream = if !client.is_mock() {
@@ -422,14 +560,28 @@ impl Worker {
                 };
 
                 let np_metrics = metrics.with_new_attrs([nexus_poller()]);
-                let nexus_poll_buffer = Box::new(new_nexus_task_buffer(
+                let np_health = health.poller(PollerKind::Nexus);
+                let np_pollers_health = np_health.num_pollers_handler();
+                let nexus_poll_buffer = new_nexus_task_buffer(
                     client.clone(),
                     config.task_queue.clone(),
-                    config.max_concurrent_nexus_polls,
+                    PollerBehavior::new(
+                        config.max_concurrent_nexus_polls,
+                        config.nexus_poller_autoscaling,
+                    ),
                     nexus_slots.clone(),
                     shutdown_token.child_token(),
-                    Some(move |np| np_metrics.record_num_pollers(np)),
-                )) as BoxedNexusPoller;
+                    Some(move |np| {
+                        np_metrics.record_num_pollers(np);
+                        np_pollers_health(np);
+                    }),
+                );
+                poller_controls
+                    .pausers
+                    .push((PollerKind::Nexus, nexus_poll_buffer.pauser()));
+                let nexus_poll_buffer =
+                    Box::new(HealthTrackedPoller::new(nexus_poll_buffer, np_health))
+                        as BoxedNexusPoller;
 
                 #[cfg(test)]
                 let wft_stream = wft_stream.left_stream();
@@ -469,6 +621,12 @@ impl Worker {
             slot_context_data,
         );
         let la_permits = la_pemit_dealer.get_extant_count_rcv();
+        let permit_dealers = PermitDealers {
+            workflow: wft_slots.clone(),
+            activity: act_slots.clone(),
+            local_activity: la_pemit_dealer.clone(),
+            nexus: nexus_slots.clone(),
+        };
         let local_act_mgr = Arc::new(LocalActivityManager::new(
             config.namespace.clone(),
             la_pemit_dealer,
@@ -549,6 +707,7 @@ impl Worker {
             ),
             at_task_mgr,
             local_act_mgr,
+            current_config: Mutex::new(config.clone()),
             config,
             shutdown_token,
             post_activate_hook: None,
@@ -561,6 +720,9 @@ impl Worker {
                 la_permits,
             }),
             nexus_mgr,
+            poller_controls,
+            permit_dealers,
+            _health: health,
         }
     }
 
@@ -628,6 +790,36 @@ impl Worker {
             .unwrap_or_default()
     }
 
+    /// Returns a serializable snapshot of everything this worker is currently tracking. Meant for
+    /// debugging stuck workers, ex: by exposing it on an admin endpoint.
+    pub async fn debug_snapshot(&self) -> WorkerDebugSnapshot {
+        let heartbeats = async {
+            match self.at_task_mgr.as_ref() {
+                Some(mgr) => mgr.heartbeat_debug_snapshot().await,
+                None => Ok(None),
+            }
+        };
+        let (workflows, heartbeats) = tokio::join!(self.workflows.debug_snapshot(), heartbeats);
+        let workflows_unresponsive = workflows.is_err();
+        let heartbeats_unresponsive = heartbeats.is_err();
+        WorkerDebugSnapshot {
+            task_queue: self.config.task_queue.clone(),
+            shutdown_requested: self.shutdown_token.is_cancelled(),
+            workflows: workflows.ok().flatten(),
+            workflows_unresponsive,
+            outstanding_activity_tasks: self
+                .at_task_mgr
+                .as_ref()
+                .map(|mgr| mgr.debug_snapshot())
+                .unwrap_or_default(),
+            outstanding_local_activity_tasks: self.local_act_mgr.debug_snapshot(),
+            outstanding_nexus_tasks: self.nexus_mgr.debug_snapshot(),
+            slots: self.permit_dealers.debug_snapshot(),
+            heartbeats: heartbeats.ok().flatten(),
+            heartbeats_unresponsive,
+        }
+    }
+
     /// Returns number of currently outstanding workflow tasks
     #[cfg(test)]
     pub(crate) async fn outstanding_workflow_tasks(&self) -> usize {
@@ -646,6 +838,10 @@ impl Worker {
     pub(crate) fn unused_wft_permits(&self) -> Option<usize> {
         self.workflows.unused_wft_permits()
     }
+    #[cfg(test)]
+    pub(crate) fn slot_usage(&self) -> Vec<SlotUsageDebugSnapshot> {
+        self.permit_dealers.debug_snapshot()
+    }
 
     /// Get new activity tasks (may be local or nonlocal). Local activities are returned first
     /// before polling the server if there are any.
@@ -864,6 +1060,9 @@ impl Worker {
 pub(crate) struct PostActivateHookData<'a> {
     pub(crate) run_id: &'a str,
     pub(crate) replaying: bool,
+    /// True if the completion resulted in a workflow task failure being reported
+    pub(crate) reported_wft_failure: bool,
+    pub(crate) nondeterminism: Option<&'a NondeterminismDiagnostic>,
 }
 
 pub(crate) enum TaskPollers {
@@ -5,7 +5,10 @@ use crate::{
         metrics,
         metrics::{FailureReason, MetricsContext},
     },
-    worker::client::WorkerClient,
+    worker::{
+        client::WorkerClient,
+        debug_snapshot::{NexusTaskDebugSnapshot, task_token_fingerprint},
+    },
 };
 use anyhow::anyhow;
 use futures_util::{
@@ -198,6 +201,22 @@ impl NexusManager {
         Ok(())
     }
 
+    pub(super) fn debug_snapshot(&self) -> Vec<NexusTaskDebugSnapshot> {
+        self.outstanding_task_map
+            .lock()
+            .iter()
+            .map(|(tt, task)| NexusTaskDebugSnapshot {
+                task_token: task_token_fingerprint(tt),
+                request_kind: match task.request_kind {
+                    RequestKind::Start => "start",
+                    RequestKind::Cancel => "cancel",
+                }
+                .to_string(),
+                age: task.start_time.elapsed(),
+            })
+            .collect()
+    }
+
     pub(super) async fn shutdown(&self) {
         if !self.ever_polled.load(Ordering::Relaxed) {
             return;
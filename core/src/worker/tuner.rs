@@ -1,3 +1,4 @@
+mod cgroup;
 mod fixed_size;
 mod resource_based;
 
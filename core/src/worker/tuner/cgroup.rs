@@ -0,0 +1,281 @@
+//! Reads memory and CPU limits and usage from the cgroup the process runs in, so that a worker
+//! running in a container measures itself against the container's quota rather than the host's
+//! resources. Both cgroup v1 and v2 are supported.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+const CGROUP_MOUNT: &str = "/sys/fs/cgroup";
+const PROC_SELF_CGROUP: &str = "/proc/self/cgroup";
+/// cgroup v1 reports "no limit" as a huge page-aligned number rather than a sentinel
+const V1_UNLIMITED_THRESHOLD: u64 = 1 << 62;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Hierarchy {
+    /// Directories of the memory and cpu controllers, plus the cpuacct one for usage
+    V1 {
+        memory: PathBuf,
+        cpu: PathBuf,
+        cpuacct: PathBuf,
+    },
+    /// The process's directory in the unified hierarchy
+    V2(PathBuf),
+}
+
+/// Samples the process's cgroup. CPU usage is only reported as a rate, so it's computed from the
+/// difference between consecutive samples.
+#[derive(Debug)]
+pub(super) struct CgroupStats {
+    hierarchy: Hierarchy,
+    last_cpu_sample: Option<(u64, Instant)>,
+}
+
+impl CgroupStats {
+    /// Returns `None` when the process does not appear to be running in a cgroup with any
+    /// controllers we understand.
+    pub(super) fn detect() -> Option<Self> {
+        let proc_cgroup = fs::read_to_string(PROC_SELF_CGROUP).unwrap_or_default();
+        Self::detect_at(Path::new(CGROUP_MOUNT), &proc_cgroup)
+    }
+
+    fn detect_at(mount: &Path, proc_cgroup: &str) -> Option<Self> {
+        let hierarchy = if mount.join("cgroup.controllers").exists() {
+            // Entries look like `0::/some/path`. Inside a container with its own cgroup namespace
+            // the path is `/`, and the mount point is the process's cgroup.
+            let dir = proc_cgroup
+                .lines()
+                .find_map(|l| l.strip_prefix("0::"))
+                .map(|p| mount.join(p.trim_start_matches('/')))
+                .filter(|d| d.join("memory.max").exists() || d.join("cpu.max").exists())
+                .unwrap_or_else(|| mount.to_path_buf());
+            Hierarchy::V2(dir)
+        } else {
+            // Entries look like `4:cpu,cpuacct:/some/path`, giving the process's path within each
+            // controller's hierarchy. As with v2, it's `/` inside a cgroup namespace.
+            let proc_path = |name: &str| {
+                proc_cgroup.lines().find_map(|l| {
+                    let mut parts = l.splitn(3, ':');
+                    let (controllers, path) = (parts.nth(1)?, parts.next()?);
+                    controllers.split(',').any(|c| c == name).then_some(path)
+                })
+            };
+            let controller = |mount_names: &[&str], name: &str, marker: &str| {
+                let root = mount_names
+                    .iter()
+                    .map(|n| mount.join(n))
+                    .find(|p| p.is_dir())?;
+                let dir = proc_path(name)
+                    .map(|p| root.join(p.trim_start_matches('/')))
+                    .filter(|d| d.join(marker).exists())
+                    .unwrap_or(root);
+                Some(dir)
+            };
+            let memory = controller(&["memory"], "memory", "memory.limit_in_bytes")?;
+            let cpu = controller(&["cpu", "cpu,cpuacct"], "cpu", "cpu.cfs_quota_us")?;
+            let cpuacct = controller(
+                &["cpuacct", "cpu,cpuacct", "cpuacct,cpu"],
+                "cpuacct",
+                "cpuacct.usage",
+            )?;
+            Hierarchy::V1 {
+                memory,
+                cpu,
+                cpuacct,
+            }
+        };
+        let stats = Self {
+            hierarchy,
+            last_cpu_sample: None,
+        };
+        (stats.mem_limit().is_some() || stats.cpu_quota_cores().is_some()).then_some(stats)
+    }
+
+    /// The memory limit in bytes, if one is set
+    pub(super) fn mem_limit(&self) -> Option<u64> {
+        match &self.hierarchy {
+            Hierarchy::V1 { memory, .. } => read_u64(&memory.join("memory.limit_in_bytes"))
+                .filter(|l| *l < V1_UNLIMITED_THRESHOLD),
+            // `max` means unlimited and fails to parse
+            Hierarchy::V2(dir) => read_u64(&dir.join("memory.max")),
+        }
+    }
+
+    /// Memory used by the cgroup in bytes. Like the kubelet's working set, this excludes
+    /// inactive page cache, which the kernel reclaims before resorting to the OOM killer.
+    pub(super) fn mem_used(&self) -> Option<u64> {
+        let (usage, stat, inactive_key) = match &self.hierarchy {
+            Hierarchy::V1 { memory, .. } => (
+                read_u64(&memory.join("memory.usage_in_bytes"))?,
+                memory.join("memory.stat"),
+                "total_inactive_file",
+            ),
+            Hierarchy::V2(dir) => (
+                read_u64(&dir.join("memory.current"))?,
+                dir.join("memory.stat"),
+                "inactive_file",
+            ),
+        };
+        let inactive = read_keyed(&stat, inactive_key).unwrap_or(0);
+        Some(usage.saturating_sub(inactive))
+    }
+
+    /// The CPU quota as a number of cores, if one is set
+    pub(super) fn cpu_quota_cores(&self) -> Option<f64> {
+        let (quota, period) = match &self.hierarchy {
+            Hierarchy::V1 { cpu, .. } => {
+                // -1 means unlimited and fails to parse as unsigned
+                let quota = read_u64(&cpu.join("cpu.cfs_quota_us"))?;
+                (quota, read_u64(&cpu.join("cpu.cfs_period_us"))?)
+            }
+            Hierarchy::V2(dir) => {
+                let contents = fs::read_to_string(dir.join("cpu.max")).ok()?;
+                let mut parts = contents.split_whitespace();
+                let quota = parts.next()?.parse::<u64>().ok()?;
+                (quota, parts.next()?.parse::<u64>().ok()?)
+            }
+        };
+        (quota > 0 && period > 0).then(|| quota as f64 / period as f64)
+    }
+
+    /// Total CPU time consumed by the cgroup, in nanoseconds
+    fn cpu_usage_nanos(&self) -> Option<u64> {
+        match &self.hierarchy {
+            Hierarchy::V1 { cpuacct, .. } => read_u64(&cpuacct.join("cpuacct.usage")),
+            Hierarchy::V2(dir) => {
+                read_keyed(&dir.join("cpu.stat"), "usage_usec").map(|us| us.saturating_mul(1000))
+            }
+        }
+    }
+
+    /// CPU used since the last call, as a fraction of the quota in the range [0.0, 1.0]. Returns
+    /// `None` if there is no quota, or on the first call since there's nothing to compare to yet.
+    pub(super) fn used_cpu_percent(&mut self) -> Option<f64> {
+        let cores = self.cpu_quota_cores()?;
+        let now = Instant::now();
+        let usage = self.cpu_usage_nanos()?;
+        let (last_usage, last_at) = self.last_cpu_sample.replace((usage, now))?;
+        let elapsed = now.duration_since(last_at).as_nanos() as f64;
+        if elapsed == 0.0 {
+            return None;
+        }
+        let used_cores = usage.saturating_sub(last_usage) as f64 / elapsed;
+        Some((used_cores / cores).clamp(0.0, 1.0))
+    }
+}
+
+fn read_u64(path: &Path) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Reads a value from a file of `key value` lines, like `memory.stat` or `cpu.stat`
+fn read_keyed(path: &Path, key: &str) -> Option<u64> {
+    fs::read_to_string(path).ok()?.lines().find_map(|l| {
+        let (k, v) = l.split_once(' ')?;
+        (k == key).then(|| v.trim().parse().ok()).flatten()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FakeMount(PathBuf);
+    impl FakeMount {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("cgroup-test-{}", uuid::Uuid::new_v4()));
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+        fn write(&self, file: &str, contents: &str) {
+            let path = self.0.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+    }
+    impl Drop for FakeMount {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn reads_v2_limits_and_usage() {
+        let mount = FakeMount::new();
+        mount.write("cgroup.controllers", "cpu memory");
+        mount.write("memory.max", "2147483648\n");
+        mount.write("memory.current", "1073741824\n");
+        mount.write("memory.stat", "anon 1\ninactive_file 73741824\n");
+        mount.write("cpu.max", "200000 100000\n");
+        mount.write("cpu.stat", "usage_usec 5000\n");
+        let mut stats = CgroupStats::detect_at(&mount.0, "0::/\n").unwrap();
+        assert_eq!(stats.hierarchy, Hierarchy::V2(mount.0.clone()));
+        assert_eq!(stats.mem_limit(), Some(2 << 30));
+        assert_eq!(stats.mem_used(), Some(1_000_000_000));
+        assert_eq!(stats.cpu_quota_cores(), Some(2.0));
+        assert_eq!(stats.used_cpu_percent(), None);
+        mount.write("cpu.stat", "usage_usec 100005000\n");
+        let used = stats.used_cpu_percent().unwrap();
+        assert!(used > 0.0 && used <= 1.0);
+    }
+
+    #[test]
+    fn v2_finds_nested_cgroup() {
+        let mount = FakeMount::new();
+        mount.write("cgroup.controllers", "cpu memory");
+        mount.write("kubepods/pod1/memory.max", "1000\n");
+        let stats = CgroupStats::detect_at(&mount.0, "0::/kubepods/pod1\n").unwrap();
+        assert_eq!(stats.mem_limit(), Some(1000));
+    }
+
+    #[test]
+    fn v2_unlimited_is_none() {
+        let mount = FakeMount::new();
+        mount.write("cgroup.controllers", "cpu memory");
+        mount.write("memory.max", "max\n");
+        mount.write("cpu.max", "max 100000\n");
+        assert!(CgroupStats::detect_at(&mount.0, "0::/\n").is_none());
+    }
+
+    #[test]
+    fn reads_v1_limits_and_usage() {
+        let mount = FakeMount::new();
+        mount.write("memory/memory.limit_in_bytes", "4096\n");
+        mount.write("memory/memory.usage_in_bytes", "3000\n");
+        mount.write("memory/memory.stat", "total_inactive_file 1000\n");
+        mount.write("cpu,cpuacct/cpu.cfs_quota_us", "50000\n");
+        mount.write("cpu,cpuacct/cpu.cfs_period_us", "100000\n");
+        mount.write("cpu,cpuacct/cpuacct.usage", "0\n");
+        let stats = CgroupStats::detect_at(&mount.0, "").unwrap();
+        assert_eq!(stats.mem_limit(), Some(4096));
+        assert_eq!(stats.mem_used(), Some(2000));
+        assert_eq!(stats.cpu_quota_cores(), Some(0.5));
+    }
+
+    #[test]
+    fn v1_finds_nested_cgroup() {
+        let mount = FakeMount::new();
+        mount.write("memory/memory.limit_in_bytes", "9223372036854771712\n");
+        mount.write("memory/docker/abc/memory.limit_in_bytes", "4096\n");
+        mount.write("cpu,cpuacct/cpu.cfs_quota_us", "-1\n");
+        mount.write("cpu,cpuacct/docker/abc/cpu.cfs_quota_us", "200000\n");
+        mount.write("cpu,cpuacct/docker/abc/cpu.cfs_period_us", "100000\n");
+        mount.write("cpu,cpuacct/docker/abc/cpuacct.usage", "0\n");
+        let proc_cgroup = "5:memory:/docker/abc\n3:cpu,cpuacct:/docker/abc\n1:name=systemd:/\n";
+        let stats = CgroupStats::detect_at(&mount.0, proc_cgroup).unwrap();
+        assert_eq!(stats.mem_limit(), Some(4096));
+        assert_eq!(stats.cpu_quota_cores(), Some(2.0));
+    }
+
+    #[test]
+    fn v1_unlimited_is_none() {
+        let mount = FakeMount::new();
+        mount.write("memory/memory.limit_in_bytes", "9223372036854771712\n");
+        mount.write("cpu/cpu.cfs_quota_us", "-1\n");
+        mount.write("cpu/cpu.cfs_period_us", "100000\n");
+        mount.write("cpuacct/cpuacct.usage", "0\n");
+        assert!(CgroupStats::detect_at(&mount.0, "").is_none());
+    }
+}
@@ -1,3 +1,4 @@
+use super::cgroup::CgroupStats;
 use crossbeam_utils::atomic::AtomicCell;
 use parking_lot::Mutex;
 use std::{
@@ -470,10 +471,13 @@ impl<MI: SystemResourceInfo + Sync + Send> ResourceController<MI> {
     }
 }
 
-/// Implements [SystemResourceInfo] using the [sysinfo] crate
+/// Implements [SystemResourceInfo] using the [sysinfo] crate. When running in a cgroup (ex: a
+/// container) with memory or CPU limits, usage is measured against those limits instead of the
+/// host's resources.
 #[derive(Debug)]
 pub struct RealSysInfo {
     sys: Mutex<sysinfo::System>,
+    cgroup: Option<Mutex<CgroupStats>>,
     total_mem: AtomicU64,
     cur_mem_usage: AtomicU64,
     cur_cpu_usage: AtomicU64,
@@ -486,6 +490,7 @@ impl RealSysInfo {
         let total_mem = sys.total_memory();
         let s = Self {
             sys: Mutex::new(sys),
+            cgroup: CgroupStats::detect().map(Mutex::new),
             last_refresh: AtomicCell::new(Instant::now()),
             cur_mem_usage: AtomicU64::new(0),
             cur_cpu_usage: AtomicU64::new(0),
@@ -507,16 +512,21 @@ impl RealSysInfo {
         let mut lock = self.sys.lock();
         lock.refresh_memory();
         lock.refresh_cpu_usage();
-        let mem = lock.used_memory();
-        let cpu = lock.global_cpu_usage() as f64 / 100.;
-        if let Some(cgroup_limits) = lock.cgroup_limits() {
-            self.total_mem
-                .store(cgroup_limits.total_memory, Ordering::Release);
-            self.cur_mem_usage.store(
-                cgroup_limits.total_memory - cgroup_limits.free_memory,
-                Ordering::Release,
-            );
+        let mut total_mem = lock.total_memory();
+        let mut mem = lock.used_memory();
+        let mut cpu = lock.global_cpu_usage() as f64 / 100.;
+        if let Some(cgroup) = self.cgroup.as_ref() {
+            let mut cgroup = cgroup.lock();
+            if let Some((limit, used)) = cgroup.mem_limit().zip(cgroup.mem_used()) {
+                // A limit above what the host has can't be reached anyway
+                total_mem = total_mem.min(limit);
+                mem = used;
+            }
+            if let Some(cgroup_cpu) = cgroup.used_cpu_percent() {
+                cpu = cgroup_cpu;
+            }
         }
+        self.total_mem.store(total_mem, Ordering::Release);
         self.cur_mem_usage.store(mem, Ordering::Release);
         self.cur_cpu_usage.store(cpu.to_bits(), Ordering::Release);
         self.last_refresh.store(Instant::now());
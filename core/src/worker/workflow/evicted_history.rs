@@ -0,0 +1,252 @@
+//! Keeps the history of runs which were evicted from the cache, so that when a task for one
+//! arrives on the sticky queue, the run can be rebuilt without fetching its whole history again.
+
+use lru::LruCache;
+use prost::Message;
+use siphasher::sip128::{Hasher128, SipHasher13};
+use std::{fs, hash::Hasher, path::PathBuf};
+use temporal_sdk_core_api::worker::EvictedHistoryRetention;
+use temporal_sdk_core_protos::temporal::api::history::v1::{History, HistoryEvent};
+
+/// Histories of evicted runs, keyed by run id. The most recently evicted are kept in memory, and
+/// older ones are spilled to disk if configured, or otherwise discarded.
+pub(super) struct EvictedHistories {
+    opts: EvictedHistoryRetention,
+    in_memory: LruCache<String, Vec<HistoryEvent>>,
+    bytes_in_memory: usize,
+    /// Run id -> Size of its spilled history
+    on_disk: LruCache<String, usize>,
+    bytes_on_disk: usize,
+}
+
+impl EvictedHistories {
+    pub(super) fn new(opts: EvictedHistoryRetention) -> Self {
+        if let Some(dir) = opts.spill_directory.as_ref() {
+            if let Err(e) = fs::create_dir_all(dir) {
+                warn!(error=%e, dir=?dir, "Couldn't create evicted history spill directory");
+            }
+        }
+        Self {
+            opts,
+            in_memory: LruCache::unbounded(),
+            bytes_in_memory: 0,
+            on_disk: LruCache::unbounded(),
+            bytes_on_disk: 0,
+        }
+    }
+
+    /// Keeps the history of an evicted run. Partial histories, which don't start from the first
+    /// event, are useless for rebuilding the run and are ignored.
+    pub(super) fn retain(&mut self, run_id: String, events: Vec<HistoryEvent>) {
+        if events.first().map(|e| e.event_id) != Some(1) {
+            return;
+        }
+        self.discard(&run_id);
+        self.bytes_in_memory += history_size(&events);
+        self.in_memory.put(run_id, events);
+        while self.bytes_in_memory > self.opts.max_bytes_in_memory {
+            let Some((run_id, events)) = self.in_memory.pop_lru() else {
+                break;
+            };
+            let size = history_size(&events);
+            self.bytes_in_memory -= size;
+            self.spill(run_id, events, size);
+        }
+    }
+
+    /// Removes and returns the history kept for a run, if there is one
+    pub(super) fn take(&mut self, run_id: &str) -> Option<Vec<HistoryEvent>> {
+        if let Some(events) = self.in_memory.pop(run_id) {
+            self.bytes_in_memory -= history_size(&events);
+            return Some(events);
+        }
+        let size = self.on_disk.pop(run_id)?;
+        self.bytes_on_disk -= size;
+        let path = self.spill_path(run_id)?;
+        let read = fs::read(&path);
+        let _ = fs::remove_file(&path);
+        match read
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| Ok(History::decode(bytes.as_slice())?))
+        {
+            Ok(history) => Some(history.events),
+            Err(e) => {
+                warn!(error=%e, run_id, "Couldn't read spilled history of evicted run");
+                None
+            }
+        }
+    }
+
+    /// Forgets the history kept for a run, if there is one. Spilled histories are deleted without
+    /// being read.
+    pub(super) fn discard(&mut self, run_id: &str) {
+        if let Some(events) = self.in_memory.pop(run_id) {
+            self.bytes_in_memory -= history_size(&events);
+        } else if let Some(size) = self.on_disk.pop(run_id) {
+            self.bytes_on_disk -= size;
+            self.remove_spilled(run_id);
+        }
+    }
+
+    fn spill(&mut self, run_id: String, events: Vec<HistoryEvent>, size: usize) {
+        if size > self.opts.max_bytes_on_disk {
+            return;
+        }
+        let Some(path) = self.spill_path(&run_id) else {
+            return;
+        };
+        if let Err(e) = fs::write(&path, History { events }.encode_to_vec()) {
+            warn!(error=%e, run_id, "Couldn't spill history of evicted run");
+            return;
+        }
+        self.bytes_on_disk += size;
+        self.on_disk.put(run_id, size);
+        while self.bytes_on_disk > self.opts.max_bytes_on_disk {
+            let Some((run_id, size)) = self.on_disk.pop_lru() else {
+                break;
+            };
+            self.bytes_on_disk -= size;
+            self.remove_spilled(&run_id);
+        }
+    }
+
+    fn remove_spilled(&self, run_id: &str) {
+        if let Some(path) = self.spill_path(run_id) {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    /// Spilled files are named after a hash of the run id, since run ids are chosen by users and
+    /// may contain anything, including path separators
+    fn spill_path(&self, run_id: &str) -> Option<PathBuf> {
+        let mut hasher = SipHasher13::new();
+        hasher.write(run_id.as_bytes());
+        let name = format!("{:032x}.history", hasher.finish128().as_u128());
+        self.opts.spill_directory.as_ref().map(|dir| dir.join(name))
+    }
+}
+
+impl Drop for EvictedHistories {
+    fn drop(&mut self) {
+        for (run_id, _) in self.on_disk.iter() {
+            self.remove_spilled(run_id);
+        }
+    }
+}
+
+fn history_size(events: &[HistoryEvent]) -> usize {
+    events.iter().map(Message::encoded_len).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temporal_sdk_core_protos::TestHistoryBuilder;
+
+    fn history() -> Vec<HistoryEvent> {
+        let mut t = TestHistoryBuilder::default();
+        t.add_workflow_execution_started("wf", Default::default());
+        t.add_full_wf_task();
+        t.get_full_history_info().unwrap().into_events()
+    }
+
+    #[test]
+    fn keeps_most_recent_in_memory() {
+        let size = history_size(&history());
+        let mut store = EvictedHistories::new(EvictedHistoryRetention {
+            max_bytes_in_memory: size * 2,
+            ..Default::default()
+        });
+        for run_id in ["a", "b", "c"] {
+            store.retain(run_id.to_string(), history());
+        }
+        // Nothing to spill to, so the oldest is gone
+        assert!(store.take("a").is_none());
+        assert_eq!(store.take("b").unwrap(), history());
+        assert_eq!(store.take("c").unwrap(), history());
+        assert!(store.take("c").is_none());
+        assert_eq!(store.bytes_in_memory, 0);
+    }
+
+    #[test]
+    fn ignores_partial_history() {
+        let mut store = EvictedHistories::new(EvictedHistoryRetention {
+            max_bytes_in_memory: usize::MAX,
+            ..Default::default()
+        });
+        store.retain("a".to_string(), history().split_off(1));
+        assert!(store.take("a").is_none());
+    }
+
+    #[test]
+    fn spills_to_disk() {
+        let dir = std::env::temp_dir().join(format!("evicted-history-{}", uuid::Uuid::new_v4()));
+        let size = history_size(&history());
+        let mut store = EvictedHistories::new(EvictedHistoryRetention {
+            max_bytes_in_memory: size,
+            spill_directory: Some(dir.clone()),
+            max_bytes_on_disk: size,
+        });
+        for run_id in ["a", "b", "c"] {
+            store.retain(run_id.to_string(), history());
+        }
+        // "c" is in memory, "b" on disk, and "a" was pushed off the disk
+        let (a_path, b_path) = (
+            store.spill_path("a").unwrap(),
+            store.spill_path("b").unwrap(),
+        );
+        assert!(!a_path.exists());
+        assert!(b_path.exists());
+        assert!(store.take("a").is_none());
+        assert_eq!(store.take("b").unwrap(), history());
+        assert!(!b_path.exists());
+        assert_eq!(store.take("c").unwrap(), history());
+        drop(store);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn discard_deletes_spilled_history() {
+        let dir = std::env::temp_dir().join(format!("evicted-history-{}", uuid::Uuid::new_v4()));
+        let size = history_size(&history());
+        let mut store = EvictedHistories::new(EvictedHistoryRetention {
+            max_bytes_in_memory: 0,
+            spill_directory: Some(dir.clone()),
+            max_bytes_on_disk: size,
+        });
+        store.retain("a".to_string(), history());
+        assert_eq!(store.bytes_on_disk, size);
+        // Corrupt the file, which doesn't matter since discarding never reads it
+        let path = store.spill_path("a").unwrap();
+        fs::write(&path, b"garbage").unwrap();
+        store.discard("a");
+        assert!(!path.exists());
+        assert_eq!(store.bytes_on_disk, 0);
+        assert!(store.take("a").is_none());
+        drop(store);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn spill_paths_stay_in_directory() {
+        let dir = std::env::temp_dir().join(format!("evicted-history-{}", uuid::Uuid::new_v4()));
+        let size = history_size(&history());
+        let mut store = EvictedHistories::new(EvictedHistoryRetention {
+            max_bytes_in_memory: 0,
+            spill_directory: Some(dir.clone()),
+            max_bytes_on_disk: size * 2,
+        });
+        let sneaky = "../../escaped/run";
+        assert_eq!(
+            store.spill_path(sneaky).unwrap().parent(),
+            Some(dir.as_path())
+        );
+        store.retain(sneaky.to_string(), history());
+        store.retain("run".to_string(), history());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+        assert_eq!(store.take(sneaky).unwrap(), history());
+        assert_eq!(store.take("run").unwrap(), history());
+        drop(store);
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -7,6 +7,7 @@ use crate::{
 };
 use futures_util::{FutureExt, Stream, TryFutureExt, future::BoxFuture};
 use itertools::Itertools;
+use prost::Message;
 use std::{
     collections::VecDeque,
     fmt::Debug,
@@ -33,6 +34,10 @@ static EMPTY_TASK_ERR: LazyLock<tonic::Status> = LazyLock::new(|| {
 
 /// Represents one or more complete WFT sequences. History events are expected to be consumed from
 /// it and applied to the state machines via [HistoryUpdate::take_next_wft_sequence]
+#[cfg_attr(
+    feature = "save_wf_inputs",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub(crate) struct HistoryUpdate {
     events: Vec<HistoryEvent>,
     /// The event ID of the last started WFT, as according to the WFT which this update was
@@ -81,6 +86,10 @@ pub(crate) enum NextWFT {
 
 #[derive(derive_more::Debug)]
 #[debug("HistoryPaginator(run_id: {run_id})")]
+#[cfg_attr(
+    feature = "save_wf_inputs",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub(crate) struct HistoryPaginator {
     pub(crate) wf_id: String,
     pub(crate) run_id: String,
@@ -88,6 +97,7 @@ pub(crate) struct HistoryPaginator {
     pub(crate) wft_started_event_id: i64,
     id_of_last_event_in_last_extracted_update: Option<i64>,
 
+    #[cfg_attr(feature = "save_wf_inputs", serde(skip, default = "replay_client"))]
     client: Arc<dyn WorkerClient>,
     event_queue: VecDeque<HistoryEvent>,
     next_page_token: NextPageToken,
@@ -96,7 +106,18 @@ pub(crate) struct HistoryPaginator {
     final_events: Vec<HistoryEvent>,
 }
 
+/// Paginators replayed from recorded workflow stream inputs never fetch, since the recording
+/// contains the pages that were fetched
+#[cfg(feature = "save_wf_inputs")]
+fn replay_client() -> Arc<dyn WorkerClient> {
+    Arc::new(crate::worker::client::mocks::mock_manual_workflow_client())
+}
+
 #[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "save_wf_inputs",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub(crate) enum NextPageToken {
     /// There is no page token, we need to fetch history from the beginning
     FetchFromStart,
@@ -451,6 +472,45 @@ impl HistoryUpdate {
         self.events.first().map(|e| e.event_id)
     }
 
+    /// Estimates the memory held by the events which have yet to be taken from this update, using
+    /// their encoded size as a proxy for their size in memory.
+    pub(crate) fn estimated_size(&self) -> usize {
+        self.events
+            .iter()
+            .map(|e| mem::size_of::<HistoryEvent>() + e.encoded_len())
+            .sum()
+    }
+
+    /// True if [HistoryUpdate::with_prefix] would accept the given earlier history of the run:
+    /// it must start at the first event and reach the first event of this update
+    pub(crate) fn accepts_prefix(&self, prefix: &[HistoryEvent]) -> bool {
+        let Some(first_id) = self.first_event_id() else {
+            return false;
+        };
+        self.has_last_wft
+            && prefix.first().map(|e| e.event_id) == Some(1)
+            && prefix.iter().any(|e| e.event_id == first_id - 1)
+    }
+
+    /// Prepends the earlier history of the run to a partial update, so that it can be applied to a
+    /// run which isn't cached. If [HistoryUpdate::accepts_prefix] is false, the update is handed
+    /// back unchanged.
+    pub(crate) fn with_prefix(self, mut prefix: Vec<HistoryEvent>) -> Result<Self, Self> {
+        if !self.accepts_prefix(&prefix) {
+            return Err(self);
+        }
+        let first_id = self.first_event_id().expect("Accepted updates have events");
+        prefix.retain(|e| e.event_id < first_id);
+        prefix.extend(self.events);
+        let (update, _) = Self::from_events(
+            prefix,
+            self.previous_wft_started_id,
+            self.wft_started_id,
+            true,
+        );
+        Ok(update)
+    }
+
     #[cfg(debug_assertions)]
     fn assert_contiguous(&self) -> bool {
         use crate::abstractions::dbg_panic;
@@ -276,7 +276,7 @@ impl TryFrom<HistEventData> for ActivityMachineEvents {
                 }
             }
             _ => {
-                return Err(WFMachinesError::Nondeterminism(format!(
+                return Err(WFMachinesError::nondeterminism(format!(
                     "Activity machine does not handle this event: {e}"
                 )));
             }
@@ -375,14 +375,14 @@ impl ScheduleCommandCreated {
             sched_dat.last_task_in_history,
         ) {
             if sched_dat.act_id != dat.attrs.activity_id {
-                return TransitionResult::Err(WFMachinesError::Nondeterminism(format!(
+                return TransitionResult::Err(WFMachinesError::nondeterminism(format!(
                     "Activity id of scheduled event '{}' does not \
                  match activity id of activity command '{}'",
                     sched_dat.act_id, dat.attrs.activity_id
                 )));
             }
             if sched_dat.act_type != dat.attrs.activity_type {
-                return TransitionResult::Err(WFMachinesError::Nondeterminism(format!(
+                return TransitionResult::Err(WFMachinesError::nondeterminism(format!(
                     "Activity type of scheduled event '{}' does not \
                  match activity type of activity command '{}'",
                     sched_dat.act_type, dat.attrs.activity_type
@@ -649,7 +649,7 @@ impl Canceled {
         if dat.cancellation_type == ActivityCancellationType::Abandon {
             TransitionResult::default()
         } else {
-            TransitionResult::Err(WFMachinesError::Nondeterminism(format!(
+            TransitionResult::Err(WFMachinesError::nondeterminism(format!(
                 "Non-Abandon cancel mode activities cannot be started after being cancelled. \
                  Seq: {seq_num:?}"
             )))
@@ -664,7 +664,7 @@ impl Canceled {
         if dat.cancellation_type == ActivityCancellationType::Abandon {
             TransitionResult::default()
         } else {
-            TransitionResult::Err(WFMachinesError::Nondeterminism(format!(
+            TransitionResult::Err(WFMachinesError::nondeterminism(format!(
                 "Non-Abandon cancel mode activities cannot be completed after being cancelled: {attrs:?}"
             )))
         }
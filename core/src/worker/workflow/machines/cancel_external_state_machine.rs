@@ -161,7 +161,7 @@ impl TryFrom<HistEventData> for CancelExternalMachineEvents {
                 }
             }
             _ => {
-                return Err(WFMachinesError::Nondeterminism(format!(
+                return Err(WFMachinesError::nondeterminism(format!(
                     "Cancel external WF machine does not handle this event: {e}"
                 )))
             }
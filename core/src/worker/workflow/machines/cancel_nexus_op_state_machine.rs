@@ -76,7 +76,7 @@ impl TryFrom<HistEventData> for CancelNexusOpMachineEvents {
         Ok(match e.event_type() {
             EventType::NexusOperationCancelRequested => Self::NexusOpCancelRequested,
             _ => {
-                return Err(WFMachinesError::Nondeterminism(format!(
+                return Err(WFMachinesError::nondeterminism(format!(
                     "Cancel external WF machine does not handle this event: {e}"
                 )))
             }
@@ -68,7 +68,7 @@ impl TryFrom<HistEventData> for CancelWorkflowMachineEvents {
         Ok(match EventType::try_from(e.event_type) {
             Ok(EventType::WorkflowExecutionCanceled) => Self::WorkflowExecutionCanceled,
             _ => {
-                return Err(WFMachinesError::Nondeterminism(format!(
+                return Err(WFMachinesError::nondeterminism(format!(
                     "Cancel workflow machine does not handle this event: {e}"
                 )));
             }
@@ -134,7 +134,7 @@ pub(super) struct Cancelled {
 }
 
 fn completion_of_not_abandoned_err() -> WFMachinesError {
-    WFMachinesError::Nondeterminism(
+    WFMachinesError::nondeterminism(
         "Child workflows which don't have the ABANDON cancellation type cannot complete after \
          being cancelled."
             .to_string(),
@@ -234,14 +234,14 @@ impl StartCommandCreated {
             event_dat.last_task_in_history,
         ) {
             if event_dat.wf_id != state.workflow_id {
-                return TransitionResult::Err(WFMachinesError::Nondeterminism(format!(
+                return TransitionResult::Err(WFMachinesError::nondeterminism(format!(
                     "Child workflow id of scheduled event '{}' does not \
                      match child workflow id of command '{}'",
                     event_dat.wf_id, state.workflow_id
                 )));
             }
             if event_dat.wf_type != state.workflow_type {
-                return TransitionResult::Err(WFMachinesError::Nondeterminism(format!(
+                return TransitionResult::Err(WFMachinesError::nondeterminism(format!(
                     "Child workflow type of scheduled event '{}' does not \
                      match child workflow type of command '{}'",
                     event_dat.wf_type, state.workflow_type
@@ -624,7 +624,7 @@ impl TryFrom<HistEventData> for ChildWorkflowMachineEvents {
             }
             Ok(EventType::ChildWorkflowExecutionCanceled) => Self::ChildWorkflowExecutionCancelled,
             _ => {
-                return Err(WFMachinesError::Nondeterminism(format!(
+                return Err(WFMachinesError::nondeterminism(format!(
                     "Child workflow machine does not handle this event: {e:?}"
                 )));
             }
@@ -58,7 +58,7 @@ impl TryFrom<HistEventData> for CompleteWorkflowMachineEvents {
         Ok(match e.event_type() {
             EventType::WorkflowExecutionCompleted => Self::WorkflowExecutionCompleted,
             _ => {
-                return Err(WFMachinesError::Nondeterminism(format!(
+                return Err(WFMachinesError::nondeterminism(format!(
                     "Complete workflow machine does not handle this event: {e}"
                 )));
             }
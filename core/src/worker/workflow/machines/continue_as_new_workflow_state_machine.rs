@@ -74,7 +74,7 @@ impl TryFrom<HistEventData> for ContinueAsNewWorkflowMachineEvents {
         Ok(match e.event_type() {
             EventType::WorkflowExecutionContinuedAsNew => Self::WorkflowExecutionContinuedAsNew,
             _ => {
-                return Err(WFMachinesError::Nondeterminism(format!(
+                return Err(WFMachinesError::nondeterminism(format!(
                     "Continue as new workflow machine does not handle this event: {e}"
                 )));
             }
@@ -85,7 +85,7 @@ impl TryFrom<HistEventData> for FailWorkflowMachineEvents {
         Ok(match e.event_type() {
             EventType::WorkflowExecutionFailed => Self::WorkflowExecutionFailed,
             _ => {
-                return Err(WFMachinesError::Nondeterminism(format!(
+                return Err(WFMachinesError::nondeterminism(format!(
                     "Fail workflow machine does not handle this event: {e}"
                 )));
             }
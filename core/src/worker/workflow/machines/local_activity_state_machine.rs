@@ -163,7 +163,7 @@ pub(super) fn new_local_activity(
         }
     } else {
         if maybe_pre_resolved.is_some() {
-            return Err(WFMachinesError::Nondeterminism(
+            return Err(WFMachinesError::nondeterminism(
                 "Local activity cannot be created as pre-resolved while not replaying".to_string(),
             ));
         }
@@ -543,13 +543,13 @@ impl ResultNotified {
         dat: CompleteLocalActivityData,
     ) -> LocalActivityMachineTransition<MarkerCommandRecorded> {
         if self.result_type == ResultType::Completed && dat.result.is_err() {
-            return TransitionResult::Err(WFMachinesError::Nondeterminism(format!(
+            return TransitionResult::Err(WFMachinesError::nondeterminism(format!(
                 "Local activity (seq {}) completed successfully locally, but history said \
                  it failed!",
                 shared.attrs.seq
             )));
         } else if self.result_type == ResultType::Failed && dat.result.is_ok() {
-            return TransitionResult::Err(WFMachinesError::Nondeterminism(format!(
+            return TransitionResult::Err(WFMachinesError::nondeterminism(format!(
                 "Local activity (seq {}) failed locally, but history said it completed!",
                 shared.attrs.seq
             )));
@@ -810,14 +810,14 @@ impl TryFrom<HistEventData> for LocalActivityMachineEvents {
     fn try_from(e: HistEventData) -> Result<Self, Self::Error> {
         let e = e.event;
         if e.event_type() != EventType::MarkerRecorded {
-            return Err(WFMachinesError::Nondeterminism(format!(
+            return Err(WFMachinesError::nondeterminism(format!(
                 "Local activity machine cannot handle this event: {e}"
             )));
         }
 
         match e.into_local_activity_marker_details() {
             Some(marker_dat) => Ok(LocalActivityMachineEvents::MarkerRecorded(marker_dat)),
-            _ => Err(WFMachinesError::Nondeterminism(
+            _ => Err(WFMachinesError::nondeterminism(
                 "Local activity machine encountered an unparsable marker".to_string(),
             )),
         }
@@ -829,7 +829,7 @@ fn verify_marker_data_matches(
     dat: &CompleteLocalActivityData,
 ) -> Result<(), WFMachinesError> {
     if shared.attrs.seq != dat.marker_dat.seq {
-        return Err(WFMachinesError::Nondeterminism(format!(
+        return Err(WFMachinesError::nondeterminism(format!(
             "Local activity marker data has sequence number {} but matched against LA \
             command with sequence number {}",
             dat.marker_dat.seq, shared.attrs.seq
@@ -843,14 +843,14 @@ fn verify_marker_data_matches(
         !shared.replaying_when_invoked,
     ) {
         if dat.marker_dat.activity_id != shared.attrs.activity_id {
-            return Err(WFMachinesError::Nondeterminism(format!(
+            return Err(WFMachinesError::nondeterminism(format!(
                 "Activity id of recorded marker '{}' does not \
                  match activity id of local activity command '{}'",
                 dat.marker_dat.activity_id, shared.attrs.activity_id
             )));
         }
         if dat.marker_dat.activity_type != shared.attrs.activity_type {
-            return Err(WFMachinesError::Nondeterminism(format!(
+            return Err(WFMachinesError::nondeterminism(format!(
                 "Activity type of recorded marker '{}' does not \
                  match activity type of local activity command '{}'",
                 dat.marker_dat.activity_type, shared.attrs.activity_type
@@ -124,7 +124,7 @@ where
             match OnEventWrapper::on_event_mut(self, converted_command) {
                 Ok(c) => process_machine_commands(self, c, None),
                 Err(MachineError::InvalidTransition) => {
-                    Err(WFMachinesError::Nondeterminism(format!(
+                    Err(WFMachinesError::nondeterminism(format!(
                         "Unexpected command producing an invalid transition {:?} in state {}",
                         command_type,
                         self.state()
@@ -133,7 +133,7 @@ where
                 Err(MachineError::Underlying(e)) => Err(e.into()),
             }
         } else {
-            Err(WFMachinesError::Nondeterminism(format!(
+            Err(WFMachinesError::nondeterminism(format!(
                 "Unexpected command {:?} generated by a {:?} machine",
                 command_type,
                 self.name()
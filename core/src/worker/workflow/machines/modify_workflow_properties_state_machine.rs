@@ -51,7 +51,7 @@ impl WFMachinesAdapter for ModifyWorkflowPropertiesMachine {
         _my_command: Self::Command,
         _event_info: Option<EventInfo>,
     ) -> Result<Vec<MachineResponse>, Self::Error> {
-        Err(Self::Error::Nondeterminism(
+        Err(Self::Error::nondeterminism(
             "ModifyWorkflowProperties does not use state machine commands".to_string(),
         ))
     }
@@ -66,7 +66,7 @@ impl TryFrom<HistEventData> for ModifyWorkflowPropertiesMachineEvents {
             EventType::WorkflowPropertiesModified => {
                 Ok(ModifyWorkflowPropertiesMachineEvents::CommandRecorded)
             }
-            _ => Err(Self::Error::Nondeterminism(format!(
+            _ => Err(Self::Error::nondeterminism(format!(
                 "ModifyWorkflowPropertiesMachine does not handle {e}"
             ))),
         }
@@ -81,7 +81,7 @@ impl TryFrom<CommandType> for ModifyWorkflowPropertiesMachineEvents {
             CommandType::ModifyWorkflowProperties => {
                 Ok(ModifyWorkflowPropertiesMachineEvents::CommandScheduled)
             }
-            _ => Err(Self::Error::Nondeterminism(format!(
+            _ => Err(Self::Error::nondeterminism(format!(
                 "ModifyWorkflowPropertiesMachine does not handle command type {c:?}"
             ))),
         }
@@ -335,7 +335,7 @@ impl TryFrom<HistEventData> for NexusOperationMachineEvents {
                         event_id: e.event_id,
                     })
                 } else {
-                    return Err(WFMachinesError::Nondeterminism(
+                    return Err(WFMachinesError::nondeterminism(
                         "NexusOperationScheduled attributes were unset or malformed".to_string(),
                     ));
                 }
@@ -346,7 +346,7 @@ impl TryFrom<HistEventData> for NexusOperationMachineEvents {
                 {
                     Self::NexusOperationStarted(sa)
                 } else {
-                    return Err(WFMachinesError::Nondeterminism(
+                    return Err(WFMachinesError::nondeterminism(
                         "NexusOperationStarted attributes were unset or malformed".to_string(),
                     ));
                 }
@@ -357,7 +357,7 @@ impl TryFrom<HistEventData> for NexusOperationMachineEvents {
                 {
                     Self::NexusOperationCompleted(ca)
                 } else {
-                    return Err(WFMachinesError::Nondeterminism(
+                    return Err(WFMachinesError::nondeterminism(
                         "NexusOperationCompleted attributes were unset or malformed".to_string(),
                     ));
                 }
@@ -368,7 +368,7 @@ impl TryFrom<HistEventData> for NexusOperationMachineEvents {
                 {
                     Self::NexusOperationFailed(fa)
                 } else {
-                    return Err(WFMachinesError::Nondeterminism(
+                    return Err(WFMachinesError::nondeterminism(
                         "NexusOperationFailed attributes were unset or malformed".to_string(),
                     ));
                 }
@@ -379,7 +379,7 @@ impl TryFrom<HistEventData> for NexusOperationMachineEvents {
                 {
                     Self::NexusOperationCanceled(ca)
                 } else {
-                    return Err(WFMachinesError::Nondeterminism(
+                    return Err(WFMachinesError::nondeterminism(
                         "NexusOperationCanceled attributes were unset or malformed".to_string(),
                     ));
                 }
@@ -390,14 +390,14 @@ impl TryFrom<HistEventData> for NexusOperationMachineEvents {
                 {
                     Self::NexusOperationTimedOut(toa)
                 } else {
-                    return Err(WFMachinesError::Nondeterminism(
+                    return Err(WFMachinesError::nondeterminism(
                         "NexusOperationTimedOut attributes were unset or malformed".to_string(),
                     ));
                 }
             }
             Ok(EventType::NexusOperationCancelRequested) => Self::NexusOperationCancelRequested,
             _ => {
-                return Err(WFMachinesError::Nondeterminism(format!(
+                return Err(WFMachinesError::nondeterminism(format!(
                     "Nexus operation machine does not handle this event: {e:?}"
                 )));
             }
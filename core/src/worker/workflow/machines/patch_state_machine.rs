@@ -214,7 +214,7 @@ impl Notified {
         id: String,
     ) -> PatchMachineTransition<MarkerCommandRecorded> {
         if id != dat.patch_id {
-            return TransitionResult::Err(WFMachinesError::Nondeterminism(format!(
+            return TransitionResult::Err(WFMachinesError::nondeterminism(format!(
                 "Change id {} does not match expected id {}",
                 id, dat.patch_id
             )));
@@ -251,7 +251,7 @@ impl TryFrom<HistEventData> for PatchMachineEvents {
         let e = e.event;
         match e.get_patch_marker_details() {
             Some((id, _)) => Ok(Self::MarkerRecorded(id)),
-            _ => Err(WFMachinesError::Nondeterminism(format!(
+            _ => Err(WFMachinesError::nondeterminism(format!(
                 "Change machine cannot handle this event: {e}"
             ))),
         }
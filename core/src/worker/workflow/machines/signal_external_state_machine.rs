@@ -203,7 +203,7 @@ impl TryFrom<HistEventData> for SignalExternalMachineEvents {
                 }
             }
             _ => {
-                return Err(WFMachinesError::Nondeterminism(format!(
+                return Err(WFMachinesError::nondeterminism(format!(
                     "Signal external WF machine does not handle this event: {e}"
                 )));
             }
@@ -127,7 +127,7 @@ impl TryFrom<HistEventData> for TimerMachineEvents {
                 }
             }
             _ => {
-                return Err(WFMachinesError::Nondeterminism(format!(
+                return Err(WFMachinesError::nondeterminism(format!(
                     "Timer machine does not handle this event: {e}"
                 )));
             }
@@ -209,7 +209,7 @@ impl StartCommandRecorded {
         if dat.attrs.seq.to_string() == attrs.timer_id {
             TransitionResult::ok(vec![TimerMachineCommand::Complete], Fired::default())
         } else {
-            TransitionResult::Err(WFMachinesError::Nondeterminism(format!(
+            TransitionResult::Err(WFMachinesError::nondeterminism(format!(
                 "Timer fired event did not have expected timer id {}, it was {}!",
                 dat.attrs.seq, attrs.timer_id
             )))
@@ -135,7 +135,7 @@ impl UpdateMachine {
             }
         }
         .map_err(|e| match e {
-            MachineError::InvalidTransition => WFMachinesError::Nondeterminism(format!(
+            MachineError::InvalidTransition => WFMachinesError::nondeterminism(format!(
                 "Invalid transition while handling update response (id {}) in state {}",
                 &self.shared_state.meta.update_id,
                 self.state(),
@@ -221,7 +221,7 @@ impl TryFrom<HistEventData> for UpdateMachineEvents {
                 UpdateMachineEvents::WorkflowExecutionUpdateCompleted
             }
             _ => {
-                return Err(WFMachinesError::Nondeterminism(format!(
+                return Err(WFMachinesError::nondeterminism(format!(
                     "Update machine does not handle this event: {e}"
                 )));
             }
@@ -128,7 +128,7 @@ impl WFMachinesAdapter for UpsertSearchAttributesMachine {
         _event_info: Option<EventInfo>,
     ) -> Result<Vec<MachineResponse>, Self::Error> {
         // No implementation needed until this state machine emits state machine commands
-        Err(Self::Error::Nondeterminism(
+        Err(Self::Error::nondeterminism(
             "UpsertWorkflowSearchAttributesMachine does not use commands".to_string(),
         ))
     }
@@ -145,7 +145,7 @@ impl TryFrom<HistEventData> for UpsertSearchAttributesMachineEvents {
             Some(history_event::Attributes::UpsertWorkflowSearchAttributesEventAttributes(_)) => {
                 Ok(UpsertSearchAttributesMachineEvents::CommandRecorded)
             }
-            _ => Err(Self::Error::Nondeterminism(format!(
+            _ => Err(Self::Error::nondeterminism(format!(
                 "UpsertWorkflowSearchAttributesMachine does not handle {e}"
             ))),
         }
@@ -162,7 +162,7 @@ impl TryFrom<CommandType> for UpsertSearchAttributesMachineEvents {
             CommandType::UpsertWorkflowSearchAttributes => {
                 Ok(UpsertSearchAttributesMachineEvents::CommandScheduled)
             }
-            _ => Err(Self::Error::Nondeterminism(format!(
+            _ => Err(Self::Error::nondeterminism(format!(
                 "UpsertWorkflowSearchAttributesMachine does not handle command type {c:?}"
             ))),
         }
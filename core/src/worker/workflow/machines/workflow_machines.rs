@@ -39,6 +39,7 @@ use crate::{
     },
 };
 use anyhow::Context;
+use prost::Message;
 use siphasher::sip::SipHasher13;
 use slotmap::{SlotMap, SparseSecondaryMap};
 use std::{
@@ -47,6 +48,7 @@ use std::{
     convert::TryInto,
     hash::{Hash, Hasher},
     iter::Peekable,
+    mem,
     rc::Rc,
     sync::Arc,
     time::{Duration, Instant, SystemTime},
@@ -65,7 +67,7 @@ use temporal_sdk_core_protos::{
         command::v1::{
             Command as ProtoCommand, CommandAttributesExt, command::Attributes as ProtoCmdAttrs,
         },
-        enums::v1::EventType,
+        enums::v1::{CommandType, EventType},
         history::v1::{HistoryEvent, history_event},
         protocol::v1::{Message as ProtocolMessage, message::SequencingId},
         sdk::v1::{UserMetadata, WorkflowTaskCompletedMetadata},
@@ -87,6 +89,9 @@ pub(crate) struct WorkflowMachines {
     protocol_msgs: Vec<IncomingProtocolMessage>,
     /// EventId of the last handled WorkflowTaskStarted event
     current_started_event_id: i64,
+    /// The number of WorkflowTaskStarted events handled so far, IE: the one-based number of the
+    /// workflow task currently being applied.
+    workflow_task_number: u32,
     /// The event id of the next workflow task started event that the machines need to process.
     /// Eventually, this number should reach the started id in the latest history update, but
     /// we must incrementally apply the history while communicating with lang.
@@ -177,6 +182,15 @@ enum MachineAssociatedCommand {
     FakeLocalActivityMarker(u32),
 }
 
+impl MachineAssociatedCommand {
+    fn command_type(&self) -> CommandType {
+        match self {
+            MachineAssociatedCommand::Real(c) => c.command_type(),
+            MachineAssociatedCommand::FakeLocalActivityMarker(_) => CommandType::RecordMarker,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 struct ChangeInfo {
     created_command: bool,
@@ -243,7 +257,7 @@ macro_rules! cancel_machine {
         let machine = if let Machines::$machine_variant(m) = $self.machine_mut(m_key) {
             m
         } else {
-            return Err(WFMachinesError::Nondeterminism(format!(
+            return Err(WFMachinesError::nondeterminism(format!(
                 "Machine was not a {} when it should have been during cancellation: {:?}",
                 stringify!($machine_variant),
                 $cmd_id
@@ -277,6 +291,7 @@ impl WorkflowMachines {
             metrics: basics.metrics,
             // In an ideal world one could say ..Default::default() here and it'd still work.
             current_started_event_id: 0,
+            workflow_task_number: 0,
             next_started_event_id: 0,
             last_processed_event: 0,
             workflow_start_time: None,
@@ -307,6 +322,28 @@ impl WorkflowMachines {
         self.workflow_end_time.is_some()
     }
 
+    /// A rough estimate of the memory retained by these machines in bytes: history which has yet
+    /// to be applied, the machines themselves, and queued commands.
+    pub(crate) fn estimated_size(&self) -> usize {
+        let queued_commands: usize = self
+            .commands
+            .iter()
+            .chain(self.current_wf_task_commands.iter())
+            .map(|c| match &c.command {
+                MachineAssociatedCommand::Real(cmd) => cmd.encoded_len(),
+                MachineAssociatedCommand::FakeLocalActivityMarker(_) => 0,
+            })
+            .sum();
+        mem::size_of::<Self>()
+            + self.last_history_from_server.estimated_size()
+            + self.all_machines.len() * mem::size_of::<Machines>()
+            + (self.machines_by_event_id.len() + self.id_to_machine.len())
+                * mem::size_of::<(CommandID, MachineKey)>()
+            + (self.commands.len() + self.current_wf_task_commands.len())
+                * mem::size_of::<CommandAndMachine>()
+            + queued_commands
+    }
+
     /// Returns the total time it took to execute the workflow. Returns `None` if workflow is
     /// incomplete, or time went backwards.
     pub(crate) fn total_runtime(&self) -> Option<Duration> {
@@ -363,7 +400,7 @@ impl WorkflowMachines {
                     }
                     self.process_machine_responses(mk, resps)?;
                 } else {
-                    return Err(WFMachinesError::Nondeterminism(format!(
+                    return Err(WFMachinesError::nondeterminism(format!(
                         "Command matching activity with seq num {seq} existed but was not a \
                         local activity!"
                     )));
@@ -499,11 +536,17 @@ impl WorkflowMachines {
             .try_use(flag, should_record)
     }
 
+    /// Returns the one-based number of the workflow task currently being applied
+    pub(crate) fn workflow_task_number(&self) -> u32 {
+        self.workflow_task_number
+    }
+
     /// Undo a speculative workflow task by resetting to a certain WFT Started ID. This can happen
     /// when an update request is rejected.
     pub(crate) fn reset_last_started_id(&mut self, id: i64) {
         debug!("Resetting back to event id {} due to speculative WFT", id);
         self.current_started_event_id = id;
+        self.workflow_task_number = self.workflow_task_number.saturating_sub(1);
         // We must reset the last event we "processed" to be after the last WFT we really completed
         // + any command events (since the SDK "processed" those when it emitted the commands). This
         // is also equal to what we just processed in the speculative task, minus two, since we
@@ -695,14 +738,37 @@ impl WorkflowMachines {
             }
 
             if do_handle_event {
-                let eho = self.handle_event(
-                    HistEventData {
-                        event,
-                        replaying: self.replaying,
-                        current_task_is_last_in_history: has_final_event,
-                    },
-                    next_event,
-                )?;
+                let event_type = event.event_type();
+                // Noted before handling the event, since the matching command is consumed by it
+                let front_command = if event.is_command_event() {
+                    self.commands
+                        .front()
+                        .map(|c| (c.command.command_type(), c.machine))
+                } else {
+                    None
+                };
+                let eho = self
+                    .handle_event(
+                        HistEventData {
+                            event,
+                            replaying: self.replaying,
+                            current_task_is_last_in_history: has_final_event,
+                        },
+                        next_event,
+                    )
+                    .map_err(|e| {
+                        e.with_nondeterminism_context(|diag| {
+                            diag.event_id = eid;
+                            diag.expected = format!("{event_type:?}");
+                            diag.workflow_task_number = self.workflow_task_number;
+                            if let Some((command_type, mk)) = front_command {
+                                diag.actual = format!("{command_type:?}");
+                                if let Some(m) = self.all_machines.get(mk) {
+                                    diag.machine = m.name().to_string();
+                                }
+                            }
+                        })
+                    })?;
                 if matches!(
                     eho,
                     EventHandlingOutcome::SkipEvent {
@@ -869,7 +935,7 @@ impl WorkflowMachines {
                 .machines_by_event_id
                 .get(&initial_cmd_id)
                 .ok_or_else(|| {
-                    WFMachinesError::Nondeterminism(format!(
+                    WFMachinesError::nondeterminism(format!(
                         "During event handling, this event had an initial command ID but we \
                          could not find a matching command for it: {event:?}"
                     ))
@@ -934,7 +1000,7 @@ impl WorkflowMachines {
             let command = if let Some(c) = maybe_command {
                 c
             } else {
-                return Err(WFMachinesError::Nondeterminism(format!(
+                return Err(WFMachinesError::nondeterminism(format!(
                     "No command scheduled for event {event}"
                 )));
             };
@@ -1262,6 +1328,7 @@ impl WorkflowMachines {
     /// of the current started event as well as workflow time properly.
     fn task_started(&mut self, task_started_event_id: i64, time: SystemTime) -> Result<()> {
         self.current_started_event_id = task_started_event_id;
+        self.workflow_task_number += 1;
         self.wft_start_time = Some(time);
         self.set_current_time(time);
 
@@ -1290,246 +1357,254 @@ impl WorkflowMachines {
     /// server.
     fn handle_driven_results(&mut self, results: Vec<WFCommand>) -> Result<()> {
         for cmd in results {
-            match cmd.variant {
-                WFCommandVariant::AddTimer(attrs) => {
-                    let seq = attrs.seq;
-                    self.add_cmd_to_wf_task(
-                        new_timer(attrs),
-                        cmd.metadata,
-                        CommandID::Timer(seq).into(),
-                    );
-                }
-                WFCommandVariant::UpsertSearchAttributes(attrs) => {
-                    self.drive_me
-                        .search_attributes_update(attrs.search_attributes.clone());
-                    self.add_cmd_to_wf_task(
-                        upsert_search_attrs(
-                            attrs,
-                            self.observed_internal_flags.clone(),
-                            self.replaying,
-                        ),
-                        cmd.metadata,
-                        CommandIdKind::NeverResolves,
-                    );
-                }
-                WFCommandVariant::CancelTimer(attrs) => {
-                    cancel_machine!(self, CommandID::Timer(attrs.seq), TimerMachine, cancel);
-                }
-                WFCommandVariant::AddActivity(attrs) => {
-                    let seq = attrs.seq;
-                    let use_compat = self.determine_use_compatible_flag(
-                        attrs.versioning_intent(),
-                        &attrs.task_queue,
-                    );
-                    self.add_cmd_to_wf_task(
-                        ActivityMachine::new_scheduled(
-                            attrs,
-                            self.observed_internal_flags.clone(),
-                            use_compat,
-                        ),
-                        cmd.metadata,
-                        CommandID::Activity(seq).into(),
-                    );
-                }
-                WFCommandVariant::AddLocalActivity(attrs) => {
-                    let seq = attrs.seq;
-                    let attrs: ValidScheduleLA =
-                        ValidScheduleLA::from_schedule_la(attrs).map_err(|e| {
-                            WFMachinesError::Fatal(format!(
-                                "Invalid schedule local activity request (seq {seq}): {e}"
-                            ))
-                        })?;
-                    let (la, mach_resp) = new_local_activity(
+            // Kept to describe the command in case it does not match the machines from history
+            let command = cmd.variant.to_string();
+            self.handle_driven_result(cmd).map_err(|e| {
+                e.with_nondeterminism_context(|diag| {
+                    diag.event_id = self.last_processed_event;
+                    diag.actual = command;
+                    diag.workflow_task_number = self.workflow_task_number;
+                })
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Handles a single command from the workflow activation, see [Self::handle_driven_results]
+    fn handle_driven_result(&mut self, cmd: WFCommand) -> Result<()> {
+        match cmd.variant {
+            WFCommandVariant::AddTimer(attrs) => {
+                let seq = attrs.seq;
+                self.add_cmd_to_wf_task(
+                    new_timer(attrs),
+                    cmd.metadata,
+                    CommandID::Timer(seq).into(),
+                );
+            }
+            WFCommandVariant::UpsertSearchAttributes(attrs) => {
+                self.drive_me
+                    .search_attributes_update(attrs.search_attributes.clone());
+                self.add_cmd_to_wf_task(
+                    upsert_search_attrs(
                         attrs,
+                        self.observed_internal_flags.clone(),
                         self.replaying,
-                        self.local_activity_data.take_preresolution(seq),
-                        self.current_wf_time,
+                    ),
+                    cmd.metadata,
+                    CommandIdKind::NeverResolves,
+                );
+            }
+            WFCommandVariant::CancelTimer(attrs) => {
+                cancel_machine!(self, CommandID::Timer(attrs.seq), TimerMachine, cancel);
+            }
+            WFCommandVariant::AddActivity(attrs) => {
+                let seq = attrs.seq;
+                let use_compat = self
+                    .determine_use_compatible_flag(attrs.versioning_intent(), &attrs.task_queue);
+                self.add_cmd_to_wf_task(
+                    ActivityMachine::new_scheduled(
+                        attrs,
                         self.observed_internal_flags.clone(),
-                    )?;
-                    let machkey = self.all_machines.insert(la.into());
-                    self.id_to_machine
-                        .insert(CommandID::LocalActivity(seq), machkey);
-                    self.process_machine_responses(machkey, mach_resp)?;
-                }
-                WFCommandVariant::RequestCancelActivity(attrs) => {
-                    cancel_machine!(
-                        self,
-                        CommandID::Activity(attrs.seq),
-                        ActivityMachine,
-                        cancel
-                    );
-                }
-                WFCommandVariant::RequestCancelLocalActivity(attrs) => {
-                    cancel_machine!(
-                        self,
-                        CommandID::LocalActivity(attrs.seq),
-                        LocalActivityMachine,
-                        cancel
-                    );
-                }
-                WFCommandVariant::CompleteWorkflow(attrs) => {
-                    self.add_terminal_command(complete_workflow(attrs), cmd.metadata);
-                }
-                WFCommandVariant::FailWorkflow(attrs) => {
-                    self.add_terminal_command(fail_workflow(attrs), cmd.metadata);
-                }
-                WFCommandVariant::ContinueAsNew(attrs) => {
-                    let attrs = self.augment_continue_as_new_with_current_values(attrs);
-                    let use_compat = self.determine_use_compatible_flag(
-                        attrs.versioning_intent(),
-                        &attrs.task_queue,
-                    );
-                    self.add_terminal_command(continue_as_new(attrs, use_compat), cmd.metadata);
-                }
-                WFCommandVariant::CancelWorkflow(attrs) => {
-                    self.add_terminal_command(cancel_workflow(attrs), cmd.metadata);
-                }
-                WFCommandVariant::SetPatchMarker(attrs) => {
-                    // Do not create commands for change IDs that we have already created commands
-                    // for.
-                    let encountered_entry = self.encountered_patch_markers.get(&attrs.patch_id);
-                    if !matches!(encountered_entry,
-                                 Some(ChangeInfo {created_command}) if *created_command)
-                    {
-                        let (patch_machine, other_cmds) = has_change(
-                            attrs.patch_id.clone(),
-                            self.replaying,
-                            attrs.deprecated,
-                            encountered_entry.is_some(),
-                            self.encountered_patch_markers.keys().map(|s| s.as_str()),
-                            self.observed_internal_flags.clone(),
-                        )?;
-                        let mkey = self.add_cmd_to_wf_task(
-                            patch_machine,
-                            cmd.metadata,
-                            CommandIdKind::NeverResolves,
-                        );
-                        self.process_machine_responses(mkey, other_cmds)?;
-
-                        if let Some(ci) = self.encountered_patch_markers.get_mut(&attrs.patch_id) {
-                            ci.created_command = true;
-                        } else {
-                            self.encountered_patch_markers.insert(
-                                attrs.patch_id,
-                                ChangeInfo {
-                                    created_command: true,
-                                },
-                            );
-                        }
-                    }
-                }
-                WFCommandVariant::AddChildWorkflow(attrs) => {
-                    let seq = attrs.seq;
-                    let use_compat = self.determine_use_compatible_flag(
-                        attrs.versioning_intent(),
-                        &attrs.task_queue,
-                    );
-                    self.add_cmd_to_wf_task(
-                        ChildWorkflowMachine::new_scheduled(
-                            attrs,
-                            self.observed_internal_flags.clone(),
-                            use_compat,
-                        ),
-                        cmd.metadata,
-                        CommandID::ChildWorkflowStart(seq).into(),
-                    );
-                }
-                WFCommandVariant::CancelChild(attrs) => {
-                    cancel_machine!(
-                        self,
-                        CommandID::ChildWorkflowStart(attrs.child_workflow_seq),
-                        ChildWorkflowMachine,
-                        cancel,
-                        attrs.reason
-                    );
-                }
-                WFCommandVariant::RequestCancelExternalWorkflow(attrs) => {
-                    let we = attrs.workflow_execution.ok_or_else(|| {
-                        WFMachinesError::Fatal(
-                            "Cancel external workflow command had no workflow_execution field"
-                                .to_string(),
-                        )
+                        use_compat,
+                    ),
+                    cmd.metadata,
+                    CommandID::Activity(seq).into(),
+                );
+            }
+            WFCommandVariant::AddLocalActivity(attrs) => {
+                let seq = attrs.seq;
+                let attrs: ValidScheduleLA =
+                    ValidScheduleLA::from_schedule_la(attrs).map_err(|e| {
+                        WFMachinesError::Fatal(format!(
+                            "Invalid schedule local activity request (seq {seq}): {e}"
+                        ))
                     })?;
-                    self.add_cmd_to_wf_task(
-                        new_external_cancel(
-                            attrs.seq,
-                            we,
-                            false,
-                            format!(
-                                "Cancel requested by workflow with run id {} with reason: {}",
-                                self.run_id, attrs.reason
-                            ),
-                        ),
-                        cmd.metadata,
-                        CommandID::CancelExternal(attrs.seq).into(),
-                    );
-                }
-                WFCommandVariant::SignalExternalWorkflow(attrs) => {
-                    let seq = attrs.seq;
-                    self.add_cmd_to_wf_task(
-                        new_external_signal(attrs, &self.worker_config.namespace)?,
-                        cmd.metadata,
-                        CommandID::SignalExternal(seq).into(),
-                    );
-                }
-                WFCommandVariant::CancelSignalWorkflow(attrs) => {
-                    cancel_machine!(
-                        self,
-                        CommandID::SignalExternal(attrs.seq),
-                        SignalExternalMachine,
-                        cancel
-                    );
-                }
-                WFCommandVariant::QueryResponse(_) => {
-                    // Nothing to do here, queries are handled above the machine level
-                    unimplemented!("Query responses should not make it down into the machines")
-                }
-                WFCommandVariant::ModifyWorkflowProperties(attrs) => {
-                    self.add_cmd_to_wf_task(
-                        modify_workflow_properties(attrs),
+                let (la, mach_resp) = new_local_activity(
+                    attrs,
+                    self.replaying,
+                    self.local_activity_data.take_preresolution(seq),
+                    self.current_wf_time,
+                    self.observed_internal_flags.clone(),
+                )?;
+                let machkey = self.all_machines.insert(la.into());
+                self.id_to_machine
+                    .insert(CommandID::LocalActivity(seq), machkey);
+                self.process_machine_responses(machkey, mach_resp)?;
+            }
+            WFCommandVariant::RequestCancelActivity(attrs) => {
+                cancel_machine!(
+                    self,
+                    CommandID::Activity(attrs.seq),
+                    ActivityMachine,
+                    cancel
+                );
+            }
+            WFCommandVariant::RequestCancelLocalActivity(attrs) => {
+                cancel_machine!(
+                    self,
+                    CommandID::LocalActivity(attrs.seq),
+                    LocalActivityMachine,
+                    cancel
+                );
+            }
+            WFCommandVariant::CompleteWorkflow(attrs) => {
+                self.add_terminal_command(complete_workflow(attrs), cmd.metadata);
+            }
+            WFCommandVariant::FailWorkflow(attrs) => {
+                self.add_terminal_command(fail_workflow(attrs), cmd.metadata);
+            }
+            WFCommandVariant::ContinueAsNew(attrs) => {
+                let attrs = self.augment_continue_as_new_with_current_values(attrs);
+                let use_compat = self
+                    .determine_use_compatible_flag(attrs.versioning_intent(), &attrs.task_queue);
+                self.add_terminal_command(continue_as_new(attrs, use_compat), cmd.metadata);
+            }
+            WFCommandVariant::CancelWorkflow(attrs) => {
+                self.add_terminal_command(cancel_workflow(attrs), cmd.metadata);
+            }
+            WFCommandVariant::SetPatchMarker(attrs) => {
+                // Do not create commands for change IDs that we have already created commands
+                // for.
+                let encountered_entry = self.encountered_patch_markers.get(&attrs.patch_id);
+                if !matches!(encountered_entry,
+                             Some(ChangeInfo {created_command}) if *created_command)
+                {
+                    let (patch_machine, other_cmds) = has_change(
+                        attrs.patch_id.clone(),
+                        self.replaying,
+                        attrs.deprecated,
+                        encountered_entry.is_some(),
+                        self.encountered_patch_markers.keys().map(|s| s.as_str()),
+                        self.observed_internal_flags.clone(),
+                    )?;
+                    let mkey = self.add_cmd_to_wf_task(
+                        patch_machine,
                         cmd.metadata,
                         CommandIdKind::NeverResolves,
                     );
-                }
-                WFCommandVariant::UpdateResponse(ur) => {
-                    let m_key = self.get_machine_by_msg(&ur.protocol_instance_id)?;
-                    let m = if let Machines::UpdateMachine(m) = self.machine_mut(m_key) {
-                        m
+                    self.process_machine_responses(mkey, other_cmds)?;
+
+                    if let Some(ci) = self.encountered_patch_markers.get_mut(&attrs.patch_id) {
+                        ci.created_command = true;
                     } else {
-                        return Err(WFMachinesError::Nondeterminism(format!(
-                            "Tried to handle an update response for \
-                             update with instance id {} but it was not found!",
-                            &ur.protocol_instance_id
-                        )));
-                    };
-                    let resps = m.handle_response(ur)?;
-                    self.process_machine_responses(m_key, resps)?;
-                }
-                WFCommandVariant::ScheduleNexusOperation(attrs) => {
-                    let seq = attrs.seq;
-                    self.add_cmd_to_wf_task(
-                        NexusOperationMachine::new_scheduled(attrs),
-                        cmd.metadata,
-                        CommandID::NexusOperation(seq).into(),
-                    );
-                }
-                WFCommandVariant::RequestCancelNexusOperation(attrs) => {
-                    cancel_machine!(
-                        self,
-                        CommandID::NexusOperation(attrs.seq),
-                        NexusOperationMachine,
-                        cancel
-                    );
+                        self.encountered_patch_markers.insert(
+                            attrs.patch_id,
+                            ChangeInfo {
+                                created_command: true,
+                            },
+                        );
+                    }
                 }
-                WFCommandVariant::NoCommandsFromLang => (),
             }
+            WFCommandVariant::AddChildWorkflow(attrs) => {
+                let seq = attrs.seq;
+                let use_compat = self
+                    .determine_use_compatible_flag(attrs.versioning_intent(), &attrs.task_queue);
+                self.add_cmd_to_wf_task(
+                    ChildWorkflowMachine::new_scheduled(
+                        attrs,
+                        self.observed_internal_flags.clone(),
+                        use_compat,
+                    ),
+                    cmd.metadata,
+                    CommandID::ChildWorkflowStart(seq).into(),
+                );
+            }
+            WFCommandVariant::CancelChild(attrs) => {
+                cancel_machine!(
+                    self,
+                    CommandID::ChildWorkflowStart(attrs.child_workflow_seq),
+                    ChildWorkflowMachine,
+                    cancel,
+                    attrs.reason
+                );
+            }
+            WFCommandVariant::RequestCancelExternalWorkflow(attrs) => {
+                let we = attrs.workflow_execution.ok_or_else(|| {
+                    WFMachinesError::Fatal(
+                        "Cancel external workflow command had no workflow_execution field"
+                            .to_string(),
+                    )
+                })?;
+                self.add_cmd_to_wf_task(
+                    new_external_cancel(
+                        attrs.seq,
+                        we,
+                        false,
+                        format!(
+                            "Cancel requested by workflow with run id {} with reason: {}",
+                            self.run_id, attrs.reason
+                        ),
+                    ),
+                    cmd.metadata,
+                    CommandID::CancelExternal(attrs.seq).into(),
+                );
+            }
+            WFCommandVariant::SignalExternalWorkflow(attrs) => {
+                let seq = attrs.seq;
+                self.add_cmd_to_wf_task(
+                    new_external_signal(attrs, &self.worker_config.namespace)?,
+                    cmd.metadata,
+                    CommandID::SignalExternal(seq).into(),
+                );
+            }
+            WFCommandVariant::CancelSignalWorkflow(attrs) => {
+                cancel_machine!(
+                    self,
+                    CommandID::SignalExternal(attrs.seq),
+                    SignalExternalMachine,
+                    cancel
+                );
+            }
+            WFCommandVariant::QueryResponse(_) => {
+                // Nothing to do here, queries are handled above the machine level
+                unimplemented!("Query responses should not make it down into the machines")
+            }
+            WFCommandVariant::ModifyWorkflowProperties(attrs) => {
+                self.add_cmd_to_wf_task(
+                    modify_workflow_properties(attrs),
+                    cmd.metadata,
+                    CommandIdKind::NeverResolves,
+                );
+            }
+            WFCommandVariant::UpdateResponse(ur) => {
+                let m_key = self.get_machine_by_msg(&ur.protocol_instance_id)?;
+                let m = if let Machines::UpdateMachine(m) = self.machine_mut(m_key) {
+                    m
+                } else {
+                    return Err(WFMachinesError::nondeterminism(format!(
+                        "Tried to handle an update response for \
+                         update with instance id {} but it was not found!",
+                        &ur.protocol_instance_id
+                    )));
+                };
+                let resps = m.handle_response(ur)?;
+                self.process_machine_responses(m_key, resps)?;
+            }
+            WFCommandVariant::ScheduleNexusOperation(attrs) => {
+                let seq = attrs.seq;
+                self.add_cmd_to_wf_task(
+                    NexusOperationMachine::new_scheduled(attrs),
+                    cmd.metadata,
+                    CommandID::NexusOperation(seq).into(),
+                );
+            }
+            WFCommandVariant::RequestCancelNexusOperation(attrs) => {
+                cancel_machine!(
+                    self,
+                    CommandID::NexusOperation(attrs.seq),
+                    NexusOperationMachine,
+                    cancel
+                );
+            }
+            WFCommandVariant::NoCommandsFromLang => (),
         }
         Ok(())
     }
 
     fn get_machine_key(&self, id: CommandID) -> Result<MachineKey> {
         Ok(*self.id_to_machine.get(&id).ok_or_else(|| {
-            WFMachinesError::Nondeterminism(format!("Missing associated machine for {id:?}"))
+            WFMachinesError::nondeterminism(format!("Missing associated machine for {id:?}"))
         })?)
     }
 
@@ -1735,7 +1810,7 @@ fn patch_marker_handling(
                 debug!("Deprecated patch marker tried against non-patch machine, skipping.");
                 skip_one_or_two_events(next_event)
             } else {
-                Err(WFMachinesError::Nondeterminism(format!(
+                Err(WFMachinesError::nondeterminism(format!(
                     "Non-deprecated patch marker encountered for change {patch_name}, but there is \
                      no corresponding change command!"
                 )))
@@ -141,7 +141,7 @@ impl TryFrom<HistEventData> for WorkflowTaskMachineEvents {
                 }
             }
             _ => {
-                return Err(WFMachinesError::Nondeterminism(format!(
+                return Err(WFMachinesError::nondeterminism(format!(
                     "Event does not apply to a wf task machine: {e}"
                 )));
             }
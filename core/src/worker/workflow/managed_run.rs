@@ -6,6 +6,9 @@ use crate::{
     telemetry::metrics,
     worker::{
         LEGACY_QUERY_ID, LocalActRequest,
+        debug_snapshot::{
+            CachedRunDebugSnapshot, OutstandingWftDebugSnapshot, task_token_fingerprint,
+        },
         workflow::{
             ActivationAction, ActivationCompleteOutcome, ActivationCompleteResult,
             ActivationOrAuto, BufferedTasks, DrivenWorkflow, EvictionRequestResult,
@@ -16,10 +19,12 @@ use crate::{
             WFT_HEARTBEAT_TIMEOUT_FRACTION, WFTReportStatus, WorkflowTaskInfo,
             history_update::HistoryPaginator,
             machines::{MachinesWFTResponseContent, WorkflowMachines},
+            run_cache::partition_name,
         },
     },
 };
 use futures_util::future::AbortHandle;
+use prost::Message;
 use std::{
     collections::HashSet,
     mem,
@@ -33,7 +38,7 @@ use temporal_sdk_core_protos::{
     TaskToken,
     coresdk::{
         workflow_activation::{
-            WorkflowActivation, create_evict_activation, query_to_job,
+            NondeterminismDiagnostic, WorkflowActivation, create_evict_activation, query_to_job,
             remove_from_cache::EvictionReason, workflow_activation_job,
         },
         workflow_commands::{FailWorkflowExecution, QueryResult},
@@ -41,7 +46,7 @@ use temporal_sdk_core_protos::{
     },
     temporal::api::{
         command::v1::command::Attributes as CmdAttribs, enums::v1::WorkflowTaskFailedCause,
-        failure::v1::Failure,
+        failure::v1::Failure, history::v1::HistoryEvent,
     },
 };
 use tokio::sync::oneshot;
@@ -89,6 +94,12 @@ pub(super) struct ManagedRun {
     task_buffer: BufferedTasks,
     /// Is set if an eviction has been requested for this run
     trying_to_evict: Option<RequestEvictMsg>,
+    /// Is set to true if lang failed to complete an activation for this run before the activation
+    /// deadline. The run is doomed to be evicted once this happens.
+    deadlock_detected: bool,
+    /// Is set if the machines encountered nondeterminism, so the details can be given to lang
+    /// when the run is evicted.
+    nondeterminism: Option<NondeterminismDiagnostic>,
 
     /// We track if we have recorded useful debugging values onto a certain span yet, to overcome
     /// duplicating field values. Remove this once https://github.com/tokio-rs/tracing/issues/2334
@@ -118,6 +129,8 @@ impl ManagedRun {
             activation: None,
             task_buffer: Default::default(),
             trying_to_evict: None,
+            deadlock_detected: false,
+            nondeterminism: None,
             recorded_span_ids: Default::default(),
             metrics,
             paginator: None,
@@ -156,6 +169,12 @@ impl ManagedRun {
         self.activation.as_ref()
     }
 
+    /// Returns true if lang has failed to complete an activation for this run before the activation
+    /// deadline
+    pub(super) fn deadlock_detected(&self) -> bool {
+        self.deadlock_detected
+    }
+
     /// Returns this run's eviction reason if it is going to be evicted
     pub(super) fn trying_to_evict(&self) -> Option<&RequestEvictMsg> {
         self.trying_to_evict.as_ref()
@@ -233,7 +252,10 @@ impl ManagedRun {
 
         // The update field is only populated in the event we hit the cache
         let activation = if work.update.is_real() {
-            self.metrics.sticky_cache_hit();
+            let partition = partition_name(&self.config, self.workflow_type());
+            self.metrics
+                .with_new_attrs([metrics::cache_partition(partition)])
+                .sticky_cache_hit();
             self.wfm.new_work_from_server(work.update, work.messages)?
         } else {
             let r = self.wfm.get_next_activation()?;
@@ -352,9 +374,8 @@ impl ManagedRun {
                     )));
                 }
             }
-            if let Some(wte) = self.trying_to_evict.clone() {
-                let act =
-                    create_evict_activation(self.run_id().to_string(), wte.message, wte.reason);
+            if let Some(wte) = self.trying_to_evict.as_ref() {
+                let act = self.create_evict_activation(wte);
                 Ok(Some(ActivationOrAuto::LangActivation(act)))
             } else {
                 Ok(None)
@@ -818,6 +839,34 @@ impl ManagedRun {
         false
     }
 
+    /// Called when lang has not completed the outstanding activation within the configured
+    /// activation deadline. Lang's eventual completion of the activation (if any) is dropped before
+    /// it gets here, so the activation is auto-failed instead, which will evict the run.
+    pub(super) fn activation_deadline_elapsed(&mut self) -> RunUpdateAct {
+        if !matches!(
+            self.activation,
+            Some(OutstandingActivation::Normal | OutstandingActivation::LegacyQuery)
+        ) {
+            debug!(run_id=%self.run_id(),
+                   "Activation deadline elapsed, but there is no outstanding lang activation");
+            return None;
+        }
+        let deadline = self.config.workflow_activation_deadline.unwrap_or_default();
+        warn!(run_id=%self.run_id(), deadline=?deadline,
+              "Workflow activation was not completed before the activation deadline");
+        self.metrics.wf_activation_deadlock();
+        self.deadlock_detected = true;
+        self.activation = None;
+        let maybe_act = Some(ActivationOrAuto::AutoFail {
+            run_id: self.run_id().to_string(),
+            machines_err: WFMachinesError::Deadlock(format!(
+                "Workflow activation was not completed within {deadline:?}. The workflow code is \
+                 likely blocking or busy-looping without yielding."
+            )),
+        });
+        self.update_to_acts(Ok(maybe_act.into()))
+    }
+
     /// Returns true if the managed run has any form of pending work
     /// If `ignore_evicts` is true, pending evictions do not count as pending work.
     /// If `ignore_buffered` is true, buffered workflow tasks do not count as pending work.
@@ -867,6 +916,40 @@ impl ManagedRun {
         self.task_buffer.has_tasks()
     }
 
+    pub(super) fn workflow_type(&self) -> &str {
+        &self.wfm.machines.workflow_type
+    }
+
+    pub(super) fn debug_snapshot(&self) -> CachedRunDebugSnapshot {
+        CachedRunDebugSnapshot {
+            run_id: self.wfm.machines.run_id.clone(),
+            workflow_id: self.wfm.machines.workflow_id.clone(),
+            workflow_type: self.wfm.machines.workflow_type.clone(),
+            last_event_id: self.wfm.machines.last_processed_event,
+            pending_activation: self.activation.map(|a| format!("{a:?}")),
+            outstanding_wft: self.wft.as_ref().map(|wft| OutstandingWftDebugSnapshot {
+                task_token: task_token_fingerprint(&wft.info.task_token),
+                attempt: wft.info.attempt,
+                age: wft.start_time.elapsed(),
+                pending_queries: wft.pending_queries.len(),
+            }),
+            has_buffered_wft: self.has_buffered_wft(),
+            waiting_on_local_activities: self.waiting_on_la.is_some(),
+            eviction_requested: self.trying_to_evict.as_ref().map(|e| e.message.clone()),
+        }
+    }
+
+    /// A rough estimate of the memory this run retains, in bytes
+    pub(super) fn estimated_size(&self) -> usize {
+        mem::size_of::<Self>() + self.wfm.machines.estimated_size() + self.wfm.seen_history_bytes
+    }
+
+    /// Takes the events of this run's history seen so far, if they're being kept and are complete
+    pub(super) fn take_seen_history(&mut self) -> Option<Vec<HistoryEvent>> {
+        self.wfm.seen_history_bytes = 0;
+        self.wfm.seen_history.take()
+    }
+
     pub(super) fn request_eviction(&mut self, info: RequestEvictMsg) -> EvictionRequestResult {
         let attempts = self.wft.as_ref().map(|wt| wt.info.attempt);
 
@@ -967,11 +1050,7 @@ impl ManagedRun {
                             // If we had nothing to do, but we're trying to evict, just do that now
                             // as long as there's no other outstanding work.
                             if self.activation.is_none() && !self.more_pending_work() {
-                                let mut evict_act = create_evict_activation(
-                                    self.run_id().to_string(),
-                                    reason.message.clone(),
-                                    reason.reason,
-                                );
+                                let mut evict_act = self.create_evict_activation(reason);
                                 evict_act.history_length =
                                     self.most_recently_processed_event_number() as u32;
                                 Some(ActivationOrAuto::LangActivation(evict_act))
@@ -1007,6 +1086,13 @@ impl ManagedRun {
             }
             Err(fail) => {
                 self.am_broken = true;
+                if let Some(diag) = fail.source.nondeterminism_diagnostic() {
+                    let mut diag = diag.clone();
+                    if diag.workflow_task_number == 0 {
+                        diag.workflow_task_number = self.wfm.machines.workflow_task_number();
+                    }
+                    self.nondeterminism = Some(diag);
+                }
                 let rur = if let Some(resp_chan) = fail.complete_resp_chan {
                     // Automatically fail the workflow task in the event we couldn't update machines
                     let fail_cause = if matches!(&fail.source, WFMachinesError::Nondeterminism(_)) {
@@ -1033,6 +1119,26 @@ impl ManagedRun {
         }
     }
 
+    /// Creates an eviction activation for this run, attaching details about any nondeterminism
+    /// that broke it.
+    fn create_evict_activation(&self, evict: &RequestEvictMsg) -> WorkflowActivation {
+        let mut act = create_evict_activation(
+            self.run_id().to_string(),
+            evict.message.clone(),
+            evict.reason,
+        );
+        if let Some(diag) = self.nondeterminism.as_ref() {
+            for job in act.jobs.iter_mut() {
+                if let Some(workflow_activation_job::Variant::RemoveFromCache(rfc)) =
+                    job.variant.as_mut()
+                {
+                    rfc.nondeterminism = Some(diag.clone());
+                }
+            }
+        }
+        act
+    }
+
     fn insert_outstanding_activation(&mut self, act: &ActivationOrAuto) {
         let act_type = match &act {
             ActivationOrAuto::LangActivation(act) | ActivationOrAuto::ReadyForQueries(act) => {
@@ -1148,6 +1254,7 @@ impl ManagedRun {
             result: ActivationCompleteResult {
                 outcome,
                 replaying: machines_wft_response.replaying,
+                nondeterminism: self.nondeterminism.clone(),
             },
             resp_chan,
         }
@@ -1177,6 +1284,7 @@ impl ManagedRun {
                 .send(ActivationCompleteResult {
                     outcome,
                     replaying: self.wfm.machines.replaying,
+                    nondeterminism: self.nondeterminism.clone(),
                 })
                 .is_err()
             {
@@ -1346,17 +1454,30 @@ struct WorkflowManager {
     /// Is always `Some` in normal operation. Optional to allow for unit testing with the test
     /// workflow driver, which does not need to complete activations the normal way.
     command_sink: Option<Sender<Vec<WFCommand>>>,
+    /// Every event of the run's history seen so far, kept only when the worker retains the
+    /// history of evicted runs. Becomes `None` if the events seen stop being contiguous.
+    seen_history: Option<Vec<HistoryEvent>>,
+    /// Estimated memory held by `seen_history`, kept up to date as events are recorded so that
+    /// estimating the run's size doesn't need to walk its whole history
+    seen_history_bytes: usize,
 }
 
 impl WorkflowManager {
     /// Create a new workflow manager given workflow history and execution info as would be found
     /// in [PollWorkflowTaskQueueResponse]
     fn new(basics: RunBasics) -> Self {
+        let seen_history = basics
+            .worker_config
+            .evicted_history_retention
+            .is_some()
+            .then(Vec::new);
         let (wfb, cmd_sink) = DrivenWorkflow::new();
         let state_machines = WorkflowMachines::new(basics, wfb);
         Self {
             machines: state_machines,
             command_sink: Some(cmd_sink),
+            seen_history,
+            seen_history_bytes: 0,
         }
     }
 
@@ -1370,6 +1491,7 @@ impl WorkflowManager {
         update: HistoryUpdate,
         messages: Vec<IncomingProtocolMessage>,
     ) -> Result<WorkflowActivation> {
+        self.record_seen_history(&update);
         self.machines.new_work_from_server(update, messages)?;
         self.get_next_activation()
     }
@@ -1377,9 +1499,29 @@ impl WorkflowManager {
     /// Update the machines with some events from fetching another page of history. Does *not*
     /// attempt to pull the next activation, unlike [Self::new_work_from_server].
     fn feed_history_from_new_page(&mut self, update: HistoryUpdate) -> Result<()> {
+        self.record_seen_history(&update);
         self.machines.new_history_from_server(update)
     }
 
+    fn record_seen_history(&mut self, update: &HistoryUpdate) {
+        let Some(seen) = self.seen_history.as_mut() else {
+            return;
+        };
+        for event in update.get_events() {
+            let last_seen = seen.last().map(|e| e.event_id).unwrap_or_default();
+            if event.event_id <= last_seen {
+                continue;
+            }
+            if event.event_id != last_seen + 1 {
+                self.seen_history = None;
+                self.seen_history_bytes = 0;
+                return;
+            }
+            self.seen_history_bytes += mem::size_of::<HistoryEvent>() + event.encoded_len();
+            seen.push(event.clone());
+        }
+    }
+
     /// Let this workflow know that something we've been waiting locally on has resolved, like a
     /// local activity or side effect
     ///
@@ -3,6 +3,7 @@
 //! a diagram of the internals.
 
 mod driven_workflow;
+mod evicted_history;
 mod history_update;
 mod machines;
 mod managed_run;
@@ -13,6 +14,8 @@ mod workflow_stream;
 
 pub(crate) use driven_workflow::DrivenWorkflow;
 pub(crate) use history_update::HistoryUpdate;
+#[cfg(feature = "save_wf_inputs")]
+pub use workflow_stream::replay_wf_state_inputs;
 
 use crate::{
     MetricsContext,
@@ -29,6 +32,7 @@ use crate::{
         PostActivateHookData,
         activities::{ActivitiesFromWFTsHandle, LocalActivityManager},
         client::{WorkerClient, WorkflowTaskCompletion},
+        debug_snapshot::{DEBUG_SNAPSHOT_TIMEOUT, WorkflowsDebugSnapshot},
         workflow::{
             history_update::HistoryPaginator,
             managed_run::RunUpdateAct,
@@ -45,14 +49,17 @@ use prost_types::TimestampError;
 use rustfsm::MachineError;
 use std::{
     cell::RefCell,
-    collections::VecDeque,
+    collections::{HashMap, VecDeque},
     fmt::Debug,
     future::Future,
     mem,
     ops::DerefMut,
     rc::Rc,
     result,
-    sync::{Arc, atomic, atomic::AtomicBool},
+    sync::{
+        Arc, atomic,
+        atomic::{AtomicBool, AtomicU64},
+    },
     thread,
     time::{Duration, Instant},
 };
@@ -64,7 +71,7 @@ use temporal_sdk_core_protos::{
     TaskToken,
     coresdk::{
         workflow_activation::{
-            QueryWorkflow, WorkflowActivation, WorkflowActivationJob,
+            NondeterminismDiagnostic, QueryWorkflow, WorkflowActivation, WorkflowActivationJob,
             remove_from_cache::EvictionReason, workflow_activation_job,
         },
         workflow_commands::*,
@@ -89,11 +96,12 @@ use tokio::{
         mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel},
         oneshot,
     },
-    task::{LocalSet, spawn_blocking},
+    task::{AbortHandle, LocalSet, spawn_blocking},
+    time::error::Elapsed,
 };
 use tokio_stream::wrappers::UnboundedReceiverStream;
 use tokio_util::sync::CancellationToken;
-use tracing::Span;
+use tracing::{Span, Subscriber};
 
 pub(crate) const LEGACY_QUERY_ID: &str = "legacy_query";
 /// What percentage of a WFT timeout we are willing to wait before sending a WFT heartbeat when
@@ -105,11 +113,20 @@ type Result<T, E = WFMachinesError> = result::Result<T, E>;
 type BoxedActivationStream = BoxStream<'static, Result<ActivationOrAuto, PollError>>;
 type InternalFlagsRef = Rc<RefCell<InternalFlags>>;
 
+#[cfg(test)]
+thread_local! {
+    /// When set, workers created on this thread process workflows as a task on the current
+    /// `LocalSet` rather than on their own thread, so that processing shares the test's runtime,
+    /// including its paused clock and scheduling
+    pub(crate) static PROCESS_WORKFLOWS_LOCALLY: std::cell::Cell<bool> =
+        const { std::cell::Cell::new(false) };
+}
+
 /// Centralizes all state related to workflows and workflow tasks
 pub(crate) struct Workflows {
     task_queue: String,
     local_tx: UnboundedSender<LocalInput>,
-    processing_task: TakeCell<thread::JoinHandle<()>>,
+    processing_task: TakeCell<ProcessingTask>,
     activation_stream: tokio::sync::Mutex<(
         BoxedActivationStream,
         // Used to indicate polling may begin
@@ -125,6 +142,38 @@ pub(crate) struct Workflows {
     wft_semaphore: MeteredPermitDealer<WorkflowSlotKind>,
     local_act_mgr: Arc<LocalActivityManager>,
     ever_polled: AtomicBool,
+    /// Set if activations lang does not complete within a deadline are considered deadlocked
+    activation_deadlines: Option<Arc<ActivationDeadlines>>,
+}
+
+/// Whatever is running the workflow stream
+enum ProcessingTask {
+    Thread(thread::JoinHandle<()>),
+    #[cfg(test)]
+    Local(tokio::task::JoinHandle<()>),
+}
+
+impl ProcessingTask {
+    /// Runs processing on its own thread, with its own runtime
+    fn spawn_thread<F: Future<Output = ()>>(
+        process: impl FnOnce() -> F + Send + 'static,
+        tracing_sub: Option<Arc<dyn Subscriber + Send + Sync>>,
+    ) -> Self {
+        let thread = thread::Builder::new()
+            .name("workflow-processing".to_string())
+            .spawn(move || {
+                if let Some(ts) = tracing_sub {
+                    set_trace_subscriber_for_current_thread(ts);
+                }
+                let rt = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .unwrap();
+                LocalSet::new().block_on(&rt, process());
+            })
+            .expect("Must be able to spawn workflow processing thread");
+        Self::Thread(thread)
+    }
 }
 
 pub(crate) struct WorkflowBasics {
@@ -166,6 +215,10 @@ impl Workflows {
         let (fetch_tx, fetch_rx) = unbounded_channel();
         let shutdown_tok = basics.shutdown_token.clone();
         let task_queue = basics.worker_config.task_queue.clone();
+        let activation_deadlines = basics
+            .worker_config
+            .workflow_activation_deadline
+            .map(|deadline| Arc::new(ActivationDeadlines::new(deadline)));
         let extracted_wft_stream = WFTExtractor::build(
             client.clone(),
             basics.worker_config.fetching_concurrency,
@@ -181,62 +234,58 @@ impl Workflows {
         // We must spawn a task to constantly poll the activation stream, because otherwise
         // activation completions would not cause anything to happen until the next poll.
         let tracing_sub = telem_instance.and_then(|ti| ti.trace_subscriber());
-        let processing_task = thread::Builder::new()
-            .name("workflow-processing".to_string())
-            .spawn(move || {
-                if let Some(ts) = tracing_sub {
-                    set_trace_subscriber_for_current_thread(ts);
-                }
-                let rt = tokio::runtime::Builder::new_current_thread()
-                    .enable_all()
-                    .build()
-                    .unwrap();
-                let local = LocalSet::new();
-                local.block_on(&rt, async move {
-                    let mut stream = WFStream::build(
-                        basics,
-                        extracted_wft_stream,
-                        locals_stream,
-                        local_activity_request_sink,
-                    );
+        // The stream isn't Send, so it is built wherever processing runs
+        let process = move || async move {
+            let mut stream = WFStream::build(
+                basics,
+                extracted_wft_stream,
+                locals_stream,
+                local_activity_request_sink,
+            );
 
-                    // However, we want to avoid plowing ahead until we've been asked to poll at
-                    // least once. This supports activity-only workers.
-                    let do_poll = tokio::select! {
-                        sp = start_polling_rx => {
-                            sp.is_ok()
+            // However, we want to avoid plowing ahead until we've been asked to poll at
+            // least once. This supports activity-only workers.
+            let do_poll = tokio::select! {
+                sp = start_polling_rx => {
+                    sp.is_ok()
+                }
+                _ = shutdown_tok.cancelled() => {
+                    false
+                }
+            };
+            if !do_poll {
+                return;
+            }
+            while let Some(output) = stream.next().await {
+                match output {
+                    Ok(o) => {
+                        for fetchreq in o.fetch_histories {
+                            fetch_tx
+                                .send(fetchreq)
+                                .expect("Fetch channel must not be dropped");
                         }
-                        _ = shutdown_tok.cancelled() => {
-                            false
+                        for act in o.activations {
+                            activation_tx
+                                .send(Ok(act))
+                                .expect("Activation processor channel not dropped");
                         }
-                    };
-                    if !do_poll {
-                        return;
                     }
-                    while let Some(output) = stream.next().await {
-                        match output {
-                            Ok(o) => {
-                                for fetchreq in o.fetch_histories {
-                                    fetch_tx
-                                        .send(fetchreq)
-                                        .expect("Fetch channel must not be dropped");
-                                }
-                                for act in o.activations {
-                                    activation_tx
-                                        .send(Ok(act))
-                                        .expect("Activation processor channel not dropped");
-                                }
-                            }
-                            Err(e) => {
-                                let _ = activation_tx.send(Err(e)).inspect_err(|e| {
-                                    error!(activation=?e.0, "Activation processor channel dropped");
-                                });
-                            }
-                        }
+                    Err(e) => {
+                        let _ = activation_tx.send(Err(e)).inspect_err(|e| {
+                            error!(activation=?e.0, "Activation processor channel dropped");
+                        });
                     }
-                });
-            })
-            .expect("Must be able to spawn workflow processing thread");
+                }
+            }
+        };
+        #[cfg(test)]
+        let processing_task = if PROCESS_WORKFLOWS_LOCALLY.get() {
+            ProcessingTask::Local(tokio::task::spawn_local(process()))
+        } else {
+            ProcessingTask::spawn_thread(process, tracing_sub)
+        };
+        #[cfg(not(test))]
+        let processing_task = ProcessingTask::spawn_thread(process, tracing_sub);
         Self {
             task_queue,
             local_tx,
@@ -251,6 +300,7 @@ impl Workflows {
             wft_semaphore,
             local_act_mgr,
             ever_polled: AtomicBool::new(false),
+            activation_deadlines,
         }
     }
 
@@ -269,6 +319,14 @@ impl Workflows {
                 ActivationOrAuto::LangActivation(mut act)
                 | ActivationOrAuto::ReadyForQueries(mut act) => {
                     prepare_to_ship_activation(&mut act);
+                    if let Some(deadlines) = &self.activation_deadlines {
+                        let eviction_only = act.is_only_eviction();
+                        let id = deadlines.issue(&act.run_id, eviction_only);
+                        if !eviction_only {
+                            let timer = self.start_activation_deadline(deadlines, &act.run_id, id);
+                            deadlines.set_timer(&act.run_id, id, timer);
+                        }
+                    }
                     debug!(activation=%act, "Sending activation to lang");
                     break Ok(act);
                 }
@@ -324,6 +382,16 @@ impl Workflows {
         post_activate_hook: Option<impl Fn(PostActivateHookData)>,
     ) -> Result<(), CompleteWfError> {
         let is_empty_completion = completion.is_empty();
+        if !is_autocomplete
+            && self
+                .activation_deadlines
+                .as_ref()
+                .is_some_and(|d| d.complete(&completion.run_id, is_empty_completion))
+        {
+            warn!(run_id=%completion.run_id,
+                  "Dropping completion of an activation which exceeded the activation deadline");
+            return Ok(());
+        }
         let completion = validate_completion(completion, is_autocomplete)?;
         let run_id = completion.run_id().to_string();
         let (tx, rx) = oneshot::channel();
@@ -355,6 +423,10 @@ impl Workflows {
         };
 
         let mut wft_from_complete = None;
+        let reported_wft_failure = matches!(
+            completion_outcome.outcome,
+            ActivationCompleteOutcome::ReportWFTFail(FailedActivationWFTReport::Report(..))
+        );
         let wft_report_status = match completion_outcome.outcome {
             ActivationCompleteOutcome::ReportWFTSuccess(report) => match report {
                 ServerCommandsWithWorkflowInfo {
@@ -470,6 +542,8 @@ impl Workflows {
             h(PostActivateHookData {
                 run_id: &run_id,
                 replaying: completion_outcome.replaying,
+                reported_wft_failure,
+                nondeterminism: completion_outcome.nondeterminism.as_ref(),
             });
         }
 
@@ -483,6 +557,30 @@ impl Workflows {
         Ok(())
     }
 
+    /// Starts a timer which will fail the activation with the provided id, just handed to lang for
+    /// the provided run, if it is not completed before the deadline elapses.
+    fn start_activation_deadline(
+        &self,
+        deadlines: &Arc<ActivationDeadlines>,
+        run_id: &str,
+        id: u64,
+    ) -> AbortHandle {
+        let deadlines = deadlines.clone();
+        let run_id = run_id.to_string();
+        let local_tx = self.local_tx.clone();
+        let span = Span::current();
+        tokio::spawn(async move {
+            tokio::time::sleep(deadlines.deadline).await;
+            if deadlines.elapse(&run_id, id) {
+                let _ = local_tx.send(LocalInput {
+                    input: ActivationDeadlineMsg { run_id }.into(),
+                    span,
+                });
+            }
+        })
+        .abort_handle()
+    }
+
     /// Tell workflow that a local activity has finished with the provided result
     pub(super) fn notify_of_local_result(
         &self,
@@ -513,9 +611,7 @@ impl Workflows {
     /// Send a `GetStateInfoMsg` to the workflow stream. Can be used to bump the stream if there
     /// would otherwise be no new inputs.
     pub(super) fn send_get_state_info_msg(&self) -> oneshot::Receiver<WorkflowStateInfo> {
-        let (tx, rx) = oneshot::channel();
-        self.send_local(GetStateInfoMsg { response_tx: tx });
-        rx
+        self.send_state_info_req(false)
     }
 
     /// Query the state of workflow management. Can return `None` if workflow state is shut down.
@@ -524,6 +620,25 @@ impl Workflows {
         async move { rx.await.ok() }
     }
 
+    /// Snapshot the state of all cached runs and buffered polls. Can return `None` if workflow
+    /// state is shut down, and errors if the stream doesn't respond within
+    /// [DEBUG_SNAPSHOT_TIMEOUT], since snapshots are most useful when something is stuck.
+    pub(super) async fn debug_snapshot(&self) -> Result<Option<WorkflowsDebugSnapshot>, Elapsed> {
+        let rx = self.send_state_info_req(true);
+        tokio::time::timeout(DEBUG_SNAPSHOT_TIMEOUT, rx)
+            .await
+            .map(|info| info.ok().and_then(|info| info.snapshot))
+    }
+
+    fn send_state_info_req(&self, with_snapshot: bool) -> oneshot::Receiver<WorkflowStateInfo> {
+        let (tx, rx) = oneshot::channel();
+        self.send_local(GetStateInfoMsg {
+            response_tx: tx,
+            with_snapshot,
+        });
+        rx
+    }
+
     pub(super) fn available_wft_permits(&self) -> Option<usize> {
         self.wft_semaphore.available_permits()
     }
@@ -543,18 +658,25 @@ impl Workflows {
                     let _ = self.get_state_info().await;
                 }
             });
-            let (_, jh_res) = tokio::join!(
-                waker,
-                spawn_blocking(move || {
-                    let r = jh.join();
-                    stop_waker.abort();
-                    r
-                })
-            );
-            jh_res?.map_err(|e| {
-                let as_str = e.downcast::<&str>();
-                anyhow!("Error joining workflow processing thread: {as_str:?}")
-            })?;
+            let joined = async move {
+                let res = match jh {
+                    ProcessingTask::Thread(jh) => spawn_blocking(move || jh.join())
+                        .await
+                        .map_err(Into::into)
+                        .and_then(|r| {
+                            r.map_err(|e| {
+                                let as_str = e.downcast::<&str>();
+                                anyhow!("Error joining workflow processing thread: {as_str:?}")
+                            })
+                        }),
+                    #[cfg(test)]
+                    ProcessingTask::Local(jh) => jh.await.map_err(Into::into),
+                };
+                stop_waker.abort();
+                res
+            };
+            let (_, res) = tokio::join!(waker, joined);
+            res?;
         }
         Ok(())
     }
@@ -773,13 +895,25 @@ enum ActivationOrAuto {
 /// applied to workflow state.
 #[derive(derive_more::Debug)]
 #[debug("PermittedWft({work:?})")]
+#[cfg_attr(
+    feature = "save_wf_inputs",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub(crate) struct PermittedWFT {
     work: PreparedWFT,
+    #[cfg_attr(
+        feature = "save_wf_inputs",
+        serde(skip, default = "UsedMeteredSemPermit::fake_deserialized")
+    )]
     permit: UsedMeteredSemPermit<WorkflowSlotKind>,
     paginator: HistoryPaginator,
 }
 /// A WFT without a permit
 #[derive(Debug)]
+#[cfg_attr(
+    feature = "save_wf_inputs",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 struct WFTWithPaginator {
     wft: PreparedWFT,
     paginator: HistoryPaginator,
@@ -787,6 +921,10 @@ struct WFTWithPaginator {
 
 /// A WFT which has been validated and had a history update extracted from it.
 #[derive(Debug)]
+#[cfg_attr(
+    feature = "save_wf_inputs",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 struct PreparedWFT {
     task_token: TaskToken,
     attempt: u32,
@@ -916,19 +1054,33 @@ impl EvictionRequestResult {
 pub(crate) struct WorkflowStateInfo {
     pub(crate) cached_workflows: usize,
     pub(crate) outstanding_wft: usize,
+    pub(crate) snapshot: Option<WorkflowsDebugSnapshot>,
 }
 
 #[derive(Debug)]
+#[cfg_attr(
+    feature = "save_wf_inputs",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 struct WFActCompleteMsg {
     completion: ValidatedCompletion,
+    #[cfg_attr(feature = "save_wf_inputs", serde(skip))]
     response_tx: Option<oneshot::Sender<ActivationCompleteResult>>,
 }
 #[derive(Debug)]
+#[cfg_attr(
+    feature = "save_wf_inputs",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 struct LocalResolutionMsg {
     run_id: String,
     res: LocalResolution,
 }
 #[derive(Debug)]
+#[cfg_attr(
+    feature = "save_wf_inputs",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 struct PostActivationMsg {
     run_id: String,
     wft_report_status: WFTReportStatus,
@@ -936,6 +1088,10 @@ struct PostActivationMsg {
     is_autocomplete: bool,
 }
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "save_wf_inputs",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 struct RequestEvictMsg {
     run_id: String,
     message: String,
@@ -950,9 +1106,144 @@ pub(crate) struct HeartbeatTimeoutMsg {
     pub(crate) run_id: String,
     pub(crate) span: Span,
 }
+/// Tracks the activations which have been handed to lang, so that lang completions racing with
+/// an elapsed activation deadline are resolved consistently.
+///
+/// Completions don't say which activation they are for, so they are matched up by run and kind.
+/// Core only ever has one non-eviction activation outstanding per run, but once its deadline
+/// elapses the run is evicted, so lang may then have both it and an eviction-only activation
+/// outstanding, and may complete them in either order. Lang replies to evictions with empty
+/// completions, so an empty completion is matched to an outstanding eviction, and anything else
+/// to the outstanding non-eviction activation. Once lang replies to an eviction the run is gone,
+/// so it stops being tracked, and any late completion for it is dropped.
+struct ActivationDeadlines {
+    deadline: Duration,
+    next_id: AtomicU64,
+    outstanding: parking_lot::Mutex<HashMap<String, Vec<IssuedActivation>>>,
+}
+#[derive(Debug)]
+struct IssuedActivation {
+    id: u64,
+    eviction_only: bool,
+    /// Set once the activation's deadline elapsed before lang completed it
+    elapsed: bool,
+    /// Aborts the timer which elapses the deadline
+    timer: Option<AbortHandle>,
+}
+impl IssuedActivation {
+    fn stop_timer(&self) {
+        if let Some(timer) = &self.timer {
+            timer.abort();
+        }
+    }
+}
+impl ActivationDeadlines {
+    fn new(deadline: Duration) -> Self {
+        Self {
+            deadline,
+            next_id: AtomicU64::new(0),
+            outstanding: Default::default(),
+        }
+    }
+
+    /// Begin tracking a newly issued activation, returning an id for it
+    fn issue(&self, run_id: &str, eviction_only: bool) -> u64 {
+        let id = self.next_id.fetch_add(1, atomic::Ordering::Relaxed);
+        let mut outstanding = self.outstanding.lock();
+        let issued = outstanding.entry(run_id.to_string()).or_default();
+        if !eviction_only {
+            // Any non-eviction activation still tracked here belonged to an instance of the run
+            // which has since been evicted, and whose lang code never returned. A completion from
+            // it can no longer be told apart from one for this activation.
+            issued.retain(|a| {
+                if !a.eviction_only {
+                    a.stop_timer();
+                }
+                a.eviction_only
+            });
+        }
+        issued.push(IssuedActivation {
+            id,
+            eviction_only,
+            elapsed: false,
+            timer: None,
+        });
+        id
+    }
+
+    /// Attaches the timer which elapses the deadline of the activation with the provided id
+    fn set_timer(&self, run_id: &str, id: u64, timer: AbortHandle) {
+        let mut outstanding = self.outstanding.lock();
+        match outstanding
+            .get_mut(run_id)
+            .and_then(|issued| issued.iter_mut().find(|a| a.id == id))
+        {
+            Some(a) => a.timer = Some(timer),
+            None => timer.abort(),
+        }
+    }
+
+    /// Marks the activation with the provided id as having exceeded its deadline. Returns false
+    /// if the activation was already completed (or superseded by a newer one).
+    fn elapse(&self, run_id: &str, id: u64) -> bool {
+        let mut outstanding = self.outstanding.lock();
+        match outstanding
+            .get_mut(run_id)
+            .and_then(|issued| issued.iter_mut().find(|a| a.id == id))
+        {
+            Some(a) if !a.eviction_only && !a.elapsed => {
+                a.elapsed = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Stops tracking the activation lang just completed for the run. Returns true if that
+    /// activation's deadline had already elapsed, or the run was already evicted, meaning the
+    /// completion must be dropped. Replies to eviction-only activations are never dropped.
+    fn complete(&self, run_id: &str, is_empty_completion: bool) -> bool {
+        let mut outstanding = self.outstanding.lock();
+        let Some(issued) = outstanding.get_mut(run_id) else {
+            // Every activation handed to lang is tracked until its run is evicted, so this is
+            // a late completion for a run which no longer exists
+            return !is_empty_completion;
+        };
+        let eviction_reply = is_empty_completion && issued.iter().any(|a| a.eviction_only);
+        if eviction_reply {
+            for a in outstanding.remove(run_id).into_iter().flatten() {
+                a.stop_timer();
+            }
+            return false;
+        }
+        let drop_completion = match issued.iter().position(|a| !a.eviction_only) {
+            Some(ix) => {
+                let completed = issued.remove(ix);
+                completed.stop_timer();
+                completed.elapsed
+            }
+            None => false,
+        };
+        if issued.is_empty() {
+            outstanding.remove(run_id);
+        }
+        drop_completion
+    }
+}
+
+#[derive(Debug)]
+#[cfg_attr(
+    feature = "save_wf_inputs",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+struct ActivationDeadlineMsg {
+    run_id: String,
+}
 #[derive(Debug)]
 struct GetStateInfoMsg {
     response_tx: oneshot::Sender<WorkflowStateInfo>,
+    /// If set, the response includes a detailed snapshot of all the runs and buffered polls
+    with_snapshot: bool,
 }
 
 /// Each activation completion produces one of these
@@ -960,6 +1251,8 @@ struct GetStateInfoMsg {
 struct ActivationCompleteResult {
     replaying: bool,
     outcome: ActivationCompleteOutcome,
+    /// Details of any nondeterminism the run has encountered
+    nondeterminism: Option<NondeterminismDiagnostic>,
 }
 
 /// What needs to be done after calling [Workflows::activation_completed]
@@ -978,6 +1271,10 @@ enum ActivationCompleteOutcome {
 }
 /// Did we report, or not, completion of a WFT to server?
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(
+    feature = "save_wf_inputs",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 enum WFTReportStatus {
     Reported {
         reset_last_started_to: Option<i64>,
@@ -1100,6 +1397,10 @@ fn validate_completion(
 
 #[derive(Debug)]
 #[allow(clippy::large_enum_variant)]
+#[cfg_attr(
+    feature = "save_wf_inputs",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 enum ValidatedCompletion {
     Success {
         run_id: String,
@@ -1123,6 +1424,10 @@ impl ValidatedCompletion {
 }
 
 #[derive(Debug)]
+#[cfg_attr(
+    feature = "save_wf_inputs",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub(crate) enum LocalResolution {
     LocalActivity(LocalActivityResolution),
 }
@@ -1144,6 +1449,10 @@ struct EmptyWorkflowCommandErr;
 /// EX: Create a new timer, complete the workflow, etc.
 #[derive(Debug, derive_more::From, derive_more::Display)]
 #[display("{}", variant)]
+#[cfg_attr(
+    feature = "save_wf_inputs",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 struct WFCommand {
     variant: WFCommandVariant,
     metadata: Option<UserMetadata>,
@@ -1151,6 +1460,10 @@ struct WFCommand {
 
 #[derive(Debug, derive_more::From, derive_more::Display)]
 #[allow(clippy::large_enum_variant)]
+#[cfg_attr(
+    feature = "save_wf_inputs",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 enum WFCommandVariant {
     /// Returned when we need to wait for the lang sdk to send us something
     NoCommandsFromLang,
@@ -1305,17 +1618,50 @@ impl From<OutgoingJob> for WorkflowActivationJob {
 /// Errors thrown inside of workflow machines
 #[derive(thiserror::Error, Debug)]
 pub(crate) enum WFMachinesError {
-    #[error("[TMPRL1100] Nondeterminism error: {0}")]
-    Nondeterminism(String),
+    #[error("[TMPRL1100] Nondeterminism error: {}", .0.message)]
+    Nondeterminism(Box<NondeterminismDiagnostic>),
     #[error("Fatal error in workflow machines: {0}")]
     Fatal(String),
+    #[error("[TMPRL1101] Potential deadlock detected: {0}")]
+    Deadlock(String),
 }
 
 impl WFMachinesError {
+    /// Create a nondeterminism error with only a message. Context about where in history the error
+    /// happened is attached later by [WorkflowMachines], which knows about it.
+    pub(crate) fn nondeterminism(message: impl Into<String>) -> Self {
+        WFMachinesError::Nondeterminism(Box::new(NondeterminismDiagnostic {
+            message: message.into(),
+            ..Default::default()
+        }))
+    }
+
+    /// If this is a nondeterminism error, attach context about where in history it happened using
+    /// the provided function. Errors are created with only a message, so this is what fills in
+    /// the rest of the diagnostic.
+    pub(crate) fn with_nondeterminism_context(
+        mut self,
+        fill: impl FnOnce(&mut NondeterminismDiagnostic),
+    ) -> Self {
+        if let WFMachinesError::Nondeterminism(ref mut diag) = self {
+            fill(diag);
+        }
+        self
+    }
+
+    /// Returns the structured diagnostic if this is a nondeterminism error
+    pub(crate) fn nondeterminism_diagnostic(&self) -> Option<&NondeterminismDiagnostic> {
+        match self {
+            WFMachinesError::Nondeterminism(diag) => Some(diag),
+            _ => None,
+        }
+    }
+
     fn evict_reason(&self) -> EvictionReason {
         match self {
             WFMachinesError::Nondeterminism(_) => EvictionReason::Nondeterminism,
             WFMachinesError::Fatal(_) => EvictionReason::Fatal,
+            WFMachinesError::Deadlock(_) => EvictionReason::DeadlockDetected,
         }
     }
 
@@ -1337,7 +1683,7 @@ impl From<MachineError<WFMachinesError>> for WFMachinesError {
         match v {
             MachineError::InvalidTransition => {
                 // TODO: Get states back
-                WFMachinesError::Nondeterminism("Invalid transition in state machine".to_string())
+                WFMachinesError::nondeterminism("Invalid transition in state machine".to_string())
             }
             MachineError::Underlying(e) => e,
         }
@@ -1510,6 +1856,21 @@ mod tests {
         )
     }
 
+    #[tokio::test]
+    async fn activation_deadlines_forget_evicted_runs() {
+        let deadlines = ActivationDeadlines::new(Duration::from_secs(60));
+        let id = deadlines.issue("run", false);
+        let timer = tokio::spawn(std::future::pending::<()>());
+        deadlines.set_timer("run", id, timer.abort_handle());
+        assert!(deadlines.elapse("run", id));
+        deadlines.issue("run", true);
+        assert!(!deadlines.complete("run", true));
+        assert!(deadlines.outstanding.lock().is_empty());
+        assert!(timer.await.unwrap_err().is_cancelled());
+        // The stuck activation's completion arriving after the eviction reply is dropped
+        assert!(deadlines.complete("run", false));
+    }
+
     #[test]
     #[should_panic]
     fn queries_cannot_go_with_other_jobs() {
@@ -3,23 +3,43 @@ use crate::{
     telemetry::metrics::workflow_type,
     worker::workflow::{
         HistoryUpdate, LocalActivityRequestSink, PermittedWFT, RequestEvictMsg, RunBasics,
+        evicted_history::EvictedHistories,
         managed_run::{ManagedRun, RunUpdateAct},
     },
 };
 use lru::LruCache;
-use std::{num::NonZeroUsize, rc::Rc, sync::Arc};
-use temporal_sdk_core_api::worker::WorkerConfig;
+use std::{collections::HashMap, mem, num::NonZeroUsize, rc::Rc, sync::Arc};
+use temporal_sdk_core_api::worker::{WorkerConfig, WorkflowCachePolicy};
 use temporal_sdk_core_protos::{
     coresdk::workflow_activation::remove_from_cache::EvictionReason,
     temporal::api::workflowservice::v1::get_system_info_response,
 };
 
+/// The cache partition shared by all workflow types which have no cache policy
+const SHARED_PARTITION: &str = "shared";
+
+/// Returns the name of the cache partition runs of the given type belong to, for use in metrics
+pub(super) fn partition_name(config: &WorkerConfig, workflow_type: &str) -> String {
+    if config.workflow_cache_policies.contains_key(workflow_type) {
+        workflow_type.to_owned()
+    } else {
+        SHARED_PARTITION.to_owned()
+    }
+}
+
 pub(super) struct RunCache {
     worker_config: Arc<WorkerConfig>,
     sdk_name_and_version: (String, String),
     server_capabilities: get_system_info_response::Capabilities,
     /// Run id -> Data
     runs: LruCache<String, ManagedRun>,
+    /// Run id -> The most recent estimate of the memory it retains, in bytes
+    estimated_sizes: HashMap<String, usize>,
+    total_estimated_bytes: usize,
+    /// Workflow type -> Number of its runs in the cache
+    runs_per_type: HashMap<String, usize>,
+    /// Histories of evicted runs, if the worker is configured to retain them
+    evicted_histories: Option<EvictedHistories>,
     local_activity_request_sink: Rc<dyn LocalActivityRequestSink>,
 
     metrics: MetricsContext,
@@ -40,6 +60,10 @@ impl RunCache {
         } else {
             1
         };
+        let evicted_histories = worker_config
+            .evicted_history_retention
+            .clone()
+            .map(EvictedHistories::new);
         Self {
             worker_config,
             sdk_name_and_version,
@@ -47,6 +71,10 @@ impl RunCache {
             runs: LruCache::new(
                 NonZeroUsize::new(lru_size).expect("LRU size is guaranteed positive"),
             ),
+            estimated_sizes: Default::default(),
+            total_estimated_bytes: 0,
+            runs_per_type: Default::default(),
+            evicted_histories,
             local_activity_request_sink: Rc::new(local_activity_request_sink),
             metrics,
         }
@@ -59,14 +87,20 @@ impl RunCache {
         if let Some(run_handle) = self.runs.get_mut(&run_id) {
             let rur = run_handle.incoming_wft(pwft);
             self.metrics.cache_size(cur_num_cached_runs as u64);
+            self.refresh_estimated_size(&run_id);
             return rur;
         }
 
+        // Any history retained for the run is stale now that it's being rebuilt
+        if let Some(eh) = self.evicted_histories.as_mut() {
+            eh.discard(&run_id);
+        }
         // Create a new workflow machines instance for this workflow, initialize it, and
         // track it.
+        let wf_type = pwft.work.workflow_type.clone();
         let metrics = self
             .metrics
-            .with_new_attrs([workflow_type(pwft.work.workflow_type.clone())]);
+            .with_new_attrs([workflow_type(wf_type.clone())]);
         let (mrh, rur) = ManagedRun::new(
             RunBasics {
                 worker_config: self.worker_config.clone(),
@@ -82,17 +116,32 @@ impl RunCache {
             pwft,
             self.local_activity_request_sink.clone(),
         );
-        if self.runs.push(run_id, mrh).is_some() {
+        if self.runs.push(run_id.clone(), mrh).is_some() {
             panic!("Overflowed run cache! Cache owner is expected to avoid this!");
         }
+        *self.runs_per_type.entry(wf_type).or_default() += 1;
         self.metrics.cache_size(cur_num_cached_runs as u64 + 1);
+        self.refresh_estimated_size(&run_id);
         rur
     }
 
     pub(super) fn remove(&mut self, k: &str) -> Option<ManagedRun> {
-        let r = self.runs.pop(k);
+        let mut r = self.runs.pop(k);
         self.metrics.cache_size(self.len() as u64);
         if let Some(rh) = &r {
+            if let Some(count) = self.runs_per_type.get_mut(rh.workflow_type()) {
+                *count -= 1;
+                if *count == 0 {
+                    self.runs_per_type.remove(rh.workflow_type());
+                }
+            }
+        }
+        if let Some(size) = self.estimated_sizes.remove(k) {
+            self.total_estimated_bytes -= size;
+            self.metrics
+                .cache_estimated_bytes(self.total_estimated_bytes as u64);
+        }
+        if let Some(rh) = &mut r {
             // A workflow completing normally doesn't count as a forced eviction.
             if !matches!(
                 rh.trying_to_evict(),
@@ -102,11 +151,46 @@ impl RunCache {
                 })
             ) {
                 self.metrics.forced_cache_eviction();
+                if let Some((eh, history)) =
+                    self.evicted_histories.as_mut().zip(rh.take_seen_history())
+                {
+                    eh.retain(k.to_owned(), history);
+                }
             }
         }
         r
     }
 
+    /// Fills in the history missing from an incremental task for a run which isn't cached, using
+    /// the history retained when the run was evicted. Returns false if no usable history was
+    /// retained, in which case the task is left untouched and the history must be fetched. Only
+    /// history which reaches the task's first event is usable, the events in between are never
+    /// fetched on their own.
+    pub(super) fn fill_from_retained_history(&mut self, pwft: &mut PermittedWFT) -> bool {
+        let Some(eh) = self.evicted_histories.as_mut() else {
+            return false;
+        };
+        let run_id = &pwft.work.execution.run_id;
+        let Some(history) = eh.take(run_id) else {
+            return false;
+        };
+        if !pwft.work.update.accepts_prefix(&history) {
+            eh.retain(run_id.clone(), history);
+            return false;
+        }
+        let update = mem::replace(&mut pwft.work.update, HistoryUpdate::dummy());
+        match update.with_prefix(history) {
+            Ok(filled) => {
+                pwft.work.update = filled;
+                true
+            }
+            Err(update) => {
+                pwft.work.update = update;
+                false
+            }
+        }
+    }
+
     pub(super) fn get_mut(&mut self, k: &str) -> Option<&mut ManagedRun> {
         self.runs.get_mut(k)
     }
@@ -137,8 +221,181 @@ impl RunCache {
         self.runs.iter().map(|(_, v)| v)
     }
 
-    pub(super) fn is_full(&self) -> bool {
-        self.runs.cap().get() == self.runs.len()
+    /// Re-estimates the memory retained by a run. Must be called after anything which may have
+    /// changed it, for the memory budget to be enforced accurately.
+    pub(super) fn refresh_estimated_size(&mut self, k: &str) {
+        let Some(size) = self.runs.peek(k).map(ManagedRun::estimated_size) else {
+            return;
+        };
+        let old_size = match self.estimated_sizes.get_mut(k) {
+            Some(s) => mem::replace(s, size),
+            None => {
+                self.estimated_sizes.insert(k.to_owned(), size);
+                0
+            }
+        };
+        self.total_estimated_bytes = self.total_estimated_bytes - old_size + size;
+        self.metrics
+            .cache_estimated_bytes(self.total_estimated_bytes as u64);
+    }
+
+    /// Returns the runs which must be evicted to bring the cache back under its memory budget, if
+    /// it has one. Runs already being evicted count towards the memory which will be freed. The
+    /// most recently used run is never included, so that a run which is larger than the whole
+    /// budget can still make progress.
+    pub(super) fn runs_over_memory_budget(&self) -> Vec<String> {
+        let Some(budget) = self.worker_config.max_cached_workflows_bytes else {
+            return vec![];
+        };
+        let size_of = |run_id: &str| self.estimated_sizes.get(run_id).copied().unwrap_or(0);
+        let mut excess = self.total_estimated_bytes.saturating_sub(budget);
+        for (run_id, handle) in self.runs_lru_order() {
+            if handle.trying_to_evict().is_some() {
+                excess = excess.saturating_sub(size_of(run_id));
+            }
+        }
+        let mru = self.runs.peek_mru().map(|(run_id, _)| run_id.as_str());
+        let mut evict = vec![];
+        for (run_id, _) in self.eviction_candidates() {
+            if excess == 0 {
+                break;
+            }
+            if Some(run_id) == mru {
+                continue;
+            }
+            excess = excess.saturating_sub(size_of(run_id));
+            evict.push(run_id.to_owned());
+        }
+        evict
+    }
+
+    /// True if a run of the given workflow type can't be added without evicting one first, because
+    /// the cache is full, the type is at its limit, there's no room outside of other types'
+    /// reservations, or the memory budget is used up
+    pub(super) fn needs_eviction_for(&self, workflow_type: &str) -> bool {
+        let over_budget = self
+            .worker_config
+            .max_cached_workflows_bytes
+            .is_some_and(|budget| self.total_estimated_bytes >= budget);
+        if self.runs.cap().get() == self.runs.len() || (over_budget && !self.runs.is_empty()) {
+            return true;
+        }
+        let cached = self.runs_per_type.get(workflow_type).copied().unwrap_or(0);
+        let policy = self.policy(workflow_type);
+        if policy.max_cached.is_some_and(|max| cached >= max) {
+            return true;
+        }
+        if cached < policy.reserved {
+            return false;
+        }
+        let total_reserved: usize = self
+            .worker_config
+            .workflow_cache_policies
+            .values()
+            .map(|p| p.reserved)
+            .sum();
+        let shared_used: usize = self
+            .runs_per_type
+            .iter()
+            .map(|(wf_type, count)| count.saturating_sub(self.policy(wf_type).reserved))
+            .sum();
+        shared_used >= self.runs.cap().get().saturating_sub(total_reserved)
+    }
+
+    /// True if evicting the given run would free a slot a run of the given workflow type could
+    /// use. A type at its limit only gains room from its own runs, and runs within their type's
+    /// reservation only make room for that type.
+    pub(super) fn eviction_frees_slot_for(&self, victim: &str, workflow_type: &str) -> bool {
+        let Some(victim_type) = self.runs.peek(victim).map(ManagedRun::workflow_type) else {
+            return false;
+        };
+        if victim_type == workflow_type {
+            return true;
+        }
+        let cached = self.runs_per_type.get(workflow_type).copied().unwrap_or(0);
+        if self
+            .policy(workflow_type)
+            .max_cached
+            .is_some_and(|max| cached >= max)
+        {
+            return false;
+        }
+        let victim_type_cached = self.runs_per_type.get(victim_type).copied().unwrap_or(0);
+        victim_type_cached > self.policy(victim_type).reserved
+            || cached < self.policy(workflow_type).reserved
+    }
+
+    /// Chooses a run to evict to make room for a run of the given workflow type, or returns `None`
+    /// if no run can be evicted right now. If the type is at its limit, only runs of the same type
+    /// are chosen. Otherwise, runs within their type's reservation are spared if possible. Runs in
+    /// `exclude` are assumed to be evicted already.
+    pub(super) fn eviction_victim_for(
+        &self,
+        workflow_type: &str,
+        exclude: &[String],
+    ) -> Option<String> {
+        // Runs which are already on their way out don't count towards their type's usage
+        let mut remaining = self.runs_per_type.clone();
+        for (run_id, handle) in self.runs_lru_order() {
+            if handle.trying_to_evict().is_some() || exclude.iter().any(|e| e == run_id) {
+                if let Some(count) = remaining.get_mut(handle.workflow_type()) {
+                    *count = count.saturating_sub(1);
+                }
+            }
+        }
+        let remaining_of = |wf_type: &str| remaining.get(wf_type).copied().unwrap_or(0);
+        let mut candidates = self
+            .eviction_candidates()
+            .filter(|(run_id, _)| !exclude.iter().any(|e| e == run_id));
+
+        let at_type_limit = self
+            .policy(workflow_type)
+            .max_cached
+            .is_some_and(|max| remaining_of(workflow_type) >= max);
+        let victim = if at_type_limit {
+            candidates.find(|(_, h)| h.workflow_type() == workflow_type)
+        } else {
+            let candidates: Vec<_> = candidates.collect();
+            candidates
+                .iter()
+                .find(|(_, h)| {
+                    remaining_of(h.workflow_type()) > self.policy(h.workflow_type()).reserved
+                })
+                .or_else(|| candidates.first())
+                .copied()
+        };
+        victim.map(|(run_id, _)| run_id.to_owned())
+    }
+
+    /// Runs which may be evicted to make room, in the order they should be chosen: runs of types
+    /// which don't prefer to be kept come first, and less recently used runs first after that.
+    /// Runs with buffered tasks are about to be worked on, so are never included, and neither are
+    /// runs which are already being evicted.
+    fn eviction_candidates(&self) -> impl Iterator<Item = (&str, &ManagedRun)> {
+        let (keep, others): (Vec<_>, Vec<_>) = self
+            .runs_lru_order()
+            .filter(|(_, h)| !h.has_buffered_wft() && h.trying_to_evict().is_none())
+            .partition(|(_, h)| self.policy(h.workflow_type()).prefer_keep);
+        others.into_iter().chain(keep)
+    }
+
+    /// The cache policy for the given workflow type, which is the default one for types which
+    /// share the cache
+    fn policy(&self, workflow_type: &str) -> &WorkflowCachePolicy {
+        static SHARED: WorkflowCachePolicy = WorkflowCachePolicy {
+            max_cached: None,
+            reserved: 0,
+            prefer_keep: false,
+        };
+        self.worker_config
+            .workflow_cache_policies
+            .get(workflow_type)
+            .unwrap_or(&SHARED)
+    }
+
+    /// The name of the cache partition runs of the given type belong to
+    pub(super) fn partition_of(&self, workflow_type: &str) -> String {
+        partition_name(&self.worker_config, workflow_type)
     }
 
     pub(super) fn len(&self) -> usize {
@@ -1,6 +1,8 @@
 use crate::{
     MetricsContext,
     abstractions::dbg_panic,
+    telemetry::metrics::cache_partition,
+    worker::debug_snapshot::{BufferedPollDebugSnapshot, WorkflowsDebugSnapshot},
     worker::workflow::{
         managed_run::RunUpdateAct,
         run_cache::RunCache,
@@ -15,6 +17,14 @@ use temporal_sdk_core_protos::coresdk::workflow_activation::remove_from_cache::E
 use tokio_util::sync::CancellationToken;
 use tracing::{Level, Span};
 
+#[cfg(feature = "save_wf_inputs")]
+mod saved_wf_inputs;
+#[cfg(feature = "save_wf_inputs")]
+mod tonic_status_serde;
+
+#[cfg(feature = "save_wf_inputs")]
+pub use saved_wf_inputs::replay_wf_state_inputs;
+
 /// This struct holds all the state needed for tracking the state of currently cached workflow runs
 /// and directs all actions which affect them. It is ultimately the top-level arbiter of nearly
 /// everything important relating to workflow state.
@@ -35,6 +45,9 @@ pub(super) struct WFStream {
     ignore_evicts_on_shutdown: bool,
 
     metrics: MetricsContext,
+    /// Records every input, if the worker is configured to
+    #[cfg(feature = "save_wf_inputs")]
+    input_recorder: Option<Arc<saved_wf_inputs::InputRecorder>>,
 }
 impl WFStream {
     /// Constructs workflow state management and returns a stream which outputs activations.
@@ -83,6 +96,22 @@ impl WFStream {
         basics: WorkflowBasics,
         local_activity_request_sink: impl LocalActivityRequestSink,
     ) -> impl Stream<Item = Result<WFStreamOutput, PollError>> {
+        #[cfg(feature = "save_wf_inputs")]
+        let input_recorder = basics
+            .worker_config
+            .record_workflow_inputs_to
+            .as_deref()
+            .and_then(|path| {
+                saved_wf_inputs::InputRecorder::new(path, &basics)
+                    .inspect_err(|e| error!(error=%e, "Couldn't start recording workflow inputs"))
+                    .ok()
+            })
+            .map(Arc::new);
+        #[cfg(feature = "save_wf_inputs")]
+        let local_activity_request_sink = saved_wf_inputs::RecordingLaReqSink {
+            inner: local_activity_request_sink,
+            recorder: input_recorder.clone(),
+        };
         let mut state = WFStream {
             buffered_polls_need_cache_slot: Default::default(),
             runs: RunCache::new(
@@ -97,13 +126,20 @@ impl WFStream {
             metrics: basics.metrics,
             runs_needing_fetching: Default::default(),
             history_fetch_refcounter: Arc::new(HistfetchRC {}),
+            #[cfg(feature = "save_wf_inputs")]
+            input_recorder,
         };
         all_inputs
             .map(move |action: WFStreamInput| {
                 let span = span!(Level::DEBUG, "new_stream_input", action=?action);
                 let _span_g = span.enter();
+                #[cfg(feature = "save_wf_inputs")]
+                if let Some(recorder) = state.input_recorder.as_ref() {
+                    recorder.record_input(&action);
+                }
 
                 let mut activations = vec![];
+                let touched_run = action.run_id().map(ToOwned::to_owned);
                 let maybe_act = match action {
                     WFStreamInput::NewWft(pwft) => {
                         debug!(run_id=%pwft.work.execution.run_id, "New WFT");
@@ -136,6 +172,9 @@ impl WFStream {
                             LocalInputs::HeartbeatTimeout(hbt) => {
                                 state.process_heartbeat_timeout(hbt)
                             }
+                            LocalInputs::ActivationDeadline(adm) => {
+                                state.process_activation_deadline(adm.run_id)
+                            }
                             LocalInputs::RequestEviction(evict) => {
                                 state.request_eviction(evict).into_run_update_resp()
                             }
@@ -143,6 +182,7 @@ impl WFStream {
                                 let _ = gsi.response_tx.send(WorkflowStateInfo {
                                     cached_workflows: state.runs.len(),
                                     outstanding_wft: state.outstanding_wfts(),
+                                    snapshot: gsi.with_snapshot.then(|| state.debug_snapshot()),
                                 });
                                 None
                             }
@@ -171,6 +211,10 @@ impl WFStream {
                 };
 
                 activations.extend(maybe_act);
+                if let Some(run_id) = touched_run {
+                    state.runs.refresh_estimated_size(&run_id);
+                }
+                activations.extend(state.evict_runs_over_memory_budget());
                 activations.extend(state.reconcile_buffered());
 
                 if state.shutdown_done() {
@@ -217,7 +261,7 @@ impl WFStream {
     ) -> Result<RunUpdateAct, HistoryFetchReq> {
         // If the run already exists, possibly buffer the work and return early if we can't handle
         // it yet.
-        let pwft = if let Some(rh) = self.runs.get_mut(&pwft.work.execution.run_id) {
+        let mut pwft = if let Some(rh) = self.runs.get_mut(&pwft.work.execution.run_id) {
             if let Some(w) = rh.buffer_wft_if_outstanding_work(pwft) {
                 w
             } else {
@@ -230,7 +274,7 @@ impl WFStream {
         let run_id = pwft.work.execution.run_id.clone();
         // If our cache is full and this WFT is for an unseen run we must first evict a run before
         // we can deal with this task. So, buffer the task in that case.
-        if !self.runs.has_run(&run_id) && self.runs.is_full() {
+        if !self.runs.has_run(&run_id) && self.runs.needs_eviction_for(&pwft.work.workflow_type) {
             self.buffer_resp_on_full_cache(pwft);
             return Ok(None);
         }
@@ -239,9 +283,18 @@ impl WFStream {
         // not fetch more history, send the task, see cache is full, buffer it, then evict that
         // run, and now we still have a cache miss.
         if !self.runs.has_run(&run_id) && pwft.work.is_incremental() {
+            self.metrics
+                .with_new_attrs([cache_partition(
+                    self.runs.partition_of(&pwft.work.workflow_type),
+                )])
+                .sticky_cache_miss();
+            if self.runs.fill_from_retained_history(&mut pwft) {
+                debug!(run_id=?run_id, "Workflow task has partial history, but workflow is not in \
+                       cache. Using history retained when it was evicted");
+                return Ok(self.runs.instantiate_or_update(pwft));
+            }
             debug!(run_id=?run_id, "Workflow task has partial history, but workflow is not in \
                    cache. Will fetch history");
-            self.metrics.sticky_cache_miss();
             return Err(HistoryFetchReq::Full(
                 Box::new(CacheMissFetchReq { original_wft: pwft }),
                 self.history_fetch_refcounter.clone(),
@@ -287,7 +340,9 @@ impl WFStream {
                     ..
                 } => rh.failed_completion(
                     failure.force_cause(),
-                    if is_autocomplete {
+                    if rh.deadlock_detected() {
+                        EvictionReason::DeadlockDetected
+                    } else if is_autocomplete {
                         EvictionReason::Unspecified
                     } else {
                         EvictionReason::LangFail
@@ -353,9 +408,11 @@ impl WFStream {
                 .map(|x| vec![x])
                 .or_else(|| {
                     // Attempt to apply a buffered poll for some *other* run, if we didn't have a
-                    // wft from complete or a buffered poll for *this* run and we evicted
+                    // wft from complete or a buffered poll for *this* run and we evicted. The
+                    // freed slot may not be usable by every workflow type, so take the first
+                    // buffered poll which the cache policies now admit.
                     if should_evict {
-                        self.buffered_polls_need_cache_slot.pop_front()
+                        self.take_admissible_buffered_poll()
                     } else {
                         None
                     }
@@ -423,6 +480,14 @@ impl WFStream {
         }
     }
 
+    fn process_activation_deadline(&mut self, run_id: String) -> RunUpdateAct {
+        if let Some(rh) = self.runs.get_mut(&run_id) {
+            rh.activation_deadline_elapsed()
+        } else {
+            None
+        }
+    }
+
     /// Request a workflow eviction. This will (eventually, after replay is done) queue up an
     /// activation to evict the workflow from the lang side. Workflow will not *actually* be evicted
     /// until lang replies to that activation
@@ -500,27 +565,46 @@ impl WFStream {
         }
     }
 
+    /// Removes and returns the first buffered poll whose run can be added to the cache without
+    /// evicting another run first
+    fn take_admissible_buffered_poll(&mut self) -> Option<Vec<PermittedWFT>> {
+        let ix = self
+            .buffered_polls_need_cache_slot
+            .iter()
+            .position(|wfts| {
+                wfts.first()
+                    .is_some_and(|w| !self.runs.needs_eviction_for(&w.work.workflow_type))
+            })?;
+        self.buffered_polls_need_cache_slot.remove(ix)
+    }
+
     /// Makes sure we have enough pending evictions to fulfill the needs of buffered WFTs who are
     /// waiting on a cache slot
     fn reconcile_buffered(&mut self) -> Vec<ActivationOrAuto> {
-        // We must ensure that there are at least as many pending evictions as there are tasks
-        // that we might need to un-buffer (skipping runs which already have buffered tasks for
-        // themselves)
-        let num_in_buff = self.buffered_polls_need_cache_slot.len();
-        let mut evict_these = vec![];
-        let num_existing_evictions = self
+        // Every buffered task needs a pending eviction which frees a slot its workflow type can
+        // use, per the cache policies. Evictions already in progress are matched to the tasks
+        // first, and victims are chosen for whichever tasks are left without one.
+        let mut unclaimed: Vec<String> = self
             .runs
             .runs_lru_order()
             .filter(|(_, h)| h.trying_to_evict().is_some())
-            .count();
-        let mut num_evicts_needed = num_in_buff.saturating_sub(num_existing_evictions);
-        for (rid, handle) in self.runs.runs_lru_order() {
-            if num_evicts_needed == 0 {
-                break;
+            .map(|(run_id, _)| run_id.to_owned())
+            .collect();
+        let mut evict_these: Vec<String> = vec![];
+        for wfts in self.buffered_polls_need_cache_slot.iter() {
+            let Some(first) = wfts.first() else {
+                continue;
+            };
+            let wf_type = &first.work.workflow_type;
+            if let Some(ix) = unclaimed
+                .iter()
+                .position(|run_id| self.runs.eviction_frees_slot_for(run_id, wf_type))
+            {
+                unclaimed.swap_remove(ix);
+                continue;
             }
-            if !handle.has_buffered_wft() {
-                num_evicts_needed -= 1;
-                evict_these.push(rid.to_string());
+            if let Some(victim) = self.runs.eviction_victim_for(wf_type, &evict_these) {
+                evict_these.push(victim);
             }
         }
         let mut acts = vec![];
@@ -538,6 +622,24 @@ impl WFStream {
         acts
     }
 
+    /// Requests eviction of least-recently-used runs while the cache's estimated memory use is
+    /// over budget
+    fn evict_runs_over_memory_budget(&mut self) -> Vec<ActivationOrAuto> {
+        let mut acts = vec![];
+        for run_id in self.runs.runs_over_memory_budget() {
+            acts.extend(
+                self.request_eviction(RequestEvictMsg {
+                    run_id,
+                    message: "Workflow cache over memory budget".to_string(),
+                    reason: EvictionReason::CacheFull,
+                    auto_reply_fail_tt: None,
+                })
+                .into_run_update_resp(),
+            );
+        }
+        acts
+    }
+
     fn shutdown_done(&self) -> bool {
         if self.shutdown_token.is_cancelled() {
             if Arc::strong_count(&self.history_fetch_refcounter) > 1 {
@@ -559,6 +661,25 @@ impl WFStream {
         self.runs.handles().filter(|r| r.wft().is_some()).count()
     }
 
+    fn debug_snapshot(&self) -> WorkflowsDebugSnapshot {
+        WorkflowsDebugSnapshot {
+            cached_runs: self.runs.handles().map(|r| r.debug_snapshot()).collect(),
+            buffered_polls: self
+                .buffered_polls_need_cache_slot
+                .iter()
+                .filter_map(|tasks| {
+                    let first = tasks.first()?;
+                    Some(BufferedPollDebugSnapshot {
+                        run_id: first.work.execution.run_id.clone(),
+                        workflow_id: first.work.execution.workflow_id.clone(),
+                        workflow_type: first.work.workflow_type.clone(),
+                        num_tasks: tasks.len(),
+                    })
+                })
+                .collect(),
+        }
+    }
+
     // Useful when debugging
     #[allow(dead_code)]
     fn info_dump(&self, run_id: &str) {
@@ -574,6 +695,10 @@ impl WFStream {
 
 /// All possible inputs to the [WFStream]
 #[derive(derive_more::From, Debug)]
+#[cfg_attr(
+    feature = "save_wf_inputs",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 enum WFStreamInput {
     NewWft(Box<PermittedWFT>),
     Local(LocalInput),
@@ -581,19 +706,38 @@ enum WFStreamInput {
     PollerDead,
     /// The stream given to us which represents the poller (or a mock) encountered a non-retryable
     /// error while polling
-    PollerError(tonic::Status),
+    PollerError(
+        #[cfg_attr(feature = "save_wf_inputs", serde(with = "tonic_status_serde"))] tonic::Status,
+    ),
     FailedFetch {
         run_id: String,
+        #[cfg_attr(feature = "save_wf_inputs", serde(with = "tonic_status_serde"))]
         err: tonic::Status,
         auto_reply_fail_tt: Option<TaskToken>,
     },
 }
+impl WFStreamInput {
+    /// The run this input applies to, if any
+    fn run_id(&self) -> Option<&str> {
+        match self {
+            WFStreamInput::NewWft(pwft) => Some(&pwft.work.execution.run_id),
+            WFStreamInput::Local(li) => li.input.run_id(),
+            WFStreamInput::FailedFetch { run_id, .. } => Some(run_id),
+            WFStreamInput::PollerDead | WFStreamInput::PollerError(_) => None,
+        }
+    }
+}
 
 /// A non-poller-received input to the [WFStream]
 #[derive(derive_more::Debug)]
 #[debug("LocalInput {{ {input:?} }}")]
+#[cfg_attr(
+    feature = "save_wf_inputs",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub(super) struct LocalInput {
     pub(super) input: LocalInputs,
+    #[cfg_attr(feature = "save_wf_inputs", serde(skip, default = "Span::none"))]
     pub(super) span: Span,
 }
 impl From<HeartbeatTimeoutMsg> for LocalInput {
@@ -607,6 +751,10 @@ impl From<HeartbeatTimeoutMsg> for LocalInput {
 /// Everything that _isn't_ a poll which may affect workflow state. Always higher priority than
 /// new polls.
 #[derive(Debug, derive_more::From)]
+#[cfg_attr(
+    feature = "save_wf_inputs",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub(super) enum LocalInputs {
     Completion(WFActCompleteMsg),
     FetchedPageCompletion {
@@ -617,6 +765,9 @@ pub(super) enum LocalInputs {
     PostActivation(Box<PostActivationMsg>),
     RequestEviction(RequestEvictMsg),
     HeartbeatTimeout(String),
+    ActivationDeadline(ActivationDeadlineMsg),
+    /// Doesn't affect workflow state, so is never recorded
+    #[cfg_attr(feature = "save_wf_inputs", serde(skip))]
     GetStateInfo(GetStateInfoMsg),
 }
 impl LocalInputs {
@@ -628,6 +779,7 @@ impl LocalInputs {
             LocalInputs::PostActivation(pa) => &pa.run_id,
             LocalInputs::RequestEviction(re) => &re.run_id,
             LocalInputs::HeartbeatTimeout(hb) => hb,
+            LocalInputs::ActivationDeadline(adm) => &adm.run_id,
             LocalInputs::GetStateInfo(_) => return None,
         })
     }
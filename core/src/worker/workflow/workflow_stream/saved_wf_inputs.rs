@@ -0,0 +1,434 @@
+use crate::{
+    MetricsContext, PollError,
+    worker::{
+        LocalActRequest, LocalActivityResolution,
+        workflow::{
+            LocalActivityRequestSink, WFStreamOutput, WorkflowBasics,
+            workflow_stream::{LocalInput, LocalInputs, WFStream, WFStreamInput},
+        },
+    },
+};
+use anyhow::{Context, bail};
+use futures_util::{Stream, StreamExt, stream};
+use parking_lot::Mutex;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::Path,
+    pin::pin,
+    sync::{Arc, mpsc},
+    thread,
+    time::Duration,
+};
+use temporal_sdk_core_api::{
+    errors::WorkflowErrorType,
+    worker::{EvictedHistoryRetention, WorkerConfig, WorkflowCachePolicy},
+};
+use temporal_sdk_core_protos::temporal::api::workflowservice::v1::get_system_info_response;
+use tokio_util::sync::CancellationToken;
+
+/// Replays workflow stream inputs recorded by a worker configured with
+/// [WorkerConfig::record_workflow_inputs_to], feeding them into fresh workflow state. Since all
+/// workflow state changes are a function of these inputs, this deterministically reproduces
+/// whatever happened to the recorded worker, including any panics.
+///
+/// The server capabilities and the parts of the config which affect workflow state are taken from
+/// the recording. The rest of the config is used as given, though recording is always disabled
+/// here.
+pub async fn replay_wf_state_inputs(
+    config: WorkerConfig,
+    recording: impl AsRef<Path>,
+) -> Result<(), anyhow::Error> {
+    let mut stream = pin!(replay_stream(config, recording)?);
+    while let Some(output) = stream.next().await {
+        trace!("Stream output: {:?}", output);
+    }
+    Ok(())
+}
+
+/// Reads a recording and returns the workflow stream fed with its inputs
+fn replay_stream(
+    mut config: WorkerConfig,
+    recording: impl AsRef<Path>,
+) -> Result<impl Stream<Item = Result<WFStreamOutput, PollError>>, anyhow::Error> {
+    config.record_workflow_inputs_to = None;
+    let bytes = fs::read(recording).context("Couldn't read workflow input recording")?;
+    let (first, mut rest) =
+        split_record(&bytes).context("Workflow input recording doesn't start with a record")?;
+    let StoredWFStateInputDeSer::Basics(recorded) =
+        rmp_serde::from_slice(first).context("Couldn't decode recorded worker settings")?
+    else {
+        bail!("Workflow input recording doesn't start with the recorded worker's settings");
+    };
+    let (server_capabilities, sdk_name, sdk_version) = recorded.apply(&mut config);
+    let mut inputs = vec![];
+    let mut la_resolutions = VecDeque::new();
+    while !rest.is_empty() {
+        let Some((record, tail)) = split_record(rest) else {
+            // The recording worker may have died part way through writing the last record
+            warn!("Ignoring truncated record at the end of workflow input recording");
+            break;
+        };
+        match rmp_serde::from_slice(record).context("Couldn't decode recorded workflow input")? {
+            StoredWFStateInputDeSer::Stream(input) => inputs.push(input),
+            StoredWFStateInputDeSer::ImmediateLASinkResolutions(res) => {
+                la_resolutions.push_back(res)
+            }
+            StoredWFStateInputDeSer::Basics(_) => {
+                bail!("Workflow input recording has the worker's settings more than once")
+            }
+        }
+        rest = tail;
+    }
+
+    let basics = WorkflowBasics {
+        worker_config: Arc::new(config),
+        shutdown_token: CancellationToken::new(),
+        metrics: MetricsContext::no_op(),
+        server_capabilities,
+        sdk_name,
+        sdk_version,
+    };
+    let sink = ReplayingLaReqSink {
+        resolutions: Mutex::new(la_resolutions),
+    };
+    info!(
+        num_inputs = inputs.len(),
+        "Beginning workflow stream input replay"
+    );
+    Ok(WFStream::build_internal(stream::iter(inputs), basics, sink))
+}
+
+/// Splits the first length-prefixed record from the rest of a recording
+fn split_record(bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (len, rest) = bytes.split_first_chunk::<4>()?;
+    rest.split_at_checked(u32::from_le_bytes(*len) as usize)
+}
+
+#[derive(serde::Serialize)]
+enum StoredWFStateInputSer<'a> {
+    Basics(&'a RecordedBasics),
+    Stream(&'a WFStreamInput),
+    ImmediateLASinkResolutions(&'a Vec<LocalActivityResolution>),
+}
+
+#[derive(serde::Deserialize)]
+enum StoredWFStateInputDeSer {
+    Basics(RecordedBasics),
+    Stream(WFStreamInput),
+    ImmediateLASinkResolutions(Vec<LocalActivityResolution>),
+}
+
+/// Everything about the recorded worker, besides the inputs themselves, which determines how
+/// workflow state reacts to the inputs. Always the first record.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct RecordedBasics {
+    server_capabilities: get_system_info_response::Capabilities,
+    sdk_name: String,
+    sdk_version: String,
+    namespace: String,
+    task_queue: String,
+    worker_build_id: String,
+    max_cached_workflows: usize,
+    max_cached_workflows_bytes: Option<usize>,
+    /// Workflow type -> (max cached, reserved, prefer keep)
+    workflow_cache_policies: HashMap<String, (Option<usize>, usize, bool)>,
+    /// Max bytes in memory and on disk. Spilling goes to the directory in the replay config.
+    evicted_history_retention: Option<(usize, usize)>,
+    ignore_evicts_on_shutdown: bool,
+    fetching_concurrency: usize,
+    workflow_activation_deadline: Option<Duration>,
+    workflow_failure_errors: HashSet<String>,
+    workflow_types_to_failure_errors: HashMap<String, HashSet<String>>,
+}
+
+impl RecordedBasics {
+    fn new(basics: &WorkflowBasics) -> Self {
+        let config = &basics.worker_config;
+        let error_names =
+            |errs: &HashSet<WorkflowErrorType>| errs.iter().map(error_type_name).collect();
+        Self {
+            server_capabilities: basics.server_capabilities,
+            sdk_name: basics.sdk_name.clone(),
+            sdk_version: basics.sdk_version.clone(),
+            namespace: config.namespace.clone(),
+            task_queue: config.task_queue.clone(),
+            worker_build_id: config.worker_build_id.clone(),
+            max_cached_workflows: config.max_cached_workflows,
+            max_cached_workflows_bytes: config.max_cached_workflows_bytes,
+            workflow_cache_policies: config
+                .workflow_cache_policies
+                .iter()
+                .map(|(wf_type, p)| (wf_type.clone(), (p.max_cached, p.reserved, p.prefer_keep)))
+                .collect(),
+            evicted_history_retention: config
+                .evicted_history_retention
+                .as_ref()
+                .map(|r| (r.max_bytes_in_memory, r.max_bytes_on_disk)),
+            ignore_evicts_on_shutdown: config.ignore_evicts_on_shutdown,
+            fetching_concurrency: config.fetching_concurrency,
+            workflow_activation_deadline: config.workflow_activation_deadline,
+            workflow_failure_errors: error_names(&config.workflow_failure_errors),
+            workflow_types_to_failure_errors: config
+                .workflow_types_to_failure_errors
+                .iter()
+                .map(|(wf_type, errs)| (wf_type.clone(), error_names(errs)))
+                .collect(),
+        }
+    }
+
+    /// Overwrites the recorded parts of the config, and returns the recorded server capabilities,
+    /// SDK name, and SDK version
+    fn apply(
+        self,
+        config: &mut WorkerConfig,
+    ) -> (get_system_info_response::Capabilities, String, String) {
+        let error_types =
+            |names: HashSet<String>| names.iter().filter_map(|n| error_type(n)).collect();
+        config.namespace = self.namespace;
+        config.task_queue = self.task_queue;
+        config.worker_build_id = self.worker_build_id;
+        config.max_cached_workflows = self.max_cached_workflows;
+        config.max_cached_workflows_bytes = self.max_cached_workflows_bytes;
+        config.workflow_cache_policies = self
+            .workflow_cache_policies
+            .into_iter()
+            .map(|(wf_type, (max_cached, reserved, prefer_keep))| {
+                let policy = WorkflowCachePolicy {
+                    max_cached,
+                    reserved,
+                    prefer_keep,
+                };
+                (wf_type, policy)
+            })
+            .collect();
+        let spill_directory = config
+            .evicted_history_retention
+            .take()
+            .and_then(|r| r.spill_directory);
+        let retention = |(max_bytes_in_memory, max_bytes_on_disk)| EvictedHistoryRetention {
+            max_bytes_in_memory,
+            spill_directory,
+            max_bytes_on_disk,
+        };
+        config.evicted_history_retention = self.evicted_history_retention.map(retention);
+        config.ignore_evicts_on_shutdown = self.ignore_evicts_on_shutdown;
+        config.fetching_concurrency = self.fetching_concurrency;
+        config.workflow_activation_deadline = self.workflow_activation_deadline;
+        config.workflow_failure_errors = error_types(self.workflow_failure_errors);
+        config.workflow_types_to_failure_errors = self
+            .workflow_types_to_failure_errors
+            .into_iter()
+            .map(|(wf_type, names)| (wf_type, error_types(names)))
+            .collect();
+        (self.server_capabilities, self.sdk_name, self.sdk_version)
+    }
+}
+
+fn error_type_name(error_type: &WorkflowErrorType) -> String {
+    match error_type {
+        WorkflowErrorType::Nondeterminism => "Nondeterminism".to_string(),
+    }
+}
+
+fn error_type(name: &str) -> Option<WorkflowErrorType> {
+    match name {
+        "Nondeterminism" => Some(WorkflowErrorType::Nondeterminism),
+        _ => {
+            warn!(name, "Ignoring unknown recorded workflow error type");
+            None
+        }
+    }
+}
+
+/// Writes recorded inputs to a file from a separate thread, so that recording doesn't hold up
+/// workflow processing. Every record is flushed, so that the recording is useful even if the
+/// process dies.
+pub(super) struct InputRecorder {
+    tx: Option<mpsc::Sender<Vec<u8>>>,
+    writer: Option<thread::JoinHandle<()>>,
+}
+
+impl InputRecorder {
+    /// Starts a recording at the given path, beginning with the settings in the given basics
+    pub(super) fn new(path: &Path, basics: &WorkflowBasics) -> Result<Self, anyhow::Error> {
+        let mut out = BufWriter::new(File::create(path)?);
+        let (tx, rx) = mpsc::channel::<Vec<u8>>();
+        let writer = thread::Builder::new()
+            .name("wf-input-recorder".to_string())
+            .spawn(move || {
+                for record in rx {
+                    let res = out
+                        .write_all(&(record.len() as u32).to_le_bytes())
+                        .and_then(|_| out.write_all(&record))
+                        .and_then(|_| out.flush());
+                    if let Err(e) = res {
+                        error!(error=%e, "Failed to write workflow input recording, stopping");
+                        return;
+                    }
+                }
+            })?;
+        let recorder = Self {
+            tx: Some(tx),
+            writer: Some(writer),
+        };
+        recorder.record(StoredWFStateInputSer::Basics(&RecordedBasics::new(basics)));
+        Ok(recorder)
+    }
+
+    pub(super) fn record_input(&self, input: &WFStreamInput) {
+        if matches!(
+            input,
+            WFStreamInput::Local(LocalInput {
+                input: LocalInputs::GetStateInfo(_),
+                ..
+            })
+        ) {
+            return;
+        }
+        self.record(StoredWFStateInputSer::Stream(input));
+    }
+
+    fn record(&self, stored: StoredWFStateInputSer<'_>) {
+        match rmp_serde::to_vec_named(&stored) {
+            Ok(bytes) => {
+                if let Some(tx) = self.tx.as_ref() {
+                    let _ = tx.send(bytes);
+                }
+            }
+            Err(e) => warn!(error=%e, "Couldn't serialize workflow input for recording"),
+        }
+    }
+}
+
+impl Drop for InputRecorder {
+    fn drop(&mut self) {
+        // Hang up, then let the writer finish whatever is still queued
+        drop(self.tx.take());
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+/// Records what the wrapped sink immediately resolves, since those resolutions are inputs to
+/// workflow state which don't come through the stream.
+pub(super) struct RecordingLaReqSink<S> {
+    pub(super) inner: S,
+    pub(super) recorder: Option<Arc<InputRecorder>>,
+}
+
+impl<S: LocalActivityRequestSink> LocalActivityRequestSink for RecordingLaReqSink<S> {
+    fn sink_reqs(&self, reqs: Vec<LocalActRequest>) -> Vec<LocalActivityResolution> {
+        let had_reqs = !reqs.is_empty();
+        let resolutions = self.inner.sink_reqs(reqs);
+        if let Some(recorder) = self.recorder.as_ref().filter(|_| had_reqs) {
+            recorder.record(StoredWFStateInputSer::ImmediateLASinkResolutions(
+                &resolutions,
+            ));
+        }
+        resolutions
+    }
+}
+
+/// Hands back the recorded immediate resolutions, in the order they were recorded
+struct ReplayingLaReqSink {
+    resolutions: Mutex<VecDeque<Vec<LocalActivityResolution>>>,
+}
+
+impl LocalActivityRequestSink for ReplayingLaReqSink {
+    fn sink_reqs(&self, reqs: Vec<LocalActRequest>) -> Vec<LocalActivityResolution> {
+        if reqs.is_empty() {
+            return vec![];
+        }
+        self.resolutions
+            .lock()
+            .pop_front()
+            .expect("Local activities were requested, but no resolutions were recorded")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_help::{canned_histories, mock_worker, single_hist_mock_sg, test_worker_cfg},
+        worker::{client::mocks::mock_workflow_client, workflow::ActivationOrAuto},
+    };
+    use std::time::Duration;
+    use temporal_sdk_core_api::Worker as WorkerTrait;
+    use temporal_sdk_core_protos::coresdk::{
+        workflow_commands::CompleteWorkflowExecution,
+        workflow_completion::WorkflowActivationCompletion,
+    };
+    use temporal_sdk_core_test_utils::start_timer_cmd;
+
+    #[tokio::test]
+    async fn recorded_inputs_replay() {
+        let path = std::env::temp_dir().join(format!("wf-inputs-{}", uuid::Uuid::new_v4()));
+        let t = canned_histories::single_timer("1");
+        let mut mock = single_hist_mock_sg("fake_wf_id", t, [1, 2], mock_workflow_client(), true);
+        mock.worker_cfg(|wc| {
+            wc.record_workflow_inputs_to = Some(path.clone());
+            wc.max_cached_workflows = 10;
+        });
+        let core = mock_worker(mock);
+
+        let mut activations = vec![];
+        let act = core.poll_workflow_activation().await.unwrap();
+        core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+            act.run_id.clone(),
+            start_timer_cmd(1, Duration::from_secs(1)),
+        ))
+        .await
+        .unwrap();
+        activations.push(act);
+        let act = core.poll_workflow_activation().await.unwrap();
+        core.complete_workflow_activation(WorkflowActivationCompletion::from_cmd(
+            act.run_id.clone(),
+            CompleteWorkflowExecution { result: None }.into(),
+        ))
+        .await
+        .unwrap();
+        activations.push(act);
+        core.shutdown().await;
+
+        let recording = fs::read(&path).unwrap();
+        let (first, _) = split_record(&recording).unwrap();
+        assert_matches!(
+            rmp_serde::from_slice(first).unwrap(),
+            StoredWFStateInputDeSer::Basics(RecordedBasics {
+                max_cached_workflows: 10,
+                ..
+            })
+        );
+        let mut num_records = 0;
+        let mut rest = recording.as_slice();
+        while let Some((_, tail)) = split_record(rest) {
+            num_records += 1;
+            rest = tail;
+        }
+        // At least both tasks and both completions
+        assert!(num_records >= 4);
+        assert!(rest.is_empty());
+
+        // The recorded settings win over the ones given for replay
+        let replay_cfg = test_worker_cfg()
+            .max_cached_workflows(0_usize)
+            .build()
+            .unwrap();
+        let outputs: Vec<_> = replay_stream(replay_cfg, &path).unwrap().collect().await;
+        // Evictions queued during shutdown are never polled by lang, so aren't compared
+        let replayed: Vec<_> = outputs
+            .into_iter()
+            .flat_map(|o| o.unwrap().activations)
+            .filter_map(|a| match a {
+                ActivationOrAuto::LangActivation(act) if !act.is_only_eviction() => Some(act),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(replayed, activations);
+        let _ = fs::remove_file(path);
+    }
+}
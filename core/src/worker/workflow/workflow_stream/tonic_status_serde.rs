@@ -0,0 +1,31 @@
+//! Serde support for [tonic::Status], which only keeps its code and message
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Serialize, Deserialize)]
+struct SerdeStatus {
+    code: i32,
+    message: String,
+}
+
+pub(super) fn serialize<S>(status: &tonic::Status, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    SerdeStatus {
+        code: status.code() as i32,
+        message: status.message().to_owned(),
+    }
+    .serialize(serializer)
+}
+
+pub(super) fn deserialize<'de, D>(deserializer: D) -> Result<tonic::Status, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let status = SerdeStatus::deserialize(deserializer)?;
+    Ok(tonic::Status::new(
+        tonic::Code::from(status.code),
+        status.message,
+    ))
+}
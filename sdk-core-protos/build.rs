@@ -7,10 +7,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("cargo:rerun-if-changed=./protos");
     let out = PathBuf::from(env::var("OUT_DIR").unwrap());
     let descriptor_file = out.join("descriptors.bin");
+    // Servers are only used by test utilities which stand in for a real Temporal server, and
+    // which implement just the subset of RPCs they need, so most users don't need them built.
+    let build_servers = env::var("CARGO_FEATURE_SERVERS").is_ok();
     tonic_build::configure()
-        // We don't actually want to build the grpc definitions - we don't need them (for now).
-        // Just build the message structs.
-        .build_server(false)
+        .build_server(build_servers)
+        .generate_default_stubs(build_servers)
         .build_client(true)
         // Make conversions easier for some types
         .type_attribute(
@@ -126,6 +128,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 "./protos/api_cloud_upstream/temporal/api/cloud/cloudservice/v1/service.proto",
                 "./protos/testsrv_upstream/temporal/api/testservice/v1/service.proto",
                 "./protos/grpc/health/v1/health.proto",
+                "./protos/api_upstream/temporal/api/errordetails/v1/message.proto",
+                "./protos/google/rpc/status.proto",
             ],
             &[
                 "./protos/api_upstream",
@@ -133,6 +137,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 "./protos/local",
                 "./protos/testsrv_upstream",
                 "./protos/grpc",
+                // Only for google/rpc, so must come last to avoid shadowing the paths above
+                "./protos",
             ],
         )?;
 
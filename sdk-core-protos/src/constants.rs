@@ -5,3 +5,11 @@ pub const PATCH_MARKER_NAME: &str = "core_patch";
 
 /// Used as `marker_name` field when recording local activity markers
 pub const LOCAL_ACTIVITY_MARKER_NAME: &str = "core_local_activity";
+
+/// Name of the built-in query which returns a description of what the workflow is currently
+/// blocked on
+pub const STACK_TRACE_QUERY_NAME: &str = "__stack_trace";
+
+/// Name of the built-in query which returns the workflow's `WorkflowMetadata`, including the
+/// queries, signals, and updates it currently has handlers for
+pub const WORKFLOW_METADATA_QUERY_NAME: &str = "__temporal_workflow_metadata";
@@ -0,0 +1,257 @@
+//! Reading and writing histories in the proto3 JSON format used by the Temporal UI and CLI, so
+//! that exported histories can be replayed.
+
+use crate::{ENCODING_PAYLOAD_KEY, JSON_ENCODING_VAL, temporal::api::history::v1::History};
+use anyhow::anyhow;
+use base64::{Engine, prelude::BASE64_STANDARD};
+use prost::Message;
+use prost_reflect::{
+    DescriptorPool, DeserializeOptions, DynamicMessage, EnumDescriptor, Kind, MessageDescriptor,
+    SerializeOptions,
+};
+use serde_json::Value;
+use std::sync::LazyLock;
+
+type Result<T, E = anyhow::Error> = std::result::Result<T, E>;
+
+static DESCRIPTOR_POOL: LazyLock<DescriptorPool> = LazyLock::new(|| {
+    DescriptorPool::decode(include_bytes!(concat!(env!("OUT_DIR"), "/descriptors.bin")).as_ref())
+        .expect("Descriptors generated at build time must be valid")
+});
+static HISTORY_MESSAGE: &str = "temporal.api.history.v1.History";
+static PAYLOAD_MESSAGE: &str = "temporal.api.common.v1.Payload";
+
+/// Parses a history from JSON, as exported by the Temporal UI or by
+/// `temporal workflow show -o json`.
+///
+/// Enum values may be spelled either with their full proto names, ex:
+/// `EVENT_TYPE_WORKFLOW_EXECUTION_STARTED`, or with the shorter spelling newer tooling emits, ex:
+/// `WorkflowExecutionStarted`. Payloads which were rendered as plain JSON values rather than as
+/// `metadata` and `data` are turned back into `json/plain` payloads.
+pub fn history_from_json(json: &str) -> Result<History> {
+    history_from_json_value(serde_json::from_str(json)?)
+}
+
+/// Like [history_from_json], but accepts already-parsed JSON
+pub fn history_from_json_value(mut value: Value) -> Result<History> {
+    // Some tools export just the list of events
+    if value.is_array() {
+        value = serde_json::json!({ "events": value });
+    }
+    let desc = history_descriptor()?;
+    normalize_message(&desc, &mut value);
+    let dynamic = DynamicMessage::deserialize_with_options(
+        desc,
+        value,
+        &DeserializeOptions::new().deny_unknown_fields(false),
+    )?;
+    Ok(dynamic.transcode_to()?)
+}
+
+/// Serializes a history to pretty-printed proto3 JSON. Enum values are written with their full
+/// proto names, which every version of the Temporal tooling (and [history_from_json]) accepts.
+pub fn history_to_json(history: &History) -> Result<String> {
+    let dynamic =
+        DynamicMessage::decode(history_descriptor()?, history.encode_to_vec().as_slice())?;
+    let mut out = vec![];
+    let mut serializer = serde_json::Serializer::pretty(&mut out);
+    dynamic.serialize_with_options(&mut serializer, &SerializeOptions::new())?;
+    Ok(String::from_utf8(out)?)
+}
+
+fn history_descriptor() -> Result<MessageDescriptor> {
+    DESCRIPTOR_POOL
+        .get_message_by_name(HISTORY_MESSAGE)
+        .ok_or_else(|| anyhow!("History descriptor is missing"))
+}
+
+/// Rewrites the parts of the JSON which are not standard proto3 JSON, using the message's
+/// descriptor to know which values are enums or payloads.
+fn normalize_message(desc: &MessageDescriptor, value: &mut Value) {
+    // Well known types have special JSON representations which are already standard
+    if desc.package_name() == "google.protobuf" {
+        return;
+    }
+    if desc.full_name() == PAYLOAD_MESSAGE {
+        normalize_payload(value);
+        return;
+    }
+    let Value::Object(fields) = value else {
+        return;
+    };
+    for (name, field_val) in fields.iter_mut() {
+        let Some(field) = desc
+            .get_field_by_json_name(name)
+            .or_else(|| desc.get_field_by_name(name))
+        else {
+            continue;
+        };
+        if field.is_map() {
+            let Kind::Message(entry) = field.kind() else {
+                continue;
+            };
+            let value_kind = entry.map_entry_value_field().kind();
+            if let Value::Object(entries) = field_val {
+                entries
+                    .values_mut()
+                    .for_each(|v| normalize_value(&value_kind, v));
+            }
+        } else if field.is_list() {
+            if let Value::Array(elements) = field_val {
+                let kind = field.kind();
+                elements.iter_mut().for_each(|v| normalize_value(&kind, v));
+            }
+        } else {
+            normalize_value(&field.kind(), field_val);
+        }
+    }
+}
+
+fn normalize_value(kind: &Kind, value: &mut Value) {
+    match kind {
+        Kind::Message(desc) => normalize_message(desc, value),
+        Kind::Enum(desc) => normalize_enum(desc, value),
+        _ => {}
+    }
+}
+
+/// Newer tooling drops the enum's name prefix and uses PascalCase, ex: `WorkflowExecutionStarted`
+/// rather than `EVENT_TYPE_WORKFLOW_EXECUTION_STARTED`.
+fn normalize_enum(desc: &EnumDescriptor, value: &mut Value) {
+    let Value::String(name) = value else {
+        return;
+    };
+    if desc.get_value_by_name(name).is_some() {
+        return;
+    }
+    let full_name = format!(
+        "{}_{}",
+        screaming_snake_case(desc.name()),
+        screaming_snake_case(name)
+    );
+    if desc.get_value_by_name(&full_name).is_some() {
+        *name = full_name;
+    }
+}
+
+/// Payloads may be rendered as the JSON value they contain, rather than as metadata and
+/// base64-encoded data.
+fn normalize_payload(value: &mut Value) {
+    let is_standard = match value {
+        Value::Object(fields) => fields.keys().all(|k| k == "metadata" || k == "data"),
+        _ => false,
+    };
+    if is_standard {
+        return;
+    }
+    let data = serde_json::to_vec(value).expect("Serializing a JSON value can't fail");
+    *value = serde_json::json!({
+        "metadata": { ENCODING_PAYLOAD_KEY: BASE64_STANDARD.encode(JSON_ENCODING_VAL) },
+        "data": BASE64_STANDARD.encode(data),
+    });
+}
+
+fn screaming_snake_case(s: &str) -> String {
+    let chars: Vec<char> = s.chars().collect();
+    let mut out = String::with_capacity(s.len() + 8);
+    for (i, c) in chars.iter().enumerate() {
+        if i > 0 && c.is_uppercase() {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            if prev.is_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_uppercase() && next_is_lower)
+            {
+                out.push('_');
+            }
+        }
+        out.extend(c.to_uppercase());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temporal::api::{enums::v1::EventType, history::v1::history_event::Attributes};
+
+    static LEGACY: &str = r#"{
+      "events": [
+        {
+          "eventId": "1",
+          "eventTime": "2024-01-01T00:00:00Z",
+          "eventType": "EVENT_TYPE_WORKFLOW_EXECUTION_STARTED",
+          "workflowExecutionStartedEventAttributes": {
+            "workflowType": { "name": "wf" },
+            "taskQueue": { "name": "q", "kind": "TASK_QUEUE_KIND_NORMAL" },
+            "input": { "payloads": [ { "metadata": { "encoding": "anNvbi9wbGFpbg==" }, "data": "ImhpIg==" } ] },
+            "workflowTaskTimeout": "10s",
+            "originalExecutionRunId": "run"
+          }
+        },
+        {
+          "eventId": "2",
+          "eventType": "EVENT_TYPE_WORKFLOW_TASK_SCHEDULED",
+          "workflowTaskScheduledEventAttributes": { "taskQueue": { "name": "q" } }
+        }
+      ]
+    }"#;
+
+    static SHORTHAND: &str = r#"{
+      "events": [
+        {
+          "eventId": "1",
+          "eventTime": "2024-01-01T00:00:00Z",
+          "eventType": "WorkflowExecutionStarted",
+          "workflowExecutionStartedEventAttributes": {
+            "workflowType": { "name": "wf" },
+            "taskQueue": { "name": "q", "kind": "Normal" },
+            "input": { "payloads": [ "hi" ] },
+            "workflowTaskTimeout": "10s",
+            "originalExecutionRunId": "run"
+          }
+        },
+        {
+          "eventId": "2",
+          "eventType": "WorkflowTaskScheduled",
+          "workflowTaskScheduledEventAttributes": { "taskQueue": { "name": "q" } }
+        }
+      ]
+    }"#;
+
+    #[test]
+    fn both_dialects_parse_the_same() {
+        let legacy = history_from_json(LEGACY).unwrap();
+        let shorthand = history_from_json(SHORTHAND).unwrap();
+        assert_eq!(legacy, shorthand);
+        assert_eq!(legacy.events.len(), 2);
+        assert_eq!(
+            legacy.events[1].event_type(),
+            EventType::WorkflowTaskScheduled
+        );
+        let Some(Attributes::WorkflowExecutionStartedEventAttributes(attrs)) =
+            &legacy.events[0].attributes
+        else {
+            panic!("First event must be workflow execution started");
+        };
+        assert_eq!(attrs.input.as_ref().unwrap().payloads[0].data, b"\"hi\"");
+    }
+
+    #[test]
+    fn round_trips() {
+        let history = history_from_json(SHORTHAND).unwrap();
+        let json = history_to_json(&history).unwrap();
+        assert!(json.contains("EVENT_TYPE_WORKFLOW_EXECUTION_STARTED"));
+        assert_eq!(history_from_json(&json).unwrap(), history);
+    }
+
+    #[test]
+    fn converts_enum_spellings() {
+        assert_eq!(
+            screaming_snake_case("WorkflowExecutionStarted"),
+            "WORKFLOW_EXECUTION_STARTED"
+        );
+        assert_eq!(screaming_snake_case("EventType"), "EVENT_TYPE");
+        assert_eq!(screaming_snake_case("HTTPServer"), "HTTP_SERVER");
+    }
+}
@@ -9,6 +9,8 @@ pub mod utilities;
 mod history_builder;
 #[cfg(feature = "history_builders")]
 mod history_info;
+#[cfg(feature = "history_builders")]
+mod history_json;
 mod task_token;
 
 #[cfg(feature = "history_builders")]
@@ -18,6 +20,8 @@ pub use history_builder::{
 };
 #[cfg(feature = "history_builders")]
 pub use history_info::HistoryInfo;
+#[cfg(feature = "history_builders")]
+pub use history_json::{history_from_json, history_from_json_value, history_to_json};
 pub use task_token::TaskToken;
 
 pub static ENCODING_PAYLOAD_KEY: &str = "encoding";
@@ -484,6 +488,7 @@ pub mod coresdk {
                     workflow_activation_job::Variant::RemoveFromCache(RemoveFromCache {
                         message,
                         reason: reason as i32,
+                        nondeterminism: None,
                     }),
                 )],
                 available_internal_flags: vec![],
@@ -525,6 +530,43 @@ pub mod coresdk {
                     }
                 })
             }
+
+            /// Returns the nondeterminism diagnostic if this activation is an eviction caused by
+            /// core detecting nondeterminism
+            pub fn nondeterminism_diagnostic(&self) -> Option<&NondeterminismDiagnostic> {
+                self.jobs.iter().find_map(|j| {
+                    if let Some(workflow_activation_job::Variant::RemoveFromCache(ref rj)) =
+                        j.variant
+                    {
+                        rj.nondeterminism.as_ref()
+                    } else {
+                        None
+                    }
+                })
+            }
+        }
+
+        impl Display for NondeterminismDiagnostic {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                write!(f, "NondeterminismDiagnostic({}", self.message)?;
+                if self.workflow_task_number > 0 {
+                    write!(f, ", workflow task: {}", self.workflow_task_number)?;
+                }
+                if self.event_id > 0 {
+                    write!(f, ", event id: {}", self.event_id)?;
+                }
+                if !self.machine.is_empty() {
+                    write!(f, ", machine: {}", self.machine)?;
+                }
+                if !self.expected.is_empty() {
+                    write!(f, ", expected: {}", self.expected)?;
+                }
+                if self.actual.is_empty() {
+                    write!(f, ", actual: <no command>)")
+                } else {
+                    write!(f, ", actual: {})", self.actual)
+                }
+            }
         }
 
         impl workflow_activation_job::Variant {
@@ -545,6 +587,9 @@ pub mod coresdk {
                     EvictionReason::Nondeterminism => {
                         WorkflowTaskFailedCause::NonDeterministicError
                     }
+                    EvictionReason::DeadlockDetected => {
+                        WorkflowTaskFailedCause::WorkflowWorkerUnhandledFailure
+                    }
                     _ => WorkflowTaskFailedCause::Unspecified,
                 }
             }
@@ -2069,6 +2114,11 @@ pub mod temporal {
                 tonic::include_proto!("temporal.api.deployment.v1");
             }
         }
+        pub mod errordetails {
+            pub mod v1 {
+                tonic::include_proto!("temporal.api.errordetails.v1");
+            }
+        }
         pub mod enums {
             pub mod v1 {
                 use crate::camel_case_to_screaming_snake;
@@ -2645,6 +2695,18 @@ pub mod grpc {
     }
 }
 
+#[allow(
+    clippy::all,
+    missing_docs,
+    rustdoc::broken_intra_doc_links,
+    rustdoc::bare_urls
+)]
+pub mod google {
+    pub mod rpc {
+        tonic::include_proto!("google.rpc");
+    }
+}
+
 /// Case conversion, used for json -> proto enum string conversion
 pub fn camel_case_to_screaming_snake(val: &str) -> String {
     let mut out = String::new();
@@ -40,6 +40,13 @@ impl WorkerInterceptor for FailOnNondeterminismInterceptor {
             activation.eviction_reason(),
             Some(EvictionReason::Nondeterminism)
         ) {
+            if let Some(diag) = activation.nondeterminism_diagnostic() {
+                bail!(
+                    "Workflow is being evicted because of nondeterminism! {} {}",
+                    diag,
+                    activation
+                );
+            }
             bail!(
                 "Workflow is being evicted because of nondeterminism! {}",
                 activation
@@ -429,6 +429,7 @@ impl WorkflowHalf {
                 // NOTE: Don't clone args if this gets ported to be a non-test rust worker
                 sw.arguments.clone(),
                 completions_tx.clone(),
+                common.worker.get_config().workflow_activation_deadline,
             );
             let jh = tokio::spawn(async move {
                 tokio::select! {
@@ -816,6 +817,7 @@ enum RustWfCmd {
     SubscribeChildWorkflowCompletion(CommandSubscribeChildWorkflowCompletion),
     SubscribeSignal(String, UnboundedSender<SignalData>),
     RegisterUpdate(String, UpdateFunctions),
+    RegisterQuery(String, BoxQueryHandlerFn),
     SubscribeNexusOperationCompletion {
         seq: u32,
         unblocker: oneshot::Sender<UnblockEvent>,
@@ -1094,6 +1096,36 @@ where
     }
 }
 
+/// Extra information attached to workflow queries
+#[derive(Clone)]
+pub struct QueryInfo {
+    /// The query's name
+    pub query_type: String,
+    /// Headers attached to the query
+    pub headers: HashMap<String, Payload>,
+}
+
+type BoxQueryHandlerFn = Box<dyn Fn(&QueryInfo, &Payload) -> Result<Payload, anyhow::Error> + Send>;
+/// Closures / functions which can be turned into query handler functions implement this trait
+pub trait IntoQueryHandlerFunc<Arg, Res> {
+    /// Consume the closure/fn pointer and turn it into a query handler
+    fn into_query_handler_fn(self) -> BoxQueryHandlerFn;
+}
+impl<A, F, R> IntoQueryHandlerFunc<A, R> for F
+where
+    A: FromJsonPayloadExt + Send,
+    F: (for<'a> Fn(&'a QueryInfo, A) -> Result<R, anyhow::Error>) + Send + 'static,
+    R: AsJsonPayloadExt,
+{
+    fn into_query_handler_fn(self) -> BoxQueryHandlerFn {
+        let wrapper = move |info: &QueryInfo, input: &Payload| match A::from_json_payload(input) {
+            Ok(deser) => (self)(info, deser).and_then(|r| r.as_json_payload()),
+            Err(e) => Err(e.into()),
+        };
+        Box::new(wrapper)
+    }
+}
+
 /// Attempts to turn caught panics into something printable
 fn panic_formatter(panic: Box<dyn Any>) -> Box<dyn Display> {
     _panic_formatter::<&str>(panic)
@@ -7,9 +7,10 @@ pub use options::{
 
 use crate::{
     CancelExternalWfResult, CancellableID, CancellableIDWithReason, CommandCreateRequest,
-    CommandSubscribeChildWorkflowCompletion, IntoUpdateHandlerFunc, IntoUpdateValidatorFunc,
-    NexusStartResult, RustWfCmd, SignalExternalWfResult, SupportsCancelReason, TimerResult,
-    UnblockEvent, Unblockable, UpdateFunctions, workflow_context::options::IntoWorkflowCommand,
+    CommandSubscribeChildWorkflowCompletion, IntoQueryHandlerFunc, IntoUpdateHandlerFunc,
+    IntoUpdateValidatorFunc, NexusStartResult, RustWfCmd, SignalExternalWfResult,
+    SupportsCancelReason, TimerResult, UnblockEvent, Unblockable, UpdateFunctions,
+    workflow_context::options::IntoWorkflowCommand,
 };
 use futures_util::{FutureExt, Stream, StreamExt, future::Shared, task::Context};
 use parking_lot::{RwLock, RwLockReadGuard};
@@ -368,6 +369,23 @@ impl WfContext {
         ))
     }
 
+    /// Register a query handler by providing the query name and a handler function. Query
+    /// handlers are synchronous and must not mutate workflow state or issue commands. Registering
+    /// a handler with a name that is already registered replaces the old handler.
+    ///
+    /// If the query is sent without arguments, the handler receives a JSON `null` input, so
+    /// handlers which take no meaningful input can accept `()` or an `Option`.
+    pub fn query_handler<Arg, Res>(
+        &self,
+        name: impl Into<String>,
+        handler: impl IntoQueryHandlerFunc<Arg, Res>,
+    ) {
+        self.send(RustWfCmd::RegisterQuery(
+            name.into(),
+            handler.into_query_handler_fn(),
+        ))
+    }
+
     /// Start a nexus operation
     pub fn start_nexus_operation(
         &self,
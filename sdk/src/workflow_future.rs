@@ -1,10 +1,11 @@
 use crate::{
-    CancellableID, RustWfCmd, SignalData, TimerResult, UnblockEvent, UpdateContext,
-    UpdateFunctions, UpdateInfo, WfContext, WfExitValue, WorkflowFunction, WorkflowResult,
-    panic_formatter,
+    BoxQueryHandlerFn, CancellableID, QueryInfo, RustWfCmd, SignalData, TimerResult, UnblockEvent,
+    UpdateContext, UpdateFunctions, UpdateInfo, WfContext, WfExitValue, WorkflowFunction,
+    WorkflowResult, panic_formatter,
 };
 use anyhow::{Context as AnyhowContext, Error, anyhow, bail};
 use futures_util::{FutureExt, future::BoxFuture};
+use prost::Message;
 use std::{
     collections::{HashMap, hash_map::Entry},
     future::Future,
@@ -13,26 +14,35 @@ use std::{
     pin::Pin,
     sync::mpsc::Receiver,
     task::{Context, Poll},
+    time::{Duration, Instant},
 };
 use temporal_sdk_core_protos::{
+    ENCODING_PAYLOAD_KEY,
+    constants::{STACK_TRACE_QUERY_NAME, WORKFLOW_METADATA_QUERY_NAME},
     coresdk::{
+        AsJsonPayloadExt,
         workflow_activation::{
-            FireTimer, NotifyHasPatch, ResolveActivity, ResolveChildWorkflowExecution,
-            ResolveChildWorkflowExecutionStart, WorkflowActivation, WorkflowActivationJob,
-            workflow_activation_job::Variant,
+            FireTimer, NotifyHasPatch, QueryWorkflow, ResolveActivity,
+            ResolveChildWorkflowExecution, ResolveChildWorkflowExecutionStart, WorkflowActivation,
+            WorkflowActivationJob, workflow_activation_job::Variant,
         },
         workflow_commands::{
             CancelChildWorkflowExecution, CancelSignalWorkflow, CancelTimer,
-            CancelWorkflowExecution, CompleteWorkflowExecution, FailWorkflowExecution,
-            RequestCancelActivity, RequestCancelExternalWorkflowExecution,
+            CancelWorkflowExecution, CompleteWorkflowExecution, FailWorkflowExecution, QueryResult,
+            QuerySuccess, RequestCancelActivity, RequestCancelExternalWorkflowExecution,
             RequestCancelLocalActivity, RequestCancelNexusOperation, ScheduleActivity,
-            ScheduleLocalActivity, StartTimer, UpdateResponse, WorkflowCommand, update_response,
-            workflow_command,
+            ScheduleLocalActivity, StartTimer, UpdateResponse, WorkflowCommand, query_result,
+            update_response, workflow_command,
         },
         workflow_completion,
         workflow_completion::{WorkflowActivationCompletion, workflow_activation_completion},
     },
-    temporal::api::{common::v1::Payload, failure::v1::Failure},
+    temporal::api::{
+        common::v1::Payload,
+        enums::v1::WorkflowTaskFailedCause,
+        failure::v1::Failure,
+        sdk::v1::{WorkflowDefinition, WorkflowInteractionDefinition, WorkflowMetadata},
+    },
     utilities::TryIntoOrNone,
 };
 use tokio::sync::{
@@ -52,6 +62,7 @@ impl WorkflowFunction {
         workflow_type: &str,
         args: Vec<Payload>,
         outgoing_completions: UnboundedSender<WorkflowActivationCompletion>,
+        activation_deadline: Option<Duration>,
     ) -> (
         impl Future<Output = WorkflowResult<Payload>> + use<>,
         UnboundedSender<WorkflowActivation>,
@@ -67,10 +78,12 @@ impl WorkflowFunction {
         let inner_fut = (self.wf_func)(wf_context.clone()).instrument(span);
         (
             WorkflowFuture {
+                workflow_type: workflow_type.to_string(),
                 wf_ctx: wf_context,
                 // We need to mark the workflow future as unconstrained, otherwise Tokio will impose
-                // an artificial limit on how many commands we can unblock in one poll round.
-                // TODO: Now we *need* deadlock detection or we could hose the whole system
+                // an artificial limit on how many commands we can unblock in one poll round. Core
+                // enforces the activation deadline (if configured) in case workflow code never
+                // yields, so a stuck run gets failed and evicted rather than silently hanging.
                 inner: tokio::task::unconstrained(inner_fut).fuse().boxed(),
                 incoming_commands: cmd_receiver,
                 outgoing_completions,
@@ -80,6 +93,8 @@ impl WorkflowFunction {
                 sig_chans: Default::default(),
                 updates: Default::default(),
                 update_futures: Default::default(),
+                queries: Default::default(),
+                activation_deadline,
             },
             tx,
         )
@@ -100,6 +115,8 @@ enum SigChanOrBuffer {
 }
 
 pub(crate) struct WorkflowFuture {
+    /// The type of the workflow being run
+    workflow_type: String,
     /// Future produced by calling the workflow function
     inner: BoxFuture<'static, WorkflowResult<Payload>>,
     /// Commands produced inside user's wf code
@@ -120,6 +137,10 @@ pub(crate) struct WorkflowFuture {
     updates: HashMap<String, UpdateFunctions>,
     /// Stores in-progress update futures
     update_futures: Vec<(String, BoxFuture<'static, Result<Payload, Error>>)>,
+    /// Maps query handlers by name to implementations
+    queries: HashMap<String, BoxQueryHandlerFn>,
+    /// If set, activations which take longer than this to process are reported as deadlocked
+    activation_deadline: Option<Duration>,
 }
 
 impl WorkflowFuture {
@@ -153,6 +174,22 @@ impl WorkflowFuture {
             .expect("Completion channel intact");
     }
 
+    fn fail_deadlocked_activation(&self, run_id: String, elapsed: Duration, deadline: Duration) {
+        let message = format!(
+            "[TMPRL1101] Potential deadlock detected: workflow activation took {elapsed:?} to \
+             process, exceeding the activation deadline of {deadline:?}. The workflow code is \
+             likely blocking or busy-looping without yielding."
+        );
+        warn!(run_id=%run_id, "{}", message);
+        self.outgoing_completions
+            .send(WorkflowActivationCompletion::fail(
+                run_id,
+                Failure::application_failure(message, false),
+                Some(WorkflowTaskFailedCause::WorkflowWorkerUnhandledFailure),
+            ))
+            .expect("Completion channel intact");
+    }
+
     fn send_completion(&self, run_id: String, activation_cmds: Vec<WorkflowCommand>) {
         self.outgoing_completions
             .send(WorkflowActivationCompletion {
@@ -210,10 +247,8 @@ impl WorkflowFuture {
                     self.wf_ctx.shared.write().random_seed = rs.randomness_seed;
                 }
                 Variant::QueryWorkflow(q) => {
-                    error!(
-                        "Queries are not implemented in the Rust SDK. Got query '{}'",
-                        q.query_type
-                    );
+                    outgoing_cmds
+                        .push(workflow_command::Variant::from(self.handle_query(q)).into());
                 }
                 Variant::CancelWorkflow(c) => {
                     // TODO: Cancel pending futures, etc
@@ -338,6 +373,113 @@ impl WorkflowFuture {
 
         Ok(false)
     }
+
+    /// Answer a query job, either with one of the built-in queries or a registered handler.
+    /// Queries without a registered handler, or whose handler errors or panics, are answered with
+    /// a failure.
+    fn handle_query(&self, q: QueryWorkflow) -> QueryResult {
+        let response = match q.query_type.as_str() {
+            STACK_TRACE_QUERY_NAME => self.stack_trace().as_json_payload(),
+            WORKFLOW_METADATA_QUERY_NAME => Ok(self.workflow_metadata_payload()),
+            _ => match self.queries.get(&q.query_type) {
+                Some(handler) => {
+                    // Queries sent without arguments are handed to the handler as JSON null,
+                    // which allows unit or optional argument types to deserialize successfully.
+                    let input = match q.arguments.into_iter().next() {
+                        Some(p) => p,
+                        None => ().as_json_payload().expect("Unit serializes"),
+                    };
+                    let info = QueryInfo {
+                        query_type: q.query_type,
+                        headers: q.headers,
+                    };
+                    match panic::catch_unwind(AssertUnwindSafe(|| handler(&info, &input))) {
+                        Ok(r) => r,
+                        Err(e) => Err(anyhow!("Panic in query handler {}", panic_formatter(e))),
+                    }
+                }
+                None => {
+                    let mut known: Vec<_> = self.queries.keys().map(String::as_str).collect();
+                    known.sort_unstable();
+                    Err(anyhow!(
+                        "No query handler registered for query type {}. Known query types: [{}]",
+                        q.query_type,
+                        known.join(", ")
+                    ))
+                }
+            },
+        };
+        QueryResult {
+            query_id: q.query_id,
+            variant: Some(match response {
+                Ok(response) => QuerySuccess {
+                    response: Some(response),
+                }
+                .into(),
+                Err(e) => query_result::Variant::Failed(e.into()),
+            }),
+        }
+    }
+
+    /// Rust futures don't have a stack we can capture, so the closest useful approximation is a
+    /// listing of everything the workflow is currently blocked on.
+    fn stack_trace(&self) -> String {
+        let mut blocked_on: Vec<_> = self
+            .command_status
+            .keys()
+            .map(|id| format!("{id:?}"))
+            .collect();
+        blocked_on.sort_unstable();
+        if !self.update_futures.is_empty() {
+            blocked_on.push(format!(
+                "{} in-progress update handler(s)",
+                self.update_futures.len()
+            ));
+        }
+        if blocked_on.is_empty() {
+            "Workflow is not blocked on any commands".to_string()
+        } else {
+            format!("Workflow is blocked on:\n{}", blocked_on.join("\n"))
+        }
+    }
+
+    fn workflow_metadata_payload(&self) -> Payload {
+        let metadata = WorkflowMetadata {
+            definition: Some(WorkflowDefinition {
+                r#type: self.workflow_type.clone(),
+                query_definitions: interaction_definitions(
+                    [STACK_TRACE_QUERY_NAME, WORKFLOW_METADATA_QUERY_NAME]
+                        .into_iter()
+                        .chain(self.queries.keys().map(String::as_str))
+                        .collect(),
+                ),
+                signal_definitions: interaction_definitions(
+                    self.sig_chans
+                        .iter()
+                        .filter(|(_, c)| matches!(c, SigChanOrBuffer::Chan(_)))
+                        .map(|(name, _)| name.as_str())
+                        .collect(),
+                ),
+                update_definitions: interaction_definitions(
+                    self.updates.keys().map(String::as_str).collect(),
+                ),
+            }),
+            current_details: "".to_string(),
+        };
+        Payload {
+            metadata: HashMap::from([
+                (
+                    ENCODING_PAYLOAD_KEY.to_string(),
+                    b"binary/protobuf".to_vec(),
+                ),
+                (
+                    "messageType".to_string(),
+                    b"temporal.api.sdk.v1.WorkflowMetadata".to_vec(),
+                ),
+            ]),
+            data: metadata.encode_to_vec(),
+        }
+    }
 }
 
 impl Future for WorkflowFuture {
@@ -357,6 +499,7 @@ impl Future for WorkflowFuture {
                 },
                 Poll::Pending => return Poll::Pending,
             };
+            let activation_start = Instant::now();
 
             let is_only_eviction = activation.is_only_eviction();
             let run_id = activation.run_id;
@@ -456,11 +599,16 @@ impl Future for WorkflowFuture {
                 continue;
             }
 
-            // TODO: deadlock detector
-            // Check if there's nothing to unblock and workflow has not completed.
-            // This is different from the assertion that was here before that checked that WF did
-            // not produce any commands which is completely viable in the case WF is waiting on
-            // multiple completions.
+            // If workflow code blocked rather than yielding for longer than the activation deadline,
+            // core will already have failed the activation and will drop whatever we send. Report
+            // the deadlock anyway, so that commands from a misbehaving workflow are never sent.
+            if let Some(deadline) = self.activation_deadline {
+                let elapsed = activation_start.elapsed();
+                if elapsed > deadline {
+                    self.fail_deadlocked_activation(run_id, elapsed, deadline);
+                    continue;
+                }
+            }
 
             self.send_completion(run_id, activation_cmds);
 
@@ -632,6 +780,9 @@ impl WorkflowFuture {
                 RustWfCmd::RegisterUpdate(name, impls) => {
                     self.updates.insert(name, impls);
                 }
+                RustWfCmd::RegisterQuery(name, handler) => {
+                    self.queries.insert(name, handler);
+                }
                 RustWfCmd::SubscribeNexusOperationCompletion { seq, unblocker } => {
                     self.command_status.insert(
                         CommandID::NexusOpComplete(seq),
@@ -688,6 +839,17 @@ enum CommandID {
     NexusOpComplete(u32),
 }
 
+fn interaction_definitions(mut names: Vec<&str>) -> Vec<WorkflowInteractionDefinition> {
+    names.sort_unstable();
+    names
+        .into_iter()
+        .map(|name| WorkflowInteractionDefinition {
+            name: name.to_string(),
+            description: "".to_string(),
+        })
+        .collect()
+}
+
 fn update_response(
     instance_id: String,
     resp: update_response::Response,
@@ -0,0 +1,1858 @@
+//! An in-memory stand-in for the parts of the Temporal `WorkflowService` that workers and clients
+//! use, served over a local socket. It lets tests run workflows end-to-end without downloading or
+//! spawning a real server.
+//!
+//! Supported: starting, signaling, querying, cancelling, terminating and describing workflows,
+//! fetching history (including waiting for the close event), workflow and activity task polling
+//! and completion, activity heartbeats and retries, timers, markers, search attribute / memo
+//! upserts, and continue-as-new.
+//!
+//! It also implements the `TestService` time skipping RPCs the same way Temporal's test server
+//! does: time skipping starts out locked, and while it is unlocked the server's clock jumps
+//! straight to the next timer or activity retry whenever no workflow or activity task is
+//! outstanding.
+//!
+//! Not supported: there is one implicit namespace (any name is accepted), no visibility APIs, and
+//! sticky queues never receive tasks, so every workflow task carries the full history. Workflow
+//! and activity timeouts are not enforced. Child workflows, external signals and cancels, updates
+//! and nexus operations are rejected.
+
+use parking_lot::Mutex;
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use temporal_sdk_core::{ClientOptions, ClientOptionsBuilder};
+use temporal_sdk_core_protos::{
+    temporal::api::{
+        command::v1::command,
+        common::v1::{Payloads, RetryPolicy, WorkflowExecution, WorkflowType},
+        enums::v1::{
+            HistoryEventFilterType, NamespaceState, QueryResultType, RetryState, TaskQueueKind,
+            WorkflowExecutionStatus, WorkflowTaskFailedCause,
+        },
+        failure::v1::{Failure, failure::FailureInfo},
+        history::v1::{
+            ActivityTaskCancelRequestedEventAttributes, ActivityTaskCanceledEventAttributes,
+            ActivityTaskCompletedEventAttributes, ActivityTaskFailedEventAttributes,
+            ActivityTaskScheduledEventAttributes, ActivityTaskStartedEventAttributes, History,
+            HistoryEvent, MarkerRecordedEventAttributes, TimerCanceledEventAttributes,
+            TimerFiredEventAttributes, TimerStartedEventAttributes,
+            UpsertWorkflowSearchAttributesEventAttributes,
+            WorkflowExecutionCancelRequestedEventAttributes,
+            WorkflowExecutionCanceledEventAttributes, WorkflowExecutionCompletedEventAttributes,
+            WorkflowExecutionContinuedAsNewEventAttributes, WorkflowExecutionFailedEventAttributes,
+            WorkflowExecutionSignaledEventAttributes, WorkflowExecutionStartedEventAttributes,
+            WorkflowExecutionTerminatedEventAttributes, WorkflowPropertiesModifiedEventAttributes,
+            WorkflowTaskCompletedEventAttributes, WorkflowTaskFailedEventAttributes,
+            WorkflowTaskScheduledEventAttributes, WorkflowTaskStartedEventAttributes,
+            history_event::Attributes,
+        },
+        namespace::v1::NamespaceInfo,
+        query::v1::WorkflowQuery,
+        taskqueue::v1::TaskQueue,
+        testservice::v1::{
+            GetCurrentTimeResponse, LockTimeSkippingRequest, LockTimeSkippingResponse,
+            SleepRequest, SleepResponse, SleepUntilRequest, UnlockTimeSkippingRequest,
+            UnlockTimeSkippingResponse,
+            test_service_server::{TestService, TestServiceServer},
+        },
+        workflow::v1::WorkflowExecutionInfo,
+        workflowservice::v1::{
+            workflow_service_server::{WorkflowService, WorkflowServiceServer},
+            *,
+        },
+    },
+    utilities::TryIntoOrNone,
+};
+use tokio::{
+    net::TcpListener,
+    sync::{Notify, oneshot},
+    task::JoinHandle,
+    time::Instant,
+};
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{Request, Response, Status, transport::Server};
+use url::Url;
+
+/// How long polls and history long-polls wait for something to happen before returning empty
+const LONG_POLL_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long a query waits for a worker to answer it
+const QUERY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A running in-memory server. Shut it down with [InMemoryServer::shutdown].
+pub struct InMemoryServer {
+    addr: SocketAddr,
+    shutdown_tx: oneshot::Sender<()>,
+    server_handle: JoinHandle<()>,
+    scheduler_handle: JoinHandle<()>,
+}
+
+impl InMemoryServer {
+    /// Starts a server listening on a random local port
+    pub async fn start() -> Result<Self, anyhow::Error> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(ServerState {
+            data: Mutex::new(ServerData {
+                time_skipping_locks: 1,
+                ..Default::default()
+            }),
+            changed: Notify::new(),
+        });
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+        let service = WorkflowServiceServer::new(InMemoryWorkflowService {
+            state: state.clone(),
+        });
+        let test_service = TestServiceServer::new(InMemoryTestService {
+            state: state.clone(),
+        });
+        let server_handle = tokio::spawn(async move {
+            if let Err(e) = Server::builder()
+                .add_service(service)
+                .add_service(test_service)
+                .serve_with_incoming_shutdown(TcpListenerStream::new(listener), async {
+                    shutdown_rx.await.ok();
+                })
+                .await
+            {
+                error!(error=?e, "In-memory server exited with error");
+            }
+        });
+        let scheduler_handle = tokio::spawn(state.run_scheduled_actions());
+        Ok(Self {
+            addr,
+            shutdown_tx,
+            server_handle,
+            scheduler_handle,
+        })
+    }
+
+    /// The url clients should connect to
+    pub fn target_url(&self) -> Url {
+        Url::parse(&format!("http://{}", self.addr)).expect("Socket address is a valid url")
+    }
+
+    /// Options for a client which connects to this server
+    pub fn client_options(&self) -> ClientOptions {
+        ClientOptionsBuilder::default()
+            .identity("in_memory_tester".to_string())
+            .target_url(self.target_url())
+            .client_name("temporal-core".to_string())
+            .client_version("0.1.0".to_string())
+            .build()
+            .expect("Client options are valid")
+    }
+
+    /// Stops serving and waits for the server to exit
+    pub async fn shutdown(self) {
+        let _ = self.shutdown_tx.send(());
+        self.scheduler_handle.abort();
+        let _ = self.server_handle.await;
+    }
+}
+
+struct InMemoryWorkflowService {
+    state: Arc<ServerState>,
+}
+
+struct InMemoryTestService {
+    state: Arc<ServerState>,
+}
+
+struct ServerState {
+    data: Mutex<ServerData>,
+    /// Notified whenever tasks are enqueued or histories change
+    changed: Notify,
+}
+
+impl ServerState {
+    /// Runs `f` against the server data, then wakes anyone waiting for changes
+    fn mutate<T>(&self, f: impl FnOnce(&mut ServerData) -> T) -> T {
+        let r = f(&mut self.data.lock());
+        self.changed.notify_waiters();
+        r
+    }
+
+    /// Repeatedly runs `check` until it produces something or the long poll times out
+    async fn long_poll<T>(&self, mut check: impl FnMut(&mut ServerData) -> Option<T>) -> Option<T> {
+        let deadline = Instant::now() + LONG_POLL_TIMEOUT;
+        loop {
+            let notified = self.changed.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if let Some(t) = check(&mut self.data.lock()) {
+                return Some(t);
+            }
+            if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                return None;
+            }
+        }
+    }
+
+    /// Fires timers and activity retries as they come due
+    async fn run_scheduled_actions(self: Arc<Self>) {
+        loop {
+            let notified = self.changed.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            let next_due = {
+                let mut data = self.data.lock();
+                let mut fired = data.fire_due_actions();
+                while data.time_skipping_locks == 0 && data.is_idle() {
+                    let Some(due_in) = data.next_action_due_in() else {
+                        break;
+                    };
+                    data.time_offset += due_in;
+                    fired |= data.fire_due_actions();
+                }
+                if fired {
+                    self.changed.notify_waiters();
+                }
+                data.next_action_due_in()
+            };
+            match next_due {
+                Some(due_in) => {
+                    tokio::select! {
+                        _ = tokio::time::sleep(due_in) => {}
+                        _ = notified => {}
+                    }
+                }
+                None => notified.await,
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct ServerData {
+    runs: HashMap<String, WorkflowRun>,
+    /// The most recent run id for each workflow id
+    current_runs: HashMap<String, String>,
+    workflow_queues: HashMap<String, VecDeque<WorkflowTaskDispatch>>,
+    activity_queues: HashMap<String, VecDeque<ActivityDispatch>>,
+    queries: HashMap<u64, oneshot::Sender<RespondQueryTaskCompletedRequest>>,
+    scheduled: BTreeMap<(SystemTime, u64), ScheduledAction>,
+    next_id: u64,
+    /// How far the server's clock has been skipped ahead of real time
+    time_offset: Duration,
+    /// Time only skips while this is zero
+    time_skipping_locks: u32,
+}
+
+enum WorkflowTaskDispatch {
+    Task {
+        run_id: String,
+    },
+    LegacyQuery {
+        run_id: String,
+        query_id: u64,
+        query: WorkflowQuery,
+    },
+}
+
+struct ActivityDispatch {
+    run_id: String,
+    scheduled_event_id: i64,
+}
+
+enum ScheduledAction {
+    FireTimer {
+        run_id: String,
+        timer_id: String,
+    },
+    RetryActivity {
+        run_id: String,
+        scheduled_event_id: i64,
+    },
+    /// Resolves a `TestService` sleep
+    Wake(oneshot::Sender<()>),
+}
+
+struct WorkflowRun {
+    workflow_id: String,
+    run_id: String,
+    first_execution_run_id: String,
+    workflow_type: String,
+    task_queue: String,
+    history: Vec<HistoryEvent>,
+    status: WorkflowExecutionStatus,
+    start_time: SystemTime,
+    close_time: Option<SystemTime>,
+    wft: Option<WorkflowTaskState>,
+    /// Events which arrived while a workflow task was running. They are written once it's done.
+    buffered: Vec<PendingEvent>,
+    /// The started event id of the last completed workflow task
+    last_completed_wft_started_id: i64,
+    wft_attempt: i32,
+    activities: HashMap<i64, ActivityState>,
+    /// Started event id of each running timer
+    timers: HashMap<String, i64>,
+}
+
+struct WorkflowTaskState {
+    scheduled_event_id: i64,
+    started_event_id: Option<i64>,
+}
+
+struct ActivityState {
+    attrs: ActivityTaskScheduledEventAttributes,
+    attempt: i32,
+    /// True while a worker holds the current attempt
+    running: bool,
+    cancel_requested_event_id: Option<i64>,
+    last_failure: Option<Failure>,
+}
+
+enum PendingEvent {
+    Plain(Attributes),
+    /// Activity results are written along with the activity's started event, and must reference
+    /// its id
+    ActivityClosed {
+        started: ActivityTaskStartedEventAttributes,
+        closed: Attributes,
+    },
+}
+
+impl ServerData {
+    fn next_id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    fn now(&self) -> SystemTime {
+        SystemTime::now() + self.time_offset
+    }
+
+    /// True when nothing but the passage of time can make progress: no workflow task, activity
+    /// attempt or query is waiting on a worker.
+    fn is_idle(&self) -> bool {
+        let retrying = |run_id: &str, scheduled_event_id: i64| {
+            self.scheduled.values().any(|a| {
+                matches!(a, ScheduledAction::RetryActivity { run_id: r, scheduled_event_id: s }
+                    if r == run_id && *s == scheduled_event_id)
+            })
+        };
+        self.queries.is_empty()
+            && self
+                .runs
+                .values()
+                .filter(|r| !r.is_closed())
+                .all(|r| r.wft.is_none() && r.activities.keys().all(|id| retrying(&r.run_id, *id)))
+    }
+
+    fn run(&mut self, execution: Option<&WorkflowExecution>) -> Result<&mut WorkflowRun, Status> {
+        let execution =
+            execution.ok_or_else(|| Status::invalid_argument("Workflow execution is required"))?;
+        let run_id = if execution.run_id.is_empty() {
+            self.current_runs
+                .get(&execution.workflow_id)
+                .cloned()
+                .unwrap_or_default()
+        } else {
+            execution.run_id.clone()
+        };
+        self.runs
+            .get_mut(&run_id)
+            .filter(|r| r.workflow_id == execution.workflow_id)
+            .ok_or_else(|| Status::not_found("Workflow execution not found"))
+    }
+
+    fn open_run(
+        &mut self,
+        execution: Option<&WorkflowExecution>,
+    ) -> Result<&mut WorkflowRun, Status> {
+        let run = self.run(execution)?;
+        if run.is_closed() {
+            return Err(Status::not_found("Workflow execution already completed"));
+        }
+        Ok(run)
+    }
+
+    fn start_run(
+        &mut self,
+        workflow_id: String,
+        mut attrs: WorkflowExecutionStartedEventAttributes,
+    ) -> String {
+        let run_id = format!("{:032x}", rand::random::<u128>());
+        attrs.original_execution_run_id.clone_from(&run_id);
+        if attrs.first_execution_run_id.is_empty() {
+            attrs.first_execution_run_id.clone_from(&run_id);
+        }
+        if attrs.attempt == 0 {
+            attrs.attempt = 1;
+        }
+        let now = self.now();
+        let mut run = WorkflowRun {
+            workflow_id: workflow_id.clone(),
+            run_id: run_id.clone(),
+            first_execution_run_id: attrs.first_execution_run_id.clone(),
+            workflow_type: attrs
+                .workflow_type
+                .as_ref()
+                .map(|wt| wt.name.clone())
+                .unwrap_or_default(),
+            task_queue: attrs
+                .task_queue
+                .as_ref()
+                .map(|tq| tq.name.clone())
+                .unwrap_or_default(),
+            history: vec![],
+            status: WorkflowExecutionStatus::Running,
+            start_time: now,
+            close_time: None,
+            wft: None,
+            buffered: vec![],
+            last_completed_wft_started_id: 0,
+            wft_attempt: 1,
+            activities: HashMap::new(),
+            timers: HashMap::new(),
+        };
+        run.add_event(now, attrs);
+        self.current_runs.insert(workflow_id, run_id.clone());
+        self.runs.insert(run_id.clone(), run);
+        self.schedule_wft(&run_id);
+        run_id
+    }
+
+    /// Writes a new workflow task scheduled event and makes the task available to pollers
+    fn schedule_wft(&mut self, run_id: &str) {
+        let now = self.now();
+        let Some(run) = self.runs.get_mut(run_id) else {
+            return;
+        };
+        if run.wft.is_some() || run.is_closed() {
+            return;
+        }
+        let scheduled_event_id = run.add_event(
+            now,
+            WorkflowTaskScheduledEventAttributes {
+                task_queue: Some(TaskQueue {
+                    name: run.task_queue.clone(),
+                    kind: TaskQueueKind::Normal as i32,
+                    normal_name: "".to_string(),
+                }),
+                attempt: run.wft_attempt,
+                ..Default::default()
+            },
+        );
+        run.wft = Some(WorkflowTaskState {
+            scheduled_event_id,
+            started_event_id: None,
+        });
+        let tq = run.task_queue.clone();
+        self.workflow_queues
+            .entry(tq)
+            .or_default()
+            .push_back(WorkflowTaskDispatch::Task {
+                run_id: run_id.to_string(),
+            });
+    }
+
+    /// Records an event which happened outside of workflow task processing, and schedules a new
+    /// workflow task so the workflow can react to it
+    fn deliver_event(&mut self, run_id: &str, event: PendingEvent) {
+        let now = self.now();
+        let Some(run) = self.runs.get_mut(run_id) else {
+            return;
+        };
+        if run.is_closed() {
+            return;
+        }
+        if run
+            .wft
+            .as_ref()
+            .is_some_and(|w| w.started_event_id.is_some())
+        {
+            run.buffered.push(event);
+        } else {
+            run.write_pending(now, event);
+            self.schedule_wft(run_id);
+        }
+    }
+
+    fn schedule_action(&mut self, at: SystemTime, action: ScheduledAction) {
+        let key = (at, self.next_id());
+        self.scheduled.insert(key, action);
+    }
+
+    fn next_action_due_in(&self) -> Option<Duration> {
+        let now = self.now();
+        self.scheduled
+            .keys()
+            .next()
+            .map(|(at, _)| at.duration_since(now).unwrap_or_default())
+    }
+
+    /// Performs any scheduled actions which are due, returning true if there were some
+    fn fire_due_actions(&mut self) -> bool {
+        let now = self.now();
+        let mut fired = false;
+        while let Some(entry) = self.scheduled.first_entry() {
+            if entry.key().0 > now {
+                break;
+            }
+            fired = true;
+            match entry.remove() {
+                ScheduledAction::FireTimer { run_id, timer_id } => {
+                    let Some(started_event_id) = self
+                        .runs
+                        .get_mut(&run_id)
+                        .and_then(|r| r.timers.remove(&timer_id))
+                    else {
+                        continue;
+                    };
+                    self.deliver_event(
+                        &run_id,
+                        PendingEvent::Plain(
+                            TimerFiredEventAttributes {
+                                timer_id,
+                                started_event_id,
+                            }
+                            .into(),
+                        ),
+                    );
+                }
+                ScheduledAction::RetryActivity {
+                    run_id,
+                    scheduled_event_id,
+                } => self.enqueue_activity(&run_id, scheduled_event_id),
+                ScheduledAction::Wake(tx) => {
+                    let _ = tx.send(());
+                }
+            }
+        }
+        fired
+    }
+
+    fn enqueue_activity(&mut self, run_id: &str, scheduled_event_id: i64) {
+        let Some(act) = self
+            .runs
+            .get(run_id)
+            .and_then(|r| r.activities.get(&scheduled_event_id))
+        else {
+            return;
+        };
+        let tq = act
+            .attrs
+            .task_queue
+            .as_ref()
+            .map(|tq| tq.name.clone())
+            .unwrap_or_default();
+        self.activity_queues
+            .entry(tq)
+            .or_default()
+            .push_back(ActivityDispatch {
+                run_id: run_id.to_string(),
+                scheduled_event_id,
+            });
+    }
+
+    fn poll_workflow_task(
+        &mut self,
+        task_queue: &str,
+        identity: &str,
+    ) -> Option<PollWorkflowTaskQueueResponse> {
+        let now = self.now();
+        loop {
+            let dispatch = self.workflow_queues.get_mut(task_queue)?.pop_front()?;
+            match dispatch {
+                WorkflowTaskDispatch::Task { run_id } => {
+                    let Some(run) = self.runs.get_mut(&run_id) else {
+                        continue;
+                    };
+                    let Some(scheduled_event_id) = run
+                        .wft
+                        .as_ref()
+                        .filter(|w| w.started_event_id.is_none())
+                        .map(|w| w.scheduled_event_id)
+                    else {
+                        continue;
+                    };
+                    if run.is_closed() {
+                        continue;
+                    }
+                    let started_event_id = run.add_event(
+                        now,
+                        WorkflowTaskStartedEventAttributes {
+                            scheduled_event_id,
+                            identity: identity.to_string(),
+                            ..Default::default()
+                        },
+                    );
+                    if let Some(wft) = run.wft.as_mut() {
+                        wft.started_event_id = Some(started_event_id);
+                    }
+                    let mut resp = run.poll_response(
+                        format!("wft/{run_id}/{scheduled_event_id}").into_bytes(),
+                        started_event_id,
+                    );
+                    resp.attempt = run.wft_attempt;
+                    resp.scheduled_time = Some(now.into());
+                    resp.started_time = Some(now.into());
+                    return Some(resp);
+                }
+                WorkflowTaskDispatch::LegacyQuery {
+                    run_id,
+                    query_id,
+                    query,
+                } => {
+                    let Some(run) = self.runs.get(&run_id) else {
+                        continue;
+                    };
+                    let mut resp = run.poll_response(
+                        format!("query/{query_id}").into_bytes(),
+                        run.last_completed_wft_started_id,
+                    );
+                    resp.query = Some(query);
+                    return Some(resp);
+                }
+            }
+        }
+    }
+
+    fn poll_activity_task(
+        &mut self,
+        namespace: &str,
+        task_queue: &str,
+    ) -> Option<PollActivityTaskQueueResponse> {
+        let now = self.now();
+        loop {
+            let dispatch = self.activity_queues.get_mut(task_queue)?.pop_front()?;
+            let Some(run) = self.runs.get_mut(&dispatch.run_id) else {
+                continue;
+            };
+            if run.is_closed() {
+                continue;
+            }
+            let execution = run.execution();
+            let workflow_type = run.workflow_type.clone();
+            let Some(act) = run.activities.get_mut(&dispatch.scheduled_event_id) else {
+                continue;
+            };
+            act.running = true;
+            let attrs = &act.attrs;
+            return Some(PollActivityTaskQueueResponse {
+                task_token: format!(
+                    "act/{}/{}/{}",
+                    dispatch.run_id, dispatch.scheduled_event_id, act.attempt
+                )
+                .into_bytes(),
+                workflow_namespace: namespace.to_string(),
+                workflow_type: Some(WorkflowType {
+                    name: workflow_type,
+                }),
+                workflow_execution: Some(execution),
+                activity_type: attrs.activity_type.clone(),
+                activity_id: attrs.activity_id.clone(),
+                header: attrs.header.clone(),
+                input: attrs.input.clone(),
+                scheduled_time: Some(now.into()),
+                current_attempt_scheduled_time: Some(now.into()),
+                started_time: Some(now.into()),
+                attempt: act.attempt,
+                schedule_to_close_timeout: attrs.schedule_to_close_timeout,
+                start_to_close_timeout: attrs.start_to_close_timeout,
+                heartbeat_timeout: attrs.heartbeat_timeout,
+                retry_policy: attrs.retry_policy.clone(),
+                ..Default::default()
+            });
+        }
+    }
+
+    fn complete_workflow_task(
+        &mut self,
+        req: RespondWorkflowTaskCompletedRequest,
+    ) -> Result<(), Status> {
+        let now = self.now();
+        let (run_id, scheduled_event_id) = parse_wft_token(&req.task_token)?;
+        if let Some(unsupported) = req.commands.iter().find_map(|c| match &c.attributes {
+            Some(
+                command::Attributes::ScheduleActivityTaskCommandAttributes(_)
+                | command::Attributes::RequestCancelActivityTaskCommandAttributes(_)
+                | command::Attributes::StartTimerCommandAttributes(_)
+                | command::Attributes::CancelTimerCommandAttributes(_)
+                | command::Attributes::RecordMarkerCommandAttributes(_)
+                | command::Attributes::UpsertWorkflowSearchAttributesCommandAttributes(_)
+                | command::Attributes::ModifyWorkflowPropertiesCommandAttributes(_)
+                | command::Attributes::CompleteWorkflowExecutionCommandAttributes(_)
+                | command::Attributes::FailWorkflowExecutionCommandAttributes(_)
+                | command::Attributes::CancelWorkflowExecutionCommandAttributes(_)
+                | command::Attributes::ContinueAsNewWorkflowExecutionCommandAttributes(_),
+            )
+            | None => None,
+            Some(other) => Some(other),
+        }) {
+            return Err(Status::unimplemented(format!(
+                "The in-memory server does not support command {unsupported}"
+            )));
+        }
+        let run = self.running_wft(&run_id, scheduled_event_id)?;
+        let started_event_id = run
+            .wft
+            .take()
+            .and_then(|w| w.started_event_id)
+            .unwrap_or_default();
+
+        let closes = req.commands.iter().any(|c| {
+            matches!(
+                c.attributes,
+                Some(
+                    command::Attributes::CompleteWorkflowExecutionCommandAttributes(_)
+                        | command::Attributes::FailWorkflowExecutionCommandAttributes(_)
+                        | command::Attributes::CancelWorkflowExecutionCommandAttributes(_)
+                        | command::Attributes::ContinueAsNewWorkflowExecutionCommandAttributes(_)
+                )
+            )
+        });
+        // Like the real server, refuse to close the workflow if it hasn't seen everything yet
+        if closes && !run.buffered.is_empty() {
+            run.add_event(
+                now,
+                WorkflowTaskFailedEventAttributes {
+                    scheduled_event_id,
+                    started_event_id,
+                    cause: WorkflowTaskFailedCause::UnhandledCommand as i32,
+                    identity: req.identity,
+                    ..Default::default()
+                },
+            );
+            run.flush_buffered(now);
+            self.schedule_wft(&run_id);
+            return Ok(());
+        }
+
+        let completed_event_id = run.add_event(
+            now,
+            WorkflowTaskCompletedEventAttributes {
+                scheduled_event_id,
+                started_event_id,
+                identity: req.identity.clone(),
+                binary_checksum: req.binary_checksum.clone(),
+                sdk_metadata: req.sdk_metadata.clone(),
+                metering_metadata: req.metering_metadata.clone(),
+                ..Default::default()
+            },
+        );
+        run.last_completed_wft_started_id = started_event_id;
+        run.wft_attempt = 1;
+
+        let mut activities_to_enqueue = vec![];
+        let mut timers_to_start: Vec<(String, Duration)> = vec![];
+        let mut continue_as_new = None;
+        for cmd in req.commands {
+            if run.is_closed() {
+                break;
+            }
+            let Some(attrs) = cmd.attributes else {
+                continue;
+            };
+            match attrs {
+                command::Attributes::ScheduleActivityTaskCommandAttributes(a) => {
+                    let task_queue = a.task_queue.filter(|tq| !tq.name.is_empty()).or_else(|| {
+                        Some(TaskQueue {
+                            name: run.task_queue.clone(),
+                            kind: TaskQueueKind::Normal as i32,
+                            normal_name: "".to_string(),
+                        })
+                    });
+                    let scheduled = ActivityTaskScheduledEventAttributes {
+                        activity_id: a.activity_id,
+                        activity_type: a.activity_type,
+                        task_queue,
+                        header: a.header,
+                        input: a.input,
+                        schedule_to_close_timeout: a.schedule_to_close_timeout,
+                        schedule_to_start_timeout: a.schedule_to_start_timeout,
+                        start_to_close_timeout: a.start_to_close_timeout,
+                        heartbeat_timeout: a.heartbeat_timeout,
+                        workflow_task_completed_event_id: completed_event_id,
+                        retry_policy: a.retry_policy,
+                        ..Default::default()
+                    };
+                    let scheduled_event_id = run.add_event(now, scheduled.clone());
+                    run.activities.insert(
+                        scheduled_event_id,
+                        ActivityState {
+                            attrs: scheduled,
+                            attempt: 1,
+                            running: false,
+                            cancel_requested_event_id: None,
+                            last_failure: None,
+                        },
+                    );
+                    activities_to_enqueue.push(scheduled_event_id);
+                }
+                command::Attributes::RequestCancelActivityTaskCommandAttributes(a) => {
+                    let cancel_requested_event_id = run.add_event(
+                        now,
+                        ActivityTaskCancelRequestedEventAttributes {
+                            scheduled_event_id: a.scheduled_event_id,
+                            workflow_task_completed_event_id: completed_event_id,
+                        },
+                    );
+                    let running = match run.activities.get_mut(&a.scheduled_event_id) {
+                        Some(act) => {
+                            act.cancel_requested_event_id = Some(cancel_requested_event_id);
+                            act.running
+                        }
+                        None => continue,
+                    };
+                    // Activities no worker has picked up yet are cancelled immediately
+                    if !running {
+                        run.activities.remove(&a.scheduled_event_id);
+                        run.buffered.push(PendingEvent::Plain(
+                            ActivityTaskCanceledEventAttributes {
+                                latest_cancel_requested_event_id: cancel_requested_event_id,
+                                scheduled_event_id: a.scheduled_event_id,
+                                ..Default::default()
+                            }
+                            .into(),
+                        ));
+                    }
+                }
+                command::Attributes::StartTimerCommandAttributes(a) => {
+                    let started_event_id = run.add_event(
+                        now,
+                        TimerStartedEventAttributes {
+                            timer_id: a.timer_id.clone(),
+                            start_to_fire_timeout: a.start_to_fire_timeout,
+                            workflow_task_completed_event_id: completed_event_id,
+                        },
+                    );
+                    run.timers.insert(a.timer_id.clone(), started_event_id);
+                    timers_to_start.push((
+                        a.timer_id,
+                        a.start_to_fire_timeout
+                            .try_into_or_none()
+                            .unwrap_or_default(),
+                    ));
+                }
+                command::Attributes::CancelTimerCommandAttributes(a) => {
+                    if let Some(started_event_id) = run.timers.remove(&a.timer_id) {
+                        run.add_event(
+                            now,
+                            TimerCanceledEventAttributes {
+                                timer_id: a.timer_id,
+                                started_event_id,
+                                workflow_task_completed_event_id: completed_event_id,
+                                identity: req.identity.clone(),
+                            },
+                        );
+                    }
+                }
+                command::Attributes::RecordMarkerCommandAttributes(a) => {
+                    run.add_event(
+                        now,
+                        MarkerRecordedEventAttributes {
+                            marker_name: a.marker_name,
+                            details: a.details,
+                            workflow_task_completed_event_id: completed_event_id,
+                            header: a.header,
+                            failure: a.failure,
+                        },
+                    );
+                }
+                command::Attributes::UpsertWorkflowSearchAttributesCommandAttributes(a) => {
+                    run.add_event(
+                        now,
+                        UpsertWorkflowSearchAttributesEventAttributes {
+                            workflow_task_completed_event_id: completed_event_id,
+                            search_attributes: a.search_attributes,
+                        },
+                    );
+                }
+                command::Attributes::ModifyWorkflowPropertiesCommandAttributes(a) => {
+                    run.add_event(
+                        now,
+                        WorkflowPropertiesModifiedEventAttributes {
+                            workflow_task_completed_event_id: completed_event_id,
+                            upserted_memo: a.upserted_memo,
+                        },
+                    );
+                }
+                command::Attributes::CompleteWorkflowExecutionCommandAttributes(a) => {
+                    run.add_event(
+                        now,
+                        WorkflowExecutionCompletedEventAttributes {
+                            result: a.result,
+                            workflow_task_completed_event_id: completed_event_id,
+                            ..Default::default()
+                        },
+                    );
+                    run.close(now, WorkflowExecutionStatus::Completed);
+                }
+                command::Attributes::FailWorkflowExecutionCommandAttributes(a) => {
+                    run.add_event(
+                        now,
+                        WorkflowExecutionFailedEventAttributes {
+                            failure: a.failure,
+                            retry_state: RetryState::RetryPolicyNotSet as i32,
+                            workflow_task_completed_event_id: completed_event_id,
+                            ..Default::default()
+                        },
+                    );
+                    run.close(now, WorkflowExecutionStatus::Failed);
+                }
+                command::Attributes::CancelWorkflowExecutionCommandAttributes(a) => {
+                    run.add_event(
+                        now,
+                        WorkflowExecutionCanceledEventAttributes {
+                            workflow_task_completed_event_id: completed_event_id,
+                            details: a.details,
+                        },
+                    );
+                    run.close(now, WorkflowExecutionStatus::Canceled);
+                }
+                command::Attributes::ContinueAsNewWorkflowExecutionCommandAttributes(a) => {
+                    let new_run_started = WorkflowExecutionStartedEventAttributes {
+                        workflow_type: a.workflow_type.filter(|wt| !wt.name.is_empty()).or_else(
+                            || {
+                                Some(WorkflowType {
+                                    name: run.workflow_type.clone(),
+                                })
+                            },
+                        ),
+                        task_queue: a.task_queue.filter(|tq| !tq.name.is_empty()).or_else(|| {
+                            Some(TaskQueue {
+                                name: run.task_queue.clone(),
+                                kind: TaskQueueKind::Normal as i32,
+                                normal_name: "".to_string(),
+                            })
+                        }),
+                        input: a.input.clone(),
+                        workflow_run_timeout: a.workflow_run_timeout,
+                        workflow_task_timeout: a.workflow_task_timeout,
+                        continued_execution_run_id: run.run_id.clone(),
+                        initiator: a.initiator,
+                        continued_failure: a.failure.clone(),
+                        last_completion_result: a.last_completion_result.clone(),
+                        first_execution_run_id: run.first_execution_run_id.clone(),
+                        retry_policy: a.retry_policy,
+                        header: a.header.clone(),
+                        memo: a.memo.clone(),
+                        search_attributes: a.search_attributes.clone(),
+                        ..Default::default()
+                    };
+                    let event_id = run.add_event(
+                        now,
+                        WorkflowExecutionContinuedAsNewEventAttributes {
+                            workflow_type: new_run_started.workflow_type.clone(),
+                            task_queue: new_run_started.task_queue.clone(),
+                            input: a.input,
+                            workflow_run_timeout: a.workflow_run_timeout,
+                            workflow_task_timeout: a.workflow_task_timeout,
+                            workflow_task_completed_event_id: completed_event_id,
+                            backoff_start_interval: a.backoff_start_interval,
+                            initiator: a.initiator,
+                            failure: a.failure,
+                            last_completion_result: a.last_completion_result,
+                            header: a.header,
+                            memo: a.memo,
+                            search_attributes: a.search_attributes,
+                            ..Default::default()
+                        },
+                    );
+                    run.close(now, WorkflowExecutionStatus::ContinuedAsNew);
+                    continue_as_new = Some((event_id, new_run_started));
+                }
+                _ => unreachable!("Unsupported commands are rejected above"),
+            }
+        }
+
+        let workflow_id = run.workflow_id.clone();
+        let needs_wft =
+            !run.is_closed() && (req.force_create_new_workflow_task || !run.buffered.is_empty());
+        if run.is_closed() {
+            run.buffered.clear();
+        } else {
+            run.flush_buffered(now);
+        }
+
+        for (timer_id, fire_in) in timers_to_start {
+            self.schedule_action(
+                now + fire_in,
+                ScheduledAction::FireTimer {
+                    run_id: run_id.clone(),
+                    timer_id,
+                },
+            );
+        }
+        for scheduled_event_id in activities_to_enqueue {
+            self.enqueue_activity(&run_id, scheduled_event_id);
+        }
+        self.prune_scheduled(&run_id);
+        if needs_wft {
+            self.schedule_wft(&run_id);
+        }
+        if let Some((event_id, started)) = continue_as_new {
+            let new_run_id = self.start_run(workflow_id, started);
+            if let Some(Attributes::WorkflowExecutionContinuedAsNewEventAttributes(a)) = self
+                .runs
+                .get_mut(&run_id)
+                .and_then(|r| r.history.get_mut(event_id as usize - 1))
+                .and_then(|e| e.attributes.as_mut())
+            {
+                a.new_execution_run_id = new_run_id;
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes scheduled timer firings which no longer correspond to a running timer
+    fn prune_scheduled(&mut self, run_id: &str) {
+        let runs = &self.runs;
+        self.scheduled.retain(|_, action| match action {
+            ScheduledAction::FireTimer {
+                run_id: r,
+                timer_id,
+            } if r == run_id => runs
+                .get(r)
+                .is_some_and(|run| !run.is_closed() && run.timers.contains_key(timer_id)),
+            _ => true,
+        });
+    }
+
+    fn fail_workflow_task(&mut self, req: RespondWorkflowTaskFailedRequest) -> Result<(), Status> {
+        let now = self.now();
+        let (run_id, scheduled_event_id) = parse_wft_token(&req.task_token)?;
+        let run = self.running_wft(&run_id, scheduled_event_id)?;
+        let started_event_id = run
+            .wft
+            .take()
+            .and_then(|w| w.started_event_id)
+            .unwrap_or_default();
+        run.add_event(
+            now,
+            WorkflowTaskFailedEventAttributes {
+                scheduled_event_id,
+                started_event_id,
+                cause: req.cause,
+                failure: req.failure,
+                identity: req.identity,
+                binary_checksum: req.binary_checksum,
+                ..Default::default()
+            },
+        );
+        run.wft_attempt += 1;
+        run.flush_buffered(now);
+        self.schedule_wft(&run_id);
+        Ok(())
+    }
+
+    fn running_wft(
+        &mut self,
+        run_id: &str,
+        scheduled_event_id: i64,
+    ) -> Result<&mut WorkflowRun, Status> {
+        self.runs
+            .get_mut(run_id)
+            .filter(|r| {
+                !r.is_closed()
+                    && r.wft.as_ref().is_some_and(|w| {
+                        w.scheduled_event_id == scheduled_event_id && w.started_event_id.is_some()
+                    })
+            })
+            .ok_or_else(|| Status::not_found("Workflow task not found"))
+    }
+
+    fn running_activity(&mut self, task_token: &[u8]) -> Result<(&mut WorkflowRun, i64), Status> {
+        let (run_id, scheduled_event_id, attempt) = parse_activity_token(task_token)?;
+        let run = self
+            .runs
+            .get_mut(&run_id)
+            .filter(|r| !r.is_closed())
+            .ok_or_else(|| Status::not_found("Workflow execution already completed"))?;
+        if !run
+            .activities
+            .get(&scheduled_event_id)
+            .is_some_and(|a| a.attempt == attempt && a.running)
+        {
+            return Err(Status::not_found("Activity task not found"));
+        }
+        Ok((run, scheduled_event_id))
+    }
+
+    fn close_activity(
+        &mut self,
+        task_token: &[u8],
+        identity: String,
+        closed: impl FnOnce(i64, &ActivityState) -> Attributes,
+    ) -> Result<(), Status> {
+        let (run, scheduled_event_id) = self.running_activity(task_token)?;
+        let act = run
+            .activities
+            .remove(&scheduled_event_id)
+            .expect("Activity was just checked");
+        let run_id = run.run_id.clone();
+        let closed = closed(scheduled_event_id, &act);
+        self.deliver_event(
+            &run_id,
+            PendingEvent::ActivityClosed {
+                started: ActivityTaskStartedEventAttributes {
+                    scheduled_event_id,
+                    identity,
+                    attempt: act.attempt,
+                    last_failure: act.last_failure,
+                    ..Default::default()
+                },
+                closed,
+            },
+        );
+        Ok(())
+    }
+
+    fn fail_activity(&mut self, req: RespondActivityTaskFailedRequest) -> Result<(), Status> {
+        let now = self.now();
+        let (run, scheduled_event_id) = self.running_activity(&req.task_token)?;
+        let run_id = run.run_id.clone();
+        let act = run
+            .activities
+            .get_mut(&scheduled_event_id)
+            .expect("Activity was just checked");
+        match retry_delay(act.attrs.retry_policy.as_ref(), act.attempt, &req.failure) {
+            Ok(delay) if act.cancel_requested_event_id.is_none() => {
+                act.attempt += 1;
+                act.running = false;
+                act.last_failure = req.failure;
+                self.schedule_action(
+                    now + delay,
+                    ScheduledAction::RetryActivity {
+                        run_id,
+                        scheduled_event_id,
+                    },
+                );
+                Ok(())
+            }
+            res => {
+                let retry_state = res.err().unwrap_or(RetryState::CancelRequested);
+                self.close_activity(&req.task_token, req.identity, |_, _| {
+                    ActivityTaskFailedEventAttributes {
+                        failure: req.failure,
+                        scheduled_event_id,
+                        retry_state: retry_state as i32,
+                        ..Default::default()
+                    }
+                    .into()
+                })
+            }
+        }
+    }
+}
+
+impl WorkflowRun {
+    fn is_closed(&self) -> bool {
+        self.status != WorkflowExecutionStatus::Running
+    }
+
+    fn close(&mut self, now: SystemTime, status: WorkflowExecutionStatus) {
+        self.status = status;
+        self.close_time = Some(now);
+        self.wft = None;
+        self.activities.clear();
+        self.timers.clear();
+    }
+
+    fn execution(&self) -> WorkflowExecution {
+        WorkflowExecution {
+            workflow_id: self.workflow_id.clone(),
+            run_id: self.run_id.clone(),
+        }
+    }
+
+    fn add_event(&mut self, now: SystemTime, attrs: impl Into<Attributes>) -> i64 {
+        let attrs = attrs.into();
+        let event_id = self.history.len() as i64 + 1;
+        self.history.push(HistoryEvent {
+            event_id,
+            event_time: Some(now.into()),
+            event_type: attrs.event_type() as i32,
+            attributes: Some(attrs),
+            ..Default::default()
+        });
+        event_id
+    }
+
+    fn write_pending(&mut self, now: SystemTime, event: PendingEvent) {
+        match event {
+            PendingEvent::Plain(attrs) => {
+                self.add_event(now, attrs);
+            }
+            PendingEvent::ActivityClosed {
+                started,
+                mut closed,
+            } => {
+                let started_event_id = self.add_event(now, started);
+                match &mut closed {
+                    Attributes::ActivityTaskCompletedEventAttributes(a) => {
+                        a.started_event_id = started_event_id
+                    }
+                    Attributes::ActivityTaskFailedEventAttributes(a) => {
+                        a.started_event_id = started_event_id
+                    }
+                    Attributes::ActivityTaskCanceledEventAttributes(a) => {
+                        a.started_event_id = started_event_id
+                    }
+                    _ => {}
+                }
+                self.add_event(now, closed);
+            }
+        }
+    }
+
+    fn flush_buffered(&mut self, now: SystemTime) {
+        for event in std::mem::take(&mut self.buffered) {
+            self.write_pending(now, event);
+        }
+    }
+
+    fn poll_response(
+        &self,
+        task_token: Vec<u8>,
+        started_event_id: i64,
+    ) -> PollWorkflowTaskQueueResponse {
+        PollWorkflowTaskQueueResponse {
+            task_token,
+            workflow_execution: Some(self.execution()),
+            workflow_type: Some(WorkflowType {
+                name: self.workflow_type.clone(),
+            }),
+            previous_started_event_id: self.last_completed_wft_started_id,
+            started_event_id,
+            history: Some(History {
+                events: self.history.clone(),
+            }),
+            workflow_execution_task_queue: Some(TaskQueue {
+                name: self.task_queue.clone(),
+                kind: TaskQueueKind::Normal as i32,
+                normal_name: "".to_string(),
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn close_event(&self) -> Option<&HistoryEvent> {
+        self.history.last().filter(|_| self.is_closed())
+    }
+
+    fn info(&self) -> WorkflowExecutionInfo {
+        WorkflowExecutionInfo {
+            execution: Some(self.execution()),
+            r#type: Some(WorkflowType {
+                name: self.workflow_type.clone(),
+            }),
+            start_time: Some(self.start_time.into()),
+            close_time: self.close_time.map(Into::into),
+            status: self.status as i32,
+            history_length: self.history.len() as i64,
+            task_queue: self.task_queue.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Decides whether a failed activity attempt should be retried, returning how long to wait before
+/// doing so, or why not.
+fn retry_delay(
+    policy: Option<&RetryPolicy>,
+    attempt: i32,
+    failure: &Option<Failure>,
+) -> Result<Duration, RetryState> {
+    let default_policy = RetryPolicy::default();
+    let policy = policy.unwrap_or(&default_policy);
+    let app_failure = failure.as_ref().and_then(|f| match &f.failure_info {
+        Some(FailureInfo::ApplicationFailureInfo(af)) => Some(af),
+        _ => None,
+    });
+    if let Some(af) = app_failure {
+        if af.non_retryable
+            || policy
+                .non_retryable_error_types
+                .iter()
+                .any(|t| t.eq_ignore_ascii_case(&af.r#type))
+        {
+            return Err(RetryState::NonRetryableFailure);
+        }
+        if let Some(delay) = af.next_retry_delay.try_into_or_none() {
+            return Ok(delay);
+        }
+    }
+    if policy.maximum_attempts > 0 && attempt >= policy.maximum_attempts {
+        return Err(RetryState::MaximumAttemptsReached);
+    }
+    let initial: Duration = policy
+        .initial_interval
+        .try_into_or_none()
+        .unwrap_or(Duration::from_secs(1));
+    let max: Duration = policy
+        .maximum_interval
+        .try_into_or_none()
+        .unwrap_or_else(|| initial.saturating_mul(100));
+    let coefficient = if policy.backoff_coefficient != 0. {
+        policy.backoff_coefficient
+    } else {
+        2.0
+    };
+    let delay = initial.as_secs_f64() * coefficient.powi(attempt - 1);
+    Ok(Duration::try_from_secs_f64(delay).unwrap_or(max).min(max))
+}
+
+fn token_parts(task_token: &[u8], kind: &str, num_parts: usize) -> Result<Vec<String>, Status> {
+    let invalid = || Status::invalid_argument("Invalid task token");
+    let token = std::str::from_utf8(task_token).map_err(|_| invalid())?;
+    let parts: Vec<String> = token.split('/').map(ToString::to_string).collect();
+    if parts.len() != num_parts + 1 || parts[0] != kind {
+        return Err(invalid());
+    }
+    Ok(parts.into_iter().skip(1).collect())
+}
+
+fn parse_wft_token(task_token: &[u8]) -> Result<(String, i64), Status> {
+    let parts = token_parts(task_token, "wft", 2)?;
+    let scheduled_event_id = parts[1]
+        .parse()
+        .map_err(|_| Status::invalid_argument("Invalid task token"))?;
+    Ok((parts[0].clone(), scheduled_event_id))
+}
+
+fn parse_activity_token(task_token: &[u8]) -> Result<(String, i64, i32), Status> {
+    let parts = token_parts(task_token, "act", 3)?;
+    let invalid = |_| Status::invalid_argument("Invalid task token");
+    Ok((
+        parts[0].clone(),
+        parts[1].parse().map_err(invalid)?,
+        parts[2].parse().map_err(invalid)?,
+    ))
+}
+
+fn parse_query_token(task_token: &[u8]) -> Result<u64, Status> {
+    let parts = token_parts(task_token, "query", 1)?;
+    parts[0]
+        .parse()
+        .map_err(|_| Status::invalid_argument("Invalid task token"))
+}
+
+fn started_attributes_from_request(
+    req: &StartWorkflowExecutionRequest,
+) -> WorkflowExecutionStartedEventAttributes {
+    WorkflowExecutionStartedEventAttributes {
+        workflow_type: req.workflow_type.clone(),
+        task_queue: req.task_queue.clone(),
+        input: req.input.clone(),
+        workflow_execution_timeout: req.workflow_execution_timeout,
+        workflow_run_timeout: req.workflow_run_timeout,
+        workflow_task_timeout: req.workflow_task_timeout,
+        identity: req.identity.clone(),
+        header: req.header.clone(),
+        memo: req.memo.clone(),
+        search_attributes: req.search_attributes.clone(),
+        retry_policy: req.retry_policy.clone(),
+        cron_schedule: req.cron_schedule.clone(),
+        ..Default::default()
+    }
+}
+
+#[tonic::async_trait]
+impl WorkflowService for InMemoryWorkflowService {
+    async fn get_system_info(
+        &self,
+        _: Request<GetSystemInfoRequest>,
+    ) -> Result<Response<GetSystemInfoResponse>, Status> {
+        Ok(Response::new(GetSystemInfoResponse {
+            server_version: "in-memory".to_string(),
+            capabilities: Some(get_system_info_response::Capabilities {
+                signal_and_query_header: true,
+                internal_error_differentiation: true,
+                activity_failure_include_heartbeat: true,
+                encoded_failure_attributes: true,
+                upsert_memo: true,
+                sdk_metadata: true,
+                ..Default::default()
+            }),
+        }))
+    }
+
+    async fn describe_namespace(
+        &self,
+        req: Request<DescribeNamespaceRequest>,
+    ) -> Result<Response<DescribeNamespaceResponse>, Status> {
+        Ok(Response::new(DescribeNamespaceResponse {
+            namespace_info: Some(NamespaceInfo {
+                name: req.into_inner().namespace,
+                state: NamespaceState::Registered as i32,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }))
+    }
+
+    async fn start_workflow_execution(
+        &self,
+        req: Request<StartWorkflowExecutionRequest>,
+    ) -> Result<Response<StartWorkflowExecutionResponse>, Status> {
+        let req = req.into_inner();
+        let run_id = self.state.mutate(|d| {
+            if let Some(current) = d.current_runs.get(&req.workflow_id) {
+                if d.runs.get(current).is_some_and(|r| !r.is_closed()) {
+                    return Err(Status::already_exists("Workflow execution already started"));
+                }
+            }
+            Ok(d.start_run(
+                req.workflow_id.clone(),
+                started_attributes_from_request(&req),
+            ))
+        })?;
+        Ok(Response::new(StartWorkflowExecutionResponse {
+            run_id,
+            ..Default::default()
+        }))
+    }
+
+    async fn signal_with_start_workflow_execution(
+        &self,
+        req: Request<SignalWithStartWorkflowExecutionRequest>,
+    ) -> Result<Response<SignalWithStartWorkflowExecutionResponse>, Status> {
+        let req = req.into_inner();
+        let run_id = self.state.mutate(|d| {
+            let execution = WorkflowExecution {
+                workflow_id: req.workflow_id.clone(),
+                run_id: "".to_string(),
+            };
+            let run_id = match d.open_run(Some(&execution)) {
+                Ok(run) => run.run_id.clone(),
+                Err(_) => d.start_run(
+                    req.workflow_id.clone(),
+                    started_attributes_from_request(&StartWorkflowExecutionRequest {
+                        workflow_type: req.workflow_type.clone(),
+                        task_queue: req.task_queue.clone(),
+                        input: req.input.clone(),
+                        workflow_execution_timeout: req.workflow_execution_timeout,
+                        workflow_run_timeout: req.workflow_run_timeout,
+                        workflow_task_timeout: req.workflow_task_timeout,
+                        identity: req.identity.clone(),
+                        header: req.header.clone(),
+                        memo: req.memo.clone(),
+                        search_attributes: req.search_attributes.clone(),
+                        retry_policy: req.retry_policy.clone(),
+                        cron_schedule: req.cron_schedule.clone(),
+                        ..Default::default()
+                    }),
+                ),
+            };
+            d.deliver_event(
+                &run_id,
+                PendingEvent::Plain(
+                    WorkflowExecutionSignaledEventAttributes {
+                        signal_name: req.signal_name.clone(),
+                        input: req.signal_input.clone(),
+                        identity: req.identity.clone(),
+                        header: req.header.clone(),
+                        ..Default::default()
+                    }
+                    .into(),
+                ),
+            );
+            run_id
+        });
+        Ok(Response::new(SignalWithStartWorkflowExecutionResponse {
+            run_id,
+            ..Default::default()
+        }))
+    }
+
+    async fn signal_workflow_execution(
+        &self,
+        req: Request<SignalWorkflowExecutionRequest>,
+    ) -> Result<Response<SignalWorkflowExecutionResponse>, Status> {
+        let req = req.into_inner();
+        self.state.mutate(|d| {
+            let run_id = d.open_run(req.workflow_execution.as_ref())?.run_id.clone();
+            d.deliver_event(
+                &run_id,
+                PendingEvent::Plain(
+                    WorkflowExecutionSignaledEventAttributes {
+                        signal_name: req.signal_name,
+                        input: req.input,
+                        identity: req.identity,
+                        header: req.header,
+                        ..Default::default()
+                    }
+                    .into(),
+                ),
+            );
+            Ok::<_, Status>(())
+        })?;
+        Ok(Response::new(SignalWorkflowExecutionResponse::default()))
+    }
+
+    async fn request_cancel_workflow_execution(
+        &self,
+        req: Request<RequestCancelWorkflowExecutionRequest>,
+    ) -> Result<Response<RequestCancelWorkflowExecutionResponse>, Status> {
+        let req = req.into_inner();
+        self.state.mutate(|d| {
+            let run_id = d.open_run(req.workflow_execution.as_ref())?.run_id.clone();
+            d.deliver_event(
+                &run_id,
+                PendingEvent::Plain(
+                    WorkflowExecutionCancelRequestedEventAttributes {
+                        cause: req.reason,
+                        identity: req.identity,
+                        ..Default::default()
+                    }
+                    .into(),
+                ),
+            );
+            Ok::<_, Status>(())
+        })?;
+        Ok(Response::new(
+            RequestCancelWorkflowExecutionResponse::default(),
+        ))
+    }
+
+    async fn terminate_workflow_execution(
+        &self,
+        req: Request<TerminateWorkflowExecutionRequest>,
+    ) -> Result<Response<TerminateWorkflowExecutionResponse>, Status> {
+        let req = req.into_inner();
+        self.state.mutate(|d| {
+            let now = d.now();
+            let run = d.open_run(req.workflow_execution.as_ref())?;
+            run.add_event(
+                now,
+                WorkflowExecutionTerminatedEventAttributes {
+                    reason: req.reason,
+                    details: req.details,
+                    identity: req.identity,
+                },
+            );
+            run.close(now, WorkflowExecutionStatus::Terminated);
+            let run_id = run.run_id.clone();
+            d.prune_scheduled(&run_id);
+            Ok::<_, Status>(())
+        })?;
+        Ok(Response::new(TerminateWorkflowExecutionResponse::default()))
+    }
+
+    async fn describe_workflow_execution(
+        &self,
+        req: Request<DescribeWorkflowExecutionRequest>,
+    ) -> Result<Response<DescribeWorkflowExecutionResponse>, Status> {
+        let req = req.into_inner();
+        let info = self
+            .state
+            .mutate(|d| d.run(req.execution.as_ref()).map(|r| r.info()))?;
+        Ok(Response::new(DescribeWorkflowExecutionResponse {
+            workflow_execution_info: Some(info),
+            ..Default::default()
+        }))
+    }
+
+    async fn get_workflow_execution_history(
+        &self,
+        req: Request<GetWorkflowExecutionHistoryRequest>,
+    ) -> Result<Response<GetWorkflowExecutionHistoryResponse>, Status> {
+        let req = req.into_inner();
+        let close_only = req.history_event_filter_type == HistoryEventFilterType::CloseEvent as i32;
+        // Make sure the execution exists before waiting on it
+        self.state
+            .mutate(|d| d.run(req.execution.as_ref()).map(|_| ()))?;
+        let history = if close_only {
+            let close_event = if req.wait_new_event {
+                self.state
+                    .long_poll(|d| {
+                        d.run(req.execution.as_ref())
+                            .ok()
+                            .and_then(|r| r.close_event().cloned())
+                    })
+                    .await
+            } else {
+                self.state.mutate(|d| {
+                    d.run(req.execution.as_ref())
+                        .ok()
+                        .and_then(|r| r.close_event().cloned())
+                })
+            };
+            close_event.into_iter().collect()
+        } else {
+            self.state
+                .mutate(|d| d.run(req.execution.as_ref()).map(|r| r.history.clone()))?
+        };
+        Ok(Response::new(GetWorkflowExecutionHistoryResponse {
+            history: Some(History { events: history }),
+            ..Default::default()
+        }))
+    }
+
+    async fn poll_workflow_task_queue(
+        &self,
+        req: Request<PollWorkflowTaskQueueRequest>,
+    ) -> Result<Response<PollWorkflowTaskQueueResponse>, Status> {
+        let req = req.into_inner();
+        let task_queue = req.task_queue.map(|tq| tq.name).unwrap_or_default();
+        let resp = self
+            .state
+            .long_poll(|d| d.poll_workflow_task(&task_queue, &req.identity))
+            .await;
+        Ok(Response::new(resp.unwrap_or_default()))
+    }
+
+    async fn respond_workflow_task_completed(
+        &self,
+        req: Request<RespondWorkflowTaskCompletedRequest>,
+    ) -> Result<Response<RespondWorkflowTaskCompletedResponse>, Status> {
+        let req = req.into_inner();
+        self.state.mutate(|d| d.complete_workflow_task(req))?;
+        Ok(Response::new(
+            RespondWorkflowTaskCompletedResponse::default(),
+        ))
+    }
+
+    async fn respond_workflow_task_failed(
+        &self,
+        req: Request<RespondWorkflowTaskFailedRequest>,
+    ) -> Result<Response<RespondWorkflowTaskFailedResponse>, Status> {
+        let req = req.into_inner();
+        self.state.mutate(|d| d.fail_workflow_task(req))?;
+        Ok(Response::new(RespondWorkflowTaskFailedResponse::default()))
+    }
+
+    async fn poll_activity_task_queue(
+        &self,
+        req: Request<PollActivityTaskQueueRequest>,
+    ) -> Result<Response<PollActivityTaskQueueResponse>, Status> {
+        let req = req.into_inner();
+        let task_queue = req.task_queue.map(|tq| tq.name).unwrap_or_default();
+        let resp = self
+            .state
+            .long_poll(|d| d.poll_activity_task(&req.namespace, &task_queue))
+            .await;
+        Ok(Response::new(resp.unwrap_or_default()))
+    }
+
+    async fn record_activity_task_heartbeat(
+        &self,
+        req: Request<RecordActivityTaskHeartbeatRequest>,
+    ) -> Result<Response<RecordActivityTaskHeartbeatResponse>, Status> {
+        let req = req.into_inner();
+        let cancel_requested = self.state.mutate(|d| {
+            let (run, scheduled_event_id) = d.running_activity(&req.task_token)?;
+            Ok::<_, Status>(
+                run.activities
+                    .get(&scheduled_event_id)
+                    .is_some_and(|a| a.cancel_requested_event_id.is_some()),
+            )
+        })?;
+        Ok(Response::new(RecordActivityTaskHeartbeatResponse {
+            cancel_requested,
+            ..Default::default()
+        }))
+    }
+
+    async fn respond_activity_task_completed(
+        &self,
+        req: Request<RespondActivityTaskCompletedRequest>,
+    ) -> Result<Response<RespondActivityTaskCompletedResponse>, Status> {
+        let req = req.into_inner();
+        self.state.mutate(|d| {
+            d.close_activity(&req.task_token, req.identity, |scheduled_event_id, _| {
+                ActivityTaskCompletedEventAttributes {
+                    result: req.result,
+                    scheduled_event_id,
+                    ..Default::default()
+                }
+                .into()
+            })
+        })?;
+        Ok(Response::new(
+            RespondActivityTaskCompletedResponse::default(),
+        ))
+    }
+
+    async fn respond_activity_task_failed(
+        &self,
+        req: Request<RespondActivityTaskFailedRequest>,
+    ) -> Result<Response<RespondActivityTaskFailedResponse>, Status> {
+        let req = req.into_inner();
+        self.state.mutate(|d| d.fail_activity(req))?;
+        Ok(Response::new(RespondActivityTaskFailedResponse::default()))
+    }
+
+    async fn respond_activity_task_canceled(
+        &self,
+        req: Request<RespondActivityTaskCanceledRequest>,
+    ) -> Result<Response<RespondActivityTaskCanceledResponse>, Status> {
+        let req = req.into_inner();
+        self.state.mutate(|d| {
+            d.close_activity(&req.task_token, req.identity, |scheduled_event_id, act| {
+                ActivityTaskCanceledEventAttributes {
+                    details: req.details,
+                    latest_cancel_requested_event_id: act
+                        .cancel_requested_event_id
+                        .unwrap_or_default(),
+                    scheduled_event_id,
+                    ..Default::default()
+                }
+                .into()
+            })
+        })?;
+        Ok(Response::new(RespondActivityTaskCanceledResponse::default()))
+    }
+
+    async fn query_workflow(
+        &self,
+        req: Request<QueryWorkflowRequest>,
+    ) -> Result<Response<QueryWorkflowResponse>, Status> {
+        let req = req.into_inner();
+        let query = req
+            .query
+            .ok_or_else(|| Status::invalid_argument("Query is required"))?;
+        let (tx, rx) = oneshot::channel();
+        self.state.mutate(|d| {
+            let run = d.run(req.execution.as_ref())?;
+            let run_id = run.run_id.clone();
+            let task_queue = run.task_queue.clone();
+            let query_id = d.next_id();
+            d.queries.insert(query_id, tx);
+            d.workflow_queues.entry(task_queue).or_default().push_back(
+                WorkflowTaskDispatch::LegacyQuery {
+                    run_id,
+                    query_id,
+                    query,
+                },
+            );
+            Ok::<_, Status>(())
+        })?;
+        let answer = tokio::time::timeout(QUERY_TIMEOUT, rx)
+            .await
+            .map_err(|_| Status::deadline_exceeded("No worker answered the query"))?
+            .map_err(|_| Status::internal("Query was dropped"))?;
+        if answer.completed_type == QueryResultType::Answered as i32 {
+            Ok(Response::new(QueryWorkflowResponse {
+                query_result: answer.query_result,
+                query_rejected: None,
+            }))
+        } else {
+            Err(Status::invalid_argument(answer.error_message))
+        }
+    }
+
+    async fn respond_query_task_completed(
+        &self,
+        req: Request<RespondQueryTaskCompletedRequest>,
+    ) -> Result<Response<RespondQueryTaskCompletedResponse>, Status> {
+        let req = req.into_inner();
+        let query_id = parse_query_token(&req.task_token)?;
+        let tx = self
+            .state
+            .mutate(|d| d.queries.remove(&query_id))
+            .ok_or_else(|| Status::not_found("Query not found"))?;
+        let _ = tx.send(req);
+        Ok(Response::new(RespondQueryTaskCompletedResponse::default()))
+    }
+
+    async fn poll_nexus_task_queue(
+        &self,
+        _: Request<PollNexusTaskQueueRequest>,
+    ) -> Result<Response<PollNexusTaskQueueResponse>, Status> {
+        // Nexus tasks never arrive, but behave like an idle long poll
+        self.state.long_poll(|_| None::<()>).await;
+        Ok(Response::new(PollNexusTaskQueueResponse::default()))
+    }
+
+    async fn shutdown_worker(
+        &self,
+        _: Request<ShutdownWorkerRequest>,
+    ) -> Result<Response<ShutdownWorkerResponse>, Status> {
+        Ok(Response::new(ShutdownWorkerResponse::default()))
+    }
+}
+
+impl InMemoryTestService {
+    /// Waits until the server's clock reaches `until`, which may happen early if time is skipped
+    async fn sleep_until(&self, until: SystemTime) -> Result<(), Status> {
+        let (tx, rx) = oneshot::channel();
+        self.state
+            .mutate(|d| d.schedule_action(until, ScheduledAction::Wake(tx)));
+        rx.await
+            .map_err(|_| Status::unavailable("Server is shutting down"))
+    }
+
+    async fn sleep_for(&self, duration: Option<prost_wkt_types::Duration>) -> Result<(), Status> {
+        let duration: Duration = duration
+            .try_into_or_none()
+            .ok_or_else(|| Status::invalid_argument("A valid duration is required"))?;
+        let until = self.state.data.lock().now() + duration;
+        self.sleep_until(until).await
+    }
+
+    fn unlock(&self) -> Result<(), Status> {
+        self.state.mutate(|d| {
+            d.time_skipping_locks = d
+                .time_skipping_locks
+                .checked_sub(1)
+                .ok_or_else(|| Status::failed_precondition("Time skipping is already unlocked"))?;
+            Ok(())
+        })
+    }
+}
+
+#[tonic::async_trait]
+impl TestService for InMemoryTestService {
+    async fn lock_time_skipping(
+        &self,
+        _: Request<LockTimeSkippingRequest>,
+    ) -> Result<Response<LockTimeSkippingResponse>, Status> {
+        self.state.mutate(|d| d.time_skipping_locks += 1);
+        Ok(Response::new(LockTimeSkippingResponse::default()))
+    }
+
+    async fn unlock_time_skipping(
+        &self,
+        _: Request<UnlockTimeSkippingRequest>,
+    ) -> Result<Response<UnlockTimeSkippingResponse>, Status> {
+        self.unlock()?;
+        Ok(Response::new(UnlockTimeSkippingResponse::default()))
+    }
+
+    async fn sleep(&self, req: Request<SleepRequest>) -> Result<Response<SleepResponse>, Status> {
+        self.sleep_for(req.into_inner().duration).await?;
+        Ok(Response::new(SleepResponse::default()))
+    }
+
+    async fn sleep_until(
+        &self,
+        req: Request<SleepUntilRequest>,
+    ) -> Result<Response<SleepResponse>, Status> {
+        let until: SystemTime = req
+            .into_inner()
+            .timestamp
+            .try_into_or_none()
+            .ok_or_else(|| Status::invalid_argument("A valid timestamp is required"))?;
+        InMemoryTestService::sleep_until(self, until).await?;
+        Ok(Response::new(SleepResponse::default()))
+    }
+
+    async fn unlock_time_skipping_with_sleep(
+        &self,
+        req: Request<SleepRequest>,
+    ) -> Result<Response<SleepResponse>, Status> {
+        self.unlock()?;
+        let res = self.sleep_for(req.into_inner().duration).await;
+        self.state.mutate(|d| d.time_skipping_locks += 1);
+        res?;
+        Ok(Response::new(SleepResponse::default()))
+    }
+
+    async fn get_current_time(
+        &self,
+        _: Request<()>,
+    ) -> Result<Response<GetCurrentTimeResponse>, Status> {
+        let now = self.state.data.lock().now();
+        Ok(Response::new(GetCurrentTimeResponse {
+            time: Some(now.into()),
+        }))
+    }
+}
@@ -5,7 +5,9 @@
 extern crate tracing;
 
 pub mod canned_histories;
+pub mod in_memory_server;
 pub mod interceptors;
+pub mod workflow_environment;
 pub mod workflows;
 
 pub use temporal_sdk_core::replay::HistoryForReplay;
@@ -26,7 +28,7 @@ use temporal_client::{
     WorkflowClientTrait, WorkflowExecutionInfo, WorkflowHandle, WorkflowOptions,
 };
 use temporal_sdk::{
-    IntoActivityFunc, Worker, WorkflowFunction,
+    ActContext, ActivityError, IntoActivityFunc, Worker, WorkflowFunction,
     interceptors::{FailOnNondeterminismInterceptor, WorkerInterceptor},
 };
 #[cfg(feature = "ephemeral-server")]
@@ -41,14 +43,15 @@ use temporal_sdk_core_api::{
     Worker as CoreWorker,
     errors::PollError,
     telemetry::{
-        Logger, OtelCollectorOptionsBuilder, PrometheusExporterOptionsBuilder, TelemetryOptions,
-        TelemetryOptionsBuilder, metrics::CoreMeter,
+        Logger, OtelCollectorOptionsBuilder, OtelExportOptionsBuilder,
+        PrometheusExporterOptionsBuilder, TelemetryOptions, TelemetryOptionsBuilder,
+        metrics::CoreMeter,
     },
 };
 use temporal_sdk_core_protos::{
     DEFAULT_ACTIVITY_TYPE,
     coresdk::{
-        FromPayloadsExt,
+        AsJsonPayloadExt, FromJsonPayloadExt, FromPayloadsExt,
         workflow_activation::{WorkflowActivation, WorkflowActivationJob, workflow_activation_job},
         workflow_commands::{
             ActivityCancellationType, CompleteWorkflowExecution, QueryResult, QuerySuccess,
@@ -56,6 +59,7 @@ use temporal_sdk_core_protos::{
         },
         workflow_completion::WorkflowActivationCompletion,
     },
+    history_from_json,
     temporal::api::{
         common::v1::Payload, history::v1::History,
         workflowservice::v1::StartWorkflowExecutionResponse,
@@ -157,6 +161,16 @@ pub async fn history_from_proto_binary(path_from_root: &str) -> Result<History,
     Ok(History::decode(&*bytes)?)
 }
 
+/// Load history from a file containing a JSON export of it, as produced by the Temporal UI or
+/// `temporal workflow show -o json`
+pub async fn history_from_json_file(path_from_root: &str) -> Result<History, anyhow::Error> {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("..");
+    path.push(path_from_root);
+    let json = tokio::fs::read_to_string(path).await?;
+    history_from_json(&json)
+}
+
 static INTEG_TESTS_RT: std::sync::OnceLock<CoreRuntime> = std::sync::OnceLock::new();
 pub fn init_integ_telem() -> &'static CoreRuntime {
     INTEG_TESTS_RT.get_or_init(|| {
@@ -177,6 +191,8 @@ pub struct CoreWfStarter {
     pub worker_config: WorkerConfigBuilder,
     /// Options to use when starting workflow(s)
     pub workflow_options: WorkflowOptions,
+    /// Options used to connect to the server. Defaults to [get_integ_server_options].
+    pub client_options: ClientOptions,
     initted_worker: OnceCell<InitializedWorker>,
     runtime_override: Option<Arc<CoreRuntime>>,
 }
@@ -207,6 +223,7 @@ impl CoreWfStarter {
             worker_config,
             initted_worker: OnceCell::new(),
             workflow_options: Default::default(),
+            client_options: get_integ_server_options(),
             runtime_override: runtime_override.map(Arc::new),
         }
     }
@@ -218,6 +235,7 @@ impl CoreWfStarter {
             task_queue_name: self.task_queue_name.clone(),
             worker_config: self.worker_config.clone(),
             workflow_options: self.workflow_options.clone(),
+            client_options: self.client_options.clone(),
             runtime_override: self.runtime_override.clone(),
             initted_worker: Default::default(),
         }
@@ -328,7 +346,7 @@ impl CoreWfStarter {
                     .build()
                     .expect("Worker config must be valid");
                 let client = Arc::new(
-                    get_integ_server_options()
+                    self.client_options
                         .connect(
                             cfg.namespace.clone(),
                             rt.telemetry().get_temporal_metric_meter(),
@@ -390,6 +408,24 @@ impl TestWorker {
         self.inner.register_activity(activity_type, act_function)
     }
 
+    /// Registers a stand-in for an activity type. The mock is handed the activity's input and its
+    /// result becomes the activity's result, which lets workflows be tested without their real
+    /// activities.
+    pub fn mock_activity<A, R>(
+        &mut self,
+        activity_type: impl Into<String>,
+        mock: impl Fn(A) -> Result<R, ActivityError> + Send + Sync + 'static,
+    ) where
+        A: FromJsonPayloadExt + Send + 'static,
+        R: AsJsonPayloadExt + Send + 'static,
+    {
+        let mock = Arc::new(mock);
+        self.register_activity(activity_type, move |_: ActContext, input: A| {
+            let mock = mock.clone();
+            async move { mock(input) }
+        })
+    }
+
     /// Create a handle that can be used to submit workflows. Useful when workflows need to be
     /// started concurrently with running the worker.
     pub fn get_submitter_handle(&self) -> TestWorkerSubmitterHandle {
@@ -654,10 +690,17 @@ pub fn get_integ_telem_options() -> TelemetryOptions {
         .map(|x| x.parse::<Url>().unwrap())
     {
         let opts = OtelCollectorOptionsBuilder::default()
-            .url(url)
+            .url(url.clone())
             .build()
             .unwrap();
         ob.metrics(Arc::new(build_otlp_metric_exporter(opts).unwrap()) as Arc<dyn CoreMeter>);
+        ob.otel_traces(
+            OtelExportOptionsBuilder::default()
+                .url(url)
+                .filter(filter_string.clone())
+                .build()
+                .unwrap(),
+        );
     }
     if let Some(addr) = env::var(PROM_ENABLE_ENV_VAR)
         .ok()
@@ -836,15 +879,8 @@ where
     R: FromPayloadsExt,
 {
     async fn fetch_history_and_replay(&self, worker: &mut Worker) -> Result<(), anyhow::Error> {
-        let wf_id = self.info().workflow_id.clone();
-        let run_id = self.info().run_id.clone();
-        let history = self
-            .client()
-            .get_workflow_execution_history(wf_id.clone(), run_id, vec![])
-            .await?
-            .history
-            .expect("history field must be populated");
-        let with_id = HistoryForReplay::new(history, wf_id);
+        let history = self.fetch_history().await?;
+        let with_id = HistoryForReplay::new(history, self.info().workflow_id.clone());
         let replay_worker = init_core_replay_preloaded(worker.task_queue(), [with_id]);
         worker.with_new_core_worker(replay_worker);
         worker.set_worker_interceptor(FailOnNondeterminismInterceptor {});
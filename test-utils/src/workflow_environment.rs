@@ -0,0 +1,176 @@
+//! A test environment for workflows written with the Rust SDK, akin to the `WorkflowEnvironment`
+//! other SDKs offer. It runs workflows against either Temporal's time-skipping test server or the
+//! [in-memory server](crate::in_memory_server), and skips time whenever workflows are only waiting
+//! on timers, so that workflows which sleep for days can be tested in milliseconds.
+//!
+//! ```ignore
+//! let env = WorkflowEnvironment::start_in_memory().await?;
+//! let mut worker = env.new_worker("my-task-queue");
+//! worker.register_wf("sleepy", sleepy_wf);
+//! worker.mock_activity("send_email", |_: String| Ok(true));
+//! worker
+//!     .submit_wf("wf-id", "sleepy", vec![], Default::default())
+//!     .await?;
+//! env.run_until_done(&mut worker).await?;
+//! ```
+
+use crate::{
+    NAMESPACE, TestWorker, in_memory_server::InMemoryServer, init_integ_telem, integ_worker_config,
+};
+use anyhow::Context;
+use std::{
+    future::Future,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use temporal_client::{Client, RetryClient, TestService};
+#[cfg(feature = "ephemeral-server")]
+use temporal_sdk_core::ephemeral_server::{
+    EphemeralServer, TestServerConfig, TestServerConfigBuilder,
+};
+use temporal_sdk_core::{ClientOptions, init_worker};
+use temporal_sdk_core_protos::{
+    temporal::api::testservice::v1::{
+        LockTimeSkippingRequest, SleepRequest, UnlockTimeSkippingRequest,
+    },
+    utilities::TryIntoOrNone,
+};
+
+/// Hosts a server for workflow tests, and controls its clock. Shut it down with
+/// [WorkflowEnvironment::shutdown].
+pub struct WorkflowEnvironment {
+    server: EnvironmentServer,
+    client: Arc<RetryClient<Client>>,
+}
+
+enum EnvironmentServer {
+    #[cfg(feature = "ephemeral-server")]
+    TestServer(EphemeralServer),
+    InMemory(InMemoryServer),
+}
+
+impl WorkflowEnvironment {
+    /// Starts Temporal's time-skipping test server, downloading it first if it isn't cached
+    #[cfg(feature = "ephemeral-server")]
+    pub async fn start_time_skipping() -> Result<Self, anyhow::Error> {
+        let config = TestServerConfigBuilder::default()
+            .exe(crate::default_cached_download())
+            .build()?;
+        Self::start_with_test_server(config).await
+    }
+
+    /// Starts Temporal's time-skipping test server with the provided configuration
+    #[cfg(feature = "ephemeral-server")]
+    pub async fn start_with_test_server(config: TestServerConfig) -> Result<Self, anyhow::Error> {
+        let mut server = config.start_server().await?;
+        let target_url = format!("http://{}", server.target).parse()?;
+        let options = temporal_sdk_core::ClientOptionsBuilder::default()
+            .identity("workflow_environment".to_string())
+            .target_url(target_url)
+            .client_name("temporal-core".to_string())
+            .client_version("0.1.0".to_string())
+            .build()?;
+        match Self::connect(options).await {
+            Ok(client) => Ok(Self {
+                server: EnvironmentServer::TestServer(server),
+                client,
+            }),
+            Err(e) => {
+                server.shutdown().await?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Starts an [InMemoryServer]. It needs no download or external process, but only supports a
+    /// subset of Temporal's features - see its docs for details.
+    pub async fn start_in_memory() -> Result<Self, anyhow::Error> {
+        let server = InMemoryServer::start().await?;
+        let client = Self::connect(server.client_options()).await?;
+        Ok(Self {
+            server: EnvironmentServer::InMemory(server),
+            client,
+        })
+    }
+
+    async fn connect(options: ClientOptions) -> Result<Arc<RetryClient<Client>>, anyhow::Error> {
+        Ok(Arc::new(options.connect(NAMESPACE, None).await?))
+    }
+
+    /// A client connected to the environment's server
+    pub fn client(&self) -> Arc<RetryClient<Client>> {
+        self.client.clone()
+    }
+
+    /// Creates a worker for the given task queue on the environment's server. Workflows submitted
+    /// through it can be run with [WorkflowEnvironment::run_until_done].
+    pub fn new_worker(&self, task_queue: impl Into<String>) -> TestWorker {
+        let task_queue = task_queue.into();
+        let cfg = integ_worker_config(&task_queue)
+            .build()
+            .expect("Worker config must be valid");
+        let core_worker = init_worker(init_integ_telem(), cfg, self.client.clone())
+            .expect("Worker inits cleanly");
+        let mut worker = TestWorker::new(Arc::new(core_worker), task_queue);
+        worker.client = Some(self.client.clone());
+        worker
+    }
+
+    /// Runs the worker until all the workflows submitted through it have completed, skipping time
+    /// whenever they are only waiting on timers.
+    pub async fn run_until_done(&self, worker: &mut TestWorker) -> Result<(), anyhow::Error> {
+        self.with_time_skipping(worker.run_until_done()).await?
+    }
+
+    /// Waits for the provided future with time skipping unlocked, so the server's clock jumps
+    /// ahead whenever no workflow or activity task is outstanding and only timers remain.
+    pub async fn with_time_skipping<F: Future>(&self, fut: F) -> Result<F::Output, anyhow::Error> {
+        let mut client = (*self.client).clone();
+        client
+            .unlock_time_skipping(UnlockTimeSkippingRequest::default())
+            .await?;
+        let out = fut.await;
+        client
+            .lock_time_skipping(LockTimeSkippingRequest::default())
+            .await?;
+        Ok(out)
+    }
+
+    /// Moves the server's clock forward by `duration`, firing any timers which come due, and
+    /// returns once it has. Like the test server's `UnlockTimeSkippingWithSleep`, this fails if
+    /// called while time skipping is already unlocked.
+    pub async fn sleep(&self, duration: Duration) -> Result<(), anyhow::Error> {
+        let duration = duration
+            .try_into()
+            .ok()
+            .context("Sleep duration is out of range")?;
+        (*self.client)
+            .clone()
+            .unlock_time_skipping_with_sleep(SleepRequest {
+                duration: Some(duration),
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// The server's current time, which includes any time that has been skipped
+    pub async fn current_time(&self) -> Result<SystemTime, anyhow::Error> {
+        let resp = (*self.client).clone().get_current_time(()).await?;
+        resp.into_inner()
+            .time
+            .try_into_or_none()
+            .context("Server returned an invalid time")
+    }
+
+    /// Shuts down the server
+    pub async fn shutdown(self) -> Result<(), anyhow::Error> {
+        match self.server {
+            #[cfg(feature = "ephemeral-server")]
+            EnvironmentServer::TestServer(mut server) => server.shutdown().await,
+            EnvironmentServer::InMemory(server) => {
+                server.shutdown().await;
+                Ok(())
+            }
+        }
+    }
+}
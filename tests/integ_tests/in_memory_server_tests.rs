@@ -0,0 +1,219 @@
+use crate::integ_tests::activity_functions::echo;
+use assert_matches::assert_matches;
+use futures_util::StreamExt;
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+use temporal_client::{WfClientExt, WorkflowClientTrait, WorkflowExecutionResult, WorkflowOptions};
+use temporal_sdk::{ActivityOptions, QueryInfo, WfContext, WfExitValue, WorkflowResult};
+use temporal_sdk_core_protos::{
+    coresdk::{
+        AsJsonPayloadExt, FromJsonPayloadExt, workflow_commands::ContinueAsNewWorkflowExecution,
+    },
+    temporal::api::{history::v1::history_event::Attributes, query::v1::WorkflowQuery},
+};
+use temporal_sdk_core_test_utils::{CoreWfStarter, in_memory_server::InMemoryServer};
+
+const SIGNAL_NAME: &str = "finish";
+
+async fn timer_activity_signal_wf(ctx: WfContext) -> WorkflowResult<String> {
+    let awaiting_signal = Arc::new(AtomicBool::new(false));
+    let awaiting_signal_q = awaiting_signal.clone();
+    ctx.query_handler("awaiting_signal", move |_: &QueryInfo, _: ()| {
+        Ok(awaiting_signal_q.load(Ordering::Acquire))
+    });
+    let mut signal_chan = ctx.make_signal_channel(SIGNAL_NAME);
+
+    ctx.timer(Duration::from_millis(100)).await;
+    let act_res = ctx
+        .activity(ActivityOptions {
+            activity_type: "echo_activity".to_string(),
+            start_to_close_timeout: Some(Duration::from_secs(5)),
+            input: "hi!".as_json_payload().expect("serializes fine"),
+            ..Default::default()
+        })
+        .await;
+    let echoed = String::from_json_payload(&act_res.unwrap_ok_payload())?;
+    awaiting_signal.store(true, Ordering::Release);
+    signal_chan.next().await;
+    Ok(echoed.into())
+}
+
+/// Continues as new once, then waits for a signal in the second run
+async fn signal_after_continue_as_new_wf(ctx: WfContext) -> WorkflowResult<String> {
+    let Some(arg) = ctx.get_args().first() else {
+        ctx.timer(Duration::from_millis(100)).await;
+        return Ok(WfExitValue::continue_as_new(
+            ContinueAsNewWorkflowExecution {
+                arguments: vec!["continued".as_json_payload()?],
+                ..Default::default()
+            },
+        ));
+    };
+    let continued = String::from_json_payload(arg)?;
+    ctx.make_signal_channel(SIGNAL_NAME).next().await;
+    Ok(continued.into())
+}
+
+#[tokio::test]
+async fn workflow_runs_against_in_memory_server() {
+    let wf_name = "timer_activity_signal_wf";
+    let server = InMemoryServer::start().await.unwrap();
+    let mut starter = CoreWfStarter::new(wf_name);
+    starter.client_options = server.client_options();
+    let mut worker = starter.worker().await;
+    let client = starter.get_client().await;
+    worker.register_wf(wf_name, timer_activity_signal_wf);
+    worker.register_activity("echo_activity", echo);
+
+    let wf_id = starter.get_task_queue().to_string();
+    let run_id = worker
+        .submit_wf(wf_id.clone(), wf_name, vec![], WorkflowOptions::default())
+        .await
+        .unwrap();
+
+    let signaler = async {
+        // Wait until the workflow reports, via query, that it's blocked on the signal
+        loop {
+            let resp = client
+                .query_workflow_execution(
+                    wf_id.clone(),
+                    run_id.clone(),
+                    WorkflowQuery {
+                        query_type: "awaiting_signal".to_string(),
+                        ..Default::default()
+                    },
+                )
+                .await
+                .unwrap();
+            let awaiting =
+                bool::from_json_payload(&resp.query_result.unwrap().payloads[0]).unwrap();
+            if awaiting {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        client
+            .signal_workflow_execution(
+                wf_id.clone(),
+                run_id.clone(),
+                SIGNAL_NAME.to_string(),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+    };
+    let (_, run_res) = tokio::join!(signaler, worker.run_until_done());
+    run_res.unwrap();
+
+    let res = client
+        .get_untyped_workflow_handle(wf_id, run_id)
+        .get_workflow_result(Default::default())
+        .await
+        .unwrap();
+    let payloads = assert_matches!(res, WorkflowExecutionResult::Succeeded(p) => p);
+    assert_eq!(String::from_json_payload(&payloads[0]).unwrap(), "hi!");
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn workflow_handle_operations_against_in_memory_server() {
+    let wf_name = "timer_activity_signal_wf";
+    let server = InMemoryServer::start().await.unwrap();
+    let mut starter = CoreWfStarter::new(wf_name);
+    starter.client_options = server.client_options();
+    let mut worker = starter.worker().await;
+    let client = starter.get_client().await;
+    worker.register_wf(wf_name, timer_activity_signal_wf);
+    worker.register_activity("echo_activity", echo);
+
+    let wf_id = starter.get_task_queue().to_string();
+    let run_id = worker
+        .submit_wf(wf_id.clone(), wf_name, vec![], WorkflowOptions::default())
+        .await
+        .unwrap();
+    let handle = client.get_workflow_handle::<String>(wf_id.clone(), run_id.clone());
+
+    let signaler = async {
+        while !handle
+            .query::<bool>("awaiting_signal", vec![])
+            .await
+            .unwrap()
+        {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        let desc = handle.describe().await.unwrap();
+        assert_eq!(
+            desc.workflow_execution_info
+                .unwrap()
+                .execution
+                .unwrap()
+                .run_id,
+            run_id
+        );
+        handle.signal(SIGNAL_NAME, vec![]).await.unwrap();
+    };
+    let (_, run_res) = tokio::join!(signaler, worker.run_until_done());
+    run_res.unwrap();
+
+    let res = handle.get_result(Default::default()).await.unwrap();
+    assert_eq!(res.unwrap_success(), "hi!");
+    let history = handle.fetch_history().await.unwrap();
+    assert_matches!(
+        history.events.last().unwrap().attributes,
+        Some(Attributes::WorkflowExecutionCompletedEventAttributes(_))
+    );
+    // Closed workflows can't be terminated
+    assert!(handle.terminate("done", vec![]).await.is_err());
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn started_workflow_handle_follows_continue_as_new() {
+    let wf_name = "signal_after_continue_as_new_wf";
+    let server = InMemoryServer::start().await.unwrap();
+    let mut starter = CoreWfStarter::new(wf_name);
+    starter.client_options = server.client_options();
+    starter.worker_config.no_remote_activities(true);
+    let mut worker = starter.worker().await;
+    let client = starter.get_client().await;
+    worker.register_wf(wf_name, signal_after_continue_as_new_wf);
+
+    let wf_id = starter.get_task_queue().to_string();
+    let first_run_id = worker
+        .submit_wf(wf_id.clone(), wf_name, vec![], WorkflowOptions::default())
+        .await
+        .unwrap();
+    let pinned = client.get_untyped_workflow_handle(wf_id.clone(), first_run_id.clone());
+    let handle = client.get_started_workflow_handle::<String>(wf_id, first_run_id.clone());
+    assert_eq!(handle.first_execution_run_id(), Some(first_run_id.as_str()));
+
+    let signaler = async {
+        loop {
+            let current = handle
+                .describe()
+                .await
+                .unwrap()
+                .workflow_execution_info
+                .unwrap();
+            if current.execution.unwrap().run_id != first_run_id && current.close_time.is_none() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        // A handle pinned to the first run can no longer reach the workflow
+        assert!(pinned.signal(SIGNAL_NAME, vec![]).await.is_err());
+        handle.signal(SIGNAL_NAME, vec![]).await.unwrap();
+    };
+    let (_, run_res) = tokio::join!(signaler, worker.run_until_done());
+    run_res.unwrap();
+
+    let res = handle.get_result(Default::default()).await.unwrap();
+    assert_eq!(res.unwrap_success(), "continued");
+    server.shutdown().await;
+}
@@ -0,0 +1,91 @@
+use futures_util::TryStreamExt;
+use std::time::Duration;
+use temporal_client::{
+    CreateScheduleOptions, Schedule, ScheduleAction, ScheduleCalendarSpec, ScheduleOverlapPolicy,
+    ScheduleRange, ScheduleSpec, WfClientExt,
+};
+use temporal_sdk_core_test_utils::CoreWfStarter;
+use tokio::time::sleep;
+
+#[tokio::test]
+async fn schedule_lifecycle() {
+    let wf_name = "schedule_lifecycle";
+    let mut starter = CoreWfStarter::new(wf_name);
+    let client = starter.get_client().await;
+    let schedule_id = format!("{}-schedule", starter.get_task_queue());
+
+    let mut schedule = Schedule::new(
+        ScheduleAction::start_workflow(wf_name, wf_name, starter.get_task_queue(), vec![]),
+        ScheduleSpec::interval(Duration::from_secs(60 * 60)),
+    );
+    schedule.state.paused = true;
+    schedule.state.note = Some("created paused".to_string());
+    let handle = client
+        .create_schedule(&schedule_id, schedule, CreateScheduleOptions::default())
+        .await
+        .unwrap();
+
+    let desc = handle.describe().await.unwrap();
+    assert!(desc.schedule.state.paused);
+    assert_eq!(
+        desc.schedule.spec.intervals[0].every,
+        Duration::from_secs(60 * 60)
+    );
+
+    // Replace the interval with a calendar, read-modify-write style
+    handle
+        .update(|desc| {
+            let mut schedule = desc.schedule;
+            schedule.spec = ScheduleSpec::calendar(ScheduleCalendarSpec {
+                hour: vec![ScheduleRange::single(12)],
+                ..Default::default()
+            });
+            Some(schedule)
+        })
+        .await
+        .unwrap();
+    let desc = handle.describe().await.unwrap();
+    assert!(desc.schedule.spec.intervals.is_empty());
+    assert_eq!(
+        desc.schedule.spec.calendars[0].hour,
+        vec![ScheduleRange::single(12)]
+    );
+
+    handle.unpause(Some("go".to_string())).await.unwrap();
+    let desc = handle.describe().await.unwrap();
+    assert!(!desc.schedule.state.paused);
+    assert_eq!(desc.schedule.state.note.as_deref(), Some("go"));
+    handle.pause(None).await.unwrap();
+    assert!(handle.describe().await.unwrap().schedule.state.paused);
+
+    handle
+        .trigger(ScheduleOverlapPolicy::AllowAll)
+        .await
+        .unwrap();
+    // Triggering is processed asynchronously by the schedule
+    let mut action_count = 0;
+    for _ in 0..10 {
+        action_count = handle.describe().await.unwrap().info.action_count;
+        if action_count > 0 {
+            break;
+        }
+        sleep(Duration::from_millis(200)).await;
+    }
+    assert_eq!(action_count, 1);
+
+    // Visibility doesn't always update immediately so we give this a few tries
+    let mut found = false;
+    for _ in 0..20 {
+        let listed: Vec<_> = client.list_schedules("").try_collect().await.unwrap();
+        if let Some(summary) = listed.iter().find(|s| s.id == schedule_id) {
+            assert_eq!(summary.workflow_type.as_deref(), Some(wf_name));
+            found = true;
+            break;
+        }
+        sleep(Duration::from_millis(500)).await;
+    }
+    assert!(found, "Schedule never showed up in listing");
+
+    handle.delete().await.unwrap();
+    assert!(handle.describe().await.is_err());
+}
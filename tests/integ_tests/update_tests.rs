@@ -9,7 +9,8 @@ use std::{
     time::Duration,
 };
 use temporal_client::{
-    Client, NamespacedClient, RetryClient, WorkflowClientTrait, WorkflowService,
+    Client, NamespacedClient, RetryClient, StartUpdateOpts, UpdateWithStartOptions, WfClientExt,
+    WorkflowClientTrait, WorkflowService,
 };
 use temporal_sdk::{ActContext, ActivityOptions, LocalActivityOptions, UpdateContext, WfContext};
 use temporal_sdk_core::replay::HistoryForReplay;
@@ -27,7 +28,7 @@ use temporal_sdk_core_protos::{
         workflow_completion::WorkflowActivationCompletion,
     },
     temporal::api::{
-        common::v1::WorkflowExecution,
+        common::v1::{Payloads, WorkflowExecution},
         enums::v1::{EventType, ResetReapplyType, UpdateWorkflowExecutionLifecycleStage},
         update::{self, v1::WaitPolicy},
         workflowservice::v1::ResetWorkflowExecutionRequest,
@@ -1151,3 +1152,110 @@ async fn update_after_empty_wft() {
         .await
         .unwrap();
 }
+
+#[tokio::test]
+async fn update_handle_waits_for_result() {
+    let wf_name = "update_handle_waits_for_result";
+    let mut starter = CoreWfStarter::new(wf_name);
+    starter.worker_config.no_remote_activities(true);
+    let mut worker = starter.worker().await;
+    let client = starter.get_client().await;
+    worker.register_wf(wf_name.to_owned(), |ctx: WfContext| async move {
+        ctx.update_handler(
+            "update",
+            |_: &_, _: ()| Ok(()),
+            move |ctx: UpdateContext, _: ()| async move {
+                // Make sure the result isn't already available when the update is accepted
+                ctx.wf_ctx.timer(Duration::from_millis(500)).await;
+                Ok("done".to_string())
+            },
+        );
+        ctx.timer(Duration::from_secs(2)).await;
+        Ok(().into())
+    });
+
+    let handle = starter.start_with_worker(wf_name, &mut worker).await;
+    let wf_id = starter.get_task_queue().to_string();
+    let update = async {
+        let update_handle = handle
+            .start_update(
+                "update",
+                vec![().as_json_payload().unwrap()],
+                StartUpdateOpts::default(),
+            )
+            .await
+            .unwrap();
+        // A handle obtained only from ids sees the same outcome
+        let res = client
+            .get_update_handle(wf_id.clone(), "", update_handle.id())
+            .get_result::<String>()
+            .await
+            .unwrap();
+        assert_eq!(res.unwrap_success(), "done");
+        let res = update_handle.get_result::<String>().await.unwrap();
+        assert_eq!(res.unwrap_success(), "done");
+    };
+    let run = async {
+        worker.run_until_done().await.unwrap();
+    };
+    join!(update, run);
+}
+
+#[tokio::test]
+async fn update_with_start_starts_then_reuses_workflow() {
+    let wf_name = "update_with_start_starts_then_reuses_workflow";
+    let mut starter = CoreWfStarter::new(wf_name);
+    starter.worker_config.no_remote_activities(true);
+    let mut worker = starter.worker().await;
+    let client = starter.get_client().await;
+    worker.register_wf(wf_name.to_owned(), |ctx: WfContext| async move {
+        ctx.update_handler(
+            "update",
+            |_: &_, _: ()| Ok(()),
+            move |_: UpdateContext, i: u32| async move { Ok(i + 1) },
+        );
+        let mut sig = ctx.make_signal_channel("done");
+        sig.next().await;
+        Ok(().into())
+    });
+
+    let wf_id = starter.get_task_queue().to_string();
+    worker.expect_workflow_completion(&wf_id, None);
+    let options = |input: u32| {
+        UpdateWithStartOptions::builder()
+            .task_queue(starter.get_task_queue())
+            .workflow_id(&wf_id)
+            .workflow_type(wf_name)
+            .update_name("update")
+            .update_input(Payloads {
+                payloads: vec![input.as_json_payload().unwrap()],
+            })
+            .wait_for_stage(UpdateWorkflowExecutionLifecycleStage::Completed)
+            .build()
+            .unwrap()
+    };
+    let updates = async {
+        let (wf_handle, update_handle) = client
+            .update_with_start_workflow(options(1), Default::default())
+            .await
+            .unwrap();
+        let res = update_handle.get_result::<u32>().await.unwrap();
+        assert_eq!(res.unwrap_success(), 2);
+        let run_id = wf_handle.info().run_id.clone();
+        assert!(run_id.is_some());
+
+        // The workflow is already running, so the second update lands on the same run
+        let (wf_handle, update_handle) = client
+            .update_with_start_workflow(options(5), Default::default())
+            .await
+            .unwrap();
+        assert_eq!(wf_handle.info().run_id, run_id);
+        let res = update_handle.get_result::<u32>().await.unwrap();
+        assert_eq!(res.unwrap_success(), 6);
+        wf_handle.signal("done", vec![]).await.unwrap();
+    };
+    let run = async {
+        worker.run_until_done().await.unwrap();
+    };
+    join!(updates, run);
+}
@@ -1,11 +1,13 @@
 use assert_matches::assert_matches;
+use futures_util::TryStreamExt;
 use std::{sync::Arc, time::Duration};
 use temporal_client::{
-    ListClosedFilters, ListOpenFilters, Namespace, RegisterNamespaceOptions, StartTimeFilter,
-    WorkflowClientTrait, WorkflowExecutionFilter,
+    ListClosedFilters, ListOpenFilters, ListOptions, Namespace, RegisterNamespaceOptions,
+    StartTimeFilter, WfClientExt, WorkflowClientTrait, WorkflowExecutionFilter,
 };
-use temporal_sdk_core_protos::coresdk::workflow_activation::{
-    WorkflowActivationJob, workflow_activation_job,
+use temporal_sdk_core_protos::{
+    coresdk::workflow_activation::{WorkflowActivationJob, workflow_activation_job},
+    temporal::api::enums::v1::WorkflowExecutionStatus,
 };
 use temporal_sdk_core_test_utils::{
     CoreWfStarter, NAMESPACE, WorkerTestHelpers, drain_pollers_and_shutdown,
@@ -150,3 +152,83 @@ async fn client_describe_namespace() {
         .unwrap();
     assert_eq!(namespace_result.namespace_info.unwrap().name, NAMESPACE);
 }
+
+#[tokio::test]
+async fn list_and_count_workflows() {
+    let wf_name = "list_and_count_workflows";
+    let mut starter = CoreWfStarter::new(wf_name);
+    let client = starter.get_client().await;
+    let task_queue = starter.get_task_queue().to_owned();
+    let wf_ids: Vec<_> = (0..3).map(|i| format!("{task_queue}-{i}")).collect();
+    for wf_id in &wf_ids {
+        client
+            .start_workflow(
+                vec![],
+                task_queue.clone(),
+                wf_id.clone(),
+                wf_name.to_owned(),
+                None,
+                Default::default(),
+            )
+            .await
+            .unwrap();
+    }
+    let query = format!("TaskQueue = '{task_queue}'");
+
+    // Visibility doesn't always update immediately so we give this a few tries. A page size of
+    // one makes sure we're following page tokens.
+    let one_per_page = ListOptions {
+        page_size: Some(1),
+        limit: None,
+    };
+    let mut listed = vec![];
+    for _ in 0..20 {
+        listed = client
+            .list_workflows(&query, one_per_page.clone())
+            .try_collect()
+            .await
+            .unwrap();
+        if listed.len() == wf_ids.len() {
+            break;
+        }
+        sleep(Duration::from_millis(200)).await;
+    }
+    assert_eq!(listed.len(), wf_ids.len());
+    for wf in &listed {
+        assert!(wf_ids.contains(&wf.workflow_id));
+        assert_eq!(wf.workflow_type, wf_name);
+        assert_eq!(wf.status, WorkflowExecutionStatus::Running);
+    }
+
+    let limited: Vec<_> = client
+        .list_workflows(
+            &query,
+            ListOptions {
+                limit: Some(2),
+                ..one_per_page
+            },
+        )
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(limited.len(), 2);
+
+    let count = client
+        .count_workflow_executions(query.clone())
+        .await
+        .unwrap();
+    assert_eq!(count.count, 3);
+    let grouped = client
+        .count_workflow_executions(format!("{query} GROUP BY ExecutionStatus"))
+        .await
+        .unwrap();
+    assert_eq!(grouped.groups.len(), 1);
+    assert_eq!(grouped.groups[0].count, 3);
+
+    for wf_id in wf_ids {
+        client
+            .terminate_workflow_execution(wf_id, None)
+            .await
+            .unwrap();
+    }
+}
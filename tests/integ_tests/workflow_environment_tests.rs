@@ -0,0 +1,79 @@
+use assert_matches::assert_matches;
+use std::time::{Duration, Instant};
+use temporal_client::{WfClientExt, WorkflowExecutionResult, WorkflowOptions};
+use temporal_sdk::{ActivityOptions, WfContext, WorkflowResult};
+use temporal_sdk_core_protos::coresdk::{AsJsonPayloadExt, FromJsonPayloadExt};
+use temporal_sdk_core_test_utils::workflow_environment::WorkflowEnvironment;
+
+const WEEK: Duration = Duration::from_secs(60 * 60 * 24 * 7);
+
+async fn weekly_charge_wf(ctx: WfContext) -> WorkflowResult<String> {
+    ctx.timer(WEEK).await;
+    let res = ctx
+        .activity(ActivityOptions {
+            activity_type: "charge_card".to_string(),
+            start_to_close_timeout: Some(Duration::from_secs(5)),
+            input: "card-1".as_json_payload()?,
+            ..Default::default()
+        })
+        .await;
+    Ok(String::from_json_payload(&res.unwrap_ok_payload())?.into())
+}
+
+async fn skips_long_timer(env: WorkflowEnvironment) {
+    let wf_name = "weekly_charge_wf";
+    let mut worker = env.new_worker(wf_name);
+    worker.register_wf(wf_name, weekly_charge_wf);
+    worker.mock_activity("charge_card", |card: String| Ok(format!("charged {card}")));
+
+    let started_at = env.current_time().await.unwrap();
+    let wall_clock = Instant::now();
+    let run_id = worker
+        .submit_wf(wf_name, wf_name, vec![], WorkflowOptions::default())
+        .await
+        .unwrap();
+    env.run_until_done(&mut worker).await.unwrap();
+
+    assert!(wall_clock.elapsed() < Duration::from_secs(30));
+    let skipped = env
+        .current_time()
+        .await
+        .unwrap()
+        .duration_since(started_at)
+        .unwrap();
+    assert!(skipped >= WEEK);
+    let res = env
+        .client()
+        .get_untyped_workflow_handle(wf_name, run_id)
+        .get_workflow_result(Default::default())
+        .await
+        .unwrap();
+    let payloads = assert_matches!(res, WorkflowExecutionResult::Succeeded(p) => p);
+    assert_eq!(
+        String::from_json_payload(&payloads[0]).unwrap(),
+        "charged card-1"
+    );
+    env.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn in_memory_environment_skips_long_timer() {
+    skips_long_timer(WorkflowEnvironment::start_in_memory().await.unwrap()).await;
+}
+
+#[tokio::test]
+async fn in_memory_environment_sleep_advances_clock() {
+    let env = WorkflowEnvironment::start_in_memory().await.unwrap();
+    let before = env.current_time().await.unwrap();
+    env.sleep(WEEK).await.unwrap();
+    let after = env.current_time().await.unwrap();
+    assert!(after.duration_since(before).unwrap() >= WEEK);
+    env.shutdown().await.unwrap();
+}
+
+// Test server downloads aren't available for arm linux
+#[cfg(not(all(target_os = "linux", any(target_arch = "arm", target_arch = "aarch64"))))]
+#[tokio::test]
+async fn test_server_environment_skips_long_timer() {
+    skips_long_timer(WorkflowEnvironment::start_time_skipping().await.unwrap()).await;
+}
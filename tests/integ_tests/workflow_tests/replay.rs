@@ -1,9 +1,13 @@
 use crate::integ_tests::workflow_tests::patches::changes_wf;
 use assert_matches::assert_matches;
+use futures_util::{StreamExt, stream};
 use parking_lot::Mutex;
-use std::{collections::HashSet, sync::Arc, time::Duration};
+use std::{collections::HashSet, num::NonZeroUsize, sync::Arc, time::Duration};
 use temporal_sdk::{WfContext, Worker, WorkflowFunction, interceptors::WorkerInterceptor};
-use temporal_sdk_core::replay::{HistoryFeeder, HistoryForReplay};
+use temporal_sdk_core::{
+    init_replay_worker,
+    replay::{HistoryFeeder, HistoryForReplay, ReplayOutcome, ReplayWorkerInput},
+};
 use temporal_sdk_core_api::errors::PollError;
 use temporal_sdk_core_protos::{
     DEFAULT_WORKFLOW_TYPE, TestHistoryBuilder,
@@ -16,7 +20,7 @@ use temporal_sdk_core_protos::{
 };
 use temporal_sdk_core_test_utils::{
     WorkerTestHelpers, canned_histories, history_from_proto_binary, init_core_replay_preloaded,
-    replay_sdk_worker, replay_sdk_worker_stream,
+    integ_worker_config, replay_sdk_worker, replay_sdk_worker_stream,
 };
 use tokio::join;
 
@@ -238,6 +242,48 @@ async fn multiple_histories_can_handle_dupe_run_ids() {
     worker.run().await.unwrap();
 }
 
+#[tokio::test]
+async fn parallel_replay_yields_verdict_per_history() {
+    let mut hists = vec![];
+    for _ in 0..3 {
+        let mut t = canned_histories::single_timer("1");
+        t.set_wf_type("onetimer");
+        hists.push(test_hist_to_replay(t));
+    }
+    // The workflow starts a timer, but this history scheduled an activity
+    let mut nondeterministic = canned_histories::single_activity("1");
+    nondeterministic.set_wf_type("onetimer");
+    let bad_run_id = nondeterministic.get_orig_run_id().to_string();
+    hists.push(test_hist_to_replay(nondeterministic));
+
+    let worker_cfg = integ_worker_config("parallel_replay")
+        .build()
+        .expect("Configuration options construct properly");
+    let mut rwi = ReplayWorkerInput::new(worker_cfg, stream::iter(hists))
+        .with_concurrency(NonZeroUsize::new(4).unwrap());
+    let verdicts = rwi.verdicts();
+    let core = init_replay_worker(rwi).unwrap();
+    let mut worker = Worker::new_from_core(Arc::new(core), "replay_q".to_string());
+    worker.register_wf("onetimer", timers_wf(1));
+    worker.run().await.unwrap();
+
+    let verdicts: Vec<_> = verdicts.take(4).collect().await;
+    assert_eq!(verdicts.len(), 4);
+    for verdict in verdicts {
+        if verdict.run_id == bad_run_id {
+            assert_matches!(
+                verdict.outcome,
+                ReplayOutcome::Nondeterminism {
+                    diagnostic: Some(d),
+                    ..
+                } if d.event_id == 5
+            );
+        } else {
+            assert!(verdict.outcome.is_success());
+        }
+    }
+}
+
 // Verifies SDK can decode patch markers before changing them to use json encoding
 #[tokio::test]
 async fn replay_old_patch_format() {
@@ -11,12 +11,15 @@ mod integ_tests {
     mod client_tests;
     mod ephemeral_server_tests;
     mod heartbeat_tests;
+    mod in_memory_server_tests;
     mod metrics_tests;
     mod polling_tests;
     mod queries_tests;
+    mod schedule_tests;
     mod update_tests;
     mod visibility_tests;
     mod worker_tests;
+    mod workflow_environment_tests;
     mod workflow_tests;
 
     use std::{env, str::FromStr, time::Duration};